@@ -0,0 +1,70 @@
+/*
+* Benchmarks of the interpreter, run with cargo bench. They cover tokenizing a statement, routing
+* the nodes of a statement through the compiler and running loops on the virtual machine and the
+* tree-walking interpreter so a change that slows one of them shows up in review
+*/
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use jist::compiler::compilers::route_to_parser;
+use jist::globals::TREE_WALK;
+use jist::node::nodes::{match_token_to_node, ASTNode};
+use jist::output::{self, CallbackOutput};
+use jist::statement_tokenizer::tokenizer::tokenizers::tokenize;
+use jist::Engine;
+use std::hint::black_box;
+
+const DECLARATION: &str = "let bench_declared: int = 60 * 60 * 24 + 7;";
+const CALL: &str = "let bench_called: string = concat(\"a\", to_string(42));";
+const ASSIGNMENT: &str = "bench_counter = bench_counter + 1;";
+const LOOP: &str = "bench_i = 0;\nwhile (bench_i < 1000) {\n    bench_total = bench_total + bench_i;\n    bench_i++;\n}";
+
+/// An engine with the variables the benchmarks use, what the programs print is thrown away
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.set_output(CallbackOutput::new(|_: &str| {}));
+    engine
+        .eval("let bench_counter: int = 0; let bench_i: int = 0; let bench_total: int = 0;")
+        .unwrap();
+    engine
+}
+
+fn nodes(statement: &str) -> Vec<ASTNode> {
+    tokenize(statement.to_string())
+        .into_iter()
+        .map(match_token_to_node)
+        .collect()
+}
+
+fn tokenizer(c: &mut Criterion) {
+    let _engine = engine();
+    c.bench_function("tokenize/declaration", |b| {
+        b.iter(|| tokenize(black_box(DECLARATION.to_string())))
+    });
+    c.bench_function("tokenize/function_call", |b| {
+        b.iter(|| tokenize(black_box(CALL.to_string())))
+    });
+}
+
+fn parser(c: &mut Criterion) {
+    let _engine = engine();
+    let assignment = nodes(ASSIGNMENT);
+    c.bench_function("route_to_parser/assignment", |b| {
+        b.iter(|| route_to_parser(&mut black_box(assignment.clone()), None).unwrap())
+    });
+}
+
+fn loops(c: &mut Criterion) {
+    let mut engine = engine();
+    c.bench_function("loops/while_bytecode", |b| {
+        b.iter(|| engine.eval(black_box(LOOP)).unwrap())
+    });
+    unsafe { TREE_WALK = true };
+    c.bench_function("loops/while_tree_walk", |b| {
+        b.iter(|| engine.eval(black_box(LOOP)).unwrap())
+    });
+    unsafe { TREE_WALK = false };
+    output::set_output(Box::new(output::StdoutOutput));
+}
+
+criterion_group!(benches, tokenizer, parser, loops);
+criterion_main!(benches);
@@ -1,15 +1,226 @@
 pub mod variables {
     use super::variable::Variable;
+    use crate::intern::{intern, lookup, Symbol};
+    use lazy_static::lazy_static;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
     // use super::base_variables::BaseVariables::{Pi, E};
 
-    pub static mut VARIABLE_STACK: Vec<Variable> = Vec::new();
+    lazy_static! {
+        pub static ref VARIABLE_STACK: Mutex<Vec<Variable>> = Mutex::new(Vec::new());
+    }
+
+    /// The slots of the variables on the VARIABLE_STACK by name, used by the tree walk and for the
+    /// names a compiled chunk does not declare itself. A name declared again e.g. a function
+    /// parameter gets another slot and the last one is the one in scope
+    #[derive(Default)]
+    struct SlotIndex {
+        slots: HashMap<Symbol, Vec<usize>>,
+        len: usize,
+    }
+
+    impl SlotIndex {
+        fn rebuild(&mut self, stack: &[Variable]) {
+            self.slots.clear();
+            for (slot, variable) in stack.iter().enumerate() {
+                self.slots
+                    .entry(intern(&variable.name))
+                    .or_default()
+                    .push(slot);
+            }
+            self.len = stack.len();
+        }
+
+        /// Rebuild the index when the stack was changed without going through it
+        fn sync(&mut self, stack: &[Variable]) {
+            if self.len != stack.len() {
+                self.rebuild(stack);
+            }
+        }
+
+        fn find(&self, symbol: Symbol) -> Option<usize> {
+            self.slots.get(&symbol)?.last().copied()
+        }
+    }
+
+    lazy_static! {
+        static ref SLOTS: Mutex<SlotIndex> = Mutex::new(SlotIndex::default());
+    }
+
+    /// Find the slot of the variable with a name that is in scope
+    ///
+    /// params: name: &str -> The name of the variable
+    ///
+    /// returns: Option<usize> -> The index on the VARIABLE_STACK or None when there is no variable
+    pub fn find_variable(name: &str) -> Option<usize> {
+        find_symbol(lookup(name)?)
+    }
+
+    /// Find the slot of the variable with an interned name, see find_variable
+    pub fn find_symbol(symbol: Symbol) -> Option<usize> {
+        find_slot(&VARIABLE_STACK.lock().unwrap(), symbol)
+    }
+
+    fn find_slot(stack: &[Variable], symbol: Symbol) -> Option<usize> {
+        let mut slots = SLOTS.lock().unwrap();
+        slots.sync(stack);
+        match slots.find(symbol) {
+            Some(slot) if *stack[slot].name == *symbol.name() => Some(slot),
+            // a variable was replaced without going through the index
+            Some(_) => {
+                slots.rebuild(stack);
+                slots.find(symbol)
+            }
+            None => None,
+        }
+    }
+
+    /// A copy of the variable with a name that is in scope, Variable::assign writes a changed
+    /// value back to the stack
+    pub fn get_variable(name: &str) -> Option<Variable> {
+        get_symbol_variable(lookup(name)?)
+    }
+
+    /// A copy of the variable with an interned name e.g. from a VariableCallNode, see get_variable
+    pub fn get_symbol_variable(symbol: Symbol) -> Option<Variable> {
+        let stack = VARIABLE_STACK.lock().unwrap();
+        let slot = find_slot(&stack, symbol)?;
+        stack.get(slot).cloned()
+    }
+
+    /// Change the variable with a name that is in scope, the stack is locked during the call so
+    /// the call must not reach the stack itself
+    ///
+    /// params: name: &str -> The name of the variable
+    /// params: change: impl FnOnce(&mut Variable) -> R -> Changes the variable
+    ///
+    /// returns: Option<R> -> The result of the change or None when there is no variable
+    pub fn with_variable<R>(name: &str, change: impl FnOnce(&mut Variable) -> R) -> Option<R> {
+        let mut stack = VARIABLE_STACK.lock().unwrap();
+        let slot = find_slot(&stack, lookup(name)?)?;
+        stack.get_mut(slot).map(change)
+    }
+
+    /// Change the variable at a slot found by find_variable, see with_variable
+    pub fn with_variable_at<R>(slot: usize, change: impl FnOnce(&mut Variable) -> R) -> Option<R> {
+        VARIABLE_STACK.lock().unwrap().get_mut(slot).map(change)
+    }
+
+    /// The number of variables on the stack, a block truncates back to it when it ends
+    pub fn variable_count() -> usize {
+        VARIABLE_STACK.lock().unwrap().len()
+    }
+
+    /// Add a variable to the top of the stack
+    ///
+    /// params: variable: Variable -> The variable to add
+    ///
+    /// returns: usize -> The index of the variable on the stack
+    pub fn push_variable(variable: Variable) -> usize {
+        let mut stack = VARIABLE_STACK.lock().unwrap();
+        let mut slots = SLOTS.lock().unwrap();
+        slots.sync(&stack);
+        slots
+            .slots
+            .entry(intern(&variable.name))
+            .or_default()
+            .push(stack.len());
+        stack.push(variable);
+        slots.len = stack.len();
+        slots.len - 1
+    }
+
+    /// Remove the variable at the top of the stack
+    pub fn pop_variable() -> Option<Variable> {
+        let (len, variable) = {
+            let stack = VARIABLE_STACK.lock().unwrap();
+            (stack.len(), stack.last().cloned()?)
+        };
+        truncate_variables(len - 1);
+        Some(variable)
+    }
+
+    /// Remove the variables above len, used when a function returns
+    pub fn truncate_variables(len: usize) {
+        let mut stack = VARIABLE_STACK.lock().unwrap();
+        let mut slots = SLOTS.lock().unwrap();
+        slots.sync(&stack);
+        for variable in stack.iter().skip(len).rev() {
+            if let Some(symbol_slots) = slots.slots.get_mut(&intern(&variable.name)) {
+                symbol_slots.pop();
+            }
+        }
+        stack.truncate(len);
+        slots.len = stack.len();
+    }
+
+    /// Take every variable off the stack, used to run a module in its own scope
+    pub fn take_variables() -> Vec<Variable> {
+        let variables = std::mem::take(&mut *VARIABLE_STACK.lock().unwrap());
+        SLOTS.lock().unwrap().rebuild(&[]);
+        variables
+    }
+
+    /// Replace the stack with variables taken before
+    pub fn replace_variables(variables: Vec<Variable>) {
+        let mut stack = VARIABLE_STACK.lock().unwrap();
+        *stack = variables;
+        SLOTS.lock().unwrap().rebuild(&stack);
+    }
+
+    pub fn clear_variables() {
+        take_variables();
+    }
+}
+
+#[cfg(test)]
+mod variables_tests {
+    use super::base_types::BaseTypes;
+    use super::variable::Variable;
+    use super::variables::*;
+    use crate::intern::intern;
+
+    // the stack is shared with the other tests so the names are only used here
+
+    #[test]
+    fn test_find_variable_returns_the_last_declared() {
+        let name = "slots_test_n".to_string();
+        push_variable(Variable::new(name.clone(), BaseTypes::Int(1), BaseTypes::Int(0)));
+        push_variable(Variable::new(name.clone(), BaseTypes::Int(2), BaseTypes::Int(0)));
+        let value = &get_variable(&name).unwrap().value;
+        assert!(matches!(value, BaseTypes::Int(2)));
+        assert!(find_variable("slots_test_missing").is_none());
+
+        let mut variable = get_variable(&name).unwrap();
+        variable.assign(BaseTypes::Int(3)).unwrap();
+        let value = &get_variable(&name).unwrap().value;
+        assert!(matches!(value, BaseTypes::Int(3)));
+    }
+
+    #[test]
+    fn test_with_variable_changes_the_stack() {
+        let name = "slots_test_with".to_string();
+        push_variable(Variable::new(
+            name.clone(),
+            BaseTypes::Int(1),
+            BaseTypes::Int(0),
+        ));
+        let copy = get_variable(&name).unwrap();
+        with_variable(&name, |variable| variable.value = BaseTypes::Int(5));
+        assert!(matches!(copy.value, BaseTypes::Int(1)));
+
+        let variable = get_symbol_variable(intern(&name)).unwrap();
+        assert!(matches!(variable.value, BaseTypes::Int(5)));
+        assert!(with_variable("slots_test_with_missing", |_| ()).is_none());
+    }
 }
 
 pub mod variable {
     use super::base_types::BaseTypes;
-    use super::variables::VARIABLE_STACK;
+    use super::variables::with_variable;
     use crate::base_variable::base_types::GetType;
     use crate::node::nodes::ASTNode;
+    use crate::output;
     use std::fmt;
 
     #[derive(Debug, Clone)]
@@ -17,6 +228,7 @@ pub mod variable {
         pub name: String,
         pub value: BaseTypes,
         pub var_type: BaseTypes,
+        pub nullable: bool,
     }
 
     pub trait GetValue {
@@ -51,7 +263,15 @@ pub mod variable {
                 (BaseTypes::Int(x), BaseTypes::Int(y)) => x == y,
                 (BaseTypes::Float(x), BaseTypes::Float(y)) => x == y,
                 (BaseTypes::StringWrapper(s1), BaseTypes::StringWrapper(s2)) => s1 == s2,
+                (BaseTypes::Bool(b1), BaseTypes::Bool(b2)) => b1 == b2,
                 (BaseTypes::Char(c1), BaseTypes::Char(c2)) => c1 == c2,
+                (BaseTypes::Array(a1), BaseTypes::Array(a2)) => a1 == a2,
+                (BaseTypes::Dictionary(d1), BaseTypes::Dictionary(d2)) => d1 == d2,
+                (BaseTypes::Regex(r1), BaseTypes::Regex(r2)) => r1.as_str() == r2.as_str(),
+                (BaseTypes::DateTime(t1), BaseTypes::DateTime(t2)) => t1 == t2,
+                (BaseTypes::Duration(d1), BaseTypes::Duration(d2)) => d1 == d2,
+                (BaseTypes::Null, BaseTypes::Null) => true,
+                // values of different types are never equal
                 _ => false,
             }
         }
@@ -64,6 +284,8 @@ pub mod variable {
                 (BaseTypes::Float(x), BaseTypes::Float(y)) => x.partial_cmp(y),
                 (BaseTypes::StringWrapper(s1), BaseTypes::StringWrapper(s2)) => s1.partial_cmp(s2),
                 (BaseTypes::Char(c1), BaseTypes::Char(c2)) => c1.partial_cmp(c2),
+                (BaseTypes::DateTime(t1), BaseTypes::DateTime(t2)) => t1.partial_cmp(t2),
+                (BaseTypes::Duration(d1), BaseTypes::Duration(d2)) => d1.partial_cmp(d2),
                 _ => {
                     println!("Warning: Cannot compare different types.");
                     None
@@ -77,6 +299,8 @@ pub mod variable {
                 (BaseTypes::Float(x), BaseTypes::Float(y)) => x < y,
                 (BaseTypes::StringWrapper(s1), BaseTypes::StringWrapper(s2)) => s1 < s2,
                 (BaseTypes::Char(c1), BaseTypes::Char(c2)) => c1 < c2,
+                (BaseTypes::DateTime(t1), BaseTypes::DateTime(t2)) => t1 < t2,
+                (BaseTypes::Duration(d1), BaseTypes::Duration(d2)) => d1 < d2,
                 _ => {
                     println!("Warning: Cannot compare different types.");
                     false
@@ -90,6 +314,8 @@ pub mod variable {
                 (BaseTypes::Float(x), BaseTypes::Float(y)) => x > y,
                 (BaseTypes::StringWrapper(s1), BaseTypes::StringWrapper(s2)) => s1 > s2,
                 (BaseTypes::Char(c1), BaseTypes::Char(c2)) => c1 > c2,
+                (BaseTypes::DateTime(t1), BaseTypes::DateTime(t2)) => t1 > t2,
+                (BaseTypes::Duration(d1), BaseTypes::Duration(d2)) => d1 > d2,
                 _ => {
                     println!("Warning: Cannot compare different types.");
                     false
@@ -103,6 +329,8 @@ pub mod variable {
                 (BaseTypes::Float(x), BaseTypes::Float(y)) => x >= y,
                 (BaseTypes::StringWrapper(s1), BaseTypes::StringWrapper(s2)) => s1 >= s2,
                 (BaseTypes::Char(c1), BaseTypes::Char(c2)) => c1 >= c2,
+                (BaseTypes::DateTime(t1), BaseTypes::DateTime(t2)) => t1 >= t2,
+                (BaseTypes::Duration(d1), BaseTypes::Duration(d2)) => d1 >= d2,
                 _ => {
                     println!("Warning: Cannot compare different types.");
                     false
@@ -116,6 +344,8 @@ pub mod variable {
                 (BaseTypes::Float(x), BaseTypes::Float(y)) => x <= y,
                 (BaseTypes::StringWrapper(s1), BaseTypes::StringWrapper(s2)) => s1 <= s2,
                 (BaseTypes::Char(c1), BaseTypes::Char(c2)) => c1 <= c2,
+                (BaseTypes::DateTime(t1), BaseTypes::DateTime(t2)) => t1 <= t2,
+                (BaseTypes::Duration(d1), BaseTypes::Duration(d2)) => d1 <= d2,
                 _ => {
                     println!("Warning: Cannot compare different types.");
                     false
@@ -204,6 +434,14 @@ pub mod variable {
         }
     }
 
+    /// Warn that a value does not fit the type of a variable and the type default is used
+    fn warn_mismatch(name: &str, type_name: &str) {
+        output::eprintln(&format!(
+            "Warning: Value type mismatch for '{}'. Setting default {} value.",
+            name, type_name
+        ));
+    }
+
     impl Variable {
         pub fn new(name: String, value: BaseTypes, var_type: BaseTypes) -> Variable {
             //println!("Variable info: {}, {:?}, {:?}", name, value, var_type);
@@ -215,79 +453,141 @@ pub mod variable {
                     BaseTypes::Null => BaseTypes::Int(0),
                     BaseTypes::Float(_) => BaseTypes::Int(value.clone().into()),
                     _ => {
-                        println!(
-                            "Warning: Value type mismatch for '{}'. Setting default Int value.",
-                            name
-                        );
+                        warn_mismatch(&name, "Int");
                         BaseTypes::Int(0)
                     }
                 },
-                BaseTypes::Float(_) => {
-                    match value {
-                        BaseTypes::Float(_) => value.clone(),
-                        BaseTypes::Null => BaseTypes::Float(0.0),
-                        BaseTypes::Int(_) => BaseTypes::Float(value.clone().into()),
-                        _ => {
-                            println!("Warning: Value type mismatch for '{}'. Setting default Float value.", name);
-                            BaseTypes::Float(0.0)
-                        }
+                BaseTypes::Float(_) => match value {
+                    BaseTypes::Float(_) => value.clone(),
+                    BaseTypes::Null => BaseTypes::Float(0.0),
+                    BaseTypes::Int(_) => BaseTypes::Float(value.clone().into()),
+                    _ => {
+                        warn_mismatch(&name, "Float");
+                        BaseTypes::Float(0.0)
                     }
-                }
-                BaseTypes::StringWrapper(_) => {
-                    match value {
-                        BaseTypes::StringWrapper(_) => value.clone(),
-                        BaseTypes::Char(_) => BaseTypes::StringWrapper(value.clone().into()),
+                },
+                BaseTypes::StringWrapper(_) => match value {
+                    BaseTypes::StringWrapper(_) => value.clone(),
+                    BaseTypes::Char(_) => BaseTypes::StringWrapper(value.clone().into()),
 
-                        BaseTypes::Null => BaseTypes::StringWrapper(String::new()),
+                    BaseTypes::Null => BaseTypes::StringWrapper(String::new()),
 
-                        _ => {
-                            println!("Warning: Value type mismatch for '{}'. Setting default String value.", name);
-                            BaseTypes::StringWrapper(String::new())
-                        }
+                    _ => {
+                        warn_mismatch(&name, "String");
+                        BaseTypes::StringWrapper(String::new())
                     }
-                }
-                BaseTypes::Bool(_) => {
-                    match value {
-                        BaseTypes::Bool(_) => value.clone(),
-                        BaseTypes::Int(1) => BaseTypes::Bool(true),
-                        BaseTypes::Int(0) => BaseTypes::Bool(false),
-                        BaseTypes::Null => BaseTypes::Bool(false),
-
-                        _ => {
-                            println!("Warning: Value type mismatch for '{}'. Setting default Bool value.", name);
-                            BaseTypes::Bool(false)
-                        }
+                },
+                BaseTypes::Bool(_) => match value {
+                    BaseTypes::Bool(_) => value.clone(),
+                    BaseTypes::Int(1) => BaseTypes::Bool(true),
+                    BaseTypes::Int(0) => BaseTypes::Bool(false),
+                    BaseTypes::Null => BaseTypes::Bool(false),
+
+                    _ => {
+                        warn_mismatch(&name, "Bool");
+                        BaseTypes::Bool(false)
                     }
-                }
+                },
 
-                BaseTypes::Char(_) => {
-                    match value {
-                        BaseTypes::Char(_) => value.clone(),
-                        BaseTypes::Null => BaseTypes::Char('\0'),
+                BaseTypes::Char(_) => match value {
+                    BaseTypes::Char(_) => value.clone(),
+                    BaseTypes::Null => BaseTypes::Char('\0'),
 
-                        _ => {
-                            println!("Warning: Value type mismatch for '{}'. Setting default Char value.", name);
-                            BaseTypes::Char('\0')
-                        }
+                    _ => {
+                        warn_mismatch(&name, "Char");
+                        BaseTypes::Char('\0')
                     }
-                }
+                },
 
-                BaseTypes::Null => {
-                    match value {
-                        BaseTypes::Null => value.clone(),
-                        _ => {
-                            println!("Warning: Value type mismatch for '{}'. Setting default Null value.", name);
-                            BaseTypes::Null
-                        }
+                BaseTypes::Array(_) => match value {
+                    BaseTypes::Array(_) => value.clone(),
+                    BaseTypes::Null => BaseTypes::Array(Default::default()),
+
+                    _ => {
+                        warn_mismatch(&name, "Array");
+                        BaseTypes::Array(Default::default())
                     }
-                }
+                },
+
+                BaseTypes::Dictionary(_) => match value {
+                    BaseTypes::Dictionary(_) => value.clone(),
+                    BaseTypes::Null => BaseTypes::Dictionary(Default::default()),
+
+                    _ => {
+                        warn_mismatch(&name, "Dictionary");
+                        BaseTypes::Dictionary(Default::default())
+                    }
+                },
+
+                BaseTypes::Regex(_) => match value {
+                    BaseTypes::Regex(_) => value.clone(),
+                    _ => {
+                        warn_mismatch(&name, "Regex");
+                        var_type.clone()
+                    }
+                },
+
+                BaseTypes::DateTime(_) => match value {
+                    BaseTypes::DateTime(_) => value.clone(),
+                    _ => {
+                        warn_mismatch(&name, "DateTime");
+                        var_type.clone()
+                    }
+                },
+
+                BaseTypes::Duration(_) => match value {
+                    BaseTypes::Duration(_) => value.clone(),
+                    _ => {
+                        warn_mismatch(&name, "Duration");
+                        var_type.clone()
+                    }
+                },
+
+                BaseTypes::Null => match value {
+                    BaseTypes::Null => value.clone(),
+                    _ => {
+                        warn_mismatch(&name, "Null");
+                        BaseTypes::Null
+                    }
+                },
             };
 
             Variable {
                 name,
                 value: checked_value,
                 var_type,
+                nullable: false,
+            }
+        }
+
+        /// Creates a variable of an optional type e.g. int?, which unlike Variable::new keeps a
+        /// null value instead of replacing it with the type default
+        pub fn new_optional(name: String, value: BaseTypes, var_type: BaseTypes) -> Variable {
+            let mut variable = match value {
+                BaseTypes::Null => Variable {
+                    name,
+                    value: BaseTypes::Null,
+                    var_type,
+                    nullable: false,
+                },
+                _ => Variable::new(name, value, var_type),
+            };
+            variable.nullable = true;
+            variable
+        }
+
+        /// Checks that a value may be stored in this variable, non optional variables can never
+        /// hold null
+        pub fn check_null_safety(&self, value: &BaseTypes) -> Result<(), String> {
+            if !self.nullable && matches!(value, BaseTypes::Null) {
+                return Err(format!(
+                    "Null safety error: '{}' has non-optional type {} and cannot hold null, declare it as {}? to allow null",
+                    self.name,
+                    self.var_type.GetType(),
+                    self.var_type.GetType().to_lowercase()
+                ));
             }
+            Ok(())
         }
 
         pub fn set_name(&mut self, name: String) {
@@ -309,6 +609,16 @@ pub mod variable {
             &self.value
         }
 
+        /// Assigns a new value after checking null safety and writes it back to the variable stack
+        pub fn assign(&mut self, value: BaseTypes) -> Result<(), String> {
+            self.check_null_safety(&value)?;
+            crate::limits::check_value(&value)?;
+            self.value = value;
+
+            with_variable(&self.name, |variable| variable.value = self.value.clone());
+            Ok(())
+        }
+
         pub fn increment(&mut self) {
             match self.value {
                 BaseTypes::Int(ref mut i) => {
@@ -316,24 +626,14 @@ pub mod variable {
                     *i += 1;
 
                     // Now update the variable in the stack
-                    for variable in unsafe { VARIABLE_STACK.iter_mut() } {
-                        if variable.name == self.name {
-                            variable.value = BaseTypes::Int(*i);
-                            break; // Exit loop once the variable is found and updated
-                        }
-                    }
+                    with_variable(&self.name, |variable| variable.value = BaseTypes::Int(*i));
                 }
                 BaseTypes::Float(ref mut f) => {
                     // Increment the float value in place
                     *f += 1.0;
 
                     // Now update the variable in the stack
-                    for variable in unsafe { VARIABLE_STACK.iter_mut() } {
-                        if variable.name == self.name {
-                            variable.value = BaseTypes::Float(*f);
-                            break; // Exit loop once the variable is found and updated
-                        }
-                    }
+                    with_variable(&self.name, |variable| variable.value = BaseTypes::Float(*f));
                 }
                 _ => println!("Warning: Cannot increment non-numeric type."),
             }
@@ -347,33 +647,27 @@ pub mod variable {
                     *i -= 1;
 
                     // Now update the variable in the stack
-                    for variable in unsafe { VARIABLE_STACK.iter_mut() } {
-                        if variable.name == self.name {
-                            variable.value = BaseTypes::Int(*i);
-                            break; // Exit loop once the variable is found and updated
-                        }
-                    }
+                    with_variable(&self.name, |variable| variable.value = BaseTypes::Int(*i));
                 }
                 BaseTypes::Float(ref mut f) => {
                     // Decrement the float value in place
                     *f -= 1.0;
 
                     // Now update the variable in the stack
-                    for variable in unsafe { VARIABLE_STACK.iter_mut() } {
-                        if variable.name == self.name {
-                            variable.value = BaseTypes::Float(*f);
-                            break; // Exit loop once the variable is found and updated
-                        }
-                    }
+                    with_variable(&self.name, |variable| variable.value = BaseTypes::Float(*f));
                 }
                 _ => println!("Warning: Cannot decrement non-numeric type."),
             }
         }
 
         pub fn print(&self) {
-            println!("Variable Name: {}", self.name);
-            println!("Variable Type: {}", self.var_type.GetType());
-            println!("Variable Value: {}", self.value);
+            output::println(&format!("Variable Name: {}", self.name));
+            if self.nullable {
+                output::println(&format!("Variable Type: {}?", self.var_type.GetType()));
+            } else {
+                output::println(&format!("Variable Type: {}", self.var_type.GetType()));
+            }
+            output::println(&format!("Variable Value: {}", self.value));
         }
     }
 
@@ -539,6 +833,29 @@ pub mod variable {
                 BaseTypes::StringWrapper(s) => write!(f, "{}", s),
                 BaseTypes::Bool(b) => write!(f, "{}", b),
                 BaseTypes::Char(c) => write!(f, "{}", c),
+                BaseTypes::Array(values) => {
+                    write!(f, "[")?;
+                    for (i, value) in values.iter().enumerate() {
+                        if i > 0 {
+                            write!(f, ", ")?;
+                        }
+                        write!(f, "{}", value)?;
+                    }
+                    write!(f, "]")
+                }
+                BaseTypes::Dictionary(values) => {
+                    write!(f, "{{")?;
+                    for (i, (key, value)) in values.iter().enumerate() {
+                        if i > 0 {
+                            write!(f, ", ")?;
+                        }
+                        write!(f, "\"{}\" => {}", key, value)?;
+                    }
+                    write!(f, "}}")
+                }
+                BaseTypes::Regex(r) => write!(f, "{}", r.as_str()),
+                BaseTypes::DateTime(t) => write!(f, "{}", t.to_rfc3339()),
+                BaseTypes::Duration(d) => write!(f, "{}", crate::std_lib::time::format_duration(d)),
                 BaseTypes::Null => write!(f, "null"),
             }
         }
@@ -552,6 +869,7 @@ pub mod base_variables {
     pub enum BaseVariables {
         Pi,
         E,
+        Tau,
     }
 
     pub struct Pi {
@@ -601,6 +919,50 @@ pub mod base_variables {
             println!("E: {}", self.value);
         }
     }
+
+    pub struct Tau {
+        pub value: f64,
+    }
+
+    impl Tau {
+        pub fn new() -> Tau {
+            Tau {
+                value: std::f64::consts::TAU,
+            }
+        }
+
+        pub fn get_value(&self) -> f64 {
+            self.value
+        }
+
+        pub fn get_type(&self) -> BaseTypes {
+            BaseTypes::Float(self.value)
+        }
+
+        pub fn print(&self) {
+            println!("Tau: {}", self.value);
+        }
+    }
+
+    impl Default for Tau {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    /// Get the value of a built in constant, variables with the same name are found first
+    ///
+    /// params: name: &str -> The name used in a script e.g. PI
+    ///
+    /// returns: Option<BaseTypes> -> The value or None when the name is not a constant
+    pub fn constant_value(name: &str) -> Option<BaseTypes> {
+        match name {
+            "PI" => Some(Pi::new().get_type()),
+            "E" => Some(E::new().get_type()),
+            "TAU" => Some(Tau::new().get_type()),
+            _ => None,
+        }
+    }
 }
 
 pub mod base_types {
@@ -613,6 +975,12 @@ pub mod base_types {
         StringWrapper(String),
         Bool(bool),
         Char(char),
+        /// The elements are shared by the clones of an array and copied when one is changed
+        Array(std::sync::Arc<Vec<BaseTypes>>),
+        Dictionary(std::sync::Arc<crate::collection::entries::DictionaryEntries>),
+        Regex(regex::Regex),
+        DateTime(chrono::DateTime<chrono::FixedOffset>),
+        Duration(chrono::Duration),
         Null,
     }
 
@@ -628,6 +996,11 @@ pub mod base_types {
                 BaseTypes::StringWrapper(_) => "String".to_string(),
                 BaseTypes::Bool(_) => "Bool".to_string(),
                 BaseTypes::Char(_) => "Char".to_string(),
+                BaseTypes::Array(_) => "Array".to_string(),
+                BaseTypes::Dictionary(_) => "Dictionary".to_string(),
+                BaseTypes::Regex(_) => "Regex".to_string(),
+                BaseTypes::DateTime(_) => "DateTime".to_string(),
+                BaseTypes::Duration(_) => "Duration".to_string(),
                 BaseTypes::Null => "Null".to_string(),
             }
         }
@@ -0,0 +1,251 @@
+/*
+* This file contains jist bench. A function marked with bench is declared like any other function
+* when a program runs, jist bench runs the program once and then calls every marked function a few
+* times to warm up followed by the timed runs, and reports the mean, median and standard deviation
+*/
+
+use crate::engine::Engine;
+use crate::module::is_identifier;
+use crate::output::{self, CallbackOutput};
+use lazy_static::lazy_static;
+use std::error::Error;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Calls made before timing starts so caches are filled
+pub const DEFAULT_WARMUP: usize = 3;
+
+/// Timed calls per function
+pub const DEFAULT_RUNS: usize = 20;
+
+lazy_static! {
+    static ref BENCH_FUNCTIONS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+}
+
+/// Check if a line starts with the bench marker, the marker is removed and the function name is
+/// recorded for jist bench
+///
+/// params: line: &str -> The line of code
+///
+/// returns: Result<Option<String>, Box<dyn Error>> -> The line without the marker or None when
+/// the line is not marked bench
+pub fn strip_bench_marker(line: &str) -> Result<Option<String>, Box<dyn Error>> {
+    let Some((name, declaration)) = bench_declaration(line)? else {
+        return Ok(None);
+    };
+    mark_bench(&[name]);
+    Ok(Some(declaration))
+}
+
+/// Split the bench marker from a line without recording the name, used by the bytecode compiler
+/// which keeps the names with the program
+///
+/// params: line: &str -> The line of code
+///
+/// returns: Result<Option<(String, String)>, Box<dyn Error>> -> The function name and the line
+/// without the marker or None when the line is not marked bench
+pub fn bench_declaration(line: &str) -> Result<Option<(String, String)>, Box<dyn Error>> {
+    let declaration = match line.trim_start().strip_prefix("bench ") {
+        Some(declaration) => declaration.trim_start(),
+        None => return Ok(None),
+    };
+    let name = match declaration.strip_prefix("func ") {
+        Some(rest) => rest.split('(').next().unwrap_or_default().trim(),
+        None => {
+            return Err(format!(
+                "Syntax Error: bench must be followed by a func declaration in '{}'",
+                line.trim()
+            )
+            .into())
+        }
+    };
+    if !is_identifier(name) {
+        return Err(format!("Syntax Error: invalid bench declaration '{}'", line.trim()).into());
+    }
+
+    Ok(Some((name.to_string(), declaration.to_string())))
+}
+
+/// Record functions for jist bench, a name is kept once in the order it was first marked
+pub fn mark_bench(names: &[String]) {
+    let mut functions = BENCH_FUNCTIONS.lock().unwrap();
+    for name in names {
+        if !functions.contains(name) {
+            functions.push(name.clone());
+        }
+    }
+}
+
+/// How many times each function is called
+#[derive(Clone, Copy, Debug)]
+pub struct BenchOptions {
+    pub warmup: usize,
+    pub runs: usize,
+}
+
+impl Default for BenchOptions {
+    fn default() -> BenchOptions {
+        BenchOptions {
+            warmup: DEFAULT_WARMUP,
+            runs: DEFAULT_RUNS,
+        }
+    }
+}
+
+/// The timings of one function
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Summary {
+    pub mean: Duration,
+    pub median: Duration,
+    pub stddev: Duration,
+}
+
+/// Summarize the time of each run, the standard deviation is of the sample so one run has none
+///
+/// params: samples: &[Duration] -> The time of each run
+///
+/// returns: Summary -> The mean, median and standard deviation
+pub fn summarize(samples: &[Duration]) -> Summary {
+    if samples.is_empty() {
+        return Summary {
+            mean: Duration::ZERO,
+            median: Duration::ZERO,
+            stddev: Duration::ZERO,
+        };
+    }
+    let seconds: Vec<f64> = samples.iter().map(Duration::as_secs_f64).collect();
+    let count = seconds.len() as f64;
+    let mean = seconds.iter().sum::<f64>() / count;
+
+    let mut sorted = seconds.clone();
+    sorted.sort_by(f64::total_cmp);
+    let middle = sorted.len() / 2;
+    let median = if sorted.len().is_multiple_of(2) {
+        (sorted[middle - 1] + sorted[middle]) / 2.0
+    } else {
+        sorted[middle]
+    };
+
+    let variance = if seconds.len() > 1 {
+        seconds.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / (count - 1.0)
+    } else {
+        0.0
+    };
+    Summary {
+        mean: Duration::from_secs_f64(mean),
+        median: Duration::from_secs_f64(median),
+        stddev: Duration::from_secs_f64(variance.sqrt()),
+    }
+}
+
+/// Show a duration in the largest unit that keeps it above one e.g. 1.52 ms
+pub fn format_time(duration: Duration) -> String {
+    let nanos = duration.as_secs_f64() * 1e9;
+    if nanos < 1e3 {
+        format!("{:.0} ns", nanos)
+    } else if nanos < 1e6 {
+        format!("{:.2} µs", nanos / 1e3)
+    } else if nanos < 1e9 {
+        format!("{:.2} ms", nanos / 1e6)
+    } else {
+        format!("{:.2} s", nanos / 1e9)
+    }
+}
+
+/// Run a program and benchmark the functions it marks with bench. The program prints as usual
+/// while it runs, what the functions print while they are timed is thrown away
+///
+/// params: file_path: &str -> The program
+/// params: options: BenchOptions -> How many times each function is called
+///
+/// returns: Result<Vec<(String, Summary)>, Box<dyn Error>> -> The timings in declaration order
+pub fn run(
+    file_path: &str,
+    options: BenchOptions,
+) -> Result<Vec<(String, Summary)>, Box<dyn Error>> {
+    if options.runs == 0 {
+        return Err("Bench error: --runs must be at least 1".into());
+    }
+    BENCH_FUNCTIONS.lock().unwrap().clear();
+    let mut engine = Engine::new();
+    engine.run_file(file_path)?;
+    let functions = std::mem::take(&mut *BENCH_FUNCTIONS.lock().unwrap());
+    if functions.is_empty() {
+        return Err(format!("Bench error: {} has no functions marked bench", file_path).into());
+    }
+
+    let printed = output::set_output(Box::new(CallbackOutput::new(|_: &str| {})));
+    let results = functions
+        .into_iter()
+        .map(|name| {
+            let summary = time_function(&mut engine, &name, options)
+                .map_err(|e| format!("Bench error: {} failed: {}", name, e))?;
+            Ok((name, summary))
+        })
+        .collect();
+    output::set_output(printed);
+    results
+}
+
+fn time_function(
+    engine: &mut Engine,
+    name: &str,
+    options: BenchOptions,
+) -> Result<Summary, Box<dyn Error>> {
+    for _ in 0..options.warmup {
+        engine.call_function(name, &[])?;
+    }
+    let mut samples = Vec::with_capacity(options.runs);
+    for _ in 0..options.runs {
+        let start = Instant::now();
+        engine.call_function(name, &[])?;
+        samples.push(start.elapsed());
+    }
+    Ok(summarize(&samples))
+}
+
+#[cfg(test)]
+mod bench_tests {
+    use super::*;
+
+    fn millis(values: &[u64]) -> Vec<Duration> {
+        values.iter().map(|m| Duration::from_millis(*m)).collect()
+    }
+
+    /// Durations built from floats can be a nanosecond off
+    fn assert_millis(duration: Duration, expected: f64) {
+        assert!((duration.as_secs_f64() * 1e3 - expected).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_summarize() {
+        let summary = summarize(&millis(&[2, 4, 4, 4, 5, 5, 7, 9]));
+        assert_millis(summary.mean, 5.0);
+        assert_millis(summary.median, 4.5);
+        // the sample standard deviation of the values is sqrt(32 / 7)
+        assert_millis(summary.stddev, (32.0f64 / 7.0).sqrt());
+
+        let single = summarize(&millis(&[3]));
+        assert_millis(single.median, 3.0);
+        assert_eq!(single.stddev, Duration::ZERO);
+    }
+
+    #[test]
+    fn test_format_time() {
+        assert_eq!(format_time(Duration::from_nanos(420)), "420 ns");
+        assert_eq!(format_time(Duration::from_micros(1520)), "1.52 ms");
+        assert_eq!(format_time(Duration::from_secs(2)), "2.00 s");
+    }
+
+    #[test]
+    fn test_strip_bench_marker() {
+        let line = strip_bench_marker("bench func bench_marker_test() {}").unwrap();
+        assert_eq!(line.as_deref(), Some("func bench_marker_test() {}"));
+        assert!(BENCH_FUNCTIONS
+            .lock()
+            .unwrap()
+            .contains(&"bench_marker_test".to_string()));
+        assert_eq!(strip_bench_marker("let x: int = 1;").unwrap(), None);
+        assert!(strip_bench_marker("bench let x: int = 1;").is_err());
+    }
+}
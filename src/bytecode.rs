@@ -0,0 +1,1171 @@
+/*
+* This file contains the bytecode the virtual machine runs and the compiler that lowers programs
+* and function bodies to it. A program is tokenized once before it runs, the statements the
+* compiler understands become instructions working on a value stack and slots, anything else is
+* kept as parsed nodes that the tree walking compiler runs when the instruction is reached. Each
+* variable a chunk declares gets a slot number while it is compiled, the number is reused once the
+* block of the variable ends, names the chunk does not declare are kept by name
+*/
+
+use crate::base_variable::base_types::BaseTypes;
+use crate::bench::bench_declaration;
+use crate::engine::split_source;
+use crate::intern::{intern, Symbol};
+use crate::module::{parse_import_statement, pub_declaration, ImportStatement};
+use crate::node::nodes::{match_token_to_node, ASTNode, ForNode, FunctionNode, WhileNode};
+use crate::optimizer;
+use crate::statement_tokenizer::tokenizer::tokenizers::tokenize;
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+
+/// The operators of binary expressions
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl BinaryOp {
+    pub fn from_operator(operator: &str) -> Option<BinaryOp> {
+        match operator {
+            "+" => Some(BinaryOp::Add),
+            "-" => Some(BinaryOp::Sub),
+            "*" => Some(BinaryOp::Mul),
+            "/" => Some(BinaryOp::Div),
+            "==" => Some(BinaryOp::Eq),
+            "!=" => Some(BinaryOp::Ne),
+            "<" => Some(BinaryOp::Lt),
+            "<=" => Some(BinaryOp::Le),
+            ">" => Some(BinaryOp::Gt),
+            ">=" => Some(BinaryOp::Ge),
+            _ => None,
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Eq => "==",
+            BinaryOp::Ne => "!=",
+            BinaryOp::Lt => "<",
+            BinaryOp::Le => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::Ge => ">=",
+        }
+    }
+}
+
+/// One instruction, indexes point into the tables of the chunk and jumps to an instruction
+#[derive(Clone, Debug, PartialEq)]
+pub enum Op {
+    /// Push a value from the constant pool
+    Constant(usize),
+    /// Push the value of a variable the chunk declares
+    LoadSlot(usize),
+    /// Pop a value into a variable the chunk declares
+    StoreSlot(usize),
+    IncrementSlot(usize),
+    DecrementSlot(usize),
+    /// Push the value of a name the chunk does not declare e.g. a variable of the program read in
+    /// a function, an array or PI, the name is looked up when the instruction runs
+    LoadGlobal(usize),
+    /// Pop a value into a variable the chunk does not declare
+    StoreGlobal(usize),
+    IncrementGlobal(usize),
+    DecrementGlobal(usize),
+    Binary(BinaryOp),
+    Not,
+    Negate,
+    /// Replace the top value with whether it is true
+    ToBool,
+    /// Pop the arguments and call a function by name, the result is pushed
+    Call {
+        function: usize,
+        argc: usize,
+    },
+    Pop,
+    Jump(usize),
+    /// Pop the top value and jump when it is false
+    JumpIfFalse(usize),
+    /// Jump when the top value is false, otherwise pop it, used by &&
+    JumpIfFalseOrPop(usize),
+    /// Jump when the top value is true, otherwise pop it, used by ||
+    JumpIfTrueOrPop(usize),
+    /// Count a loop iteration against the limits
+    Step,
+    /// Evaluate a condition the compiler could not lower with the tree walk and push the result
+    Condition(usize),
+    /// Run a statement the compiler could not lower with the tree walk
+    Interpret(usize),
+    /// Evaluate a call or dot call the compiler could not lower with the tree walk and push the
+    /// value
+    Evaluate(usize),
+    /// Pop a value and declare the variable of a let statement with it in the slot of the
+    /// declaration
+    Declare(usize),
+    /// Put the variable the statement before declared in a slot, used after a let the tree walk
+    /// runs
+    Bind(usize),
+    /// Keep the top value and jump when it is not null, otherwise pop it, used by ??
+    JumpIfNotNull(usize),
+    /// Raise an error when the top value is null, used by a postfix !
+    Unwrap,
+    /// Start a block, the variables declared until ExitScope are removed by it
+    EnterScope,
+    ExitScope,
+    /// Declare the function kept as a statement with its compiled body
+    Function {
+        statement: usize,
+        body: usize,
+    },
+    /// Pop the value of a return statement and leave the chunk
+    Return,
+    /// Run the block up to EndTry and jump to the catch block when it raises an error
+    Try(usize),
+    EndTry,
+    /// Run the module of an import statement
+    Import(usize),
+}
+
+/// The variable a let statement declares
+#[derive(Clone, Debug, PartialEq)]
+pub struct Declaration {
+    pub name: String,
+    /// The type as it is written e.g. int or string?
+    pub type_name: String,
+    pub slot: usize,
+}
+
+/// Compiled code with its constant pool, the names of its slots and the statements kept for the
+/// tree walk
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Chunk {
+    pub code: Vec<Op>,
+    pub constants: Vec<BaseTypes>,
+    /// The names the chunk uses without declaring them, they are looked up when the chunk runs
+    pub globals: Vec<Symbol>,
+    /// The number of slots the variables of the chunk need
+    pub locals: usize,
+    /// The number of parameters of a function body, they are pushed before it runs and take the
+    /// first slots
+    pub parameters: usize,
+    pub functions: Vec<String>,
+    pub statements: Vec<Vec<ASTNode>>,
+    pub declarations: Vec<Declaration>,
+    pub imports: Vec<ImportStatement>,
+    /// The names the program marks pub, they are recorded as public when it runs
+    pub public: Vec<String>,
+    /// The functions the program marks bench, they are recorded for jist bench when it runs
+    pub benches: Vec<String>,
+    /// The compiled bodies of the functions the chunk declares
+    pub bodies: Vec<Arc<Chunk>>,
+    /// The file the program came from, imports are resolved relative to it
+    pub path: String,
+}
+
+impl Chunk {
+    fn constant(&mut self, value: BaseTypes) -> usize {
+        self.constants.push(value);
+        self.constants.len() - 1
+    }
+
+    fn global(&mut self, symbol: Symbol) -> usize {
+        match self.globals.iter().position(|global| *global == symbol) {
+            Some(index) => index,
+            None => {
+                self.globals.push(symbol);
+                self.globals.len() - 1
+            }
+        }
+    }
+
+    fn function(&mut self, name: &str) -> usize {
+        match self.functions.iter().position(|function| function == name) {
+            Some(index) => index,
+            None => {
+                self.functions.push(name.to_string());
+                self.functions.len() - 1
+            }
+        }
+    }
+
+    fn statement(&mut self, nodes: Vec<ASTNode>) -> usize {
+        self.statements.push(nodes);
+        self.statements.len() - 1
+    }
+
+    fn declaration(&mut self, name: &str, type_name: &str, slot: usize) -> usize {
+        self.declarations.push(Declaration {
+            name: name.to_string(),
+            type_name: type_name.to_string(),
+            slot,
+        });
+        self.declarations.len() - 1
+    }
+}
+
+impl fmt::Display for Chunk {
+    ///
+    ///Formats the chunk as a listing of its instructions for --trace
+    ///
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (index, op) in self.code.iter().enumerate() {
+            write!(f, "{:04} ", index)?;
+            match op {
+                Op::Constant(c) => writeln!(f, "Constant {:?}", self.constants[*c])?,
+                Op::LoadSlot(s) => writeln!(f, "LoadSlot {}", s)?,
+                Op::StoreSlot(s) => writeln!(f, "StoreSlot {}", s)?,
+                Op::IncrementSlot(s) => writeln!(f, "IncrementSlot {}", s)?,
+                Op::DecrementSlot(s) => writeln!(f, "DecrementSlot {}", s)?,
+                Op::LoadGlobal(g) => writeln!(f, "LoadGlobal {}", self.globals[*g])?,
+                Op::StoreGlobal(g) => writeln!(f, "StoreGlobal {}", self.globals[*g])?,
+                Op::IncrementGlobal(g) => writeln!(f, "IncrementGlobal {}", self.globals[*g])?,
+                Op::DecrementGlobal(g) => writeln!(f, "DecrementGlobal {}", self.globals[*g])?,
+                Op::Binary(op) => writeln!(f, "Binary {}", op.symbol())?,
+                Op::Call { function, argc } => {
+                    writeln!(f, "Call {} {}", self.functions[*function], argc)?
+                }
+                Op::Interpret(s) => writeln!(f, "Interpret {:?}", self.statements[*s])?,
+                Op::Condition(s) => writeln!(f, "Condition {:?}", self.statements[*s])?,
+                Op::Evaluate(s) => writeln!(f, "Evaluate {:?}", self.statements[*s])?,
+                Op::Declare(d) => {
+                    let declaration = &self.declarations[*d];
+                    writeln!(
+                        f,
+                        "Declare {}: {} in {}",
+                        declaration.name, declaration.type_name, declaration.slot
+                    )?
+                }
+                Op::Function { statement, .. } => match self.statements[*statement].first() {
+                    Some(ASTNode::Function(function)) => writeln!(f, "Function {}", function.name)?,
+                    _ => writeln!(f, "Function")?,
+                },
+                Op::Import(i) => writeln!(f, "Import {:?}", self.imports[*i])?,
+                other => writeln!(f, "{:?}", other)?,
+            }
+        }
+        for body in &self.bodies {
+            write!(f, "\n{}", body)?;
+        }
+        Ok(())
+    }
+}
+
+/// Compile the statements of a program, imports, pub and bench markers are handled here so the
+/// whole program is lowered before it runs
+///
+/// params: lines: Vec<String> -> The statements of the program
+/// params: path: &str -> The file the program came from
+///
+/// returns: Result<Chunk, Box<dyn Error>> -> The compiled program or a syntax error
+pub fn compile_program(lines: Vec<String>, path: &str) -> Result<Chunk, Box<dyn Error>> {
+    let mut compiler = Compiler::default();
+    compiler.chunk.path = path.to_string();
+    let mut statements = Vec::new();
+    for line in lines {
+        // imports run the module before the statements after them
+        if let Some(statement) = parse_import_statement(&line)? {
+            compiler.chunk.imports.push(statement);
+            let import = compiler.chunk.imports.len() - 1;
+            statements.push(Statement::Import(import));
+            continue;
+        }
+        // pub marks a declaration that modules importing this file can use
+        let line = match pub_declaration(&line)? {
+            Some((name, declaration)) => {
+                compiler.chunk.public.push(name);
+                declaration
+            }
+            None => line,
+        };
+        // bench marks a function for jist bench, otherwise it is declared as usual
+        let line = match bench_declaration(&line)? {
+            Some((name, declaration)) => {
+                compiler.chunk.benches.push(name);
+                declaration
+            }
+            None => line,
+        };
+
+        let (nodes, notes) = optimizer::optimize_statement(to_nodes(&line));
+        if optimizer::emit_enabled() {
+            optimizer::emit(&nodes, &notes);
+        }
+        statements.push(Statement::Parsed(line, nodes));
+    }
+    compiler.statements(statements, true)?;
+    Ok(compiler.chunk)
+}
+
+/// Compile the body of a function, the value of a return statement is left for the caller to
+/// check against the return type
+///
+/// params: line: &str -> The declaration of the function with its body
+/// params: function: &FunctionNode -> The parsed declaration with the parameters and return type
+///
+/// returns: Result<Chunk, Box<dyn Error>> -> The compiled body or a syntax error
+pub fn compile_function(line: &str, function: &FunctionNode) -> Result<Chunk, Box<dyn Error>> {
+    let mut compiler = Compiler {
+        return_type: Some(function.return_type.trim().to_string()),
+        ..Compiler::default()
+    };
+    // the values of a variadic parameter are kept as an array instead of a variable
+    for (name, _, _) in &function.arguments {
+        if !name.starts_with("...") {
+            compiler.local(intern(name));
+        }
+    }
+    compiler.chunk.parameters = compiler.locals.len();
+    compiler.block(block_statements(line)?, true)?;
+    Ok(compiler.chunk)
+}
+
+/// Compile an expression e.g. the condition of a loop
+///
+/// params: nodes: &[ASTNode] -> The tokenized expression
+///
+/// returns: Option<Chunk> -> The compiled expression leaving its value on the stack or None when
+/// it can not be lowered
+pub fn compile_expression(nodes: &[ASTNode]) -> Option<Chunk> {
+    let mut compiler = Compiler::default();
+    let mut parser = ExpressionParser::new(nodes);
+    parser.expression(&mut compiler, 0)?;
+    if !parser.at_end() {
+        return None;
+    }
+    Some(compiler.chunk)
+}
+
+fn to_nodes(text: &str) -> Vec<ASTNode> {
+    tokenize(text.to_string())
+        .into_iter()
+        .map(match_token_to_node)
+        .collect()
+}
+
+/// Split the source of a block into its statements
+fn block_lines(source: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    if source.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    split_source(source)
+}
+
+/// The statements of the first block of a statement e.g. the body of an if
+///
+/// params: line: &str -> The statement with the block
+///
+/// returns: Result<Vec<String>, Box<dyn Error>> -> The statements split like the lines of a file
+pub fn block_statements(line: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    block_lines(block_source(line)?)
+}
+
+/// The source between the braces of the first block of a statement e.g. the body of a loop,
+/// braces in strings and in the condition are skipped
+pub fn block_source(line: &str) -> Result<&str, Box<dyn Error>> {
+    let mut depth = 0;
+    let mut parentheses = 0;
+    let mut start = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (index, char) in line.char_indices() {
+        if in_string {
+            match char {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match char {
+            '"' => in_string = true,
+            '(' => parentheses += 1,
+            ')' => parentheses -= 1,
+            '{' if parentheses == 0 => {
+                if depth == 0 {
+                    start = index + 1;
+                }
+                depth += 1;
+            }
+            '}' if parentheses == 0 && depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(&line[start..index]);
+                }
+            }
+            _ => {}
+        }
+    }
+    Err(format!("Syntax Error: Expected a block in '{}'", line.trim()).into())
+}
+
+/// Whether a statement starts with a keyword e.g. else in else {
+fn starts_with_keyword(line: &str, keyword: &str) -> bool {
+    line.trim_start()
+        .strip_prefix(keyword)
+        .is_some_and(|rest| !rest.starts_with(|c: char| c.is_alphanumeric() || c == '_'))
+}
+
+/// A statement of a program before it is compiled
+enum Statement {
+    Import(usize),
+    /// The source of the statement and its nodes
+    Parsed(String, Vec<ASTNode>),
+}
+
+#[derive(Default)]
+struct Compiler {
+    chunk: Chunk,
+    /// The return type of the function being compiled, None for a program
+    return_type: Option<String>,
+    /// The variables in scope where the compiler is, a variable is kept in the slot of its index
+    locals: Vec<Symbol>,
+}
+
+impl Compiler {
+    fn emit(&mut self, op: Op) -> usize {
+        self.chunk.code.push(op);
+        self.chunk.code.len() - 1
+    }
+
+    fn here(&self) -> usize {
+        self.chunk.code.len()
+    }
+
+    /// Give a variable the next free slot, it keeps the slot until the block it is in ends
+    fn local(&mut self, symbol: Symbol) -> usize {
+        self.locals.push(symbol);
+        self.chunk.locals = self.chunk.locals.max(self.locals.len());
+        self.locals.len() - 1
+    }
+
+    /// Emit the instruction for a variable, the slot op when the chunk declares a variable with
+    /// the name in scope and the global op otherwise
+    fn variable(&mut self, symbol: Symbol, slot: fn(usize) -> Op, global: fn(usize) -> Op) {
+        let op = match self.locals.iter().rposition(|local| *local == symbol) {
+            Some(index) => slot(index),
+            None => global(self.chunk.global(symbol)),
+        };
+        self.emit(op);
+    }
+
+    /// Declare the variable of a let statement with the value on the stack
+    fn declare(&mut self, name: &str, type_name: &str) -> usize {
+        let slot = self.local(intern(name));
+        let declaration = self.chunk.declaration(name, type_name, slot);
+        self.emit(Op::Declare(declaration));
+        slot
+    }
+
+    fn patch(&mut self, at: usize) {
+        let target = self.here();
+        match &mut self.chunk.code[at] {
+            Op::Jump(to)
+            | Op::JumpIfFalse(to)
+            | Op::JumpIfFalseOrPop(to)
+            | Op::JumpIfTrueOrPop(to)
+            | Op::JumpIfNotNull(to)
+            | Op::Try(to) => *to = target,
+            _ => unreachable!("only jumps are patched"),
+        }
+    }
+
+    /// Compile the statements of a block, steps counts every statement against the limits like
+    /// the statements of a file or a function body
+    fn block(&mut self, lines: Vec<String>, steps: bool) -> Result<(), Box<dyn Error>> {
+        let statements = lines
+            .into_iter()
+            .map(|line| {
+                let nodes = to_nodes(&line);
+                Statement::Parsed(line, nodes)
+            })
+            .collect();
+        self.statements(statements, steps)
+    }
+
+    /// Compile a block in its own scope, the variables it declares are removed when it ends
+    fn scoped_block(&mut self, line: &str) -> Result<(), Box<dyn Error>> {
+        let lines = block_statements(line)?;
+        let scope = self.locals.len();
+        self.emit(Op::EnterScope);
+        self.block(lines, false)?;
+        self.emit(Op::ExitScope);
+        self.locals.truncate(scope);
+        Ok(())
+    }
+
+    fn statements(
+        &mut self,
+        statements: Vec<Statement>,
+        steps: bool,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut statements = statements.into_iter().peekable();
+        while let Some(statement) = statements.next() {
+            let (line, nodes) = match statement {
+                Statement::Import(import) => {
+                    self.emit(Op::Import(import));
+                    continue;
+                }
+                Statement::Parsed(line, nodes) => (line, nodes),
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            if steps {
+                self.emit(Op::Step);
+            }
+            // the branches and handlers of an if or a try are the statements after it
+            let mut following = |keyword: &str| match statements.peek() {
+                Some(Statement::Parsed(line, _)) if starts_with_keyword(line, keyword) => {
+                    match statements.next() {
+                        Some(Statement::Parsed(line, nodes)) => Some((line, nodes)),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            };
+            match nodes.first() {
+                Some(ASTNode::If(node)) => {
+                    let mut branches = vec![(Some(node.condition.clone()), line)];
+                    while let Some((line, nodes)) = following("elif") {
+                        match nodes.first() {
+                            Some(ASTNode::Elif(node)) => {
+                                branches.push((Some(node.condition.clone()), line))
+                            }
+                            _ => return Err(syntax_error(&line)),
+                        }
+                    }
+                    if let Some((line, _)) = following("else") {
+                        branches.push((None, line));
+                    }
+                    self.if_chain(branches)?;
+                }
+                Some(ASTNode::Try(_)) => {
+                    let catch = following("catch").map(|(line, _)| line);
+                    let finally = following("finally").map(|(line, _)| line);
+                    self.try_catch(&line, catch, finally)?;
+                }
+                Some(ASTNode::Elif(_) | ASTNode::Else) => {
+                    return Err(
+                        format!("Syntax Error: '{}' does not follow an if", line.trim()).into(),
+                    )
+                }
+                Some(ASTNode::Catch(_) | ASTNode::Finally(_)) => {
+                    return Err(
+                        format!("Syntax Error: '{}' does not follow a try", line.trim()).into(),
+                    )
+                }
+                _ => self.statement(&line, nodes)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn if_chain(&mut self, branches: Vec<(Option<String>, String)>) -> Result<(), Box<dyn Error>> {
+        let mut exits = Vec::new();
+        for (condition, line) in branches {
+            let skip = condition.map(|condition| {
+                self.condition(&condition);
+                self.emit(Op::JumpIfFalse(0))
+            });
+            self.scoped_block(&line)?;
+            exits.push(self.emit(Op::Jump(0)));
+            if let Some(skip) = skip {
+                self.patch(skip);
+            }
+        }
+        for exit in exits {
+            self.patch(exit);
+        }
+        Ok(())
+    }
+
+    // an error in the try block is reported and handled by the catch block, the finally block
+    // runs either way
+    fn try_catch(
+        &mut self,
+        line: &str,
+        catch: Option<String>,
+        finally: Option<String>,
+    ) -> Result<(), Box<dyn Error>> {
+        let handler = self.emit(Op::Try(0));
+        self.scoped_block(line)?;
+        self.emit(Op::EndTry);
+        let skip = self.emit(Op::Jump(0));
+        self.patch(handler);
+        if let Some(catch) = catch {
+            self.scoped_block(&catch)?;
+        }
+        self.patch(skip);
+        if let Some(finally) = finally {
+            self.scoped_block(&finally)?;
+        }
+        Ok(())
+    }
+
+    fn while_loop(&mut self, node: &WhileNode, line: &str) -> Result<(), Box<dyn Error>> {
+        if node.block.iter().all(|line| line.trim().is_empty()) {
+            return Err("Error: Empty body in while loop.".into());
+        }
+        let start = self.here();
+        self.condition(&node.condition);
+        let exit = self.emit(Op::JumpIfFalse(0));
+        self.emit(Op::Step);
+        self.scoped_block(line)?;
+        self.emit(Op::Jump(start));
+        self.patch(exit);
+        Ok(())
+    }
+
+    // the variable is declared in the scope of the loop and counts up to and including the end
+    // of the range
+    fn for_loop(&mut self, node: &ForNode, line: &str) -> Result<(), Box<dyn Error>> {
+        let scope = self.locals.len();
+        self.emit(Op::EnterScope);
+        let start_value = self.chunk.constant(BaseTypes::Int(node.iterable.0));
+        self.emit(Op::Constant(start_value));
+        let slot = self.declare(&node.variable, "int");
+
+        let end = self.chunk.constant(BaseTypes::Int(node.iterable.1));
+        let start = self.here();
+        self.emit(Op::LoadSlot(slot));
+        self.emit(Op::Constant(end));
+        self.emit(Op::Binary(BinaryOp::Le));
+        let exit = self.emit(Op::JumpIfFalse(0));
+        self.emit(Op::Step);
+        self.scoped_block(line)?;
+        self.emit(Op::IncrementSlot(slot));
+        self.emit(Op::Jump(start));
+        self.patch(exit);
+        self.emit(Op::ExitScope);
+        self.locals.truncate(scope);
+        Ok(())
+    }
+
+    fn condition(&mut self, condition: &str) {
+        let nodes = to_nodes(condition);
+        let start = self.here();
+        let mut parser = ExpressionParser::new(&nodes);
+        if parser.expression(self, 0).is_some() && parser.at_end() {
+            return;
+        }
+        self.chunk.code.truncate(start);
+        let statement = self.chunk.statement(nodes);
+        self.emit(Op::Condition(statement));
+    }
+
+    /// Compile the expression of nodes, false leaves nothing emitted when it can not be lowered
+    fn value(&mut self, nodes: &[ASTNode]) -> bool {
+        let start = self.here();
+        let mut parser = ExpressionParser::new(nodes);
+        if parser.expression(self, 0).is_some() && parser.at_end() {
+            return true;
+        }
+        self.chunk.code.truncate(start);
+        false
+    }
+
+    fn statement(&mut self, line: &str, nodes: Vec<ASTNode>) -> Result<(), Box<dyn Error>> {
+        let mut nodes = nodes;
+        if let Some(ASTNode::SemiColon) = nodes.last() {
+            nodes.pop();
+        }
+        match nodes.as_slice() {
+            [] | [ASTNode::Comment(_), ..] => {}
+            [ASTNode::While(node)] => self.while_loop(node, line)?,
+            [ASTNode::For(node)] => self.for_loop(node, line)?,
+            [ASTNode::Function(function)] => {
+                // the body is compiled from the source, the block of the node is split for the
+                // tree walk
+                let body = compile_function(line, function)?;
+                self.chunk.bodies.push(Arc::new(body));
+                let body = self.chunk.bodies.len() - 1;
+                let statement = self.chunk.statement(nodes);
+                self.emit(Op::Function { statement, body });
+            }
+            [ASTNode::Return(node)] => self.return_statement(&node.value)?,
+            // a lone value does nothing, it is most likely a mistake
+            [ASTNode::VariableCall(_)
+            | ASTNode::Int(_)
+            | ASTNode::Float(_)
+            | ASTNode::String(_)
+            | ASTNode::Char(_)
+            | ASTNode::Bool(_)
+            | ASTNode::Null] => {
+                return Err(format!(
+                    "Syntax error: expression must be more than a semicolon in line: {}",
+                    line.trim()
+                )
+                .into())
+            }
+            _ => {
+                if self.simple_statement(&nodes).is_none() {
+                    // a let the tree walk runs declares its variable on the stack
+                    let declared = match nodes.first() {
+                        Some(ASTNode::Variable(v)) => Some(intern(&v.value)),
+                        _ => None,
+                    };
+                    let statement = self.chunk.statement(nodes);
+                    self.emit(Op::Interpret(statement));
+                    if let Some(symbol) = declared {
+                        let slot = self.local(symbol);
+                        self.emit(Op::Bind(slot));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn return_statement(&mut self, value: &str) -> Result<(), Box<dyn Error>> {
+        let return_type = self
+            .return_type
+            .clone()
+            .ok_or("Syntax Error: return outside of a function")?;
+        let expression = value.trim().trim_end_matches(';').trim();
+        let nodes = to_nodes(expression);
+        if expression.is_empty() {
+            let null = self.chunk.constant(BaseTypes::Null);
+            self.emit(Op::Constant(null));
+        } else if !self.value(&nodes) {
+            // the tree walk evaluates it as a variable of the return type
+            let declaration = format!("let __value: {} = {};", return_type, expression);
+            let mut nodes = to_nodes(&declaration);
+            nodes.pop();
+            let statement = self.chunk.statement(nodes);
+            self.emit(Op::Interpret(statement));
+            let slot = self.local(intern("__value"));
+            self.emit(Op::Bind(slot));
+            self.emit(Op::LoadSlot(slot));
+        }
+        self.emit(Op::Return);
+        Ok(())
+    }
+
+    /// Lower a declaration, assignment or call, None leaves it to the tree walk
+    fn simple_statement(&mut self, nodes: &[ASTNode]) -> Option<()> {
+        let start = self.here();
+        let lowered = match nodes {
+            [ASTNode::Variable(v), ASTNode::VariableType(t), ASTNode::AssignmentOperator(a), value @ ..]
+                if a.operator == "=" && !value.is_empty() =>
+            {
+                self.value(value).then(|| {
+                    self.declare(&v.value, &t.value);
+                })
+            }
+            [ASTNode::VariableCall(v), ASTNode::Operator(o)] if o.operator == "++" => {
+                self.variable(v.symbol, Op::IncrementSlot, Op::IncrementGlobal);
+                Some(())
+            }
+            [ASTNode::VariableCall(v), ASTNode::Operator(o)] if o.operator == "--" => {
+                self.variable(v.symbol, Op::DecrementSlot, Op::DecrementGlobal);
+                Some(())
+            }
+            [ASTNode::VariableCall(v), ASTNode::AssignmentOperator(a), value @ ..]
+                if a.operator == "=" && !value.is_empty() =>
+            {
+                self.value(value).then(|| {
+                    self.variable(v.symbol, Op::StoreSlot, Op::StoreGlobal);
+                })
+            }
+            // compound assignment e.g. total += i
+            [ASTNode::VariableCall(v), ASTNode::Operator(o), ASTNode::AssignmentOperator(a), value @ ..]
+                if a.operator == "=" && !value.is_empty() =>
+            {
+                let op = BinaryOp::from_operator(&o.operator)?;
+                self.variable(v.symbol, Op::LoadSlot, Op::LoadGlobal);
+                self.value(value).then(|| {
+                    self.emit(Op::Binary(op));
+                    self.variable(v.symbol, Op::StoreSlot, Op::StoreGlobal);
+                })
+            }
+            // a call for its effect, the value is dropped
+            [ASTNode::FunctionCall(_) | ASTNode::Dot(_), ..] => self.value(nodes).then(|| {
+                self.emit(Op::Pop);
+            }),
+            _ => None,
+        };
+        if lowered.is_none() {
+            self.chunk.code.truncate(start);
+        }
+        lowered
+    }
+}
+
+fn syntax_error(line: &str) -> Box<dyn Error> {
+    format!("Syntax Error: could not compile '{}'", line.trim()).into()
+}
+
+/// Parses the nodes of an expression by operator precedence and emits the instructions
+struct ExpressionParser<'a> {
+    nodes: &'a [ASTNode],
+    position: usize,
+}
+
+impl<'a> ExpressionParser<'a> {
+    fn new(nodes: &'a [ASTNode]) -> ExpressionParser<'a> {
+        ExpressionParser { nodes, position: 0 }
+    }
+
+    fn at_end(&self) -> bool {
+        self.position >= self.nodes.len()
+    }
+
+    fn peek(&self) -> Option<&'a ASTNode> {
+        self.nodes.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<&'a ASTNode> {
+        let node = self.nodes.get(self.position);
+        self.position += 1;
+        node
+    }
+
+    /// The operator at the current position and how tightly it binds
+    fn infix(&self) -> Option<(&'a str, u8)> {
+        match self.peek()? {
+            ASTNode::Operator(o) => {
+                let precedence = match o.operator.as_str() {
+                    "??" => 1,
+                    "||" => 2,
+                    "&&" => 3,
+                    "==" | "!=" => 4,
+                    "<" | "<=" | ">" | ">=" => 5,
+                    "+" | "-" => 6,
+                    "*" | "/" => 7,
+                    _ => return None,
+                };
+                Some((o.operator.as_str(), precedence))
+            }
+            _ => None,
+        }
+    }
+
+    fn expression(&mut self, compiler: &mut Compiler, min_precedence: u8) -> Option<()> {
+        self.unary(compiler)?;
+        while let Some((operator, precedence)) = self.infix() {
+            if precedence <= min_precedence {
+                break;
+            }
+            self.position += 1;
+            match operator {
+                // the right side is only evaluated when the left side is null
+                "??" => {
+                    let jump = compiler.emit(Op::JumpIfNotNull(0));
+                    self.expression(compiler, precedence)?;
+                    compiler.patch(jump);
+                }
+                "&&" | "||" => {
+                    compiler.emit(Op::ToBool);
+                    let jump = if operator == "&&" {
+                        compiler.emit(Op::JumpIfFalseOrPop(0))
+                    } else {
+                        compiler.emit(Op::JumpIfTrueOrPop(0))
+                    };
+                    self.expression(compiler, precedence)?;
+                    compiler.emit(Op::ToBool);
+                    compiler.patch(jump);
+                }
+                _ => {
+                    self.expression(compiler, precedence)?;
+                    compiler.emit(Op::Binary(BinaryOp::from_operator(operator)?));
+                }
+            }
+        }
+        Some(())
+    }
+
+    fn unary(&mut self, compiler: &mut Compiler) -> Option<()> {
+        match self.peek()? {
+            ASTNode::Operator(o) if o.operator == "-" => {
+                self.position += 1;
+                self.unary(compiler)?;
+                compiler.emit(Op::Negate);
+                Some(())
+            }
+            ASTNode::Operator(o) if o.operator == "!" => {
+                self.position += 1;
+                self.unary(compiler)?;
+                compiler.emit(Op::Not);
+                Some(())
+            }
+            _ => self.primary(compiler),
+        }
+    }
+
+    fn primary(&mut self, compiler: &mut Compiler) -> Option<()> {
+        self.value(compiler)?;
+        // a postfix ! unwraps a nullable value
+        while let Some(ASTNode::Operator(o)) = self.peek() {
+            if o.operator != "!" {
+                break;
+            }
+            self.position += 1;
+            compiler.emit(Op::Unwrap);
+        }
+        Some(())
+    }
+
+    fn value(&mut self, compiler: &mut Compiler) -> Option<()> {
+        let value = match self.peek()? {
+            ASTNode::Int(n) => BaseTypes::Int(n.value),
+            ASTNode::Float(n) => BaseTypes::Float(n.value.into()),
+            ASTNode::String(s) => BaseTypes::StringWrapper(s.value.clone()),
+            ASTNode::Char(c) => BaseTypes::Char(c.value),
+            ASTNode::Bool(b) => BaseTypes::Bool(b.value),
+            ASTNode::Null => BaseTypes::Null,
+            ASTNode::VariableCall(v) => {
+                self.position += 1;
+                compiler.variable(v.symbol, Op::LoadSlot, Op::LoadGlobal);
+                return Some(());
+            }
+            ASTNode::FunctionCall(_) => {
+                let start = (self.position, compiler.here());
+                if self.call(compiler).is_some() {
+                    return Some(());
+                }
+                // named arguments are left to the tree walk
+                self.position = start.0;
+                compiler.chunk.code.truncate(start.1);
+                let nodes = self.call_nodes()?.to_vec();
+                let statement = compiler.chunk.statement(nodes);
+                compiler.emit(Op::Evaluate(statement));
+                return Some(());
+            }
+            ASTNode::Dot(_) => {
+                let statement = compiler.chunk.statement(vec![self.next()?.clone()]);
+                compiler.emit(Op::Evaluate(statement));
+                return Some(());
+            }
+            ASTNode::LeftParenthesis => {
+                self.position += 1;
+                self.expression(compiler, 0)?;
+                return match self.next()? {
+                    ASTNode::RightParenthesis => Some(()),
+                    _ => None,
+                };
+            }
+            _ => return None,
+        };
+        self.position += 1;
+        let constant = compiler.chunk.constant(value);
+        compiler.emit(Op::Constant(constant));
+        Some(())
+    }
+
+    /// The nodes of a call from its name to its closing parenthesis
+    fn call_nodes(&mut self) -> Option<&'a [ASTNode]> {
+        let start = self.position;
+        self.position += 1;
+        let mut depth = 0;
+        loop {
+            match self.next()? {
+                ASTNode::LeftParenthesis => depth += 1,
+                ASTNode::RightParenthesis if depth == 1 => break,
+                ASTNode::RightParenthesis => depth -= 1,
+                _ if depth == 0 => return None,
+                _ => {}
+            }
+        }
+        Some(&self.nodes[start..self.position])
+    }
+
+    /// A call with positional arguments, arguments the tokenizer keeps as text are parsed here
+    fn call(&mut self, compiler: &mut Compiler) -> Option<()> {
+        let name = match self.next()? {
+            ASTNode::FunctionCall(f) => f.name.clone(),
+            _ => return None,
+        };
+        if !matches!(self.next()?, ASTNode::LeftParenthesis) {
+            return None;
+        }
+        let mut argc = 0;
+        loop {
+            match self.peek()? {
+                ASTNode::RightParenthesis => {
+                    self.position += 1;
+                    break;
+                }
+                ASTNode::ArgumentSeparator => self.position += 1,
+                ASTNode::FunctionArguments(argument) => {
+                    self.position += 1;
+                    if argument.value.contains(':') {
+                        return None;
+                    }
+                    let nodes = to_nodes(&argument.value);
+                    let mut parser = ExpressionParser::new(&nodes);
+                    parser.expression(compiler, 0)?;
+                    parser.at_end().then_some(())?;
+                    argc += 1;
+                }
+                _ => {
+                    self.expression(compiler, 0)?;
+                    argc += 1;
+                }
+            }
+        }
+        let function = compiler.chunk.function(&name);
+        compiler.emit(Op::Call { function, argc });
+        Some(())
+    }
+}
+
+#[cfg(test)]
+mod bytecode_tests {
+    use super::*;
+    use crate::node::nodes::{IntNode, OperatorNode, VariableCallNode};
+
+    fn int(value: i32) -> ASTNode {
+        ASTNode::Int(IntNode { value })
+    }
+
+    fn operator(operator: &str) -> ASTNode {
+        ASTNode::Operator(OperatorNode {
+            operator: operator.to_string(),
+        })
+    }
+
+    fn variable(name: &str) -> ASTNode {
+        ASTNode::VariableCall(VariableCallNode::new(name.to_string(), intern(name)))
+    }
+
+    #[test]
+    fn test_compile_precedence() {
+        // a + 2 * 3
+        let nodes = vec![variable("a"), operator("+"), int(2), operator("*"), int(3)];
+        let chunk = compile_expression(&nodes).unwrap();
+        assert_eq!(
+            chunk.code,
+            vec![
+                Op::LoadGlobal(0),
+                Op::Constant(0),
+                Op::Constant(1),
+                Op::Binary(BinaryOp::Mul),
+                Op::Binary(BinaryOp::Add),
+            ]
+        );
+        assert_eq!(chunk.globals, vec![intern("a")]);
+    }
+
+    #[test]
+    fn test_compile_parentheses_and_unary() {
+        // -(1 - 2)
+        let nodes = vec![
+            operator("-"),
+            ASTNode::LeftParenthesis,
+            int(1),
+            operator("-"),
+            int(2),
+            ASTNode::RightParenthesis,
+        ];
+        let chunk = compile_expression(&nodes).unwrap();
+        assert_eq!(
+            chunk.code,
+            vec![
+                Op::Constant(0),
+                Op::Constant(1),
+                Op::Binary(BinaryOp::Sub),
+                Op::Negate,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compile_short_circuit() {
+        let nodes = vec![variable("a"), operator("&&"), variable("b")];
+        let chunk = compile_expression(&nodes).unwrap();
+        assert_eq!(
+            chunk.code,
+            vec![
+                Op::LoadGlobal(0),
+                Op::ToBool,
+                Op::JumpIfFalseOrPop(5),
+                Op::LoadGlobal(1),
+                Op::ToBool,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unknown_operator_is_not_lowered() {
+        let nodes = vec![variable("a"), operator("%"), int(1)];
+        assert!(compile_expression(&nodes).is_none());
+    }
+
+    #[test]
+    fn test_compile_null_coalescing() {
+        let nodes = vec![variable("a"), operator("??"), int(1)];
+        let chunk = compile_expression(&nodes).unwrap();
+        assert_eq!(
+            chunk.code,
+            vec![Op::LoadGlobal(0), Op::JumpIfNotNull(3), Op::Constant(0)]
+        );
+    }
+
+    #[test]
+    fn test_declaring_loop_is_compiled() {
+        let lines = vec!["while (a < 3) {\n    let b: int = 1;\n}".to_string()];
+        let chunk = compile_program(lines, "").unwrap();
+        assert!(chunk.code.contains(&Op::EnterScope));
+        assert!(chunk.code.contains(&Op::Declare(0)));
+        assert!(!chunk.code.iter().any(|op| matches!(op, Op::Interpret(_))));
+    }
+
+    #[test]
+    fn test_declared_variables_get_slots() {
+        let lines = vec![
+            "let a: int = 1;".to_string(),
+            "a = a + b;".to_string(),
+            "while (a < 3) {\n    let c: int = a;\n    a++;\n}".to_string(),
+            "let d: int = 2;".to_string(),
+        ];
+        let chunk = compile_program(lines, "").unwrap();
+        assert!(chunk.code.contains(&Op::LoadSlot(0)));
+        assert!(chunk.code.contains(&Op::StoreSlot(0)));
+        assert!(chunk.code.contains(&Op::IncrementSlot(0)));
+        // only b is not declared by the program
+        assert_eq!(chunk.globals, vec![intern("b")]);
+        // the slot of c is free again once the loop body ends
+        let slots: Vec<usize> = chunk.declarations.iter().map(|d| d.slot).collect();
+        assert_eq!(slots, vec![0, 1, 1]);
+        assert_eq!(chunk.locals, 2);
+    }
+
+    #[test]
+    fn test_parameters_take_the_first_slots() {
+        let lines = vec![
+            "func scale(n: int, by: int) -> int {\n    let m: int = n * by;\n    return m + total;\n}"
+                .to_string(),
+        ];
+        let chunk = compile_program(lines, "").unwrap();
+        let body = &chunk.bodies[0];
+        assert_eq!(body.parameters, 2);
+        assert_eq!(body.declarations[0].slot, 2);
+        assert!(body.code.contains(&Op::LoadSlot(0)));
+        assert!(body.code.contains(&Op::LoadSlot(1)));
+        assert!(body.code.contains(&Op::LoadSlot(2)));
+        assert_eq!(body.globals, vec![intern("total")]);
+    }
+
+    #[test]
+    fn test_return_outside_function() {
+        let lines = vec!["return 1;".to_string()];
+        assert_eq!(
+            compile_program(lines, "").unwrap_err().to_string(),
+            "Syntax Error: return outside of a function"
+        );
+    }
+}
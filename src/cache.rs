@@ -0,0 +1,828 @@
+/*
+* This file contains the .jistc cache format. A .jistc file holds the bytecode of a program, its
+* instructions, constant pool, slot names, pub and bench names and the parsed statements kept for
+* the tree walk, so running it skips reading, splitting and compiling the source. Names are stored
+* as text and interned again when the file is read. Files are built with jist build and every .jist
+* file run is cached by the hash of its source
+*/
+
+use crate::base_variable::base_types::BaseTypes;
+use crate::bytecode::{BinaryOp, Chunk, Declaration, Op};
+use crate::engine::{compile_lines, split_source};
+use crate::globals::NO_CACHE;
+use crate::intern::intern;
+use crate::module::ImportStatement;
+use crate::node::nodes::*;
+use crate::optimizer;
+use crate::output;
+use crate::vm;
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// The first bytes of every .jistc file
+pub const MAGIC: &[u8; 5] = b"JISTC";
+
+/// The version of the binary format, files with another version are refused by jist and replaced
+/// in the automatic cache
+pub const FORMAT_VERSION: u16 = 4;
+
+/// Environment variable holding the directory the automatic cache is kept in
+pub const CACHE_DIR_VARIABLE: &str = "JIST_CACHE_DIR";
+
+/// A compiled program
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompiledScript {
+    /// The version of jist that built the file, programs compile differently between versions
+    pub jist_version: String,
+    /// The hash of the source the program was compiled from
+    pub source_hash: u64,
+    /// The source file the script was built from, empty when it was not built from a file
+    pub source_path: String,
+    /// The bytecode, its path is replaced by the file it is run from when it is loaded
+    pub chunk: Chunk,
+}
+
+impl CompiledScript {
+    /// Compile program source to the bytecode of a compiled script
+    ///
+    /// params: source: &str -> The program source
+    /// params: source_path: &str -> Where the source was read from
+    ///
+    /// returns: Result<CompiledScript, Box<dyn Error>> -> The script or the error from compiling
+    pub fn compile(source: &str, source_path: &str) -> Result<CompiledScript, Box<dyn Error>> {
+        Ok(CompiledScript {
+            jist_version: env!("CARGO_PKG_VERSION").to_string(),
+            source_hash: hash_source(source),
+            source_path: source_path.to_string(),
+            chunk: compile_lines(split_source(source)?, source_path)?,
+        })
+    }
+
+    /// Write the script in the .jistc format, the header is followed by the bytecode and a
+    /// checksum of everything before it
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = Writer::default();
+        writer.raw(MAGIC);
+        writer.raw(&FORMAT_VERSION.to_le_bytes());
+        writer.text(&self.jist_version);
+        writer.raw(&self.source_hash.to_le_bytes());
+        writer.text(&self.source_path);
+        writer.chunk(&self.chunk);
+        let checksum = fnv1a(&writer.bytes);
+        writer.raw(&checksum.to_le_bytes());
+        writer.bytes
+    }
+
+    /// Read a script written by to_bytes
+    ///
+    /// params: bytes: &[u8] -> The contents of a .jistc file
+    ///
+    /// returns: Result<CompiledScript, Box<dyn Error>> -> The script or an error when the file
+    /// is not a .jistc file, was built by another version or is corrupt
+    pub fn from_bytes(bytes: &[u8]) -> Result<CompiledScript, Box<dyn Error>> {
+        if bytes.len() < MAGIC.len() + 2 || &bytes[..MAGIC.len()] != MAGIC {
+            return Err("Cache error: not a .jistc file".into());
+        }
+        let mut reader = Reader {
+            bytes,
+            position: MAGIC.len(),
+        };
+        let version = u16::from_le_bytes(reader.take()?);
+        if version != FORMAT_VERSION {
+            return Err(format!(
+                "Cache error: the file has format version {} but this jist reads version {}, \
+                 rebuild it with jist build",
+                version, FORMAT_VERSION
+            )
+            .into());
+        }
+        if bytes.len() < reader.position + 8 {
+            return Err("Cache error: the file is corrupt".into());
+        }
+        let (body, checksum) = bytes.split_at(bytes.len() - 8);
+        if fnv1a(body) != u64::from_le_bytes(checksum.try_into().unwrap()) {
+            return Err("Cache error: the file is corrupt".into());
+        }
+        reader.bytes = body;
+
+        let jist_version = reader.text()?;
+        if jist_version != env!("CARGO_PKG_VERSION") {
+            return Err(format!(
+                "Cache error: the file was built by jist {} but this is jist {}, rebuild it with \
+                 jist build",
+                jist_version,
+                env!("CARGO_PKG_VERSION")
+            )
+            .into());
+        }
+        let source_hash = u64::from_le_bytes(reader.take()?);
+        let source_path = reader.text()?;
+        let chunk = reader.chunk()?;
+        if reader.position != body.len() {
+            return Err("Cache error: the file is corrupt".into());
+        }
+        Ok(CompiledScript {
+            jist_version,
+            source_hash,
+            source_path,
+            chunk,
+        })
+    }
+}
+
+/// The operators in the order their number is written
+const BINARY_OPS: [BinaryOp; 10] = [
+    BinaryOp::Add,
+    BinaryOp::Sub,
+    BinaryOp::Mul,
+    BinaryOp::Div,
+    BinaryOp::Eq,
+    BinaryOp::Ne,
+    BinaryOp::Lt,
+    BinaryOp::Le,
+    BinaryOp::Gt,
+    BinaryOp::Ge,
+];
+
+/// Writes the fields of a .jistc file, lists start with their length
+#[derive(Default)]
+struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    fn raw(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    fn tag(&mut self, tag: u8) {
+        self.bytes.push(tag);
+    }
+
+    fn index(&mut self, index: usize) {
+        self.raw(&(index as u32).to_le_bytes());
+    }
+
+    fn text(&mut self, text: &str) {
+        self.index(text.len());
+        self.raw(text.as_bytes());
+    }
+
+    fn list<T>(&mut self, items: &[T], mut write: impl FnMut(&mut Writer, &T)) {
+        self.index(items.len());
+        for item in items {
+            write(self, item);
+        }
+    }
+
+    fn texts(&mut self, texts: &[String]) {
+        self.list(texts, |writer, text| writer.text(text));
+    }
+
+    fn chunk(&mut self, chunk: &Chunk) {
+        self.list(&chunk.code, Writer::op);
+        self.list(&chunk.constants, Writer::constant);
+        self.list(&chunk.globals, |writer, global| writer.text(&global.name()));
+        self.index(chunk.locals);
+        self.index(chunk.parameters);
+        self.texts(&chunk.functions);
+        self.list(&chunk.statements, |writer, nodes| {
+            writer.list(nodes, Writer::node)
+        });
+        self.list(&chunk.declarations, |writer, declaration| {
+            writer.text(&declaration.name);
+            writer.text(&declaration.type_name);
+            writer.index(declaration.slot);
+        });
+        self.list(&chunk.imports, Writer::import);
+        self.texts(&chunk.public);
+        self.texts(&chunk.benches);
+        self.list(&chunk.bodies, |writer, body| writer.chunk(body));
+        self.text(&chunk.path);
+    }
+
+    fn op(&mut self, op: &Op) {
+        match op {
+            Op::Constant(index) => self.indexed(0, *index),
+            Op::LoadSlot(slot) => self.indexed(1, *slot),
+            Op::StoreSlot(slot) => self.indexed(2, *slot),
+            Op::IncrementSlot(slot) => self.indexed(3, *slot),
+            Op::DecrementSlot(slot) => self.indexed(4, *slot),
+            Op::Binary(op) => {
+                self.tag(5);
+                self.tag(BINARY_OPS.iter().position(|o| o == op).unwrap() as u8);
+            }
+            Op::Not => self.tag(6),
+            Op::Negate => self.tag(7),
+            Op::ToBool => self.tag(8),
+            Op::Call { function, argc } => {
+                self.indexed(9, *function);
+                self.index(*argc);
+            }
+            Op::Pop => self.tag(10),
+            Op::Jump(to) => self.indexed(11, *to),
+            Op::JumpIfFalse(to) => self.indexed(12, *to),
+            Op::JumpIfFalseOrPop(to) => self.indexed(13, *to),
+            Op::JumpIfTrueOrPop(to) => self.indexed(14, *to),
+            Op::Step => self.tag(15),
+            Op::Condition(statement) => self.indexed(16, *statement),
+            Op::Interpret(statement) => self.indexed(17, *statement),
+            Op::Evaluate(statement) => self.indexed(18, *statement),
+            Op::Declare(declaration) => self.indexed(19, *declaration),
+            Op::JumpIfNotNull(to) => self.indexed(20, *to),
+            Op::Unwrap => self.tag(21),
+            Op::EnterScope => self.tag(22),
+            Op::ExitScope => self.tag(23),
+            Op::Function { statement, body } => {
+                self.indexed(24, *statement);
+                self.index(*body);
+            }
+            Op::Return => self.tag(25),
+            Op::Try(to) => self.indexed(26, *to),
+            Op::EndTry => self.tag(27),
+            Op::Import(import) => self.indexed(28, *import),
+            Op::LoadGlobal(global) => self.indexed(29, *global),
+            Op::StoreGlobal(global) => self.indexed(30, *global),
+            Op::IncrementGlobal(global) => self.indexed(31, *global),
+            Op::DecrementGlobal(global) => self.indexed(32, *global),
+            Op::Bind(slot) => self.indexed(33, *slot),
+        }
+    }
+
+    fn indexed(&mut self, tag: u8, index: usize) {
+        self.tag(tag);
+        self.index(index);
+    }
+
+    fn constant(&mut self, value: &BaseTypes) {
+        match value {
+            BaseTypes::Int(value) => {
+                self.tag(0);
+                self.raw(&value.to_le_bytes());
+            }
+            BaseTypes::Float(value) => {
+                self.tag(1);
+                self.raw(&value.to_le_bytes());
+            }
+            BaseTypes::StringWrapper(value) => {
+                self.tag(2);
+                self.text(value);
+            }
+            BaseTypes::Bool(value) => {
+                self.tag(3);
+                self.tag(*value as u8);
+            }
+            BaseTypes::Char(value) => {
+                self.tag(4);
+                self.raw(&(*value as u32).to_le_bytes());
+            }
+            BaseTypes::Null => self.tag(5),
+            other => unreachable!("the constant pool only holds literals, not {:?}", other),
+        }
+    }
+
+    fn import(&mut self, import: &ImportStatement) {
+        match import {
+            ImportStatement::Namespace { path, alias } => {
+                self.tag(0);
+                self.text(path);
+                self.text(alias);
+            }
+            ImportStatement::Names { path, names } => {
+                self.tag(1);
+                self.text(path);
+                self.texts(names);
+            }
+        }
+    }
+
+    fn node(&mut self, node: &ASTNode) {
+        match node {
+            ASTNode::Return(n) => self.tagged_text(0, &n.value),
+            ASTNode::ObjectCall(n) => self.tagged_text(1, &n.name),
+            ASTNode::CollectionCall(n) => self.tagged_text(2, &n.name),
+            ASTNode::Dot(n) => {
+                self.tagged_text(3, &n.object);
+                self.text(&n.function);
+            }
+            ASTNode::While(n) => {
+                self.tagged_text(4, &n.condition);
+                self.texts(&n.block);
+            }
+            ASTNode::For(n) => {
+                self.tagged_text(5, &n.variable);
+                self.raw(&n.iterable.0.to_le_bytes());
+                self.raw(&n.iterable.1.to_le_bytes());
+                self.texts(&n.block);
+            }
+            ASTNode::If(n) => self.tagged_text(6, &n.condition),
+            ASTNode::Elif(n) => self.tagged_text(7, &n.condition),
+            ASTNode::Else => self.tag(8),
+            ASTNode::Try(n) => {
+                self.tag(9);
+                self.texts(&n.block);
+            }
+            ASTNode::Catch(n) => {
+                self.tag(10);
+                self.texts(&n.block);
+            }
+            ASTNode::Finally(n) => {
+                self.tag(11);
+                self.texts(&n.block);
+            }
+            ASTNode::SemiColon => self.tag(12),
+            ASTNode::Operator(n) => self.tagged_text(13, &n.operator),
+            ASTNode::Int(n) => {
+                self.tag(14);
+                self.raw(&n.value.to_le_bytes());
+            }
+            ASTNode::String(n) => self.tagged_text(15, &n.value),
+            ASTNode::Char(n) => {
+                self.tag(16);
+                self.raw(&(n.value as u32).to_le_bytes());
+            }
+            ASTNode::Bool(n) => {
+                self.tag(17);
+                self.tag(n.value as u8);
+            }
+            ASTNode::Float(n) => {
+                self.tag(18);
+                self.raw(&f64::from(n.value).to_le_bytes());
+            }
+            ASTNode::Null => self.tag(19),
+            ASTNode::Assignment(n) => self.tagged_text(20, &n.value),
+            ASTNode::VarTypeAssignment(n) => self.tagged_text(21, &n.value),
+            ASTNode::Variable(n) => {
+                self.tagged_text(22, &n.var_type);
+                self.text(&n.value);
+            }
+            ASTNode::Function(n) => {
+                self.tagged_text(23, &n.name);
+                self.text(&n.return_type);
+                self.list(&n.arguments, |writer, (name, type_name, default)| {
+                    writer.text(name);
+                    writer.text(type_name);
+                    writer.text(default);
+                });
+                self.texts(&n.block);
+            }
+            ASTNode::FunctionCall(n) => self.tagged_text(24, &n.name),
+            // the symbol is interned again from the name when the file is read
+            ASTNode::VariableCall(n) => self.tagged_text(25, &n.name),
+            ASTNode::VariableType(n) => self.tagged_text(26, &n.value),
+            ASTNode::VariableValue(n) => self.tagged_text(27, &n.value),
+            ASTNode::FunctionArguments(n) => self.tagged_text(28, &n.value),
+            ASTNode::AssignmentOperator(n) => self.tagged_text(29, &n.operator),
+            ASTNode::ReturnTypeAssignment(n) => self.tagged_text(30, &n.value),
+            ASTNode::Comment(n) => self.tagged_text(31, &n.value),
+            ASTNode::FunctionCallArguments(n) => self.tagged_text(32, &n.value),
+            ASTNode::Collection(n) => {
+                self.tagged_text(33, &n.name);
+                self.text(&n.collection_type);
+                match &n.value_type_tuple {
+                    Some((key, value)) => {
+                        self.tagged_text(1, key);
+                        self.text(value);
+                    }
+                    None => self.tag(0),
+                }
+                match &n.value_type_single {
+                    Some(value) => self.tagged_text(1, value),
+                    None => self.tag(0),
+                }
+            }
+            ASTNode::LeftBracket => self.tag(34),
+            ASTNode::RightBracket => self.tag(35),
+            ASTNode::LeftParenthesis => self.tag(36),
+            ASTNode::RightParenthesis => self.tag(37),
+            ASTNode::ArgumentSeparator => self.tag(38),
+            ASTNode::LeftCurly => self.tag(39),
+            ASTNode::RightCurly => self.tag(40),
+            ASTNode::FatArrow => self.tag(41),
+            ASTNode::None => self.tag(42),
+        }
+    }
+
+    fn tagged_text(&mut self, tag: u8, text: &str) {
+        self.tag(tag);
+        self.text(text);
+    }
+}
+
+/// Reads the fields of a .jistc file, running past the end means the file is corrupt
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], Box<dyn Error>> {
+        let end = self.position + N;
+        let field = self.bytes.get(self.position..end).ok_or_else(corrupt)?;
+        self.position = end;
+        Ok(field.try_into().unwrap())
+    }
+
+    fn tag(&mut self) -> Result<u8, Box<dyn Error>> {
+        Ok(self.take::<1>()?[0])
+    }
+
+    fn index(&mut self) -> Result<usize, Box<dyn Error>> {
+        Ok(u32::from_le_bytes(self.take()?) as usize)
+    }
+
+    fn int(&mut self) -> Result<i32, Box<dyn Error>> {
+        Ok(i32::from_le_bytes(self.take()?))
+    }
+
+    fn float(&mut self) -> Result<f64, Box<dyn Error>> {
+        Ok(f64::from_le_bytes(self.take()?))
+    }
+
+    fn bool(&mut self) -> Result<bool, Box<dyn Error>> {
+        match self.tag()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(corrupt()),
+        }
+    }
+
+    fn char(&mut self) -> Result<char, Box<dyn Error>> {
+        char::from_u32(u32::from_le_bytes(self.take()?)).ok_or_else(corrupt)
+    }
+
+    fn text(&mut self) -> Result<String, Box<dyn Error>> {
+        let length = self.index()?;
+        let end = self.position + length;
+        let field = self.bytes.get(self.position..end).ok_or_else(corrupt)?;
+        self.position = end;
+        String::from_utf8(field.to_vec()).map_err(|_| corrupt())
+    }
+
+    fn list<T>(
+        &mut self,
+        mut read: impl FnMut(&mut Self) -> Result<T, Box<dyn Error>>,
+    ) -> Result<Vec<T>, Box<dyn Error>> {
+        let count = self.index()?;
+        let mut items = Vec::new();
+        for _ in 0..count {
+            items.push(read(self)?);
+        }
+        Ok(items)
+    }
+
+    fn texts(&mut self) -> Result<Vec<String>, Box<dyn Error>> {
+        self.list(Reader::text)
+    }
+
+    fn chunk(&mut self) -> Result<Chunk, Box<dyn Error>> {
+        Ok(Chunk {
+            code: self.list(Reader::op)?,
+            constants: self.list(Reader::constant)?,
+            globals: self.list(|reader| Ok(intern(&reader.text()?)))?,
+            locals: self.index()?,
+            parameters: self.index()?,
+            functions: self.texts()?,
+            statements: self.list(|reader| reader.list(Reader::node))?,
+            declarations: self.list(|reader| {
+                Ok(Declaration {
+                    name: reader.text()?,
+                    type_name: reader.text()?,
+                    slot: reader.index()?,
+                })
+            })?,
+            imports: self.list(Reader::import)?,
+            public: self.texts()?,
+            benches: self.texts()?,
+            bodies: self.list(|reader| Ok(Arc::new(reader.chunk()?)))?,
+            path: self.text()?,
+        })
+    }
+
+    fn op(&mut self) -> Result<Op, Box<dyn Error>> {
+        Ok(match self.tag()? {
+            0 => Op::Constant(self.index()?),
+            1 => Op::LoadSlot(self.index()?),
+            2 => Op::StoreSlot(self.index()?),
+            3 => Op::IncrementSlot(self.index()?),
+            4 => Op::DecrementSlot(self.index()?),
+            5 => Op::Binary(*BINARY_OPS.get(self.tag()? as usize).ok_or_else(corrupt)?),
+            6 => Op::Not,
+            7 => Op::Negate,
+            8 => Op::ToBool,
+            9 => Op::Call {
+                function: self.index()?,
+                argc: self.index()?,
+            },
+            10 => Op::Pop,
+            11 => Op::Jump(self.index()?),
+            12 => Op::JumpIfFalse(self.index()?),
+            13 => Op::JumpIfFalseOrPop(self.index()?),
+            14 => Op::JumpIfTrueOrPop(self.index()?),
+            15 => Op::Step,
+            16 => Op::Condition(self.index()?),
+            17 => Op::Interpret(self.index()?),
+            18 => Op::Evaluate(self.index()?),
+            19 => Op::Declare(self.index()?),
+            20 => Op::JumpIfNotNull(self.index()?),
+            21 => Op::Unwrap,
+            22 => Op::EnterScope,
+            23 => Op::ExitScope,
+            24 => Op::Function {
+                statement: self.index()?,
+                body: self.index()?,
+            },
+            25 => Op::Return,
+            26 => Op::Try(self.index()?),
+            27 => Op::EndTry,
+            28 => Op::Import(self.index()?),
+            29 => Op::LoadGlobal(self.index()?),
+            30 => Op::StoreGlobal(self.index()?),
+            31 => Op::IncrementGlobal(self.index()?),
+            32 => Op::DecrementGlobal(self.index()?),
+            33 => Op::Bind(self.index()?),
+            _ => return Err(corrupt()),
+        })
+    }
+
+    fn constant(&mut self) -> Result<BaseTypes, Box<dyn Error>> {
+        Ok(match self.tag()? {
+            0 => BaseTypes::Int(self.int()?),
+            1 => BaseTypes::Float(self.float()?),
+            2 => BaseTypes::StringWrapper(self.text()?),
+            3 => BaseTypes::Bool(self.bool()?),
+            4 => BaseTypes::Char(self.char()?),
+            5 => BaseTypes::Null,
+            _ => return Err(corrupt()),
+        })
+    }
+
+    fn import(&mut self) -> Result<ImportStatement, Box<dyn Error>> {
+        Ok(match self.tag()? {
+            0 => ImportStatement::Namespace {
+                path: self.text()?,
+                alias: self.text()?,
+            },
+            1 => ImportStatement::Names {
+                path: self.text()?,
+                names: self.texts()?,
+            },
+            _ => return Err(corrupt()),
+        })
+    }
+
+    fn optional_text(&mut self) -> Result<Option<String>, Box<dyn Error>> {
+        match self.bool()? {
+            true => Ok(Some(self.text()?)),
+            false => Ok(None),
+        }
+    }
+
+    fn node(&mut self) -> Result<ASTNode, Box<dyn Error>> {
+        Ok(match self.tag()? {
+            0 => ASTNode::Return(ReturnNode::new(self.text()?)),
+            1 => ASTNode::ObjectCall(ObjectCallNode::new(self.text()?)),
+            2 => ASTNode::CollectionCall(CollectionCallNode::new(self.text()?)),
+            3 => ASTNode::Dot(DotNode::new(self.text()?, self.text()?)),
+            4 => ASTNode::While(WhileNode::new(self.text()?, self.texts()?)),
+            5 => ASTNode::For(ForNode::new(
+                self.text()?,
+                (self.int()?, self.int()?),
+                self.texts()?,
+            )),
+            6 => ASTNode::If(IfNode::new(self.text()?)),
+            7 => ASTNode::Elif(ElifNode::new(self.text()?)),
+            8 => ASTNode::Else,
+            9 => ASTNode::Try(TryNode::new(self.texts()?)),
+            10 => ASTNode::Catch(CatchNode::new(self.texts()?)),
+            11 => ASTNode::Finally(FinallyNode::new(self.texts()?)),
+            12 => ASTNode::SemiColon,
+            13 => ASTNode::Operator(OperatorNode::new(self.text()?)),
+            14 => ASTNode::Int(IntNode::new(self.int()?)),
+            15 => ASTNode::String(StringNode::new(self.text()?)),
+            16 => ASTNode::Char(CharNode::new(self.char()?)),
+            17 => ASTNode::Bool(BoolNode::new(self.bool()?)),
+            18 => ASTNode::Float(FloatNode::new(self.float()? as f32)),
+            19 => ASTNode::Null,
+            20 => ASTNode::Assignment(AssignmentNode::new(self.text()?)),
+            21 => ASTNode::VarTypeAssignment(VarTypeAssignmentNode::new(self.text()?)),
+            22 => ASTNode::Variable(VariableNode::new(self.text()?, self.text()?)),
+            23 => ASTNode::Function(FunctionNode::new(
+                self.text()?,
+                self.text()?,
+                self.list(|reader| Ok((reader.text()?, reader.text()?, reader.text()?)))?,
+                self.texts()?,
+            )),
+            24 => ASTNode::FunctionCall(FunctionCallNode::new(self.text()?)),
+            25 => {
+                let name = self.text()?;
+                let symbol = intern(&name);
+                ASTNode::VariableCall(VariableCallNode::new(name, symbol))
+            }
+            26 => ASTNode::VariableType(VariableTypeNode::new(self.text()?)),
+            27 => ASTNode::VariableValue(VariableValueNode::new(self.text()?)),
+            28 => ASTNode::FunctionArguments(FunctionArgumentsNode::new(self.text()?)),
+            29 => ASTNode::AssignmentOperator(AssignmentOperatorNode::new(self.text()?)),
+            30 => ASTNode::ReturnTypeAssignment(ReturnTypeAssignmentNode::new(self.text()?)),
+            31 => ASTNode::Comment(CommentNode::new(self.text()?)),
+            32 => ASTNode::FunctionCallArguments(FunctionArgumentsNode::new(self.text()?)),
+            33 => {
+                let name = self.text()?;
+                let collection_type = self.text()?;
+                let value_type_tuple = match self.bool()? {
+                    true => Some((self.text()?, self.text()?)),
+                    false => None,
+                };
+                let value_type_single = self.optional_text()?;
+                ASTNode::Collection(CollectionNode::new(
+                    name,
+                    collection_type,
+                    value_type_tuple,
+                    value_type_single,
+                ))
+            }
+            34 => ASTNode::LeftBracket,
+            35 => ASTNode::RightBracket,
+            36 => ASTNode::LeftParenthesis,
+            37 => ASTNode::RightParenthesis,
+            38 => ASTNode::ArgumentSeparator,
+            39 => ASTNode::LeftCurly,
+            40 => ASTNode::RightCurly,
+            41 => ASTNode::FatArrow,
+            42 => ASTNode::None,
+            _ => return Err(corrupt()),
+        })
+    }
+}
+
+fn corrupt() -> Box<dyn Error> {
+    "Cache error: the file is corrupt".into()
+}
+
+/// FNV-1a, used because it gives the same hash on every platform and version of rust
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// The hash of the source a script was compiled from, the automatic cache is keyed by it and a
+/// changed source makes a .jistc file stale
+pub fn hash_source(source: &str) -> u64 {
+    fnv1a(source.as_bytes())
+}
+
+/// Compile a .jist file to a .jistc file
+///
+/// params: source_path: &str -> The program to compile
+/// params: output_path: &str -> Where the .jistc file is written
+///
+/// returns: Result<CompiledScript, Box<dyn Error>> -> The compiled script
+pub fn build(source_path: &str, output_path: &str) -> Result<CompiledScript, Box<dyn Error>> {
+    let source = fs::read_to_string(source_path)?;
+    let full_path = fs::canonicalize(source_path)?;
+    let script = CompiledScript::compile(&source, &full_path.to_string_lossy())?;
+    fs::write(output_path, script.to_bytes())?;
+    Ok(script)
+}
+
+/// Read a .jistc file, it is stale when the source it was built from has changed since
+///
+/// params: path: &str -> The .jistc file
+///
+/// returns: Result<CompiledScript, Box<dyn Error>> -> The script or why it cannot be run
+pub fn load(path: &str) -> Result<CompiledScript, Box<dyn Error>> {
+    let bytes = fs::read(path)?;
+    let script = CompiledScript::from_bytes(&bytes).map_err(|e| format!("{} ({})", e, path))?;
+    // the source may not be shipped with the .jistc file, then there is nothing to compare
+    if let Ok(source) = fs::read_to_string(&script.source_path) {
+        if hash_source(&source) != script.source_hash {
+            return Err(format!(
+                "Cache error: {} is stale, {} has changed since it was built, rebuild it with \
+                 jist build",
+                path, script.source_path
+            )
+            .into());
+        }
+    }
+    Ok(script)
+}
+
+/// The directory the automatic cache is kept in, JIST_CACHE_DIR or jist-cache in the temp dir
+pub fn cache_dir() -> PathBuf {
+    env::var_os(CACHE_DIR_VARIABLE)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| env::temp_dir().join("jist-cache"))
+}
+
+/// Get the bytecode of a program, a .jistc file is loaded and a .jist file is compiled or read
+/// from the automatic cache. A cache entry that cannot be read is replaced. None leaves the source
+/// to be run as usual, that is with --tree-walk, --no-cache or --emit=opt-ast
+///
+/// params: file_path: &str -> The program file
+///
+/// returns: Result<Option<Chunk>, Box<dyn Error>> -> The bytecode with imports resolved relative
+/// to file_path
+pub fn load_chunk(file_path: &str) -> Result<Option<Chunk>, Box<dyn Error>> {
+    let compiled = Path::new(file_path).extension().and_then(|e| e.to_str()) == Some("jistc");
+    if compiled && !vm::enabled() {
+        return Err(format!(
+            "Cache error: {} holds bytecode and cannot be run with --tree-walk",
+            file_path
+        )
+        .into());
+    }
+    let mut chunk = if compiled {
+        load(file_path)?.chunk
+    } else if !vm::enabled() || unsafe { NO_CACHE } || optimizer::emit_enabled() {
+        return Ok(None);
+    } else {
+        cached_chunk(&fs::read_to_string(file_path)?, file_path)?
+    };
+    chunk.path = file_path.to_string();
+    Ok(Some(chunk))
+}
+
+fn cached_chunk(source: &str, file_path: &str) -> Result<Chunk, Box<dyn Error>> {
+    let cached_path = cache_dir().join(format!("{:016x}.jistc", hash_source(source)));
+    if let Ok(bytes) = fs::read(&cached_path) {
+        match CompiledScript::from_bytes(&bytes) {
+            // the hash is checked again in case two sources share a file name
+            Ok(script) if script.source_hash == hash_source(source) => {
+                output::trace(|| format!("Loaded {} from {}", file_path, cached_path.display()));
+                return Ok(script.chunk);
+            }
+            Ok(_) => {}
+            Err(e) => output::trace(|| format!("Replacing cache entry for {}: {}", file_path, e)),
+        }
+    }
+
+    let script = CompiledScript::compile(source, file_path)?;
+    // a cache that cannot be written only makes the next run slower
+    let written =
+        fs::create_dir_all(cache_dir()).and_then(|_| fs::write(&cached_path, script.to_bytes()));
+    if let Err(e) = written {
+        output::trace(|| format!("Could not cache {}: {}", file_path, e));
+    }
+    Ok(script.chunk)
+}
+
+#[cfg(test)]
+mod cache_tests {
+    use super::*;
+
+    fn script() -> CompiledScript {
+        CompiledScript::compile(
+            "let x: int = 1;\n// one\nfunc twice(n: int) -> int {\n    return n * 2;\n}\n\
+             let values: array<int> = [1, 2];\nwhile (x < 3) {\n    x++;\n}\n\
+             print(twice(x), 'c', 1.5, true);",
+            "",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let script = script();
+        assert!(!script.chunk.code.is_empty());
+        assert!(!script.chunk.statements.is_empty());
+        assert_eq!(script.chunk.bodies.len(), 1);
+        let read = CompiledScript::from_bytes(&script.to_bytes()).unwrap();
+        assert_eq!(read, script);
+    }
+
+    #[test]
+    fn test_rejects_other_format_version() {
+        let mut bytes = script().to_bytes();
+        bytes[MAGIC.len()..MAGIC.len() + 2].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+        let error = CompiledScript::from_bytes(&bytes).unwrap_err().to_string();
+        assert!(error.contains(&format!("format version {}", FORMAT_VERSION + 1)));
+    }
+
+    #[test]
+    fn test_rejects_corrupt_files() {
+        let bytes = script().to_bytes();
+        let mut flipped = bytes.clone();
+        flipped[20] ^= 1;
+        for corrupt in [&flipped[..], &bytes[..bytes.len() - 3]] {
+            let error = CompiledScript::from_bytes(corrupt).unwrap_err().to_string();
+            assert_eq!(error, "Cache error: the file is corrupt");
+        }
+        let error = CompiledScript::from_bytes(b"let x")
+            .unwrap_err()
+            .to_string();
+        assert_eq!(error, "Cache error: not a .jistc file");
+    }
+
+    #[test]
+    fn test_hash_is_stable() {
+        assert_eq!(hash_source(""), 0xcbf29ce484222325);
+        assert_ne!(hash_source("print(1);"), hash_source("print(2);"));
+    }
+}
@@ -1,262 +1,213 @@
-use crate::base_variable::base_types::BaseTypes;
+use crate::base_variable::base_types::{BaseTypes, GetType};
 use crate::collection::collections::Array;
 use crate::collection::collections::Dictionary;
+use crate::collection::entries::DictionaryEntries;
+use crate::output;
+use crate::std_lib::arguments::get_int;
 use lazy_static::lazy_static;
 
-use crate::function::functions::FunctionTypes;
-use std::collections::HashMap;
-use std::sync::Mutex;
+use std::error::Error;
+use std::sync::{Arc, Mutex};
 
 lazy_static! {
     pub static ref ARRAY_STACK: Mutex<Vec<Array>> = Mutex::new(Vec::new());
     pub static ref DICTIONARY_STACK: Mutex<Vec<Dictionary>> = Mutex::new(Vec::new());
 }
 
-lazy_static! {
-    pub static ref ARRAY_FUNCTIONS: Mutex<HashMap<&'static str, FunctionTypes>> = {
-        let mut map = HashMap::new();
-
-        map.insert("push", FunctionTypes::ArrayPushFn(array_push));
-        map.insert("pop", FunctionTypes::ArrayPopFn(array_pop));
-        map.insert("remove", FunctionTypes::ArrayRemoveFn(array_remove));
-        map.insert("get", FunctionTypes::ArrayGetFn(array_get));
-        map.insert("set", FunctionTypes::ArraySetFn(array_set));
-        map.insert("append", FunctionTypes::ArrayAppendFn(array_append));
-        map.insert("print", FunctionTypes::ArrayPrint(array_print));
-
-        map.into()
-    };
-}
-lazy_static! {
-    pub static ref DICTIONARY_FUNCTIONS: Mutex<HashMap<&'static str, FunctionTypes>> = {
-        let mut map = HashMap::new();
-
-        map.insert("add", FunctionTypes::DictionaryAddFn(dictionary_add));
-        map.insert(
-            "remove",
-            FunctionTypes::DictionaryRemoveFn(dictionary_remove),
-        );
-        map.insert("get", FunctionTypes::DictionaryGetFn(dictionary_get));
-        map.insert("set", FunctionTypes::DictionarySetFn(dictionary_set));
-        map.insert("keys", FunctionTypes::DictionaryKeysFn(dictionary_keys));
-        map.insert(
-            "values",
-            FunctionTypes::DictionaryValuesFn(dictionary_values),
-        );
-        map.insert("print", FunctionTypes::DictionaryPrint(dictionary_print));
-        map.into()
-    };
-}
-
+/// Replace the array with the same name on the stack, or push it if there is none
+///
+/// params: array: Array -> The updated array
 pub fn update_array_stack(array: Array) {
-    //remove array from stack then add it back
     let mut array_stack = ARRAY_STACK.lock().unwrap();
-    let mut index = None;
-    for (i, a) in array_stack.iter().enumerate() {
-        if a.name == array.name {
-            index = Some(i);
-            break;
-        }
+    match array_stack.iter_mut().find(|a| a.name == array.name) {
+        Some(existing) => *existing = array,
+        None => array_stack.push(array),
     }
-    if let Some(i) = index {
-        array_stack.remove(i);
-    }
-    array_stack.push(array);
-}
-
-// Function to push an element into the array
-fn array_push(array: &mut Array, value: BaseTypes) {
-    let mut array_functions = ArrayFunctions::Push;
-    array_functions.push(array, value);
 }
 
-fn array_append(array: &mut Array, value: BaseTypes) {
-    let mut array_functions = ArrayFunctions::Append;
-    array_functions.append(array, value);
+/// Replace the dictionary with the same name on the stack, or push it if there is none
+///
+/// params: dictionary: Dictionary -> The updated dictionary
+pub fn update_dictionary_stack(dictionary: Dictionary) {
+    let mut dictionary_stack = DICTIONARY_STACK.lock().unwrap();
+    match dictionary_stack
+        .iter_mut()
+        .find(|d| d.name == dictionary.name)
+    {
+        Some(existing) => *existing = dictionary,
+        None => dictionary_stack.push(dictionary),
+    }
 }
 
-// Function to pop an element from the array
-fn array_pop(array: &mut Array) -> Option<BaseTypes> {
-    let mut array_functions = ArrayFunctions::Pop;
-    array_functions.pop(array)
+/// Call a method on an array on the stack, the shared data is moved into the receiver and back so
+/// a call on a large array does not copy it. Arrays are found in the same order as get_array
+///
+/// params: name: &str -> The name of the array
+/// params: call: impl FnOnce(&mut BaseTypes) -> R -> Calls the method on the receiver
+///
+/// returns: Option<R> -> The result of the call or None when there is no array with the name
+pub fn with_array_receiver<R>(name: &str, call: impl FnOnce(&mut BaseTypes) -> R) -> Option<R> {
+    let data = {
+        let mut array_stack = ARRAY_STACK.lock().unwrap();
+        let array = array_stack.iter_mut().find(|a| a.name == name)?;
+        std::mem::take(&mut array.data)
+    };
+    // the lock is released so the method can reach the stacks itself
+    let mut receiver = BaseTypes::Array(data);
+    let result = call(&mut receiver);
+    if let BaseTypes::Array(data) = receiver {
+        let mut array_stack = ARRAY_STACK.lock().unwrap();
+        if let Some(array) = array_stack.iter_mut().find(|a| a.name == name) {
+            array.data = data;
+        }
+    }
+    Some(result)
 }
 
-// Function to remove an element from the array by index
-fn array_remove(array: &mut Array, index: BaseTypes) {
-    let mut array_functions = ArrayFunctions::Remove;
-    array_functions.remove(array, index.into());
+/// Call a method on a dictionary on the stack without copying its entries, see
+/// with_array_receiver
+pub fn with_dictionary_receiver<R>(
+    name: &str,
+    call: impl FnOnce(&mut BaseTypes) -> R,
+) -> Option<R> {
+    let values = {
+        let mut dictionary_stack = DICTIONARY_STACK.lock().unwrap();
+        let dictionary = dictionary_stack.iter_mut().find(|d| d.name == name)?;
+        std::mem::take(&mut dictionary.values)
+    };
+    let mut receiver = BaseTypes::Dictionary(values);
+    let result = call(&mut receiver);
+    if let BaseTypes::Dictionary(values) = receiver {
+        let mut dictionary_stack = DICTIONARY_STACK.lock().unwrap();
+        if let Some(dictionary) = dictionary_stack.iter_mut().find(|d| d.name == name) {
+            dictionary.values = values;
+        }
+    }
+    Some(result)
 }
 
-// Function to get an element from the array by index
-fn array_get(array: &Array, index: BaseTypes) -> Option<BaseTypes> {
-    let index: usize = index.into();
-    let mut array_functions = ArrayFunctions::Get;
-    array_functions.get(array, index)
+fn array_data<'a>(
+    name: &str,
+    receiver: &'a mut BaseTypes,
+) -> Result<&'a mut Vec<BaseTypes>, Box<dyn Error>> {
+    match receiver {
+        BaseTypes::Array(data) => Ok(Arc::make_mut(data)),
+        other => Err(format!("Type error: {} expects an Array but got {}", name, other.GetType()).into()),
+    }
 }
 
-// Function to set an element in the array at the specified index
-fn array_set(array: &mut Array, index: BaseTypes, value: BaseTypes) -> Option<BaseTypes> {
-    let index: usize = index.into();
-    println!("Index: {}", index);
-    let mut array_functions = ArrayFunctions::Set;
-    array_functions.set(array, index, value)
+fn dictionary_entries<'a>(
+    name: &str,
+    receiver: &'a mut BaseTypes,
+) -> Result<&'a mut DictionaryEntries, Box<dyn Error>> {
+    match receiver {
+        BaseTypes::Dictionary(entries) => Ok(Arc::make_mut(entries)),
+        other => Err(format!("Type error: {} expects a Dictionary but got {}", name, other.GetType()).into()),
+    }
 }
 
-fn array_print(array: &Array) {
-    println!("{}", array);
+/// Read an index argument and check it against the length of the array
+fn array_index(name: &str, args: &[BaseTypes], len: usize) -> Result<usize, Box<dyn Error>> {
+    let index = get_int(name, args, 0)?;
+    if index < 0 || index as usize >= len {
+        return Err(format!(
+            "Index error: {} index {} is out of bounds for an array of length {}",
+            name, index, len
+        )
+        .into());
+    }
+    Ok(index as usize)
 }
 
-pub enum ArrayFunctions {
-    Push,
-    Pop,
-    Append,
-    Remove,
-    Get,
-    Set,
-    Print,
+/// Push a value onto the end of the array
+pub fn array_push(receiver: &mut BaseTypes, args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    array_data("push", receiver)?.push(args[0].clone());
+    Ok(BaseTypes::Null)
 }
 
-impl ArrayFunctions {
-    pub fn push(&mut self, array: &mut Array, value: BaseTypes) {
-        array.push(value.clone());
-        update_array_stack(array.clone());
-    }
-    pub fn pop(&mut self, array: &mut Array) -> Option<BaseTypes> {
-        let result = array.pop();
-        update_array_stack(array.clone());
-        result
-    }
-    pub fn append(&mut self, array: &mut Array, value: BaseTypes) {
-        array.append(value);
-        update_array_stack(array.clone());
-    }
-    pub fn remove(&mut self, array: &mut Array, index: usize) {
-        array.remove(index);
-        update_array_stack(array.clone());
-    }
-    pub fn get(&mut self, array: &Array, index: usize) -> Option<BaseTypes> {
-        array.get(index)
-    }
-    pub fn set(&mut self, array: &mut Array, index: usize, value: BaseTypes) -> Option<BaseTypes> {
-        println!("Index: {}", index);
-        array.set(index, value.clone());
-        update_array_stack(array.clone());
-        Some(value)
-    }
-    pub fn print(&mut self, array: &Array) {
-        println!("{}", array);
-    }
+/// Pop the last value off the array, null when the array is empty
+pub fn array_pop(receiver: &mut BaseTypes, _args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    Ok(array_data("pop", receiver)?.pop().unwrap_or(BaseTypes::Null))
 }
 
-pub enum DictionaryFunctions {
-    Add,
-    Remove,
-    Get,
-    Set,
-    Keys,
-    Values,
-    Print,
+/// Remove the value at an index
+pub fn array_remove(receiver: &mut BaseTypes, args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    let data = array_data("remove", receiver)?;
+    let index = array_index("remove", args, data.len())?;
+    data.remove(index);
+    Ok(BaseTypes::Null)
 }
 
-pub fn update_dictionary_stack(dictionary: Dictionary) {
-    //remove dictionary from stack then add it back
-    let mut dictionary_stack = DICTIONARY_STACK.lock().unwrap();
-    let mut index = None;
-    for (i, d) in dictionary_stack.iter().enumerate() {
-        if d.name == dictionary.name {
-            index = Some(i);
-            break;
-        }
+/// Get the value at an index, null when the index is out of range
+pub fn array_get(receiver: &mut BaseTypes, args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    let data = array_data("get", receiver)?;
+    let index = get_int("get", args, 0)?;
+    if index < 0 {
+        return Ok(BaseTypes::Null);
     }
-    if let Some(i) = index {
-        dictionary_stack.remove(i);
-    }
-    dictionary_stack.push(dictionary);
-}
-
-pub fn dictionary_add(dictionary: &mut Dictionary, key: BaseTypes, value: BaseTypes) {
-    let mut dictionary_functions = DictionaryFunctions::Add;
-    dictionary_functions.add(dictionary, key, value);
+    Ok(data.get(index as usize).cloned().unwrap_or(BaseTypes::Null))
 }
 
-pub fn dictionary_remove(dictionary: &mut Dictionary, key: BaseTypes) {
-    let mut dictionary_functions = DictionaryFunctions::Remove;
-    dictionary_functions.remove(dictionary, key);
+/// Set the value at an index and return it
+pub fn array_set(receiver: &mut BaseTypes, args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    let data = array_data("set", receiver)?;
+    let index = array_index("set", args, data.len())?;
+    data[index] = args[1].clone();
+    Ok(args[1].clone())
 }
 
-pub fn dictionary_get(dictionary: &Dictionary, key: BaseTypes) -> Option<(BaseTypes, BaseTypes)> {
-    let mut dictionary_functions = DictionaryFunctions::Get;
-    dictionary_functions.get(dictionary, key)
+/// Set the value for a key, adding the entry if the key is new
+pub fn dictionary_set(receiver: &mut BaseTypes, args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    dictionary_entries("set", receiver)?.insert(args[0].clone(), args[1].clone())?;
+    Ok(BaseTypes::Null)
 }
 
-pub fn dictionary_set(dictionary: &mut Dictionary, key: BaseTypes, value: BaseTypes) {
-    let mut dictionary_functions = DictionaryFunctions::Set;
-    dictionary_functions.set(dictionary, key, value);
+/// Remove the entry for a key
+pub fn dictionary_remove(receiver: &mut BaseTypes, args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    dictionary_entries("remove", receiver)?.remove(&args[0])?;
+    Ok(BaseTypes::Null)
 }
 
-pub fn dictionary_keys(dictionary: &Dictionary) -> Vec<BaseTypes> {
-    let mut dictionary_functions = DictionaryFunctions::Keys;
-    dictionary_functions.keys(dictionary)
+/// Get the value for a key, null when the key is missing
+pub fn dictionary_get(receiver: &mut BaseTypes, args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    let entries = dictionary_entries("get", receiver)?;
+    Ok(entries.get(&args[0])?.cloned().unwrap_or(BaseTypes::Null))
 }
 
-pub fn dictionary_values(dictionary: &Dictionary) -> Vec<BaseTypes> {
-    let mut dictionary_functions = DictionaryFunctions::Values;
-    dictionary_functions.values(dictionary)
+/// The keys of the dictionary in insertion order
+pub fn dictionary_keys(receiver: &mut BaseTypes, _args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    let entries = dictionary_entries("keys", receiver)?;
+    Ok(BaseTypes::Array(Arc::new(
+        entries.keys().cloned().collect(),
+    )))
 }
 
-pub fn dictionary_print(dictionary: &Dictionary) {
-    println!("{}", dictionary);
+/// The values of the dictionary in insertion order
+pub fn dictionary_values(receiver: &mut BaseTypes, _args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    let entries = dictionary_entries("values", receiver)?;
+    Ok(BaseTypes::Array(Arc::new(
+        entries.values().cloned().collect(),
+    )))
 }
 
-impl DictionaryFunctions {
-    pub fn add(&mut self, dictionary: &mut Dictionary, key: BaseTypes, value: BaseTypes) {
-        println!("Add : {:?}", dictionary.add(key.clone(), value.clone()));
-        dictionary.add(key, value);
-        update_dictionary_stack(dictionary.clone());
-    }
-
-    pub fn remove(&mut self, dictionary: &mut Dictionary, key: BaseTypes) {
-        dictionary.remove(key);
-        update_dictionary_stack(dictionary.clone());
-    }
-
-    pub fn get(
-        &mut self,
-        dictionary: &Dictionary,
-        key: BaseTypes,
-    ) -> Option<(BaseTypes, BaseTypes)> {
-        println!("Get : {:?}", dictionary.get(key.clone()));
-        dictionary.get(key).cloned()
-    }
-
-    pub fn set(&mut self, dictionary: &mut Dictionary, key: BaseTypes, value: BaseTypes) {
-        dictionary.set(key, value);
-        update_dictionary_stack(dictionary.clone());
-    }
-
-    pub fn keys(&mut self, dictionary: &Dictionary) -> Vec<BaseTypes> {
-        println!("Keys : {:?}", dictionary.keys());
-        dictionary.keys().into_iter().cloned().collect()
-    }
-
-    pub fn values(&mut self, dictionary: &Dictionary) -> Vec<BaseTypes> {
-        println!("Values : {:?}", dictionary.values());
-        dictionary.values().into_iter().cloned().collect()
-    }
-
-    pub fn print(&mut self, dictionary: &Dictionary) {
-        println!("{}", dictionary);
-    }
+/// Print an array or dictionary
+pub fn print_receiver(receiver: &mut BaseTypes, _args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    output::println(&receiver.to_string());
+    Ok(BaseTypes::Null)
 }
 
 pub mod collections {
+    use super::entries::DictionaryEntries;
     use crate::base_variable::base_types::BaseTypes;
     use std::fmt;
+    use std::sync::Arc;
 
+    /// An array on the ARRAY_STACK, the data is shared so reading the array does not copy it and
+    /// it is only copied when a shared array is changed
     #[derive(Clone, Debug)]
     pub struct Array {
         pub name: String,
-        pub data: Vec<BaseTypes>,
+        pub data: Arc<Vec<BaseTypes>>,
         pub value_type: BaseTypes,
     }
 
@@ -275,30 +226,34 @@ pub mod collections {
 
     // functions for arrays: new, push, pop, remove, get(i), set(i), to_string
     impl Array {
-        pub fn new(name: String, value_type: BaseTypes, data: Vec<BaseTypes>) -> Array {
+        pub fn new(
+            name: String,
+            value_type: BaseTypes,
+            data: impl Into<Arc<Vec<BaseTypes>>>,
+        ) -> Array {
             Array {
                 name,
-                data,
+                data: data.into(),
                 value_type,
             }
         }
 
         pub fn push(&mut self, value: BaseTypes) {
-            self.data.push(value);
+            Arc::make_mut(&mut self.data).push(value);
         }
 
         pub fn pop(&mut self) -> Option<BaseTypes> {
-            self.data.pop()
+            Arc::make_mut(&mut self.data).pop()
         }
 
         pub fn append(&mut self, value: BaseTypes) {
             // Push the value to the existing data vector
-            self.data.push(value);
+            Arc::make_mut(&mut self.data).push(value);
         }
 
         pub fn remove(&mut self, index: usize) {
             if index < self.data.len() {
-                Some(self.data.remove(index));
+                Some(Arc::make_mut(&mut self.data).remove(index));
             } else {
                 println!("Syntax Error, during removal")
             }
@@ -310,17 +265,22 @@ pub mod collections {
 
         pub fn set(&mut self, index: usize, value: BaseTypes) -> Option<BaseTypes> {
             if index < self.data.len() {
-                Some(std::mem::replace(&mut self.data[index], value))
+                Some(std::mem::replace(
+                    &mut Arc::make_mut(&mut self.data)[index],
+                    value,
+                ))
             } else {
                 None
             }
         }
     }
 
+    /// A dictionary on the DICTIONARY_STACK, the entries are shared like the data of an array and
+    /// kept in a hash map
     #[derive(Clone, Debug)]
     pub struct Dictionary {
         pub name: String,
-        pub values: Vec<(BaseTypes, BaseTypes)>,
+        pub values: Arc<DictionaryEntries>,
         pub types: (BaseTypes, BaseTypes),
     }
 
@@ -348,17 +308,18 @@ pub mod collections {
             name: String,
             key_type: BaseTypes,
             value_type: BaseTypes,
-            values: Vec<(BaseTypes, BaseTypes)>,
+            values: impl Into<Arc<DictionaryEntries>>,
         ) -> Dictionary {
             Dictionary {
                 name,
-                values,
+                values: values.into(),
                 types: (key_type, value_type),
             }
         }
 
-        pub fn add(&mut self, key: BaseTypes, value: BaseTypes) {
-            self.values.push((key, value));
+        pub fn add(&mut self, key: BaseTypes, value: BaseTypes) -> Result<(), String> {
+            Arc::make_mut(&mut self.values).insert(key, value)?;
+            Ok(())
         }
 
         /*pub fn append(&mut self, key: ASTNode, value: ASTNode) {
@@ -366,46 +327,32 @@ pub mod collections {
             self.values.add((key, value));
         } */
 
-        pub fn remove(&mut self, key: BaseTypes) -> Option<(BaseTypes, BaseTypes)> {
-            let mut index = None;
-            for (i, (k, _)) in self.values.iter().enumerate() {
-                if k == &key {
-                    index = Some(i);
-                    break;
-                }
-            }
-            if let Some(i) = index {
-                Some(self.values.remove(i))
-            } else {
-                None
-            }
+        pub fn remove(&mut self, key: BaseTypes) -> Result<Option<BaseTypes>, String> {
+            Arc::make_mut(&mut self.values).remove(&key)
         }
 
-        pub fn get(&self, key: BaseTypes) -> Option<&(BaseTypes, BaseTypes)> {
-            self.values.iter().find(|(k, _)| k == &key)
+        pub fn get(&self, key: BaseTypes) -> Result<Option<&BaseTypes>, String> {
+            self.values.get(&key)
         }
 
-        pub fn set(&mut self, key: BaseTypes, value: BaseTypes) -> Option<BaseTypes> {
-            let mut index = None;
-            for (i, (k, _)) in self.values.iter().enumerate() {
-                if k == &key {
-                    index = Some(i);
-                    break;
-                }
-            }
-            if let Some(i) = index {
-                Some(std::mem::replace(&mut self.values[i].1, value))
-            } else {
-                None
+        /// Set the value for a key that is already in the dictionary
+        pub fn set(
+            &mut self,
+            key: BaseTypes,
+            value: BaseTypes,
+        ) -> Result<Option<BaseTypes>, String> {
+            if self.values.get(&key)?.is_none() {
+                return Ok(None);
             }
+            Arc::make_mut(&mut self.values).insert(key, value)
         }
 
         pub fn keys(&self) -> Vec<&BaseTypes> {
-            self.values.iter().map(|(k, _)| k).collect()
+            self.values.keys().collect()
         }
 
         pub fn values(&self) -> Vec<&BaseTypes> {
-            self.values.iter().map(|(_, v)| v).collect()
+            self.values.values().collect()
         }
 
         /*pub fn to_string(&self) -> String {
@@ -425,4 +372,244 @@ pub mod collections {
                 }
         */
     }
+
+    #[cfg(test)]
+    mod collections_tests {
+        use super::*;
+
+        #[test]
+        fn test_array_copies_shared_data_when_changed() {
+            let mut array = Array::new(
+                "collections_test_a".to_string(),
+                BaseTypes::Int(0),
+                vec![BaseTypes::Int(1), BaseTypes::Int(2)],
+            );
+            let value = BaseTypes::Array(array.data.clone());
+            assert!(matches!(&value, BaseTypes::Array(data) if Arc::ptr_eq(data, &array.data)));
+
+            array.push(BaseTypes::Int(3));
+            assert_eq!(array.data.len(), 3);
+            assert!(matches!(&value, BaseTypes::Array(data) if data.len() == 2));
+        }
+    }
+}
+
+pub mod entries {
+    use crate::base_variable::base_types::{BaseTypes, GetType};
+    use indexmap::IndexMap;
+    use std::hash::{Hash, Hasher};
+    use std::mem;
+
+    /// A dictionary key. Keys are equal when they have the same type and value so 1 and 1.0 are
+    /// different keys, floats compare by value so 0.0 and -0.0 are the same key
+    #[derive(Clone, Debug)]
+    pub struct DictionaryKey(BaseTypes);
+
+    impl DictionaryKey {
+        /// Check that a value can be a key. NaN is never equal to itself and null, regexes and
+        /// collections have no value to hash so they are refused
+        ///
+        /// params: value: BaseTypes -> The key
+        ///
+        /// returns: Result<DictionaryKey, String> -> The key or a type error
+        pub fn new(value: BaseTypes) -> Result<DictionaryKey, String> {
+            match value {
+                BaseTypes::Float(f) if f.is_nan() => {
+                    Err("Type error: NaN cannot be a dictionary key".to_string())
+                }
+                BaseTypes::Int(_)
+                | BaseTypes::Float(_)
+                | BaseTypes::StringWrapper(_)
+                | BaseTypes::Bool(_)
+                | BaseTypes::Char(_)
+                | BaseTypes::DateTime(_)
+                | BaseTypes::Duration(_) => Ok(DictionaryKey(value)),
+                other => Err(format!(
+                    "Type error: {} cannot be a dictionary key",
+                    other.GetType()
+                )),
+            }
+        }
+
+        pub fn value(&self) -> &BaseTypes {
+            &self.0
+        }
+    }
+
+    impl PartialEq for DictionaryKey {
+        fn eq(&self, other: &Self) -> bool {
+            self.0 == other.0
+        }
+    }
+
+    impl Eq for DictionaryKey {}
+
+    impl Hash for DictionaryKey {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            mem::discriminant(&self.0).hash(state);
+            match &self.0 {
+                BaseTypes::Int(i) => i.hash(state),
+                // -0.0 == 0.0 so both hash as 0.0
+                BaseTypes::Float(f) if *f == 0.0 => 0.0f64.to_bits().hash(state),
+                BaseTypes::Float(f) => f.to_bits().hash(state),
+                BaseTypes::StringWrapper(s) => s.hash(state),
+                BaseTypes::Bool(b) => b.hash(state),
+                BaseTypes::Char(c) => c.hash(state),
+                BaseTypes::DateTime(t) => t.hash(state),
+                BaseTypes::Duration(d) => d.hash(state),
+                // DictionaryKey::new refuses every other type
+                _ => {}
+            }
+        }
+    }
+
+    /// The entries of a dictionary. Keys are hashed so get, set and remove do not scan the
+    /// entries, and the entries are iterated in the order they were added
+    #[derive(Clone, Debug, Default, PartialEq)]
+    pub struct DictionaryEntries(IndexMap<DictionaryKey, BaseTypes>);
+
+    impl DictionaryEntries {
+        pub fn new() -> DictionaryEntries {
+            DictionaryEntries::default()
+        }
+
+        /// Build entries from key value pairs, a key given twice keeps its first position and
+        /// its last value
+        ///
+        /// params: pairs: Vec<(BaseTypes, BaseTypes)> -> The pairs in order
+        ///
+        /// returns: Result<DictionaryEntries, String> -> The entries or the error for a key that
+        /// cannot be hashed
+        pub fn from_pairs(pairs: Vec<(BaseTypes, BaseTypes)>) -> Result<DictionaryEntries, String> {
+            let mut entries = DictionaryEntries::new();
+            for (key, value) in pairs {
+                entries.insert(key, value)?;
+            }
+            Ok(entries)
+        }
+
+        pub fn len(&self) -> usize {
+            self.0.len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.0.is_empty()
+        }
+
+        /// Get the value for a key
+        ///
+        /// returns: Result<Option<&BaseTypes>, String> -> The value, None when the key is missing
+        /// or an error when the key cannot be hashed
+        pub fn get(&self, key: &BaseTypes) -> Result<Option<&BaseTypes>, String> {
+            Ok(self.0.get(&DictionaryKey::new(key.clone())?))
+        }
+
+        /// Set the value for a key, a new key is added after the others
+        ///
+        /// returns: Result<Option<BaseTypes>, String> -> The value that was replaced
+        pub fn insert(
+            &mut self,
+            key: BaseTypes,
+            value: BaseTypes,
+        ) -> Result<Option<BaseTypes>, String> {
+            Ok(self.0.insert(DictionaryKey::new(key)?, value))
+        }
+
+        /// Remove the entry for a key, the entries after it move up so the order is kept
+        ///
+        /// returns: Result<Option<BaseTypes>, String> -> The value that was removed
+        pub fn remove(&mut self, key: &BaseTypes) -> Result<Option<BaseTypes>, String> {
+            Ok(self.0.shift_remove(&DictionaryKey::new(key.clone())?))
+        }
+
+        pub fn iter(&self) -> impl Iterator<Item = (&BaseTypes, &BaseTypes)> {
+            self.0.iter().map(|(key, value)| (key.value(), value))
+        }
+
+        pub fn keys(&self) -> impl Iterator<Item = &BaseTypes> {
+            self.0.keys().map(DictionaryKey::value)
+        }
+
+        pub fn values(&self) -> impl Iterator<Item = &BaseTypes> {
+            self.0.values()
+        }
+
+        pub fn first(&self) -> Option<(&BaseTypes, &BaseTypes)> {
+            self.iter().next()
+        }
+    }
+
+    impl IntoIterator for DictionaryEntries {
+        type Item = (BaseTypes, BaseTypes);
+        type IntoIter = std::iter::Map<
+            indexmap::map::IntoIter<DictionaryKey, BaseTypes>,
+            fn((DictionaryKey, BaseTypes)) -> (BaseTypes, BaseTypes),
+        >;
+
+        fn into_iter(self) -> Self::IntoIter {
+            self.0.into_iter().map(|(key, value)| (key.0, value))
+        }
+    }
+
+    #[cfg(test)]
+    mod entries_tests {
+        use super::*;
+
+        fn string(text: &str) -> BaseTypes {
+            BaseTypes::StringWrapper(text.to_string())
+        }
+
+        #[test]
+        fn test_entries_keep_insertion_order() {
+            let mut entries = DictionaryEntries::from_pairs(vec![
+                (string("b"), BaseTypes::Int(1)),
+                (string("a"), BaseTypes::Int(2)),
+                (string("b"), BaseTypes::Int(3)),
+            ])
+            .unwrap();
+            entries.insert(string("c"), BaseTypes::Int(4)).unwrap();
+            entries.remove(&string("a")).unwrap();
+            let keys: Vec<String> = entries.keys().map(|k| k.to_string()).collect();
+            assert_eq!(keys, vec!["b", "c"]);
+            assert_eq!(entries.get(&string("b")).unwrap(), Some(&BaseTypes::Int(3)));
+        }
+
+        #[test]
+        fn test_key_equality() {
+            let keys = [
+                BaseTypes::Bool(true),
+                BaseTypes::Char('a'),
+                BaseTypes::Int(1),
+                BaseTypes::Float(0.0),
+            ];
+            let mut entries = DictionaryEntries::new();
+            for (value, key) in keys.iter().enumerate() {
+                entries
+                    .insert(key.clone(), BaseTypes::Int(value as i32))
+                    .unwrap();
+            }
+            for (value, key) in keys.iter().enumerate() {
+                assert_eq!(entries.get(key).unwrap(), Some(&BaseTypes::Int(value as i32)));
+            }
+            assert_eq!(entries.get(&BaseTypes::Float(-0.0)).unwrap(), Some(&BaseTypes::Int(3)));
+            // 1 and 1.0 are different keys
+            assert_eq!(entries.get(&BaseTypes::Float(1.0)).unwrap(), None);
+            assert_eq!(entries.get(&BaseTypes::Bool(false)).unwrap(), None);
+        }
+
+        #[test]
+        fn test_unhashable_keys() {
+            let mut entries = DictionaryEntries::new();
+            let error = entries
+                .insert(BaseTypes::Float(f64::NAN), BaseTypes::Int(1))
+                .unwrap_err();
+            assert_eq!(error, "Type error: NaN cannot be a dictionary key");
+            let error = entries
+                .get(&BaseTypes::Array(Default::default()))
+                .unwrap_err();
+            assert_eq!(error, "Type error: Array cannot be a dictionary key");
+            assert!(entries.insert(BaseTypes::Null, BaseTypes::Int(1)).is_err());
+            assert!(entries.is_empty());
+        }
+    }
 }
@@ -12,18 +12,38 @@ pub mod compilers {
     use crate::compilers::loops::loop_compilers::{compile_for_loop, compile_while_loop};
     use crate::compilers::variable::operation;
     use crate::compilers::variable::{
-        compile_dot_statement, compile_variable_call, parse_variable_declaration,
+        compile_dot_statement, compile_variable_call, evaluate_expression,
+        parse_variable_declaration,
     };
     use crate::globals::{IF_ELSE_SKIP, MAKE_LOOP};
     use crate::node::nodes::{ASTNode, IntNode};
+    use crate::output;
+    use std::cell::RefCell;
     use std::error::Error;
 
+    thread_local! {
+        // the value of a return statement in a nested block of a walked function until the
+        // function takes it, kept for the thread walking the function
+        static RETURN_STATEMENT: RefCell<Option<String>> = const { RefCell::new(None) };
+    }
+
     pub fn set_make_loop(value: bool) {
         unsafe {
             MAKE_LOOP = value;
         }
     }
 
+    /// Whether a return statement in a nested block is waiting for its function, the blocks
+    /// around it stop running
+    pub fn return_pending() -> bool {
+        RETURN_STATEMENT.with(|value| value.borrow().is_some())
+    }
+
+    /// Take the value of the return statement a nested block reached
+    pub fn take_return() -> Option<String> {
+        RETURN_STATEMENT.with(|value| value.borrow_mut().take())
+    }
+
     // Custom error type for better error messages
     #[derive(Debug)]
     pub enum CompilerError {
@@ -298,14 +318,14 @@ pub mod compilers {
                 ASTNode::Else => {}
                 ASTNode::Int(_n) => {
                     if expression.len() == 1 {
-                        println!("Int: {}", _n.value);
+                        output::trace(|| format!("Int: {}", _n.value));
                     } else {
                         let _result = operation(expression)?;
                         break;
                     }
                 }
                 ASTNode::Function(_f) => {
-                    let end = parse_function_declaration(expression);
+                    let end = parse_function_declaration(expression, None);
 
                     match end {
                         Ok(true) => {
@@ -320,14 +340,22 @@ pub mod compilers {
                     }
                 }
                 ASTNode::RightCurly => {}
+                // the function running the block takes the value once the blocks have stopped
+                ASTNode::Return(r) => {
+                    RETURN_STATEMENT.with(|value| *value.borrow_mut() = Some(r.value.clone()));
+                    return Ok(true);
+                }
                 ASTNode::String(s) => {
-                    println!("String: {}", s.value);
+                    output::trace(|| format!("String: {}", s.value));
                 }
                 ASTNode::Char(c) => {
-                    println!("Char: {}", c.value);
+                    output::trace(|| format!("Char: {}", c.value));
                 }
                 ASTNode::FunctionCall(_f) => {
                     let function_expression: Vec<ASTNode> = expression[index..].to_vec();
+                    if evaluate_expression(&function_expression)?.is_some() {
+                        return Ok(true);
+                    }
                     let result = parse_function_call(
                         &function_expression,
                         "None".to_string(),
@@ -356,16 +384,17 @@ pub mod compilers {
                     break;
                 }
                 ASTNode::None => {
-                    println!("Invlid Syntax: {:?}", node);
-                    let error = "Unhandle node type in compiler";
-                    return Err(Box::new(CompilerError::InvalidSyntax(error.into())));
+                    return Err(Box::new(CompilerError::InvalidSyntax(format!(
+                        "Unhandled node type in compiler: {:?}",
+                        node
+                    ))));
                 }
                 ASTNode::RightParenthesis => {}
                 _ => {
-                    println!("Invlid Syntax: {:?}", node);
-                    return Err(Box::new(CompilerError::InvalidSyntax(
-                        "Unhandled node type in compiler.".to_string(),
-                    )));
+                    return Err(Box::new(CompilerError::InvalidSyntax(format!(
+                        "Unhandled node type in compiler: {:?}",
+                        node
+                    ))));
                 }
             }
 
@@ -1,15 +1,21 @@
 use crate::base_variable::base_types::BaseTypes;
 use crate::collection::collections::{Array, Dictionary};
+use crate::collection::entries::DictionaryEntries;
 use crate::collection::ARRAY_STACK;
 use crate::collection::DICTIONARY_STACK;
+use crate::compilers::function::parse_function_call;
+use crate::limits;
 use crate::node::nodes::ASTNode;
+use crate::output;
 
 use std::fmt;
+use std::sync::Arc;
 
 #[derive(Debug)]
 pub enum CollectionError {
     SyntaxError(String),
     InvalidType(String),
+    FunctionError(String),
 }
 
 impl fmt::Display for CollectionError {
@@ -17,6 +23,7 @@ impl fmt::Display for CollectionError {
         match self {
             CollectionError::SyntaxError(msg) => write!(f, "Syntax Error: {}", msg),
             CollectionError::InvalidType(msg) => write!(f, "Invalid Type: {}", msg),
+            CollectionError::FunctionError(msg) => write!(f, "{}", msg),
         }
     }
 }
@@ -49,7 +56,7 @@ fn add_to_array_stack(array: Array) {
 pub fn parse_collection_call(
     expression: &[ASTNode],
 ) -> Result<(String, Vec<BaseTypes>), CollectionError> {
-    println!("Parsing collection call");
+    output::trace(|| "Parsing collection call".to_string());
     if expression.is_empty() {
         return Err(CollectionError::SyntaxError("Expression is empty".into()));
     }
@@ -131,8 +138,30 @@ fn parse_array_declaration(
     name: String,
 ) -> Result<(), CollectionError> {
     let mut values: Vec<BaseTypes> = Vec::new();
-    for node in &expression[1..] {
+    for (index, node) in expression.iter().enumerate().skip(1) {
         match node {
+            // arrays can be initialized from functions that return arrays e.g. split
+            ASTNode::FunctionCall(_) => {
+                let result = parse_function_call(
+                    &expression[index..].to_vec(),
+                    "None".to_string(),
+                    None,
+                    None,
+                    None,
+                )
+                .map_err(|e| CollectionError::FunctionError(e.to_string()))?;
+
+                match result {
+                    BaseTypes::Array(data) => values.extend(Arc::unwrap_or_clone(data)),
+                    other => {
+                        return Err(CollectionError::InvalidType(format!(
+                            "Expected an array from the function call but got {}",
+                            other
+                        )))
+                    }
+                }
+                break;
+            }
             ASTNode::Int(int) => values.push(BaseTypes::Int(int.value)),
             ASTNode::Float(float) => values.push(BaseTypes::Float(float.value.into())),
             ASTNode::String(string) => values.push(BaseTypes::StringWrapper(string.value.clone())),
@@ -151,6 +180,7 @@ fn parse_array_declaration(
 
     let array = Array::new(name, single_key_type, values);
     add_to_array_stack(array);
+    limits::check_collections().map_err(|e| CollectionError::FunctionError(e.to_string()))?;
     Ok(())
 }
 
@@ -172,8 +202,30 @@ fn parse_dict_declaration(
     let mut key: Option<BaseTypes> = None;
     let mut have_fat_arrow = false;
 
-    for node in &expression[1..] {
+    for (index, node) in expression.iter().enumerate().skip(1) {
         match node {
+            // dictionaries can be initialized from functions that return them e.g. json_parse
+            ASTNode::FunctionCall(_) => {
+                let result = parse_function_call(
+                    &expression[index..].to_vec(),
+                    "None".to_string(),
+                    None,
+                    None,
+                    None,
+                )
+                .map_err(|e| CollectionError::FunctionError(e.to_string()))?;
+
+                match result {
+                    BaseTypes::Dictionary(data) => values.extend(Arc::unwrap_or_clone(data)),
+                    other => {
+                        return Err(CollectionError::InvalidType(format!(
+                            "Expected a dictionary from the function call but got {}",
+                            other
+                        )))
+                    }
+                }
+                break;
+            }
             ASTNode::Int(int) => handle_key_value(
                 BaseTypes::Int(int.value),
                 &mut key,
@@ -218,8 +270,10 @@ fn parse_dict_declaration(
         }
     }
 
+    let values = DictionaryEntries::from_pairs(values).map_err(CollectionError::InvalidType)?;
     let dict = Dictionary::new(name, key_type, value_type, values);
     add_to_dictionary_stack(dict);
+    limits::check_collections().map_err(|e| CollectionError::FunctionError(e.to_string()))?;
     Ok(())
 }
 
@@ -2,18 +2,22 @@ pub mod conditional_compilers {
     use std::error::Error;
 
     use crate::base_variable::base_types::BaseTypes;
-    use crate::compiler::compilers::route_to_parser;
+    use crate::compiler::compilers::{return_pending, route_to_parser};
     use crate::compilers::function::parse_function_call;
     use crate::compilers::variable::compile_dot_statement;
+    use crate::compilers::variable::evaluate_expression;
     use crate::compilers::variable::parse_operator;
     use crate::compilers::variable::parse_variable_call;
     use crate::globals::TRY_FAIL;
+    use crate::limits;
     use crate::node::nodes::from_base_type;
     use crate::node::nodes::match_token_to_node;
     use crate::node::nodes::to_base_type;
     use crate::node::nodes::ASTNode;
+    use crate::output;
     use crate::statement_tokenizer::tokenizer::tokenizers::tokenize;
     use crate::statement_tokenizer::tokenizer::tokenizers::ParseInfo;
+    use crate::vm;
 
     /// Compiles a conditional statement
     /// returns a boolean value or an error
@@ -24,6 +28,10 @@ pub mod conditional_compilers {
     pub fn compile_conditional_statement(
         expression: &mut Vec<ASTNode>,
     ) -> Result<bool, Box<dyn Error>> {
+        if let Some(value) = evaluate_expression(expression)? {
+            return vm::truthy(&value);
+        }
+
         let mut index = 0;
         let mut first_value: ASTNode = ASTNode::None;
         let mut operation: ASTNode = ASTNode::None;
@@ -240,15 +248,22 @@ pub mod conditional_compilers {
                             Ok(_) => {
                                 try_handled = true;
                             }
+                            // limits stop the program, try does not catch them
+                            Err(e) if limits::is_limit_error(e.as_ref()) => return Err(e),
                             Err(e) => {
-                                // Log the error and allow execution to proceed to `catch`
-                                eprintln!("Error in Try block: {}", e);
+                                // Log the error and leave the block, the catch runs next
+                                output::eprintln(&format!("Error in Try block: {}", e));
                                 try_handled = false; // Indicate failure
                                 unsafe {
                                     TRY_FAIL = true;
                                 }
+                                break;
                             }
                         }
+                        // a return in the block leaves it
+                        if return_pending() {
+                            break;
+                        }
                     }
                 }
                 ASTNode::Catch(n) => {
@@ -296,8 +311,8 @@ pub mod conditional_compilers {
             }
         }
 
-        // Ensure at least a `try` block was handled
-        if !try_handled && !catch_handled && !finally_handled {
+        // Ensure at least a `try` block was handled, a failed try is handled by the following catch
+        if !try_handled && !catch_handled && !finally_handled && !unsafe { TRY_FAIL } {
             return Err("Error: No valid try, catch, or finally blocks found".into());
         }
 
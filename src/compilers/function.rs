@@ -1,24 +1,33 @@
 use crate::base_variable::base_types::StringWrapper;
 use crate::base_variable::variable::{self, Variable};
 use crate::collection::collections::{Array, Dictionary};
-use crate::collection::{ARRAY_FUNCTIONS, DICTIONARY_FUNCTIONS};
-use crate::compiler::compilers::route_to_parser;
-use crate::compilers::conditional::conditional_compilers::string_to_ast;
+use crate::collection::{with_array_receiver, with_dictionary_receiver, ARRAY_STACK};
+use crate::compiler::compilers::{route_to_parser, take_return};
 use crate::node::nodes::{match_token_to_node, ASTNode};
 use std::process::exit;
 
-use crate::compilers::variable::compile_dot_statement;
-use crate::function::functions::FunctionTypes;
-
-use crate::base_variable::base_types::BaseTypes;
-use crate::base_variable::variables::VARIABLE_STACK;
+use crate::bytecode::Chunk;
+use crate::compilers::conditional::conditional_compilers::compile_if_elif_else_statement;
+use crate::compilers::variable::{
+    compile_dot_statement, declared_variable, get_array, get_dict, type_from_name,
+};
+use crate::globals::{IF_ELSE_SKIP, MAKE_LOOP};
+use crate::host::host_object_type;
+use crate::limits;
+use crate::module::call_imported_function;
+use crate::vm;
+
+use crate::base_variable::base_types::{BaseTypes, GetType};
+use crate::base_variable::base_variables::constant_value;
+use crate::base_variable::variables::{
+    get_symbol_variable, pop_variable, push_variable, truncate_variables, variable_count,
+};
 use crate::compilers::variable::parse_variable_call;
-use crate::function::functions::call_function;
-use crate::function::functions::Function;
+use crate::function::functions::{CallArguments, Function, Signature};
 use crate::function::{FUNCTION_STACK, USER_FUNCTION_STACK};
 use crate::function_map::{FUNCTIONS, USER_FUNCTIONS};
-use std::any::Any;
 use std::error::Error;
+use std::sync::Arc;
 
 use crate::statement_tokenizer::tokenizer::tokenizers::tokenize;
 
@@ -57,26 +66,16 @@ fn is_function_in_stack(function_name: &str) -> bool {
     false
 }
 
-fn add_to_variable_stack(var: Variable) {
-    unsafe { VARIABLE_STACK.clone() }.push(var);
-}
-
-fn remove_from_variable_stack(var: Variable) {
-    let mut index = 0;
-    for arg in unsafe { VARIABLE_STACK.clone() } {
-        if arg.name == var.name {
-            unsafe { VARIABLE_STACK.clone() }.remove(index);
-        }
-        index += 1;
-    }
-}
-
 /// Parse the function declaration
 ///
 /// params: expression: &[ASTNode] -> The expression to be parsed
+/// params: code: Option<Arc<Chunk>> -> The compiled body, None when the body is walked
 ///
 /// Returns: Result<bool, Box<dyn Error>> -> The result of the parsing
-pub fn parse_function_declaration(expression: &[ASTNode]) -> Result<bool, Box<dyn Error>> {
+pub fn parse_function_declaration(
+    expression: &[ASTNode],
+    code: Option<Arc<Chunk>>,
+) -> Result<bool, Box<dyn Error>> {
     let function_name: String;
     let mut parameters: Vec<(String, String, String)>;
     let mut function_return_type: String;
@@ -103,15 +102,47 @@ pub fn parse_function_declaration(expression: &[ASTNode]) -> Result<bool, Box<dy
         }
     }
 
-    // make arguments into vec of vars
+    // make arguments into vec of vars, a variadic argument keeps the element type of its array
     let mut args: Vec<Variable> = Vec::new();
-    for arg in parameters.iter() {
+    let mut defaults: Vec<Option<String>> = Vec::new();
+    let mut variadic = false;
+    for (index, arg) in parameters.iter().enumerate() {
+        let mut name = arg.0.clone();
+        let mut type_name = arg.1.clone();
+        if let Some(rest) = arg.0.strip_prefix("...") {
+            if index + 1 != parameters.len() {
+                return Err(format!(
+                    "Syntax Error: variadic parameter '{}' must be the last parameter of {}",
+                    arg.0, function_name
+                )
+                .into());
+            }
+            type_name = variadic_element_type(&arg.1)
+                .ok_or_else(|| {
+                    format!(
+                        "Syntax Error: variadic parameter '{}' must have an array<T> type but got '{}'",
+                        arg.0, arg.1
+                    )
+                })?
+                .to_string();
+            if !arg.2.is_empty() {
+                return Err(format!(
+                    "Syntax Error: variadic parameter '{}' cannot have a default",
+                    arg.0
+                )
+                .into());
+            }
+            name = rest.to_string();
+            variadic = true;
+        }
         let var = Variable {
-            name: arg.0.clone(),
+            name,
             value: BaseTypes::Null,
-            var_type: arg.1.clone().into(),
+            var_type: type_from_name(&type_name)?,
+            nullable: type_name.ends_with('?'),
         };
         args.push(var);
+        defaults.push(Some(arg.2.clone()).filter(|default| !default.is_empty()));
     }
 
     use crate::function::functions::Function;
@@ -119,7 +150,10 @@ pub fn parse_function_declaration(expression: &[ASTNode]) -> Result<bool, Box<dy
         name: function_name.clone(),
         return_type: function_return_type.clone().into(),
         arguments: args.clone(),
+        defaults,
+        variadic,
         body: function_body_nodes.clone(),
+        code,
     };
 
     // add function to user function stack
@@ -142,8 +176,9 @@ pub fn parse_function_call(
     dictionary: Option<Dictionary>,
     variable: Option<Variable>,
 ) -> Result<BaseTypes, Box<dyn Error>> {
-    let mut function_name: String = "None".to_string();
+    let function_name: String;
     let mut parameter_and_value: Vec<BaseTypes> = Vec::new();
+    let mut named: Vec<(String, BaseTypes)> = Vec::new();
     let mut i = 0;
 
     match expression.get(i).unwrap() {
@@ -153,11 +188,15 @@ pub fn parse_function_call(
             while i < expression.len() {
                 match &expression[i] {
                     ASTNode::FunctionCallArguments(_) => {
-                        parameter_and_value = parse_function_call_arguments(&expression[i + 1..])?;
+                        let arguments = parse_function_call_arguments(&expression[i + 1..])?;
+                        parameter_and_value = arguments.positional;
+                        named = arguments.named;
                     }
                     ASTNode::RightParenthesis => {}
                     ASTNode::LeftParenthesis => {
-                        parameter_and_value = parse_function_call_arguments(&expression[i + 1..])?;
+                        let arguments = parse_function_call_arguments(&expression[i + 1..])?;
+                        parameter_and_value = arguments.positional;
+                        named = arguments.named;
                         break;
                     }
                     ASTNode::VariableCall(_) => {
@@ -174,7 +213,7 @@ pub fn parse_function_call(
                 i += 1;
             }
         }
-        _ => println!("Unhandled node: {:?}", expression[i]),
+        node => return Err(format!("Syntax Error: Expected a function call but found {}", node).into()),
     }
 
     match dot_notation.as_str() {
@@ -182,6 +221,7 @@ pub fn parse_function_call(
             let result = get_function_result(
                 function_name,
                 &mut parameter_and_value,
+                named,
                 dot_notation,
                 None,
                 dictionary,
@@ -196,6 +236,7 @@ pub fn parse_function_call(
             let result = get_function_result(
                 function_name,
                 &mut parameter_and_value,
+                named,
                 dot_notation,
                 array,
                 None,
@@ -210,6 +251,7 @@ pub fn parse_function_call(
             let result = get_function_result(
                 function_name,
                 &mut parameter_and_value,
+                named,
                 dot_notation,
                 None,
                 None,
@@ -224,6 +266,7 @@ pub fn parse_function_call(
             let result = get_function_result(
                 function_name,
                 &mut parameter_and_value,
+                named,
                 dot_notation,
                 None,
                 None,
@@ -246,6 +289,7 @@ pub fn parse_function_call(
 ///
 /// params: function_name: String -> The name of the function
 /// params: parameter_and_value: &mut Vec<BaseTypes> -> The parameters and values
+/// params: named: Vec<(String, BaseTypes)> -> The named arguments
 /// params: dot_notation: String -> The dot notation -> either dictionary, array or variable else
 /// no dot notation
 /// params: array: Option<Array> -> The array if dot call
@@ -256,194 +300,534 @@ pub fn parse_function_call(
 pub fn get_function_result(
     function_name: String,
     parameter_and_value: &mut Vec<BaseTypes>,
+    named: Vec<(String, BaseTypes)>,
     dot_notation: String,
     array: Option<Array>,
     dictionary: Option<Dictionary>,
-    _variable: Option<Variable>,
+    variable: Option<Variable>,
 ) -> Result<BaseTypes, Box<dyn Error>> {
-    let std_functions = FUNCTIONS
-        .lock()
-        .map_err(|_| "Failed to lock FUNCTIONS mutex")?;
-    let array_functions = ARRAY_FUNCTIONS
-        .lock()
-        .map_err(|_| "Failed to lock ARRAY_FUNCTIONS mutex")?;
-    let dictionary_functions = DICTIONARY_FUNCTIONS
-        .lock()
-        .map_err(|_| "Failed to lock DICTIONARY_FUNCTIONS mutex")?;
-    // Adjust parameter types
-    adjust_parameter_types(parameter_and_value);
+    // a variable receiver is the first argument, name.len() is len(name)
+    if let Some(variable) = variable {
+        parameter_and_value.insert(0, variable.value);
+    }
+    // user functions run jist code that may call std functions so they are found before the
+    // function maps are locked
+    if dot_notation != "dictionary" && dot_notation != "array" {
+        let arguments = CallArguments {
+            positional: parameter_and_value.clone(),
+            named,
+        };
+        if let Some(func) = find_function_in_stack(function_name.as_str()) {
+            return call_user_function(func, arguments);
+        }
+        let named = arguments.named.clone();
+        if let Some(result) = call_imported_function(&function_name, arguments) {
+            return result;
+        }
+        if !named.is_empty() {
+            let native = FUNCTIONS
+                .lock()
+                .map_err(|_| "Failed to lock FUNCTIONS mutex")?
+                .get(&function_name);
+            if let Some(native) = native {
+                *parameter_and_value =
+                    bind_native_arguments(native.signature(), parameter_and_value, named)?;
+            }
+        }
+    } else if !named.is_empty() {
+        return Err(format!(
+            "Argument error: {} does not take named arguments",
+            function_name
+        )
+        .into());
+    }
 
     match dot_notation.as_str() {
-        "dictionary" => {
-            let func: &FunctionTypes = dictionary_functions.get(&function_name.as_str()).unwrap();
-            let result =
-                call_function_with_params(func, None, dictionary.clone(), parameter_and_value)?;
-            return Ok(result);
-        }
         "array" => {
-            let func: &FunctionTypes = array_functions.get(&function_name.as_str()).unwrap();
-            let result = call_function_with_params(func, array.clone(), None, parameter_and_value)?;
+            // only the name is kept so the data on the stack is not shared while it is changed
+            let name = array.ok_or("Array not found for dot call")?.name;
+            let result = with_array_receiver(&name, |receiver| {
+                call_native_method("Array", &function_name, receiver, parameter_and_value)
+            })
+            .ok_or("Array not found for dot call")??;
+            limits::check_collections()?;
             return Ok(result);
         }
-        "variable" => {
-            // Handle variable logic if needed
+        "dictionary" => {
+            let name = dictionary.ok_or("Dictionary not found for dot call")?.name;
+            let result = with_dictionary_receiver(&name, |receiver| {
+                call_native_method("Dictionary", &function_name, receiver, parameter_and_value)
+            })
+            .ok_or("Dictionary not found for dot call")??;
+            limits::check_collections()?;
+            return Ok(result);
         }
         _ => {}
     }
 
-    if let Some(func) = find_function_in_stack(function_name.as_str()) {
-        // initalize params as vars
-        for arg in func.arguments.clone() {
-            add_to_variable_stack(arg)
-        }
-        let result: BaseTypes;
-
-        for line in func.body {
-            match line {
-                ASTNode::Return(r) => {
-                    println!("value {}", r.value);
-                    let mut line = string_to_ast(r.value);
-                    let result = route_to_parser(&mut line, 0.into());
-                    println!("result {:?}", result);
-                }
-                _ => println!("line {}", line),
-            }
-        }
-
-        // clean up vars after running
-        for arg in func.arguments {
-            remove_from_variable_stack(arg)
+    if let Some(error) = limits::sandbox_error(&function_name) {
+        return Err(error.into());
+    }
+    // the lock is released before the call so natives can reach the registry themselves
+    let native = FUNCTIONS
+        .lock()
+        .map_err(|_| "Failed to lock FUNCTIONS mutex")?
+        .get(&function_name);
+    match native {
+        Some(native) => {
+            let result = native.invoke(parameter_and_value)?;
+            limits::check_value(&result)?;
+            Ok(result)
         }
-
-        let result = 0.into();
-        return Ok(result);
+        None => Err(format!(
+            "Function call {} is not in any of the registered functions.",
+            function_name
+        )
+        .into()),
     }
+}
 
-    // Handle standard functions
-    if let Some(func) = std_functions.get(&function_name.as_str()) {
-        let result = call_standard_function(func, parameter_and_value)?;
-        return Ok(result);
+/// Call a method of a host object registered by the program embedding jist, the object is passed
+/// to the method by name
+///
+/// params: object_name: &str -> The name the object was registered with
+/// params: expression: &[ASTNode] -> The method call e.g. increment(2)
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The result of the method
+pub fn call_host_method(
+    object_name: &str,
+    expression: &[ASTNode],
+) -> Result<BaseTypes, Box<dyn Error>> {
+    let type_name = host_object_type(object_name)
+        .ok_or_else(|| format!("Object error: '{}' is not a host object", object_name))?;
+    let function_name = match expression.first() {
+        Some(ASTNode::FunctionCall(f)) => f.name.clone(),
+        _ => return Err("Syntax Error: expected a method call".into()),
+    };
+    let arguments = match expression
+        .iter()
+        .position(|node| matches!(node, ASTNode::LeftParenthesis))
+    {
+        Some(index) => parse_function_call_arguments(&expression[index + 1..])?,
+        None => CallArguments::default(),
+    };
+    if !arguments.named.is_empty() {
+        return Err(format!(
+            "Argument error: {} does not take named arguments",
+            function_name
+        )
+        .into());
     }
-
-    println!(
-        "Function call {} is not in any of the registered functions.",
-        function_name
-    );
-    Err("Function not found".into())
+    let mut receiver = BaseTypes::StringWrapper(object_name.to_string());
+    call_native_method(type_name, &function_name, &mut receiver, &arguments.positional)
 }
 
-/// Adjust parameter types to floats if needed to match function signatures
+/// Call a method registered for a receiver type e.g. push on an array
 ///
-/// params: parameter_and_value: &mut Vec<BaseTypes> -> The parameters and values
+/// params: receiver_type: &str -> The type the method is registered on, Array or Dictionary
+/// params: function_name: &str -> The name of the method
+/// params: receiver: &mut BaseTypes -> The value the method is called on
+/// params: args: &[BaseTypes] -> The arguments passed to the method
 ///
-/// Returns: None
-fn adjust_parameter_types(parameter_and_value: &mut Vec<BaseTypes>) {
-    for param in parameter_and_value.iter_mut().take(2) {
-        if let BaseTypes::Int(x) = *param {
-            *param = BaseTypes::Float(x as f64);
-        }
-    }
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The result of the method
+fn call_native_method(
+    receiver_type: &str,
+    function_name: &str,
+    receiver: &mut BaseTypes,
+    args: &[BaseTypes],
+) -> Result<BaseTypes, Box<dyn Error>> {
+    let method = FUNCTIONS
+        .lock()
+        .map_err(|_| "Failed to lock FUNCTIONS mutex")?
+        .get_method(receiver_type, function_name)
+        .ok_or_else(|| format!("{} has no method named '{}'", receiver_type, function_name))?;
+    method.invoke(receiver, args)
 }
 
-/// Call the function with parameters includes dot calls
+/// Call a user function, the arguments are bound to the parameters as variables and the body is
+/// run until a return statement. Named arguments are matched by parameter name, parameters left
+/// out use their default and the values after the fixed parameters go to a variadic parameter.
+/// Variables declared in the body are removed once the call ends
 ///
-/// params: func: &FunctionTypes -> The function to be called
-/// params: array: Option<Array> -> The array if dot call
-/// params: dict: Option<Dictionary> -> The dictionary if dot call
-/// params: parameter_and_value: &mut Vec<BaseTypes> -> The parameters and values
+/// params: func: Function -> The function to call
+/// params: arguments: CallArguments -> The values passed to the function
 ///
-/// returns: Result<BaseTypes, Box<dyn Error>> -> The result of the function
-fn call_function_with_params(
-    func: &FunctionTypes,
-    array: Option<Array>,
-    dict: Option<Dictionary>,
-    parameter_and_value: &mut Vec<BaseTypes>,
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The returned value or null without a return
+pub fn call_user_function(
+    func: Function,
+    arguments: CallArguments,
 ) -> Result<BaseTypes, Box<dyn Error>> {
-    let mut params: Vec<Box<dyn Any>> = Vec::new();
+    let fixed = func.arguments.len() - usize::from(func.variadic);
+    let CallArguments {
+        mut positional,
+        named,
+    } = arguments;
+    let rest = if positional.len() > fixed {
+        positional.split_off(fixed)
+    } else {
+        Vec::new()
+    };
+    if !rest.is_empty() && !func.variadic {
+        return Err(arity_error(&func, positional.len() + rest.len()).into());
+    }
+
+    let mut values: Vec<Option<BaseTypes>> = positional.into_iter().map(Some).collect();
+    values.resize(fixed, None);
+    for (name, value) in named {
+        let index = func.arguments[..fixed]
+            .iter()
+            .position(|parameter| parameter.name == name)
+            .ok_or_else(|| {
+                format!(
+                    "Argument error: {} has no parameter named '{}'",
+                    func.name, name
+                )
+            })?;
+        if values[index].is_some() {
+            return Err(format!(
+                "Argument error: {} got more than one value for '{}'",
+                func.name, name
+            )
+            .into());
+        }
+        values[index] = Some(value);
+    }
 
-    if let Some(collection_param) = array {
-        params.push(Box::new(collection_param));
+    // the guard lowers the call depth again however the call ends
+    let _call = limits::enter_call()?;
+    let stack_size = variable_count();
+    let result = bind_arguments(&func, values, rest).and_then(|_| match &func.code {
+        Some(code) => return_value(&func, vm::call(code, stack_size)?),
+        None => run_function_body(&func),
+    });
+
+    // clean up the parameters and locals after running
+    truncate_variables(stack_size);
+    if func.variadic {
+        ARRAY_STACK.lock().unwrap().remove(0);
     }
+    result
+}
 
-    if let Some(collection_param) = dict {
-        params.push(Box::new(collection_param));
+/// Push the parameters of a call as variables, defaults are evaluated in order so they can use
+/// the parameters before them. The variadic values become an array that shadows arrays of the
+/// same name until the call ends
+fn bind_arguments(
+    func: &Function,
+    values: Vec<Option<BaseTypes>>,
+    rest: Vec<BaseTypes>,
+) -> Result<(), Box<dyn Error>> {
+    if func.variadic {
+        let parameter = func.arguments.last().unwrap();
+        let array = Array::new(parameter.name.clone(), parameter.var_type.clone(), rest.clone());
+        ARRAY_STACK.lock().unwrap().insert(0, array);
+        for (index, value) in rest.iter().enumerate() {
+            if value.GetType() != parameter.var_type.GetType() {
+                return Err(format!(
+                    "Type error: {} expects argument {} to be {} but got {}",
+                    func.name,
+                    func.arguments.len() + index,
+                    parameter.var_type.GetType(),
+                    value.GetType()
+                )
+                .into());
+            }
+        }
     }
 
-    for param in parameter_and_value {
-        let boxed_param: Box<dyn Any> = match param {
-            BaseTypes::Int(x) => Box::new(*x),
-            BaseTypes::Float(x) => Box::new(*x),
-            BaseTypes::StringWrapper(x) => Box::new(x.clone()),
-            BaseTypes::Bool(x) => Box::new(*x),
-            BaseTypes::Char(x) => Box::new(*x),
-            _ => {
-                let error = format!("Unknown parameter type: {:?}", param);
-                return Err(error.into());
+    for (index, value) in values.into_iter().enumerate() {
+        let parameter = &func.arguments[index];
+        let value = match (value, &func.defaults[index]) {
+            (Some(value), _) => value,
+            (None, Some(default)) => {
+                evaluate_as(&type_name(parameter), default, "Argument error")?
+            }
+            (None, None) if func.defaults.iter().all(Option::is_none) && !func.variadic => {
+                return Err(arity_error(func, index).into())
             }
+            (None, None) => {
+                return Err(format!(
+                    "Argument error: {} is missing argument '{}'",
+                    func.name, parameter.name
+                )
+                .into())
+            }
+        };
+        parameter.check_null_safety(&value)?;
+        let variable = if parameter.nullable {
+            Variable::new_optional(parameter.name.clone(), value, parameter.var_type.clone())
+        } else {
+            Variable::new(parameter.name.clone(), value, parameter.var_type.clone())
         };
-        params.push(boxed_param);
+        push_variable(variable);
     }
+    Ok(())
+}
 
-    let result = call_function(func, params);
-
-    if let Some(value) = result.downcast_ref::<i32>() {
-        Ok(BaseTypes::Int(*value))
-    } else if let Some(value) = result.downcast_ref::<f64>() {
-        Ok(BaseTypes::Float(*value))
-    } else if let Some(value) = result.downcast_ref::<String>() {
-        Ok(BaseTypes::StringWrapper(value.clone()))
-    } else if let Some(value) = result.downcast_ref::<bool>() {
-        Ok(BaseTypes::Bool(*value))
-    } else if let Some(value) = result.downcast_ref::<char>() {
-        Ok(BaseTypes::Char(*value))
+/// Describe how many arguments a function takes when a call gives the wrong number
+fn arity_error(func: &Function, given: usize) -> String {
+    let fixed = func.arguments.len() - usize::from(func.variadic);
+    let required = func.defaults[..fixed]
+        .iter()
+        .filter(|default| default.is_none())
+        .count();
+    let expected = if func.variadic {
+        format!("at least {}", required)
+    } else if required == fixed {
+        fixed.to_string()
     } else {
-        Ok(BaseTypes::Null)
+        format!("{} to {}", required, fixed)
+    };
+    format!(
+        "Argument error: {} expects {} argument(s) but got {}",
+        func.name, expected, given
+    )
+}
+
+/// Put the named arguments of a std library call in the position of the parameter they name,
+/// parameters skipped between them are passed as null like a left out optional argument
+fn bind_native_arguments(
+    signature: &Signature,
+    positional: &[BaseTypes],
+    named: Vec<(String, BaseTypes)>,
+) -> Result<Vec<BaseTypes>, Box<dyn Error>> {
+    let function_name = &signature.name;
+    let mut values: Vec<Option<BaseTypes>> = positional.iter().cloned().map(Some).collect();
+    for (name, value) in named {
+        let index = signature
+            .parameters
+            .iter()
+            .position(|parameter| parameter.name == name)
+            .ok_or_else(|| {
+                format!(
+                    "Argument error: {} has no parameter named '{}'",
+                    function_name, name
+                )
+            })?;
+        if values.len() <= index {
+            values.resize(index + 1, None);
+        }
+        if values[index].is_some() {
+            return Err(format!(
+                "Argument error: {} got more than one value for '{}'",
+                function_name, name
+            )
+            .into());
+        }
+        values[index] = Some(value);
     }
+    Ok(values
+        .into_iter()
+        .map(|value| value.unwrap_or(BaseTypes::Null))
+        .collect())
 }
 
-fn call_standard_function(
-    func: &FunctionTypes,
-    parameter_and_value: &mut Vec<BaseTypes>,
-) -> Result<BaseTypes, Box<dyn Error>> {
-    let mut params: Vec<Box<dyn Any>> = Vec::new();
-
-    for param in parameter_and_value {
-        let boxed_param: Box<dyn Any> = match param {
-            BaseTypes::Int(x) => Box::new(*x),
-            BaseTypes::Float(x) => Box::new(*x),
-            BaseTypes::StringWrapper(x) => Box::new(x.clone()),
-            BaseTypes::Bool(x) => Box::new(*x),
-            BaseTypes::Char(x) => Box::new(*x),
-            _ => return Err("Unknown parameter type".into()),
-        };
+/// The declared type name of a parameter e.g. int or string? for an optional parameter
+fn type_name(parameter: &Variable) -> String {
+    let name = match &parameter.var_type {
+        BaseTypes::Bool(_) => "boolean".to_string(),
+        other => other.GetType().to_lowercase(),
+    };
+    if parameter.nullable {
+        format!("{}?", name)
+    } else {
+        name
+    }
+}
+
+/// Get the element type of a variadic parameter declared as array<T>
+fn variadic_element_type(type_name: &str) -> Option<&str> {
+    type_name
+        .trim()
+        .strip_prefix("array<")?
+        .strip_suffix('>')
+        .map(str::trim)
+}
 
-        params.push(boxed_param);
+/// Run the statements of a function body, each statement ends at a semicolon like in a file
+fn run_function_body(func: &Function) -> Result<BaseTypes, Box<dyn Error>> {
+    Ok(run_body(func, &func.body)?.unwrap_or(BaseTypes::Null))
+}
+
+/// Run the statements of a block of a function body
+///
+/// returns: Result<Option<BaseTypes>, Box<dyn Error>> -> The value of the return statement that
+/// ended the block, None when it ran to its end
+fn run_body(func: &Function, body: &[ASTNode]) -> Result<Option<BaseTypes>, Box<dyn Error>> {
+    let mut statement: Vec<ASTNode> = Vec::new();
+    // the curly braces open in an if statement, the statement ends when they are closed
+    let mut depth = 0;
+
+    for node in body.iter() {
+        let branch = matches!(
+            statement.first(),
+            Some(ASTNode::If(_) | ASTNode::Elif(_) | ASTNode::Else)
+        );
+        match node {
+            ASTNode::Return(r) if statement.is_empty() => {
+                return evaluate_return(func, &r.value).map(Some)
+            }
+            // the tokenizer reads the curly brace of an else with it
+            ASTNode::Else if statement.is_empty() => {
+                depth = 1;
+                statement.push(node.clone());
+            }
+            ASTNode::LeftCurly if branch => {
+                depth += 1;
+                statement.push(node.clone());
+            }
+            ASTNode::RightCurly if branch && depth > 1 => {
+                depth -= 1;
+                statement.push(node.clone());
+            }
+            ASTNode::RightCurly if branch => {
+                depth = 0;
+                let value = run_branch(func, &statement)?;
+                statement.clear();
+                if value.is_some() {
+                    return Ok(value);
+                }
+            }
+            ASTNode::SemiColon if !branch => {
+                if !statement.is_empty() {
+                    limits::step()?;
+                    route_to_parser(&mut statement, None)?;
+                    while unsafe { MAKE_LOOP } {
+                        route_to_parser(&mut statement, None)?;
+                    }
+                }
+                statement.clear();
+            }
+            ASTNode::Function(_) | ASTNode::Try(_) | ASTNode::Catch(_) | ASTNode::Finally(_) => {
+                statement.push(node.clone());
+                route_to_parser(&mut statement, None)?;
+                statement.clear();
+            }
+            // a loop ends at its closing brace so it is run without waiting for a semicolon
+            ASTNode::While(_) | ASTNode::For(_) if statement.is_empty() => {
+                statement.push(node.clone());
+                limits::step()?;
+                route_to_parser(&mut statement, None)?;
+                while unsafe { MAKE_LOOP } {
+                    route_to_parser(&mut statement, None)?;
+                }
+                statement.clear();
+            }
+            _ => statement.push(node.clone()),
+        }
+
+        // a return in the block of a loop or try ends the function
+        if let Some(value) = take_return() {
+            return evaluate_return(func, &value).map(Some);
+        }
     }
 
-    let result = call_function(func, params);
-
-    if let Some(value) = result.downcast_ref::<i32>() {
-        Ok(BaseTypes::Int(*value))
-    } else if let Some(value) = result.downcast_ref::<f64>() {
-        Ok(BaseTypes::Float(*value))
-    } else if let Some(value) = result.downcast_ref::<String>() {
-        Ok(BaseTypes::StringWrapper(value.clone()))
-    } else if let Some(value) = result.downcast_ref::<bool>() {
-        Ok(BaseTypes::Bool(*value))
-    } else if let Some(value) = result.downcast_ref::<char>() {
-        Ok(BaseTypes::Char(*value))
-    } else {
-        Ok(BaseTypes::Null)
+    if !statement.is_empty() {
+        route_to_parser(&mut statement, None)?;
+    }
+    match take_return() {
+        Some(value) => evaluate_return(func, &value).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Run a branch of an if chain in a function body, the elif and else after the branch that ran
+/// are skipped
+///
+/// returns: Result<Option<BaseTypes>, Box<dyn Error>> -> The value of a return statement in the
+/// branch
+fn run_branch(func: &Function, statement: &[ASTNode]) -> Result<Option<BaseTypes>, Box<dyn Error>> {
+    let taken = match statement.first() {
+        Some(ASTNode::If(_)) => {
+            unsafe { IF_ELSE_SKIP = false };
+            compile_if_elif_else_statement(&mut statement[..1].to_vec())?
+        }
+        _ if unsafe { IF_ELSE_SKIP } => false,
+        _ => compile_if_elif_else_statement(&mut statement[..1].to_vec())?,
+    };
+    if !taken {
+        return Ok(None);
+    }
+    unsafe { IF_ELSE_SKIP = true };
+    limits::step()?;
+    // the block is between the curly braces, an if is followed by the closing parenthesis of its
+    // condition and the opening curly brace
+    let start = match statement.first() {
+        Some(ASTNode::Else) => 1,
+        _ => 3,
+    };
+    run_body(func, &statement[start.min(statement.len())..])
+}
+
+/// Evaluate the expression of a return statement, it is declared as a variable of the return type
+/// so the value goes through the same evaluation and type checks as a let statement
+fn evaluate_return(func: &Function, expression: &str) -> Result<BaseTypes, Box<dyn Error>> {
+    let expression = expression.trim().trim_end_matches(';').trim();
+    let return_type = match &func.return_type {
+        BaseTypes::StringWrapper(t) => t.trim().to_string(),
+        _ => String::new(),
+    };
+    if expression.is_empty() || return_type.is_empty() {
+        return Ok(BaseTypes::Null);
+    }
+    evaluate_as(&return_type, expression, "Return error")
+}
+
+/// Check the value a compiled body returned against the return type, a function without a
+/// return type gives null
+fn return_value(func: &Function, value: Option<BaseTypes>) -> Result<BaseTypes, Box<dyn Error>> {
+    let return_type = match &func.return_type {
+        BaseTypes::StringWrapper(t) => t.trim(),
+        _ => "",
+    };
+    match value {
+        Some(value) if !return_type.is_empty() => Ok(declared_variable(
+            "__value".to_string(),
+            value,
+            type_from_name(return_type)?,
+            return_type.ends_with('?'),
+        )?
+        .value),
+        _ => Ok(BaseTypes::Null),
+    }
+}
+
+/// Evaluate an expression by declaring it as a temporary variable of the given type, used for
+/// return values and parameter defaults
+fn evaluate_as(
+    type_name: &str,
+    expression: &str,
+    error: &str,
+) -> Result<BaseTypes, Box<dyn Error>> {
+    let declaration = format!("let __value: {} = {};", type_name, expression.trim());
+    let mut nodes: Vec<ASTNode> = tokenize(declaration)
+        .into_iter()
+        .map(match_token_to_node)
+        .filter(|node| !matches!(node, ASTNode::SemiColon))
+        .collect();
+    route_to_parser(&mut nodes, None)?;
+
+    match pop_variable() {
+        Some(variable) if variable.name == "__value" => Ok(variable.value),
+        Some(variable) => {
+            push_variable(variable);
+            Err(format!("{}: could not evaluate '{}'", error, expression).into())
+        }
+        None => Err(format!("{}: could not evaluate '{}'", error, expression).into()),
     }
 }
 
-/// Parse the function call arguments
+/// Parse the function call arguments, an argument written as name: value is a named argument
 ///
 /// params: expression: &[ASTNode] -> The expression to be parsed
 ///
-/// returns: Result<Vec<BaseTypes>, Box<dyn Error>> -> The result of the parsing
-fn parse_function_call_arguments(expression: &[ASTNode]) -> Result<Vec<BaseTypes>, Box<dyn Error>> {
+/// returns: Result<CallArguments, Box<dyn Error>> -> The positional and named arguments
+pub fn parse_function_call_arguments(
+    expression: &[ASTNode],
+) -> Result<CallArguments, Box<dyn Error>> {
     let mut arguments: Vec<BaseTypes> = Vec::new();
+    let mut named: Vec<(String, BaseTypes)> = Vec::new();
+    let mut first_named: Option<usize> = None;
     let mut i = 0;
 
     while i < expression.len() {
@@ -458,9 +842,18 @@ fn parse_function_call_arguments(expression: &[ASTNode]) -> Result<Vec<BaseTypes
             ASTNode::VariableCall(v) => {
                 // Process variable call, you could push its value from a variable store
                 // For now, let's assume variables are stored in VARIABLE_STACK and extract their values
-                for var in unsafe { VARIABLE_STACK.iter() } {
-                    if var.name == v.name {
-                        arguments.push(var.value.clone());
+                let count = arguments.len();
+                if let Some(var) = get_symbol_variable(v.symbol) {
+                    arguments.push(var.value);
+                }
+                // collections are passed by value as a jist array or dictionary
+                if let Ok(array) = get_array(v.name.clone()) {
+                    arguments.push(BaseTypes::Array(array.data.clone()));
+                } else if let Ok(dict) = get_dict(v.name.clone()) {
+                    arguments.push(BaseTypes::Dictionary(dict.values.clone()));
+                } else if arguments.len() == count {
+                    if let Some(constant) = constant_value(&v.name) {
+                        arguments.push(constant);
                     }
                 }
             }
@@ -491,6 +884,37 @@ fn parse_function_call_arguments(expression: &[ASTNode]) -> Result<Vec<BaseTypes
                 // End of arguments, break out of the loop
                 break;
             }
+            ASTNode::FunctionArguments(a) if named_argument(&a.value).is_some() => {
+                let (name, value) = named_argument(&a.value).unwrap();
+                if named.iter().any(|(n, _)| *n == name) {
+                    return Err(
+                        format!("Argument error: '{}' is given more than once", name).into(),
+                    );
+                }
+                // a lone word is read as a variable by the tokenizer so literals are checked first
+                let value = match value {
+                    "true" => BaseTypes::Bool(true),
+                    "false" => BaseTypes::Bool(false),
+                    "null" => BaseTypes::Null,
+                    _ => {
+                        let nodes: Vec<ASTNode> = tokenize(value.to_string())
+                            .into_iter()
+                            .map(match_token_to_node)
+                            .collect();
+                        let mut value = parse_function_call_arguments(&nodes)?;
+                        if value.positional.len() != 1 || !value.named.is_empty() {
+                            return Err(format!(
+                                "Argument error: could not evaluate the value of '{}'",
+                                name
+                            )
+                            .into());
+                        }
+                        value.positional.remove(0)
+                    }
+                };
+                first_named.get_or_insert(arguments.len());
+                named.push((name, value));
+            }
             ASTNode::FunctionArguments(a) => {
                 // Process function arguments
                 //call tokenizer
@@ -510,7 +934,26 @@ fn parse_function_call_arguments(expression: &[ASTNode]) -> Result<Vec<BaseTypes
         i += 1;
     }
 
+    // positional values must come before the named ones so they line up with the parameters
+    if first_named.is_some_and(|count| arguments.len() > count) {
+        return Err("Argument error: positional arguments must come before named arguments".into());
+    }
     // Return the collected arguments
     //println!("@@@@@@@@@@@Arguments: {:?}", arguments);
-    Ok(arguments)
+    Ok(CallArguments {
+        positional: arguments,
+        named,
+    })
+}
+
+/// Split an argument written as name: value into the name and the text of the value
+fn named_argument(argument: &str) -> Option<(String, &str)> {
+    let (name, value) = argument.split_once(':')?;
+    let name = name.trim();
+    let is_name = name.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_');
+    if !is_name || value.trim().is_empty() {
+        return None;
+    }
+    Some((name.to_string(), value.trim()))
 }
@@ -1,12 +1,15 @@
 pub mod loop_compilers {
-    use crate::base_variable::{variable, variables::VARIABLE_STACK};
+    use crate::base_variable::variable;
+    use crate::base_variable::variables::{get_variable, push_variable};
 
     use crate::base_variable::base_types::BaseTypes;
-    use crate::compiler::compilers::route_to_parser;
+    use crate::compiler::compilers::{return_pending, route_to_parser};
     use crate::compilers::conditional::conditional_compilers::compile_conditional_statement;
     use crate::compilers::variable::search_for_var_name;
+    use crate::limits;
     use crate::node::nodes::match_token_to_node;
     use crate::node::nodes::ASTNode;
+    use crate::output;
     use crate::statement_tokenizer::tokenizer::tokenizers::tokenize;
     use crate::statement_tokenizer::tokenizer::tokenizers::ParseInfo;
     use std::error::Error;
@@ -30,21 +33,20 @@ pub mod loop_compilers {
                             fornode.iterable.0.into(),
                             BaseTypes::Int(0),
                         );
-                        unsafe { VARIABLE_STACK.push(new_var) };
+                        push_variable(new_var);
                     }
 
                     // Main iteration logic
                     let mut iter_result = false;
-                    for var in unsafe { VARIABLE_STACK.iter_mut() } {
-                        if var.name == fornode.variable {
-                            let current_value: i32 = var.value.clone().into();
-                            if current_value <= fornode.iterable.1 {
-                                iter_result = true;
-                            }
+                    if let Some(var) = get_variable(&fornode.variable) {
+                        let current_value: i32 = var.value.clone().into();
+                        if current_value <= fornode.iterable.1 {
+                            iter_result = true;
                         }
                     }
 
                     if iter_result {
+                        limits::step()?;
                         // Execute the loop body
                         for stmt in &fornode.block {
                             let tokenized_body = tokenize(stmt.to_string());
@@ -53,12 +55,14 @@ pub mod loop_compilers {
                                 .map(match_token_to_node)
                                 .collect();
                             route_to_parser(&mut nodes, 0.into())?;
-                        }
-                        for var in unsafe { VARIABLE_STACK.iter_mut() } {
-                            if var.name == fornode.variable {
-                                var.increment();
+                            // a return in the body leaves the loop
+                            if return_pending() {
+                                return Ok(false);
                             }
                         }
+                        if let Some(mut var) = get_variable(&fornode.variable) {
+                            var.increment();
+                        }
                     } else {
                         return Ok(false);
                     }
@@ -74,14 +78,14 @@ pub mod loop_compilers {
         let mut tokenized: Vec<ParseInfo> = Vec::new();
         let mut index = 0;
 
-        if expression.len() <= 1 {
-            return Err("Error: Empty body in while loop.".into());
-        }
-
         while index < expression.len() {
             let node = &expression[index].clone();
             match node {
                 ASTNode::While(while_node) => {
+                    if while_node.block.iter().all(|line| line.trim().is_empty()) {
+                        return Err("Error: Empty body in while loop.".into());
+                    }
+
                     // Tokenize and evaluate the condition
                     let tokenized_statement = tokenize(while_node.condition.clone());
                     tokenized.extend(tokenized_statement.clone());
@@ -107,6 +111,10 @@ pub mod loop_compilers {
                     }
                     while evaluation_result {
                         set_make_loop(true);
+                        if let Err(e) = limits::step() {
+                            set_make_loop(false);
+                            return Err(e);
+                        }
 
                         //println!("Entering while loop body");
 
@@ -118,7 +126,16 @@ pub mod loop_compilers {
                             for token in tokenized_body {
                                 nodes.push(match_token_to_node(token));
                             }
-                            let Result = route_to_parser(&mut nodes, 0.into());
+                            // an error in the body stops the loop like it does in the vm
+                            if let Err(e) = route_to_parser(&mut nodes, 0.into()) {
+                                set_make_loop(false);
+                                return Err(e);
+                            }
+                            // a return in the body leaves the loop
+                            if return_pending() {
+                                set_make_loop(false);
+                                return Ok(false);
+                            }
                         }
 
                         // Re-evaluate the while loop condition after each iteration
@@ -149,7 +166,7 @@ pub mod loop_compilers {
                     continue; // Skip to the next iteration
                 }
                 _ => {
-                    println!("Unhandled node: {:?}", node);
+                    output::trace(|| format!("Unhandled node: {:?}", node));
                 }
             }
             index += 1;
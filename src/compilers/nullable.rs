@@ -0,0 +1,194 @@
+/*
+* This file contains the compiler for the null safety operators used with optional types (int?,
+* string?, ...): `??` null coalescing and postfix `!` unwrap. Safe navigation `?.` is handled by
+* compile_dot_statement since it is part of a dot call.
+*/
+
+pub mod nullable_compilers {
+    use crate::base_variable::base_types::BaseTypes;
+    use crate::compilers::function::parse_function_call;
+    use crate::compilers::variable::{compile_dot_statement, parse_variable_call};
+    use crate::node::nodes::{to_base_type, ASTNode};
+    use std::error::Error;
+
+    /// Checks if the expression uses `??` or a postfix `!` and so has to be compiled by
+    /// compile_null_operators
+    ///
+    /// params: expression: &[ASTNode] -> The expression to be checked
+    ///
+    /// returns: bool -> true if a null safety operator is found
+    pub fn has_null_operator(expression: &[ASTNode]) -> bool {
+        for (i, node) in expression.iter().enumerate() {
+            if let ASTNode::Operator(o) = node {
+                if o.operator == "??" {
+                    return true;
+                }
+                // ! is only an unwrap when it follows a value, otherwise it is a not
+                if o.operator == "!" && i > 0 && is_operand(&expression[i - 1]) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    fn is_operand(node: &ASTNode) -> bool {
+        matches!(
+            node,
+            ASTNode::VariableCall(_)
+                | ASTNode::Dot(_)
+                | ASTNode::RightParenthesis
+                | ASTNode::Null
+                | ASTNode::Int(_)
+                | ASTNode::Float(_)
+                | ASTNode::String(_)
+                | ASTNode::Char(_)
+                | ASTNode::Bool(_)
+        )
+    }
+
+    /// Reads one operand starting at index
+    ///
+    /// returns: (BaseTypes, usize) -> The value of the operand and the index after it
+    fn read_operand(
+        expression: &[ASTNode],
+        index: usize,
+    ) -> Result<(BaseTypes, usize), Box<dyn Error>> {
+        match &expression[index] {
+            ASTNode::VariableCall(v) => match parse_variable_call(&expression[index])? {
+                // parse_variable_call gives no name when nothing is called that
+                (name, _) if name.is_empty() => {
+                    Err(format!("Variable '{}' not found", v.name).into())
+                }
+                (_, value) => Ok((value, index + 1)),
+            },
+            ASTNode::Dot(_) => {
+                let mut dot_call = vec![expression[index].clone()];
+                Ok((compile_dot_statement(&mut dot_call)?, index + 1))
+            }
+            ASTNode::FunctionCall(_) => {
+                // collect the call up to its closing parenthesis
+                let mut end = index;
+                let mut depth = 0;
+                while end < expression.len() {
+                    match expression[end] {
+                        ASTNode::LeftParenthesis => depth += 1,
+                        ASTNode::RightParenthesis => {
+                            depth -= 1;
+                            if depth == 0 {
+                                break;
+                            }
+                        }
+                        _ => {}
+                    }
+                    end += 1;
+                }
+                let function_call = expression[index..=end.min(expression.len() - 1)].to_vec();
+                let value =
+                    parse_function_call(&function_call, "None".to_string(), None, None, None)?;
+                Ok((value, end + 1))
+            }
+            ASTNode::Null
+            | ASTNode::Int(_)
+            | ASTNode::Float(_)
+            | ASTNode::String(_)
+            | ASTNode::Char(_)
+            | ASTNode::Bool(_) => Ok((
+                to_base_type(&expression[index]).unwrap_or(BaseTypes::Null),
+                index + 1,
+            )),
+            node => Err(format!("Syntax Error: Expected a value but found {}", node).into()),
+        }
+    }
+
+    /// Compiles an expression using the null safety operators
+    /// `a ?? b` gives b when a is null, `a!` gives a or an error when a is null
+    ///
+    /// params: expression: &[ASTNode] -> The expression to be compiled, the right hand side of an
+    /// assignment
+    ///
+    /// returns: Result<BaseTypes, Box<dyn Error>> -> The resulting value
+    pub fn compile_null_operators(expression: &[ASTNode]) -> Result<BaseTypes, Box<dyn Error>> {
+        if expression.is_empty() {
+            return Err("Syntax Error: Expected an expression".into());
+        }
+
+        let (mut value, mut index) = read_operand(expression, 0)?;
+
+        while index < expression.len() {
+            match &expression[index] {
+                ASTNode::Operator(o) if o.operator == "!" => {
+                    if let BaseTypes::Null = value {
+                        return Err("Unwrap error: tried to unwrap a null value with !".into());
+                    }
+                    index += 1;
+                }
+                ASTNode::Operator(o) if o.operator == "??" => {
+                    if index + 1 >= expression.len() {
+                        return Err("Syntax Error: Expected a value after ??".into());
+                    }
+                    let (fallback, next) = read_operand(expression, index + 1)?;
+                    if let BaseTypes::Null = value {
+                        value = fallback;
+                    }
+                    index = next;
+                }
+                ASTNode::SemiColon => break,
+                node => {
+                    return Err(format!(
+                        "Syntax Error: Unexpected {} in null safe expression",
+                        node
+                    )
+                    .into())
+                }
+            }
+        }
+
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod nullable_tests {
+    use super::nullable_compilers::{compile_null_operators, has_null_operator};
+    use crate::base_variable::base_types::BaseTypes;
+    use crate::node::nodes::{ASTNode, IntNode, OperatorNode};
+
+    fn operator(op: &str) -> ASTNode {
+        ASTNode::Operator(OperatorNode {
+            operator: op.to_string(),
+        })
+    }
+
+    #[test]
+    fn test_null_coalescing() {
+        let expression = vec![ASTNode::Null, operator("??"), ASTNode::Int(IntNode::new(5))];
+        assert!(has_null_operator(&expression));
+        let result = compile_null_operators(&expression).unwrap();
+        assert_eq!(result, BaseTypes::Int(5));
+    }
+
+    #[test]
+    fn test_null_coalescing_keeps_value() {
+        let expression = vec![
+            ASTNode::Int(IntNode::new(1)),
+            operator("??"),
+            ASTNode::Int(IntNode::new(5)),
+        ];
+        let result = compile_null_operators(&expression).unwrap();
+        assert_eq!(result, BaseTypes::Int(1));
+    }
+
+    #[test]
+    fn test_unwrap_null_is_error() {
+        let expression = vec![ASTNode::Null, operator("!")];
+        assert!(has_null_operator(&expression));
+        assert!(compile_null_operators(&expression).is_err());
+    }
+
+    #[test]
+    fn test_prefix_not_is_not_unwrap() {
+        let expression = vec![operator("!"), ASTNode::Int(IntNode::new(1))];
+        assert!(!has_null_operator(&expression));
+    }
+}
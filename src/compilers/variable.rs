@@ -1,21 +1,30 @@
-use std::process::exit;
-
 use crate::base_variable::base_types::BaseTypes;
+use crate::base_variable::base_variables::constant_value;
 use crate::base_variable::variable::Variable;
-use crate::base_variable::variables::VARIABLE_STACK;
+use crate::base_variable::variables::{
+    find_variable, get_symbol_variable, get_variable, push_variable,
+};
+use crate::bytecode::{self, BinaryOp};
 use crate::collection::collections::{Array, Dictionary};
 use crate::compilers::collection::parse_collection_call;
-use crate::compilers::function::parse_function_call;
+use crate::compilers::function::{call_host_method, parse_function_call};
+use crate::module::{get_module_member, is_module_alias};
+use crate::host::host_object_type;
+use crate::limits;
 use crate::node::nodes::match_token_to_node;
 use crate::node::nodes::ASTNode;
-use crate::node::nodes::VariableCallNode;
+use crate::node::nodes::DotNode;
+use crate::node::nodes::ObjectCallNode;
 use crate::node::nodes::{IntNode, OperatorNode};
+use crate::output;
 use crate::statement_tokenizer::tokenizer::tokenizers::tokenize;
+use crate::vm;
 use std::error::Error;
 
 use crate::collection::{ARRAY_STACK, DICTIONARY_STACK};
 
 use super::conditional::conditional_compilers::compile_conditional_statement;
+use super::nullable::nullable_compilers::{compile_null_operators, has_null_operator};
 
 pub fn search_for_dict_name(name: String) -> bool {
     // if name is in DICTIONARY_STACK reutrn true
@@ -39,13 +48,7 @@ pub fn search_for_array_name(name: String) -> bool {
 }
 
 pub fn search_for_var_name(name: String) -> bool {
-    let var_stack = unsafe { VARIABLE_STACK.clone() };
-    for var in var_stack.iter() {
-        if var.name == name {
-            return true;
-        }
-    }
-    false
+    find_variable(&name).is_some()
 }
 
 pub fn get_dict(name: String) -> Result<Dictionary, Box<dyn Error>> {
@@ -77,7 +80,7 @@ pub fn parse_object_call(node: &ASTNode) -> Result<(String, String), Box<dyn Err
             let object_name = c.object.clone();
             let dict: bool = search_for_dict_name(object_name.clone());
             let array: bool = search_for_array_name(object_name.clone());
-            let variable: bool = false;
+            let variable: bool = search_for_var_name(object_name.clone());
 
             if dict {
                 return Ok((object_name, "dictionary".to_string()));
@@ -85,6 +88,9 @@ pub fn parse_object_call(node: &ASTNode) -> Result<(String, String), Box<dyn Err
             if array {
                 return Ok((object_name, "array".to_string()));
             }
+            if host_object_type(&object_name).is_some() {
+                return Ok((object_name, "object".to_string()));
+            }
             if variable {
                 return Ok((object_name, "variable".to_string()));
             } else {
@@ -105,7 +111,27 @@ pub fn compile_dot_statement(exp_stack: &mut Vec<ASTNode>) -> Result<BaseTypes,
     // check what type object is
     // then make an object type specific function call
 
-    let node: ASTNode = exp_stack.get(0).unwrap().clone();
+    let mut node: ASTNode = exp_stack.get(0).unwrap().clone();
+
+    // members of an imported module e.g. utils.greet("jist")
+    if let ASTNode::Dot(d) = &node {
+        if is_module_alias(d.object.trim()) {
+            return get_module_member(d.object.trim(), &d.function);
+        }
+    }
+
+    // safe navigation, obj?.method() gives null when obj is null and is a normal dot call otherwise
+    if let ASTNode::Dot(d) = &node {
+        if let Some(object_name) = d.object.trim().strip_suffix('?') {
+            let object_name = object_name.trim().to_string();
+            let is_null_variable =
+                get_variable(&object_name).is_some_and(|var| matches!(var.value, BaseTypes::Null));
+            if is_null_variable {
+                return Ok(BaseTypes::Null);
+            }
+            node = ASTNode::Dot(DotNode::new(object_name, d.function.clone()));
+        }
+    }
 
     match node.clone() {
         ASTNode::Dot(d) => {
@@ -115,13 +141,24 @@ pub fn compile_dot_statement(exp_stack: &mut Vec<ASTNode>) -> Result<BaseTypes,
             let object_name_type: (String, String);
 
             // first tokenize object
-            let objects = tokenize(d.object);
+            let objects = tokenize(d.object.clone());
             let mut object_nodes: Vec<ASTNode> = Vec::new();
             //now match to node
             for object in objects.clone() {
                 object_nodes.push(match_token_to_node(object));
             }
 
+            // a bare name tokenizes as a variable call, it is the receiver of the call like
+            // collections and host objects
+            if let Some(ASTNode::VariableCall(v)) = object_nodes.first() {
+                if search_for_array_name(v.name.clone())
+                    || search_for_dict_name(v.name.clone())
+                    || host_object_type(&v.name).is_some()
+                    || search_for_var_name(v.name.clone())
+                {
+                    object_nodes[0] = ASTNode::ObjectCall(ObjectCallNode::new(v.name.clone()));
+                }
+            }
             // check type of object, var, collection? then comiple
             match object_nodes.get(0).unwrap() {
                 ASTNode::ObjectCall(c) => {
@@ -155,7 +192,7 @@ pub fn compile_dot_statement(exp_stack: &mut Vec<ASTNode>) -> Result<BaseTypes,
                                 None,
                             );
 
-                            result = func_result.unwrap();
+                            result = func_result?;
                         }
                         "array" => {
                             //println!("Object call is an array");
@@ -194,20 +231,36 @@ pub fn compile_dot_statement(exp_stack: &mut Vec<ASTNode>) -> Result<BaseTypes,
                                 None,
                             );
 
-                            result = func_result.unwrap();
+                            result = func_result?;
+                        }
+                        "object" => {
+                            let function_nodes: Vec<ASTNode> = tokenize(d.function.clone())
+                                .into_iter()
+                                .map(match_token_to_node)
+                                .collect();
+                            result = call_host_method(&object_name_type.0, &function_nodes)?;
                         }
                         "variable" => {
-                            println!("Object call is a variable");
-                            let tokenized_function = tokenize(d.function.clone());
-                            let mut function_nodes: Vec<ASTNode> = Vec::new();
-                            for function in tokenized_function {
-                                function_nodes.push(match_token_to_node(function));
-                            }
-                            //println!("Function nodes: {:?}", function_nodes);
+                            output::trace(|| "Object call is a variable".to_string());
+                            let variable: Option<Variable> = get_variable(&object_name_type.0);
+                            let function_nodes: Vec<ASTNode> = tokenize(d.function.clone())
+                                .into_iter()
+                                .map(match_token_to_node)
+                                .collect();
+                            result = parse_function_call(
+                                &function_nodes,
+                                "variable".to_string(),
+                                None,
+                                None,
+                                variable,
+                            )?;
                         }
                         _ => {
-                            println!("Object call is not a variable, dictionary or array, therefore cannot compile dot call");
-                            exit(1);
+                            return Err(format!(
+                                "Syntax Error: {} is not a variable, dictionary, array or object so it cannot be called with a dot",
+                                object_name_type.0
+                            )
+                            .into());
                         }
                     }
                 }
@@ -217,9 +270,11 @@ pub fn compile_dot_statement(exp_stack: &mut Vec<ASTNode>) -> Result<BaseTypes,
                 ASTNode::VariableCall(_c) => {
                     variable = parse_variable_call(object_nodes.get(0).unwrap())?
                 }
-                _ => {
-                    println!("Unexpected node found within comple dot statement function");
-                    exit(1);
+                node => {
+                    return Err(
+                        format!("Syntax Error: Unexpected {} before the dot of a dot call", node)
+                            .into(),
+                    );
                 }
             }
         }
@@ -238,17 +293,26 @@ pub fn parse_variable_call(node: &ASTNode) -> Result<(String, BaseTypes), Box<dy
     match node {
         ASTNode::VariableCall(v) => {
             //println!("Function argument: {}", v.name);
-            let mut _arg_name = ASTNode::VariableCall(VariableCallNode {
-                name: v.name.clone(),
-            });
+            let mut _arg_name = ASTNode::VariableCall(v.clone());
             //var stack for var with this name
             let mut arg1_value = BaseTypes::StringWrapper(String::new()); // Initialize with default value
             let mut arg1_name = String::new(); // Initialize with default value
-            for var in unsafe { VARIABLE_STACK.iter() } {
-                if var.name == v.name {
-                    arg1_value = var.value.clone();
-                    //print!("Value: {:?}", arg1_value);
-                    arg1_name = var.name.clone();
+            if let Some(var) = get_symbol_variable(v.symbol) {
+                arg1_value = var.value;
+                //print!("Value: {:?}", arg1_value);
+                arg1_name = var.name;
+            }
+            // collections are read by value as a jist array or dictionary
+            if arg1_name.is_empty() {
+                if let Ok(array) = get_array(v.name.clone()) {
+                    arg1_value = BaseTypes::Array(array.data.clone());
+                    arg1_name = array.name;
+                } else if let Ok(dict) = get_dict(v.name.clone()) {
+                    arg1_value = BaseTypes::Dictionary(dict.values.clone());
+                    arg1_name = dict.name;
+                } else if let Some(constant) = constant_value(&v.name) {
+                    arg1_value = constant;
+                    arg1_name = v.name.clone();
                 }
             }
             let arg1 = (arg1_name, arg1_value);
@@ -259,6 +323,24 @@ pub fn parse_variable_call(node: &ASTNode) -> Result<(String, BaseTypes), Box<dy
     }
 }
 
+///
+///This function evaluates an expression with the bytecode compiler so the tree walk and the virtual
+///machine agree on precedence and types, None when the expression can not be compiled
+///
+pub fn evaluate_expression(nodes: &[ASTNode]) -> Result<Option<BaseTypes>, Box<dyn Error>> {
+    let nodes = match nodes {
+        [rest @ .., ASTNode::SemiColon] => rest,
+        _ => nodes,
+    };
+    if nodes.is_empty() {
+        return Ok(None);
+    }
+    match bytecode::compile_expression(nodes) {
+        Some(chunk) => vm::evaluate(&chunk).map(Some),
+        None => Ok(None),
+    }
+}
+
 ///
 /// This function is called when the first token in the expression is a variable
 /// can be used to set the value of the variable to something else
@@ -274,10 +356,7 @@ pub fn compile_variable_call(exp_stack: &mut Vec<ASTNode>) -> Result<bool, Box<d
     let index = exp_stack
         .iter()
         .position(|node| matches!(node, ASTNode::VariableCall(_)))
-        .unwrap_or_else(|| {
-            println!("Syntax Error: Expected a variable call.");
-            exit(1);
-        });
+        .ok_or("Syntax Error: Expected a variable call.")?;
 
     let first_node = &exp_stack[index];
 
@@ -286,21 +365,17 @@ pub fn compile_variable_call(exp_stack: &mut Vec<ASTNode>) -> Result<bool, Box<d
         let mut variable = Variable::new(String::new(), BaseTypes::Null, BaseTypes::Null);
 
         // Search for the variable in the global VARIABLE_STACK
-        for var in unsafe { &VARIABLE_STACK.clone() } {
-            if var.name == v.name {
-                variable = var.clone();
-                /*println!(
-                    "Variable found: Name = {}, Value = {:?}, Type = {:?}",
-                    variable.name, variable.value, variable.var_type
-                );*/
-                break; // Break after finding the variable
-            }
+        if let Some(var) = get_symbol_variable(v.symbol) {
+            variable = var;
+            /*println!(
+                "Variable found: Name = {}, Value = {:?}, Type = {:?}",
+                variable.name, variable.value, variable.var_type
+            );*/
         }
 
         // If variable wasn't found, return false
         if variable.name.is_empty() {
-            println!("Syntax Error: Variable '{}' not found in stack.", v.name);
-            return Err("Variable not found.".into());
+            return Err(format!("Syntax Error: Variable '{}' not found in stack.", v.name).into());
         }
 
         // Process further if there are additional nodes in exp_stack
@@ -308,16 +383,23 @@ pub fn compile_variable_call(exp_stack: &mut Vec<ASTNode>) -> Result<bool, Box<d
             match node {
                 ASTNode::AssignmentOperator(_) => {
                     let mut operation_stack = exp_stack[index + 2..].to_vec();
-                    println!("Operation stack: {:?}", operation_stack);
+                    output::trace(|| format!("Operation stack: {:?}", operation_stack));
 
                     if operation_stack.is_empty() {
                         return Err(
                             "Syntax Error: Expected an expression after the assignment operator."
                                 .into(),
                         );
+                    } else if let Some(value) = evaluate_expression(&operation_stack)? {
+                        variable.assign(value)?;
+                        return Ok(true);
+                    } else if has_null_operator(&operation_stack) {
+                        let value = compile_null_operators(&operation_stack)?;
+                        variable.assign(value)?;
+                        return Ok(true);
                     } else if operation_stack.len() == 1 {
                         let value: BaseTypes = operation_stack[0].clone().into();
-                        variable.set_value(value.clone());
+                        variable.assign(value)?;
                         return Ok(true);
                     }
 
@@ -330,20 +412,20 @@ pub fn compile_variable_call(exp_stack: &mut Vec<ASTNode>) -> Result<bool, Box<d
                                 None,
                                 None,
                             );
-                            let value = result.unwrap();
-                            variable.set_value(value.clone());
+                            let value = result?;
+                            variable.assign(value)?;
                             return Ok(true);
                         }
                         Some(ASTNode::Dot(_)) => {
                             let result = compile_dot_statement(&mut operation_stack);
-                            let value = result.unwrap();
-                            variable.set_value(value.clone());
+                            let value = result?;
+                            variable.assign(value)?;
                             return Ok(true);
                         }
                         Some(ASTNode::VariableCall(_)) => {
                             let result = parse_variable_call(&operation_stack[0]);
-                            let value = result.unwrap().1;
-                            variable.set_value(value.clone());
+                            let value = result?.1;
+                            variable.assign(value)?;
                             return Ok(true);
                         }
                         _ => {}
@@ -351,15 +433,31 @@ pub fn compile_variable_call(exp_stack: &mut Vec<ASTNode>) -> Result<bool, Box<d
 
                     let result = operation(&mut operation_stack)?;
                     // Set the value to the variable based on the operation result
-                    println!("result: {:?}", result);
+                    output::trace(|| format!("result: {:?}", result));
                     variable.set_value(result.clone());
                     if let Some(next_node) = operation_stack.first() {
                         let _value: BaseTypes = next_node.into();
-                        print!("result.unwrap(): {:?}", result);
+                        output::trace(|| format!("result.unwrap(): {:?}", result));
                         variable.set_value(result.clone());
                     }
                     return Ok(true);
                 }
+                // compound assignment e.g. total += i
+                ASTNode::Operator(o)
+                    if matches!(
+                        exp_stack.get(index + 2),
+                        Some(ASTNode::AssignmentOperator(_))
+                    ) =>
+                {
+                    let op = BinaryOp::from_operator(&o.operator).ok_or_else(|| {
+                        format!("Syntax Error: Unrecognized operator '{}='", o.operator)
+                    })?;
+                    let value = evaluate_expression(&exp_stack[index + 3..])?.ok_or(
+                        "Syntax Error: Expected an expression after the assignment operator.",
+                    )?;
+                    variable.assign(vm::binary(op, variable.value.clone(), value)?)?;
+                    return Ok(true);
+                }
                 ASTNode::Operator(o) => match o.operator.as_str() {
                     "++" => {
                         variable.increment();
@@ -383,7 +481,7 @@ pub fn compile_variable_call(exp_stack: &mut Vec<ASTNode>) -> Result<bool, Box<d
                     // Handle additional argument processing here
                     let value: BaseTypes = node.into();
                     variable.set_value(value.clone());
-                    println!("Set variable value to: {:?}", value);
+                    output::trace(|| format!("Set variable value to: {:?}", value));
                 }
             }
         }
@@ -411,28 +509,35 @@ pub fn parse_variable_declaration(exp_stack: &mut Vec<ASTNode>) -> Result<bool,
     let mut var_value = ASTNode::Int(IntNode { value: 0 });
     let mut first: Option<ASTNode> = Option::None;
     let mut parenthesis: bool = false;
-    let mut variable_call_values: Vec<(String, BaseTypes)> = Vec::new();
+    let mut optional: bool = false;
+
+    if let [ASTNode::Variable(v), ASTNode::VariableType(t), ASTNode::AssignmentOperator(a), value @ ..] =
+        exp_stack.as_slice()
+    {
+        if a.operator == "=" {
+            if let Some(value) = evaluate_expression(value)? {
+                let var_type = type_from_name(&t.value)?;
+                return declare_variable(v.value.clone(), value, var_type, t.value.ends_with('?'));
+            }
+        }
+    }
 
     let mut index = 0;
     while index < exp_stack.len() {
+        if inside_assignment && has_null_operator(&exp_stack[index..]) {
+            value = compile_null_operators(&exp_stack[index..])?;
+            break;
+        }
+
         let node = &exp_stack[index];
         match node {
             ASTNode::Variable(v) => {
                 var_name = Some(v.value.clone());
             }
             ASTNode::VariableType(v) => {
-                var_type = match v.value.as_str() {
-                    "int" => Some(BaseTypes::Int(0)),
-                    "float" => Some(BaseTypes::Float(0.0)),
-                    "string" => Some(BaseTypes::StringWrapper(String::new())),
-                    "boolean" => Some(BaseTypes::Bool(false)),
-                    "char" => Some(BaseTypes::Char('\0')),
-                    _ => {
-                        let error: String =
-                            format!("Syntax Error: Unrecognized type '{}'", v.value);
-                        return Err(error.into());
-                    }
-                };
+                // a trailing ? makes the type optional so it can hold null
+                optional = v.value.ends_with('?');
+                var_type = Some(type_from_name(&v.value)?);
             }
             ASTNode::AssignmentOperator(a) => {
                 assignment_operator = Some(a.operator.clone());
@@ -440,11 +545,18 @@ pub fn parse_variable_declaration(exp_stack: &mut Vec<ASTNode>) -> Result<bool,
             }
             ASTNode::Dot(_) => {
                 if inside_assignment {
-                    let result = compile_dot_statement(exp_stack);
-                    value = result.unwrap();
+                    let result = compile_dot_statement(&mut exp_stack[index..].to_vec());
+                    value = result?;
                     break;
                 }
             }
+            ASTNode::Null => {
+                if inside_assignment {
+                    value = BaseTypes::Null;
+                } else {
+                    return Err("Syntax Error: null outside of assignment.".into());
+                }
+            }
             ASTNode::FunctionCall(_c) => {
                 if inside_assignment {
                     /*println!(
@@ -471,7 +583,7 @@ pub fn parse_variable_declaration(exp_stack: &mut Vec<ASTNode>) -> Result<bool,
                     );
 
                     // check with match then set value to result
-                    value = result.unwrap();
+                    value = result?;
 
                     /*println!(
                                             "New variable = name: {}, value: {:?}, type: {:?}",
@@ -481,32 +593,16 @@ pub fn parse_variable_declaration(exp_stack: &mut Vec<ASTNode>) -> Result<bool,
                                         );
                     */
 
-                    let variable = Variable::new(var_name.unwrap(), value, var_type.unwrap());
-                    // add to VARIABLE_STACK
-                    unsafe {
-                        VARIABLE_STACK.push(variable.clone());
-                    }
-                    return Ok(true);
+                    return declare_variable(var_name.unwrap(), value, var_type.unwrap(), optional);
                 }
             }
             ASTNode::VariableCall(_c) => {
                 if inside_assignment {
-                    let mut result = parse_variable_call(node);
-                    result = match result {
-                        Ok(result) => Ok(result),
-                        Err(e) => Err(e),
-                    };
-                    //add the value back into the epression at same index
-
-                    let var_result = result?;
-
-                    exp_stack.insert(
-                        index,
-                        ASTNode::VariableCall(VariableCallNode {
-                            name: var_result.clone().0,
-                        }),
-                    );
-                    variable_call_values.push(var_result);
+                    let (name, var_value) = parse_variable_call(node)?;
+                    if name.is_empty() {
+                        return Err(format!("Variable '{}' not found", _c.name).into());
+                    }
+                    value = var_value;
                 } else {
                     let error: String =
                         "Syntax Error: Variable call outside of assignment.".to_string();
@@ -585,15 +681,70 @@ pub fn parse_variable_declaration(exp_stack: &mut Vec<ASTNode>) -> Result<bool,
         return Err("Missing variable components.".into());
     }
 
-    let variable = Variable::new(var_name.unwrap(), value, var_type.unwrap());
-    // Add to VARIABLE_STACK
-    unsafe {
-        VARIABLE_STACK.push(variable.clone());
+    declare_variable(var_name.unwrap(), value, var_type.unwrap(), optional)
+}
+
+///
+///This function maps a type name such as int or string? to the default value of that type, the
+///optional marker is ignored
+///
+pub fn type_from_name(type_name: &str) -> Result<BaseTypes, Box<dyn Error>> {
+    match type_name.trim().trim_end_matches('?') {
+        "int" => Ok(BaseTypes::Int(0)),
+        "float" => Ok(BaseTypes::Float(0.0)),
+        "string" => Ok(BaseTypes::StringWrapper(String::new())),
+        "boolean" => Ok(BaseTypes::Bool(false)),
+        "char" => Ok(BaseTypes::Char('\0')),
+        "regex" => Ok(BaseTypes::Regex(regex::Regex::new("").unwrap())),
+        "datetime" => Ok(BaseTypes::DateTime(chrono::DateTime::UNIX_EPOCH.fixed_offset())),
+        "duration" => Ok(BaseTypes::Duration(chrono::Duration::zero())),
+        _ => Err(format!("Syntax Error: Unrecognized type '{}'", type_name).into()),
     }
-    //println!("New variable: {:?}", variable.clone());
+}
+
+///
+///This function creates the declared variable, checks that only optional types hold null and adds
+///it to the VARIABLE_STACK
+///
+fn declare_variable(
+    name: String,
+    value: BaseTypes,
+    var_type: BaseTypes,
+    optional: bool,
+) -> Result<bool, Box<dyn Error>> {
+    push_variable(declared_variable(name, value, var_type, optional)?);
     Ok(true)
 }
 
+///
+///This function creates a declared variable without adding it to the stack, the value is
+///converted to the type and only optional types hold null
+///
+pub fn declared_variable(
+    name: String,
+    value: BaseTypes,
+    var_type: BaseTypes,
+    optional: bool,
+) -> Result<Variable, Box<dyn Error>> {
+    let variable = if optional {
+        Variable::new_optional(name, value.clone(), var_type)
+    } else {
+        // null is refused before Variable::new would replace it with the type default
+        if let BaseTypes::Null = value {
+            let declared = Variable {
+                name,
+                value: BaseTypes::Null,
+                var_type,
+                nullable: false,
+            };
+            return Err(declared.check_null_safety(&value).unwrap_err().into());
+        }
+        Variable::new(name, value.clone(), var_type)
+    };
+    limits::check_value(&value)?;
+    Ok(variable)
+}
+
 ///
 ///This Function is in the variable module and takes in a mut ref of a vector of ASTNodes
 ///It returns a BaseType result of the parsed expression
@@ -835,7 +986,7 @@ pub fn parse_operator(
             }
         },
         _ => {
-            println!("Warning: Expected an operator found None returning false.");
+            output::trace(|| "Warning: Expected an operator found None returning false.".to_string());
             return Ok(ASTNode::Int(IntNode { value: 0 }));
             //let error: String = format!("Syntax Error: Expected an operator found {}.", operator);
             //return Err(error.into());
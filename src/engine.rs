@@ -0,0 +1,1068 @@
+/*
+* This file contains the engine used to embed jist in a rust program and the runner that splits
+* program source into statements and runs them, the jist binary runs files through the same
+* functions. The interpreter keeps its state in process-wide global stacks, so there is only one
+* interpreter per process: every Engine is a handle to it and reset clears it
+*/
+
+use crate::base_variable::base_types::{BaseTypes, GetType};
+use crate::base_variable::variable::Variable;
+use crate::base_variable::variables::{
+    clear_variables, get_variable, push_variable, truncate_variables, variable_count, with_variable,
+};
+use crate::cache;
+use crate::collection::collections::{Array, Dictionary};
+use crate::collection::{
+    update_array_stack, update_dictionary_stack, ARRAY_STACK, DICTIONARY_STACK,
+};
+use crate::compiler::compilers::route_to_parser;
+use crate::compilers::conditional::conditional_compilers::compile_if_elif_else_statement;
+use crate::bytecode::{self, Chunk};
+use crate::compilers::function::get_function_result;
+use crate::compilers::nullable::nullable_compilers::{compile_null_operators, has_null_operator};
+use crate::function::{FUNCTION_STACK, USER_FUNCTION_STACK};
+use crate::function_map::{FUNCTIONS, USER_FUNCTIONS};
+use crate::globals::{IF_ELSE_SKIP, MAKE_LOOP, TRY_FAIL};
+use crate::host::{HostFunction, HostMethod, HostObject, IntoHostMethod, IntoNative, HOST_OBJECTS};
+use crate::limits::{self, Limits};
+use crate::bench::{mark_bench, strip_bench_marker};
+use crate::module::{import_module, mark_public, parse_import_statement, strip_pub_marker};
+use crate::node::nodes::{match_token_to_node, ASTNode};
+use crate::optimizer;
+use crate::output::{self, Output};
+use crate::statement_tokenizer::tokenizer::tokenizers::tokenize;
+use crate::vm;
+use std::any::{self, Any};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+/// A jist value as seen by the rust program embedding the interpreter
+pub type Value = BaseTypes;
+
+/// Runs jist code from a rust program
+///
+/// # Global state
+///
+/// An engine is a handle, not an interpreter of its own. Variables, collections, functions, host
+/// objects, the output and the limits are kept in process-wide globals, so every engine in the
+/// process, on any thread, reads and changes the same state. Only sandbox mode belongs to the
+/// engine. Treat the process as
+/// having exactly one engine: create it once and call reset between programs that must not see
+/// each other. Running two engines at the same time from different threads is not supported.
+///
+/// ```no_run
+/// use jist::{Engine, Value};
+///
+/// let mut engine = Engine::new();
+/// engine.set_global("base", Value::Int(40)).unwrap();
+/// let total = engine.eval("let total: int = add(base, 2);").unwrap();
+/// assert_eq!(total, Value::Int(42));
+/// ```
+#[derive(Default)]
+pub struct Engine {
+    sandboxed: bool,
+}
+
+impl Engine {
+    pub fn new() -> Engine {
+        Engine::default()
+    }
+
+    /// Run jist source, statements are split the same way as in a file
+    ///
+    /// params: source: &str -> The program to run
+    ///
+    /// returns: Result<Value, Box<dyn Error>> -> The value of the last statement, the stored value
+    /// when it declares or assigns a variable, the value of the expression when it is one e.g.
+    /// add(1, 2); and otherwise null
+    pub fn eval(&mut self, source: &str) -> Result<Value, Box<dyn Error>> {
+        if source.trim().is_empty() {
+            return Ok(Value::Null);
+        }
+        let mut lines = split_source(source)?;
+        let last = lines.pop().unwrap_or_default();
+        let _sandbox = limits::sandbox_scope(self.sandboxed);
+        limits::reset_usage();
+        // imports in evaluated source are resolved from the working directory
+        if let Some(name) = stored_name(&last) {
+            lines.push(last);
+            run_lines(lines, "-")?;
+            return Ok(self.get_global(&name).unwrap_or(Value::Null));
+        }
+        run_lines(lines, "-")?;
+        // the last statement is tokenized once the others have declared the names it uses
+        match evaluate_expression_statement(&last)? {
+            Some(value) => Ok(value),
+            None => {
+                run_lines(vec![last], "-")?;
+                Ok(Value::Null)
+            }
+        }
+    }
+
+    /// Run a jist file, imports are resolved relative to it
+    ///
+    /// params: path: &str -> The path of the file
+    ///
+    /// returns: Result<(), Box<dyn Error>> -> An error if the file could not be read or run
+    pub fn run_file(&mut self, path: &str) -> Result<(), Box<dyn Error>> {
+        let _sandbox = limits::sandbox_scope(self.sandboxed);
+        limits::reset_usage();
+        parse_file(path)
+    }
+
+    /// Get a global variable, array or dictionary
+    ///
+    /// params: name: &str -> The name of the global
+    ///
+    /// returns: Option<Value> -> The value or None when nothing has that name
+    pub fn get_global(&self, name: &str) -> Option<Value> {
+        if let Some(variable) = get_variable(name) {
+            return Some(variable.value.clone());
+        }
+        if let Some(array) = ARRAY_STACK.lock().unwrap().iter().find(|a| a.name == name) {
+            return Some(Value::Array(array.data.clone()));
+        }
+        DICTIONARY_STACK
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|d| d.name == name)
+            .map(|d| Value::Dictionary(d.values.clone()))
+    }
+
+    /// Set a global so jist code can read it, an existing variable keeps its declared type
+    ///
+    /// params: name: &str -> The name of the global
+    /// params: value: Value -> The new value, arrays and dictionaries become collections
+    ///
+    /// returns: Result<(), Box<dyn Error>> -> A type error when the value does not fit the type
+    /// of an existing variable
+    pub fn set_global(&mut self, name: &str, value: Value) -> Result<(), Box<dyn Error>> {
+        match value {
+            Value::Array(data) => {
+                let value_type = data.first().map_or("any".to_string(), type_name);
+                update_array_stack(Array::new(name.to_string(), value_type.as_str().into(), data));
+            }
+            Value::Dictionary(values) => {
+                let (key_type, value_type) = values.first().map_or(
+                    ("any".to_string(), "any".to_string()),
+                    |(key, value)| (type_name(key), type_name(value)),
+                );
+                update_dictionary_stack(Dictionary::new(
+                    name.to_string(),
+                    key_type.as_str().into(),
+                    value_type.as_str().into(),
+                    values,
+                ));
+            }
+            value => match get_variable(name) {
+                Some(variable) => {
+                    let fits = value.GetType() == variable.var_type.GetType()
+                        || (variable.nullable && matches!(value, Value::Null));
+                    if !fits {
+                        return Err(format!(
+                            "Type error: {} is {} but got {}",
+                            name,
+                            variable.var_type.GetType(),
+                            value.GetType()
+                        )
+                        .into());
+                    }
+                    with_variable(name, |variable| variable.value = value);
+                }
+                None => {
+                    let variable = Variable::new_optional(name.to_string(), value.clone(), value);
+                    push_variable(variable);
+                }
+            },
+        }
+        Ok(())
+    }
+
+    /// Call a function by name, user functions and imported functions are found before the
+    /// standard library
+    ///
+    /// params: name: &str -> The name of the function
+    /// params: args: &[Value] -> The arguments in order
+    ///
+    /// returns: Result<Value, Box<dyn Error>> -> The returned value
+    pub fn call_function(&mut self, name: &str, args: &[Value]) -> Result<Value, Box<dyn Error>> {
+        let _sandbox = limits::sandbox_scope(self.sandboxed);
+        get_function_result(
+            name.to_string(),
+            &mut args.to_vec(),
+            Vec::new(),
+            "None".to_string(),
+            None,
+            None,
+            None,
+        )
+    }
+
+    /// Register a rust closure as a function jist code can call, the arguments are converted from
+    /// jist values to the closure's argument types and calls are checked like std functions
+    ///
+    /// ```no_run
+    /// # let mut engine = jist::Engine::new();
+    /// engine.register_fn("shout", |text: String, times: i64| -> Result<String, String> {
+    ///     Ok(text.to_uppercase().repeat(times as usize))
+    /// });
+    /// ```
+    ///
+    /// params: name: &str -> The name jist code calls the function by
+    /// params: body: F -> The closure, up to six arguments of types like i64, f64, String, bool,
+    /// Vec<T>, Option<T> or Value returning a Result
+    pub fn register_fn<F, Args>(&mut self, name: &str, body: F)
+    where
+        F: IntoNative<Args>,
+        Args: 'static,
+    {
+        FUNCTIONS
+            .lock()
+            .unwrap()
+            .insert(Arc::new(HostFunction::new(name, body)));
+    }
+
+    /// Make a rust value available to jist code by name, its methods are called with a dot e.g.
+    /// counter.increment(2);
+    ///
+    /// params: name: &str -> The name jist code uses for the object
+    /// params: object: T -> The object, it replaces an object with the same name
+    pub fn register_object<T: Any + Send>(&mut self, name: &str, object: T) {
+        HOST_OBJECTS.lock().unwrap().insert(
+            name.to_string(),
+            HostObject {
+                type_name: any::type_name::<T>(),
+                value: Box::new(object),
+            },
+        );
+    }
+
+    /// Register a method for host objects of type T, the closure gets the object first
+    ///
+    /// ```no_run
+    /// struct Counter(i64);
+    ///
+    /// # let mut engine = jist::Engine::new();
+    /// engine.register_method("increment", |counter: &mut Counter, by: i64| -> Result<i64, String> {
+    ///     counter.0 += by;
+    ///     Ok(counter.0)
+    /// });
+    /// engine.register_object("counter", Counter(0));
+    /// ```
+    ///
+    /// params: name: &str -> The name of the method
+    /// params: body: F -> The closure taking &mut T and then the method arguments
+    pub fn register_method<T, F, Args>(&mut self, name: &str, body: F)
+    where
+        T: 'static,
+        F: IntoHostMethod<T, Args>,
+        Args: 'static,
+    {
+        FUNCTIONS
+            .lock()
+            .unwrap()
+            .insert_method(any::type_name::<T>(), Arc::new(HostMethod::new(name, body)));
+    }
+
+    /// Run a closure on a registered host object to read or change it from rust
+    ///
+    /// params: name: &str -> The name the object was registered with
+    /// params: f: impl FnOnce(&mut T) -> R -> The closure
+    ///
+    /// returns: Option<R> -> The result of the closure or None when no object of type T has the
+    /// name
+    pub fn with_object<T: 'static, R>(&mut self, name: &str, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+        let mut objects = HOST_OBJECTS.lock().unwrap();
+        let object = objects.get_mut(name)?.value.downcast_mut::<T>()?;
+        Some(f(object))
+    }
+
+    /// Send what jist code prints to an output instead of stdout, e.g. a BufferOutput to capture
+    /// it
+    ///
+    /// params: output: impl Output + 'static -> The new output
+    pub fn set_output(&mut self, output: impl Output + 'static) {
+        output::set_output(Box::new(output));
+    }
+
+    /// Send warnings and the errors caught by try blocks to an output instead of stderr
+    ///
+    /// params: errors: impl Output + 'static -> The new error output
+    pub fn set_errors(&mut self, errors: impl Output + 'static) {
+        output::set_errors(Box::new(errors));
+    }
+
+    /// Turn the trace channel with the interpreter's debug messages on or off
+    ///
+    /// params: trace: Option<Box<dyn Output>> -> Where the messages go, None turns it off
+    pub fn set_trace(&mut self, trace: Option<Box<dyn Output>>) {
+        output::set_trace(trace);
+    }
+
+    /// Limit what the next programs may use, steps and time are counted for each eval or
+    /// run_file
+    ///
+    /// ```no_run
+    /// # let mut engine = jist::Engine::new();
+    /// engine.set_limits(jist::limits::Limits {
+    ///     max_steps: Some(10_000),
+    ///     max_call_depth: Some(64),
+    ///     ..Default::default()
+    /// });
+    /// ```
+    ///
+    /// params: limits: Limits -> The new limits
+    pub fn set_limits(&mut self, limits: Limits) {
+        limits::set_limits(limits);
+    }
+
+    /// Turn sandbox mode on for the programs this engine runs, calls to the filesystem,
+    /// environment, process and input functions and imports are refused and the sandbox limits
+    /// are used for the limits that are not set. Other engines and the registry are not changed
+    pub fn sandbox(&mut self) {
+        self.sandboxed = true;
+    }
+
+    /// Clear every variable, collection and function so the next program starts empty
+    pub fn reset(&mut self) {
+        clear_variables();
+        unsafe {
+            IF_ELSE_SKIP = false;
+            MAKE_LOOP = false;
+            TRY_FAIL = false;
+        }
+        ARRAY_STACK.lock().unwrap().clear();
+        DICTIONARY_STACK.lock().unwrap().clear();
+        FUNCTION_STACK.lock().unwrap().clear();
+        USER_FUNCTION_STACK.lock().unwrap().clear();
+        USER_FUNCTIONS.lock().unwrap().clear();
+    }
+}
+
+/// The jist type name of a value e.g. int or boolean
+fn type_name(value: &Value) -> String {
+    match value {
+        Value::Bool(_) => "boolean".to_string(),
+        other => other.GetType().to_lowercase(),
+    }
+}
+
+/// The variable a statement declares or assigns, let name: type = value; or name = value;
+fn stored_name(line: &str) -> Option<String> {
+    let line = line.trim().strip_prefix("pub ").unwrap_or(line.trim());
+    if let Some(rest) = line.strip_prefix("let ") {
+        let name = rest.split(':').next()?.trim();
+        return Some(name.to_string());
+    }
+    let (name, rest) = line.split_once('=')?;
+    let name = name.trim();
+    let is_name = !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_');
+    if is_name && !rest.starts_with('=') {
+        return Some(name.to_string());
+    }
+    None
+}
+
+/// The value of a statement that is only an expression e.g. add(1, 2); or total * 2;
+///
+/// params: line: &str -> The statement
+///
+/// returns: Result<Option<Value>, Box<dyn Error>> -> The value or None when the statement is not an
+/// expression and has to be run
+fn evaluate_expression_statement(line: &str) -> Result<Option<Value>, Box<dyn Error>> {
+    let mut nodes: Vec<ASTNode> = tokenize(line.to_string())
+        .into_iter()
+        .map(match_token_to_node)
+        .collect();
+    if let Some(ASTNode::SemiColon) = nodes.last() {
+        nodes.pop();
+    }
+    let is_expression = match nodes.first() {
+        Some(ASTNode::Operator(o)) => o.operator == "-" || o.operator == "!",
+        Some(node) => matches!(
+            node,
+            ASTNode::FunctionCall(_)
+                | ASTNode::Dot(_)
+                | ASTNode::VariableCall(_)
+                | ASTNode::LeftParenthesis
+                | ASTNode::Int(_)
+                | ASTNode::Float(_)
+                | ASTNode::String(_)
+                | ASTNode::Char(_)
+                | ASTNode::Bool(_)
+                | ASTNode::Null
+        ),
+        None => false,
+    };
+    // assignments and x++ change a variable, they are run as statements
+    let changes_variable = nodes.iter().any(|node| match node {
+        ASTNode::AssignmentOperator(_) => true,
+        ASTNode::Operator(o) => o.operator == "++" || o.operator == "--",
+        _ => false,
+    });
+    if !is_expression || changes_variable {
+        return Ok(None);
+    }
+
+    if let Some(chunk) = bytecode::compile_expression(&nodes) {
+        return vm::evaluate(&chunk).map(Some);
+    }
+    if has_null_operator(&nodes) {
+        return compile_null_operators(&nodes).map(Some);
+    }
+    Ok(None)
+}
+
+///
+///This function reads the file and parses it, it was added to support multiple lines of code,
+///multiline coding statements and later multiple files
+///
+pub fn parse_lines(file_path: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let contents = fs::read_to_string(file_path)?;
+    split_source(&contents)
+}
+
+///
+///This function splits program source into statements, a statement ends at a semicolon or at the
+///curly brace closing a block
+///
+pub fn split_source(contents: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    if contents.is_empty() {
+        return Err("Error: Empty file".into());
+    }
+
+    let mut brace_count = 0;
+    let mut bracket_count = 0;
+    let mut current_line = String::new();
+    let mut finished_lines: Vec<String> = Vec::new();
+    let mut multiline_comment = false;
+    let mut in_string = false;
+
+    // Iterate through each line in the file
+    for (line_number, line) in contents.lines().enumerate() {
+        let mut chars = line.chars().peekable(); // Use a peekable iterator for lookahead
+        let mut previous = '\0';
+
+        while let Some(ch) = chars.next() {
+            // braces, semicolons and comment markers inside a string literal are just text
+            if in_string {
+                current_line.push(ch);
+                if ch == '\\' {
+                    if let Some(escaped) = chars.next() {
+                        current_line.push(escaped);
+                    }
+                } else if ch == '"' {
+                    in_string = false;
+                }
+                continue;
+            }
+            let is_char_literal = previous == '\'' && chars.peek() == Some(&'\'');
+            previous = ch;
+
+            match ch {
+                '"' if !multiline_comment && !is_char_literal => {
+                    in_string = true;
+                    current_line.push(ch);
+                }
+                '/' => {
+                    if chars.peek() == Some(&'/') {
+                        break; // Skip the rest of the line if it's a single-line comment
+                    } else if chars.peek() == Some(&'*') {
+                        multiline_comment = true; // Start of multiline comment
+                        chars.next(); // Consume '*'
+                        continue;
+                    } else {
+                        current_line.push(ch);
+                    }
+                }
+                '*' => {
+                    if multiline_comment && chars.peek() == Some(&'/') {
+                        multiline_comment = false; // End of multiline comment
+                        chars.next(); // Consume '/'
+                        continue;
+                    } else {
+                        current_line.push(ch);
+                    }
+                }
+                '{' => {
+                    brace_count += 1;
+                    current_line.push(ch);
+                }
+                '}' => {
+                    brace_count -= 1;
+                    current_line.push(ch);
+                    if brace_count < 0 {
+                        return Err(format!(
+                            "Unmatched closing curly brace at line {}",
+                            line_number + 1
+                        )
+                        .into());
+                    }
+                    if brace_count == 0 && bracket_count == 0 {
+                        finished_lines.push(current_line.clone());
+                        current_line.clear();
+                    }
+                }
+                '[' => {
+                    bracket_count += 1;
+                    current_line.push(ch);
+                }
+                ']' => {
+                    bracket_count -= 1;
+                    current_line.push(ch);
+                    if bracket_count < 0 {
+                        return Err(format!(
+                            "Unmatched closing square bracket at line {}",
+                            line_number + 1
+                        )
+                        .into());
+                    }
+                }
+                ';' => {
+                    current_line.push(ch);
+                    if brace_count == 0 && bracket_count == 0 {
+                        finished_lines.push(current_line.clone());
+                        current_line.clear();
+                    }
+                }
+                _ => {
+                    if !multiline_comment {
+                        current_line.push(ch); // Only push characters if not in a multiline comment
+                    }
+                }
+            }
+        }
+
+        // Check if there are unmatched braces or brackets at the end of the line
+        if brace_count < 0 {
+            return Err(
+                format!("Unmatched closing curly brace at line {}", line_number + 1).into(),
+            );
+        }
+        if bracket_count < 0 {
+            return Err(format!(
+                "Unmatched closing square bracket at line {}",
+                line_number + 1
+            )
+            .into());
+        }
+    }
+
+    // If there's any remaining content in current_line, add it as a finished line
+    if !current_line.is_empty() {
+        if brace_count > 0 {
+            return Err("Unmatched opening curly brace at end of file".into());
+        }
+        if bracket_count > 0 {
+            return Err("Unmatched opening square bracket at end of file".into());
+        }
+        finished_lines.push(current_line);
+    }
+
+    Ok(finished_lines)
+}
+
+///
+///This function reads a program file and runs it
+///
+pub fn parse_file(file_path: &str) -> Result<(), Box<dyn Error>> {
+    // the bytecode of a .jistc file or of a cached source runs without compiling it again
+    if let Some(chunk) = cache::load_chunk(file_path)? {
+        return run_chunk(&chunk);
+    }
+    let finished_lines = parse_lines(file_path)?;
+    run_lines(finished_lines, file_path)
+}
+
+/// Remove the statements that can never run, the notes are written for --emit=opt-ast
+fn remove_dead_code(lines: Vec<String>) -> Vec<String> {
+    let mut notes = Vec::new();
+    let lines = optimizer::eliminate_dead_code(lines, false, &mut notes);
+    if optimizer::emit_enabled() && !notes.is_empty() {
+        optimizer::emit(&[], &notes);
+    }
+    lines
+}
+
+/// Compile the statements of a program to bytecode once the statements that can never run are
+/// removed
+///
+/// params: lines: Vec<String> -> The statements of the program
+/// params: file_path: &str -> The file the program came from, imports are resolved relative to it
+///
+/// returns: Result<Chunk, Box<dyn Error>> -> The compiled program or a syntax error
+pub fn compile_lines(lines: Vec<String>, file_path: &str) -> Result<Chunk, Box<dyn Error>> {
+    bytecode::compile_program(remove_dead_code(lines), file_path)
+}
+
+/// Run a compiled program
+fn run_chunk(chunk: &Chunk) -> Result<(), Box<dyn Error>> {
+    output::trace(|| format!("Compiled program to bytecode:\n{}", chunk));
+    mark_public(&chunk.public);
+    mark_bench(&chunk.benches);
+    vm::run(chunk)?;
+    Ok(())
+}
+
+///
+///This function runs the statements of a program, file_path is where the program came from and is
+///used to resolve imports
+///
+pub fn run_lines(finished_lines: Vec<String>, file_path: &str) -> Result<(), Box<dyn Error>> {
+    let mut tokenized_expression = Vec::new();
+
+    // the whole program is compiled before it runs, --tree-walk runs it statement by statement
+    if vm::enabled() {
+        return run_chunk(&compile_lines(finished_lines, file_path)?);
+    }
+    let finished_lines = remove_dead_code(finished_lines);
+
+    for line in finished_lines {
+        // imports are resolved relative to this file and run the module before the next line
+        if let Some(statement) = parse_import_statement(&line)? {
+            import_module(statement, Path::new(file_path), parse_file)?;
+            continue;
+        }
+        // pub marks a declaration that modules importing this file can use
+        let line = strip_pub_marker(&line)?.unwrap_or(line);
+        // bench marks a function for jist bench, otherwise it is declared as usual
+        let line = strip_bench_marker(&line)?.unwrap_or(line);
+
+        limits::step()?;
+        let tokens = tokenize(line.clone());
+
+        let mut first_node: ASTNode = ASTNode::None;
+        let mut result: bool;
+
+        let mut nodes: Vec<ASTNode> = Vec::new();
+        for parsed_info in tokens.iter() {
+            let node = match_token_to_node(parsed_info.clone());
+            nodes.push(node);
+        }
+        let (mut nodes, notes) = optimizer::optimize_statement(nodes);
+        if optimizer::emit_enabled() {
+            optimizer::emit(&nodes, &notes);
+        }
+
+        // an if chain runs the block of the first branch that is true
+        if tokenized_expression.is_empty()
+            && matches!(
+                nodes.first(),
+                Some(ASTNode::If(_) | ASTNode::Elif(_) | ASTNode::Else)
+            )
+        {
+            run_branch(&line, &nodes, file_path)?;
+            continue;
+        }
+
+        // a loop ends at its closing brace so it is run without waiting for a semicolon
+        if tokenized_expression.is_empty()
+            && matches!(nodes.as_slice(), [ASTNode::While(_)] | [ASTNode::For(_)])
+        {
+            route_to_parser(&mut nodes, None)?;
+            while unsafe { MAKE_LOOP } {
+                route_to_parser(&mut nodes, None)?;
+            }
+            continue;
+        }
+
+        for (i, parsed_info) in nodes.clone().iter().enumerate() {
+            if i == 0 {
+                first_node = parsed_info.clone();
+            }
+
+            match parsed_info {
+                ASTNode::Try(_) => {
+                    tokenized_expression.push(parsed_info.clone());
+                    result = route_to_parser(&mut tokenized_expression, None)?;
+                    // a statement that could not be run is an error naming the line
+                    if !result {
+                        return Err(line_error(&line));
+                    }
+                    // Clear tokenized_expression after processing
+                    tokenized_expression.clear();
+                }
+                ASTNode::Catch(_) => {
+                    if unsafe { TRY_FAIL } {
+                        tokenized_expression.push(parsed_info.clone());
+                        result = route_to_parser(&mut tokenized_expression, None)?;
+                        // a statement that could not be run is an error naming the line
+                        if !result {
+                            return Err(line_error(&line));
+                        }
+                        // Clear tokenized_expression after processing
+                        tokenized_expression.clear();
+                        // the error has been handled so later catch blocks are skipped
+                        unsafe { TRY_FAIL = false };
+                    } else {
+                        unsafe { TRY_FAIL = false };
+                    }
+                }
+                ASTNode::Finally(_) => {
+                    tokenized_expression.push(parsed_info.clone());
+                    result = route_to_parser(&mut tokenized_expression, None)?;
+                    // a statement that could not be run is an error naming the line
+                    if !result {
+                        return Err(line_error(&line));
+                    }
+                    // Clear tokenized_expression after processing
+                    tokenized_expression.clear();
+                }
+
+                ASTNode::Function(_) => {
+                    tokenized_expression.push(parsed_info.clone());
+                    result = route_to_parser(&mut tokenized_expression, None)?;
+                    // a statement that could not be run is an error naming the line
+                    if !result {
+                        return Err(line_error(&line));
+                    }
+
+                    // Clear tokenized_expression after processing
+                    tokenized_expression.clear();
+                }
+                ASTNode::SemiColon => {
+                    // a lone value does nothing, a lone call or dot call is run
+                    if matches!(
+                        tokenized_expression.as_slice(),
+                        [ASTNode::VariableCall(_)
+                            | ASTNode::Int(_)
+                            | ASTNode::Float(_)
+                            | ASTNode::String(_)
+                            | ASTNode::Char(_)
+                            | ASTNode::Bool(_)
+                            | ASTNode::Null]
+                    ) {
+                        return Err(format!(
+                            "Syntax error: expression must be more than a semicolon in line: {}",
+                            line.trim()
+                        )
+                        .into());
+                    }
+
+                    // Route to parser only if there are valid tokens
+                    match first_node.clone() {
+                        ASTNode::While(_) => result = route_to_parser(&mut nodes, None)?,
+
+                        // a false condition skips the block, the elif and else after it run
+                        ASTNode::If(_) => {
+                            unsafe { IF_ELSE_SKIP = false };
+                            route_to_parser(&mut tokenized_expression, None)?;
+                            result = true;
+                        }
+                        // Skip processing if IF_ELSE_SKIP is true
+                        ASTNode::Elif(_) if unsafe { IF_ELSE_SKIP } => {
+                            tokenized_expression.clear();
+                            break;
+                        }
+                        ASTNode::Elif(_) => {
+                            result = route_to_parser(&mut tokenized_expression, None)?;
+
+                            while unsafe { MAKE_LOOP } {
+                                result = route_to_parser(&mut tokenized_expression, None)?;
+                            }
+                        }
+                        ASTNode::Else if unsafe { IF_ELSE_SKIP } => {
+                            unsafe { IF_ELSE_SKIP = false }; // Reset IF_ELSE_SKIP
+                            tokenized_expression.clear();
+                            break; // Skip further parsing
+                        }
+                        ASTNode::Else => {
+                            result = route_to_parser(&mut tokenized_expression, None)?;
+                            while unsafe { MAKE_LOOP } {
+                                result = route_to_parser(&mut tokenized_expression, None)?;
+                            }
+                        }
+                        ASTNode::Function(_) => {
+                            output::trace(|| "route to parser".to_string());
+                            result = route_to_parser(&mut tokenized_expression, None)?;
+                        }
+                        _ => {
+                            result = route_to_parser(&mut tokenized_expression, None)?;
+                            while unsafe { MAKE_LOOP } {
+                                result = route_to_parser(&mut tokenized_expression, None)?;
+                            }
+                        }
+                    }
+
+                    // a statement that could not be run is an error naming the line
+                    if !result {
+                        return Err(line_error(&line));
+                    }
+
+                    // Clear tokenized_expression after processing
+                    tokenized_expression.clear();
+                }
+                // the closing brace of a block that has already run
+                ASTNode::RightCurly if tokenized_expression.is_empty() => {}
+                _ => {
+                    tokenized_expression.push(parsed_info.clone()); // Accumulate tokens
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Run an if, elif or else statement, once a branch has run the rest of the chain is skipped and
+/// the variables declared in the block are removed when it ends
+fn run_branch(line: &str, nodes: &[ASTNode], file_path: &str) -> Result<(), Box<dyn Error>> {
+    let taken = match nodes.first() {
+        Some(ASTNode::If(_)) => {
+            unsafe { IF_ELSE_SKIP = false };
+            compile_if_elif_else_statement(&mut nodes[..1].to_vec())?
+        }
+        _ if unsafe { IF_ELSE_SKIP } => false,
+        _ => compile_if_elif_else_statement(&mut nodes[..1].to_vec())?,
+    };
+    if taken {
+        unsafe { IF_ELSE_SKIP = true };
+        let len = variable_count();
+        let result = run_lines(bytecode::block_statements(line)?, file_path);
+        truncate_variables(len);
+        result?;
+    }
+    Ok(())
+}
+
+/// The error for a statement the compiler could not run
+fn line_error(line: &str) -> Box<dyn Error> {
+    format!("Error in parsing line: {}", line.trim()).into()
+}
+
+#[cfg(test)]
+mod engine_tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    // the stacks are shared with the other tests so every test uses its own names
+
+    lazy_static::lazy_static! {
+        // an engine is used from one thread at a time so its tests must not run at the same time
+        static ref ENGINE_LOCK: Mutex<()> = Mutex::new(());
+    }
+
+    #[test]
+    fn test_eval_returns_stored_value() {
+        let _guard = ENGINE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut engine = Engine::new();
+        let value = engine.eval("let engine_port: int = 8080;").unwrap();
+        assert_eq!(value, Value::Int(8080));
+        let value = engine.eval("engine_port = 9090;").unwrap();
+        assert_eq!(value, Value::Int(9090));
+        assert!(matches!(engine.eval("").unwrap(), Value::Null));
+        assert_eq!(engine.get_global("engine_port"), Some(Value::Int(9090)));
+        assert_eq!(engine.get_global("engine_missing"), None);
+    }
+
+    #[test]
+    fn test_eval_returns_expression_value() {
+        let _guard = ENGINE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut engine = Engine::new();
+        assert_eq!(engine.eval("add(1, 2);").unwrap(), Value::Int(3));
+        let value = engine
+            .eval("let engine_width: int = 4;\nengine_width * 2 + 1;")
+            .unwrap();
+        assert_eq!(value, Value::Int(9));
+        assert_eq!(
+            engine.eval("concat(\"a\", \"b\");").unwrap(),
+            Value::StringWrapper("ab".to_string())
+        );
+        // statements that are not expressions still give null
+        assert!(matches!(
+            engine.eval("engine_width++;").unwrap(),
+            Value::Null
+        ));
+        assert_eq!(engine.get_global("engine_width"), Some(Value::Int(5)));
+    }
+
+    #[test]
+    fn test_run_lines_returns_errors() {
+        let _guard = ENGINE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut engine = Engine::new();
+        engine.eval("let engine_lone: int = 1;").unwrap();
+        // a lone name is an error in a file but the value of the name in eval
+        let error = run_lines(vec!["engine_lone;".to_string()], "-").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Syntax error: expression must be more than a semicolon in line: engine_lone;"
+        );
+        assert_eq!(engine.eval("engine_lone;").unwrap(), Value::Int(1));
+
+        // a name that was never declared is an error, not the end of the process
+        let error = engine
+            .eval("engine_lone = engine_missing + 1;")
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Syntax Error: Variable 'engine_missing' not found in stack."
+        );
+    }
+
+    #[test]
+    fn test_set_global() {
+        let _guard = ENGINE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut engine = Engine::new();
+        engine.set_global("engine_base", Value::Int(40)).unwrap();
+        let total = engine.eval("let engine_total: int = add(engine_base, 2);").unwrap();
+        assert_eq!(total, Value::Int(42));
+
+        assert_eq!(
+            engine
+                .set_global("engine_base", Value::StringWrapper("x".to_string()))
+                .unwrap_err()
+                .to_string(),
+            "Type error: engine_base is Int but got String"
+        );
+
+        let names = Value::Array(
+            vec![
+                Value::StringWrapper("a".to_string()),
+                Value::StringWrapper("b".to_string()),
+            ]
+            .into(),
+        );
+        engine.set_global("engine_names", names.clone()).unwrap();
+        assert_eq!(engine.get_global("engine_names"), Some(names));
+        let joined = engine
+            .eval("let engine_joined: string = join(engine_names, \",\");")
+            .unwrap();
+        assert_eq!(joined, Value::StringWrapper("a,b".to_string()));
+    }
+
+    #[test]
+    fn test_call_function() {
+        let _guard = ENGINE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut engine = Engine::new();
+        engine
+            .eval("func engine_twice(value: int) -> int {\n    return mult(value, 2);\n}")
+            .unwrap();
+        assert_eq!(
+            engine.call_function("engine_twice", &[Value::Int(21)]).unwrap(),
+            Value::Int(42)
+        );
+        assert_eq!(
+            engine
+                .call_function("to_uppercase", &[Value::StringWrapper("jist".to_string())])
+                .unwrap(),
+            Value::StringWrapper("JIST".to_string())
+        );
+        assert!(engine.call_function("to_uppercase", &[Value::Int(1)]).is_err());
+    }
+
+    #[test]
+    fn test_register_fn() {
+        let _guard = ENGINE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut engine = Engine::new();
+        engine.register_fn(
+            "engine_shout",
+            |text: String, times: i64| -> Result<String, String> {
+                Ok(text.to_uppercase().repeat(times as usize))
+            },
+        );
+        engine.register_fn(
+            "engine_scale",
+            |values: Vec<f64>, factor: Option<f64>| -> Result<Vec<f64>, String> {
+                let factor = factor.unwrap_or(2.0);
+                Ok(values.into_iter().map(|v| v * factor).collect())
+            },
+        );
+
+        let value = engine
+            .eval("let engine_shouted: string = engine_shout(\"ab\", 2);")
+            .unwrap();
+        assert_eq!(value, Value::StringWrapper("ABAB".to_string()));
+        assert_eq!(
+            engine
+                .call_function("engine_scale", &[Value::Array(vec![Value::Int(1)].into())])
+                .unwrap(),
+            Value::Array(vec![Value::Float(2.0)].into())
+        );
+        assert_eq!(
+            engine
+                .call_function("engine_shout", &[Value::Int(1), Value::Int(2)])
+                .unwrap_err()
+                .to_string(),
+            "Type error: engine_shout expects argument 1 to be String but got Int"
+        );
+        assert_eq!(
+            engine
+                .call_function("engine_shout", &[])
+                .unwrap_err()
+                .to_string(),
+            "Argument error: engine_shout expects 2 argument(s) but got 0"
+        );
+    }
+
+    #[test]
+    fn test_host_object() {
+        let _guard = ENGINE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        struct Counter {
+            count: i64,
+        }
+
+        let mut engine = Engine::new();
+        engine.register_method(
+            "increment",
+            |counter: &mut Counter, by: i64| -> Result<i64, String> {
+                counter.count += by;
+                Ok(counter.count)
+            },
+        );
+        engine.register_object("engine_counter", Counter { count: 40 });
+
+        let value = engine
+            .eval("let engine_count: int = engine_counter.increment(2);")
+            .unwrap();
+        assert_eq!(value, Value::Int(42));
+        assert_eq!(
+            engine.with_object("engine_counter", |counter: &mut Counter| counter.count),
+            Some(42)
+        );
+        assert!(engine
+            .eval("let engine_bad: int = engine_counter.increment(\"x\");")
+            .is_err());
+    }
+
+    #[test]
+    fn test_sandbox_is_per_engine() {
+        let _guard = ENGINE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut sandboxed = Engine::new();
+        sandboxed.sandbox();
+        let error = sandboxed.eval("let engine_cwd: string = cwd();").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Sandbox error: cwd is not available in sandbox mode"
+        );
+        assert!(sandboxed.call_function("cwd", &[]).is_err());
+
+        // the function was refused, not removed, so other engines can still call it
+        let mut trusted = Engine::new();
+        assert!(trusted.call_function("cwd", &[]).is_ok());
+        assert!(!limits::is_sandboxed());
+    }
+
+    #[test]
+    fn test_capture_output() {
+        let _guard = ENGINE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut engine = Engine::new();
+        let buffer = output::BufferOutput::new();
+        engine.set_output(buffer.clone());
+        let result = engine.eval("println(\"engine captured\");");
+        engine.set_output(output::StdoutOutput);
+        result.unwrap();
+        assert!(buffer.contents().contains("engine captured\n"));
+
+        let errors = output::BufferOutput::new();
+        engine.set_errors(errors.clone());
+        let result =
+            engine.eval("try {\n    let engine_caught: int = 1 / 0;\n} catch {\n}\nprint(\"\");");
+        engine.set_errors(output::StderrOutput);
+        result.unwrap();
+        assert!(errors.contents().contains("Error in Try block: "));
+    }
+}
@@ -9,463 +9,421 @@ lazy_static! {
 
 pub mod functions {
 
-    use crate::base_variable::base_types::BaseTypes;
+    use crate::base_variable::base_types::{BaseTypes, GetType};
     use crate::base_variable::variable::Variable;
-    use crate::collection::collections::Array;
-    use crate::collection::collections::Dictionary;
+    use crate::bytecode::Chunk;
     use crate::node::nodes::ASTNode;
+    use std::collections::HashMap;
+    use std::error::Error;
     use std::fmt;
-
-    pub enum FunctionTypes {
-        // std array functions
-        ArrayPopFn(fn(&mut Array) -> Option<BaseTypes>),
-        ArrayPushFn(fn(&mut Array, BaseTypes)),
-        ArrayRemoveFn(fn(&mut Array, BaseTypes)),
-        ArrayInsertFn(fn(&mut Array, BaseTypes, BaseTypes)),
-        ArrayGetFn(fn(&Array, BaseTypes) -> Option<BaseTypes>),
-        ArraySetFn(fn(&mut Array, BaseTypes, BaseTypes) -> Option<BaseTypes>),
-        ArrayAppendFn(fn(&mut Array, BaseTypes)),
-        ArrayPrint(fn(&Array)),
-
-        // std dictionary functions
-        DictionaryAddFn(fn(&mut Dictionary, BaseTypes, BaseTypes)),
-        DictionaryRemoveFn(fn(&mut Dictionary, BaseTypes)),
-
-        DictionaryGetFn(fn(&Dictionary, BaseTypes) -> Option<(BaseTypes, BaseTypes)>),
-        DictionarySetFn(fn(&mut Dictionary, BaseTypes, BaseTypes)),
-        DictionaryKeysFn(fn(&Dictionary) -> Vec<BaseTypes>),
-        DictionaryValuesFn(fn(&Dictionary) -> Vec<BaseTypes>),
-        DictionaryPrint(fn(&Dictionary)),
-
-        // std functions
-        DoubleFloatArrayFn(fn(f64, f64) -> Vec<f64>),
-        DoubleStringNoOutFn(fn(String, String)),
-        FloatFn(fn(f64)),
-        DoubleFloatFn(fn(f64, f64) -> f64),
-        SingleFloatFn(fn(f64) -> f64),
-        NoArgFloatFn(fn() -> f64),
-        StringFn(fn(String)),
-        DoubleStringFn(fn(String, String) -> String),
-        SingleStringFn(fn(String) -> String),
-        EchoFn(fn(String)),
+    use std::sync::Arc;
+
+    /// The type of a value in the signature of a native function, the names are the ones used by
+    /// jist declarations with number for Int or Float and any for every value
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum ValueType {
+        Any,
+        Null,
+        Int,
+        Float,
+        Number,
+        String,
+        Bool,
+        Char,
+        Array,
+        Dictionary,
+        Regex,
+        DateTime,
+        Duration,
+        OneOf(Vec<ValueType>),
     }
 
-    use std::any::Any;
-    impl PartialEq for FunctionTypes {
-        fn eq(&self, other: &Self) -> bool {
-            match (self, other) {
-                (FunctionTypes::FloatFn(f1), FunctionTypes::FloatFn(f2)) => {
-                    f1 as *const _ == f2 as *const _
-                }
-                (FunctionTypes::DoubleFloatFn(f1), FunctionTypes::DoubleFloatFn(f2)) => {
-                    f1 as *const _ == f2 as *const _
-                }
-                (FunctionTypes::SingleFloatFn(f1), FunctionTypes::SingleFloatFn(f2)) => {
-                    f1 as *const _ == f2 as *const _
-                }
-                (FunctionTypes::NoArgFloatFn(f1), FunctionTypes::NoArgFloatFn(f2)) => {
-                    f1 as *const _ == f2 as *const _
-                }
-                (FunctionTypes::StringFn(f1), FunctionTypes::StringFn(f2)) => {
-                    f1 as *const _ == f2 as *const _
-                }
-                (FunctionTypes::DoubleStringFn(f1), FunctionTypes::DoubleStringFn(f2)) => {
-                    f1 as *const _ == f2 as *const _
-                }
-                (FunctionTypes::SingleStringFn(f1), FunctionTypes::SingleStringFn(f2)) => {
-                    f1 as *const _ == f2 as *const _
-                }
-                (FunctionTypes::EchoFn(f1), FunctionTypes::EchoFn(f2)) => {
-                    f1 as *const _ == f2 as *const _
-                }
-                _ => false, // Different types cannot be equal
+    impl ValueType {
+        /// Read a type name like int, string?, array<int> or string | regex, a ? suffix also
+        /// accepts null
+        pub fn parse(name: &str) -> Result<ValueType, String> {
+            let name = name.trim();
+            if let Some(inner) = name.strip_suffix('?') {
+                return Ok(ValueType::OneOf(vec![ValueType::parse(inner)?, ValueType::Null]));
+            }
+            if name.contains('|') {
+                let types = name
+                    .split('|')
+                    .map(ValueType::parse)
+                    .collect::<Result<Vec<_>, _>>()?;
+                return Ok(ValueType::OneOf(types));
+            }
+            // the element types of collections are not checked
+            let base = name.split('<').next().unwrap_or(name).trim();
+            match base {
+                "any" => Ok(ValueType::Any),
+                "null" => Ok(ValueType::Null),
+                "int" => Ok(ValueType::Int),
+                "float" => Ok(ValueType::Float),
+                "number" => Ok(ValueType::Number),
+                "string" => Ok(ValueType::String),
+                "boolean" => Ok(ValueType::Bool),
+                "char" => Ok(ValueType::Char),
+                "array" => Ok(ValueType::Array),
+                "dict" => Ok(ValueType::Dictionary),
+                "regex" => Ok(ValueType::Regex),
+                "datetime" => Ok(ValueType::DateTime),
+                "duration" => Ok(ValueType::Duration),
+                _ => Err(format!("unknown type '{}'", name)),
             }
         }
+
+        /// Check if a value has this type, chars are accepted as strings like the std library
+        /// has always done
+        pub fn accepts(&self, value: &BaseTypes) -> bool {
+            matches!(
+                (self, value),
+                (ValueType::Any, _)
+                    | (ValueType::Null, BaseTypes::Null)
+                    | (ValueType::Int, BaseTypes::Int(_))
+                    | (ValueType::Float, BaseTypes::Float(_))
+                    | (ValueType::Number, BaseTypes::Int(_) | BaseTypes::Float(_))
+                    | (
+                        ValueType::String,
+                        BaseTypes::StringWrapper(_) | BaseTypes::Char(_)
+                    )
+                    | (ValueType::Bool, BaseTypes::Bool(_))
+                    | (ValueType::Char, BaseTypes::Char(_))
+                    | (ValueType::Array, BaseTypes::Array(_))
+                    | (ValueType::Dictionary, BaseTypes::Dictionary(_))
+                    | (ValueType::Regex, BaseTypes::Regex(_))
+                    | (ValueType::DateTime, BaseTypes::DateTime(_))
+                    | (ValueType::Duration, BaseTypes::Duration(_))
+            ) || matches!(self, ValueType::OneOf(types) if types.iter().any(|t| t.accepts(value)))
+        }
     }
 
-    pub fn call_function(func: &FunctionTypes, arguments: Vec<Box<dyn Any>>) -> Box<dyn Any> {
-        match func {
-            FunctionTypes::DoubleFloatFn(f) => {
-                if arguments.len() == 2 {
-                    let arg1 = arguments[0].downcast_ref::<f64>().expect("Expected f64");
-                    let arg2 = arguments[1].downcast_ref::<f64>().expect("Expected f64");
-                    let result = f(*arg1, *arg2);
-                    //println!("DoubleFn result: {}", result);
-                    return Box::new(result);
-                } else {
-                    panic!("Expected exactly two arguments for DoubleFn");
+    impl fmt::Display for ValueType {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                ValueType::Any => write!(f, "Any"),
+                ValueType::Null => write!(f, "Null"),
+                ValueType::Int => write!(f, "Int"),
+                ValueType::Float => write!(f, "Float"),
+                ValueType::Number => write!(f, "Int or Float"),
+                ValueType::String => write!(f, "String"),
+                ValueType::Bool => write!(f, "Bool"),
+                ValueType::Char => write!(f, "Char"),
+                ValueType::Array => write!(f, "Array"),
+                ValueType::Dictionary => write!(f, "Dictionary"),
+                ValueType::Regex => write!(f, "Regex"),
+                ValueType::DateTime => write!(f, "DateTime"),
+                ValueType::Duration => write!(f, "Duration"),
+                ValueType::OneOf(types) => {
+                    let names: Vec<String> = types.iter().map(|t| t.to_string()).collect();
+                    write!(f, "{}", names.join(" or "))
                 }
             }
-            FunctionTypes::SingleFloatFn(f) => {
-                if arguments.len() == 1 {
-                    let arg = arguments[0].downcast_ref::<f64>().expect("Expected f64");
-                    let result = f(*arg);
-                    //println!("SingleFn result: {}", result);
-                    return Box::new(result);
-                } else {
-                    panic!("Expected exactly one argument for SingleFn");
-                }
+        }
+    }
+
+    /// A parameter of a native function, an optional parameter can be left out or given null
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Parameter {
+        pub name: String,
+        pub value_type: ValueType,
+        pub optional: bool,
+    }
+
+    /// The typed signature of a native function, when it is variadic the last parameter takes
+    /// any number of values
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Signature {
+        pub name: String,
+        pub parameters: Vec<Parameter>,
+        pub return_type: ValueType,
+        pub variadic: bool,
+    }
+
+    impl Signature {
+        pub fn new(
+            name: &str,
+            parameters: Vec<Parameter>,
+            return_type: ValueType,
+            variadic: bool,
+        ) -> Signature {
+            Signature {
+                name: name.to_string(),
+                parameters,
+                return_type,
+                variadic,
             }
-            FunctionTypes::NoArgFloatFn(f) => {
-                if arguments.is_empty() {
-                    let result = f();
-                    //println!("NoArgFn result: {}", result);
-                    return Box::new(result);
-                } else {
-                    panic!("Expected no arguments for NoArgFn");
-                }
+        }
+
+        /// Read a signature written like a jist declaration e.g.
+        /// pad_left(text: string, width: int, fill: string?) -> string, a ? on a parameter type
+        /// makes it optional and ...name makes the last parameter variadic
+        pub fn parse(text: &str) -> Result<Signature, String> {
+            let invalid = || format!("invalid signature '{}'", text);
+            let (head, return_type) = text.rsplit_once("->").ok_or_else(invalid)?;
+            let (name, parameters) = head.trim().split_once('(').ok_or_else(invalid)?;
+            let parameters = parameters.trim().strip_suffix(')').ok_or_else(invalid)?;
+
+            let mut signature = Signature::new(
+                name.trim(),
+                Vec::new(),
+                ValueType::parse(return_type)?,
+                false,
+            );
+            let mut depth = 0;
+            let mut parts = vec![String::new()];
+            for c in parameters.chars() {
+                match c {
+                    '<' => depth += 1,
+                    '>' => depth -= 1,
+                    ',' if depth == 0 => {
+                        parts.push(String::new());
+                        continue;
+                    }
+                    _ => {}
+                }
+                parts.last_mut().unwrap().push(c);
             }
-            FunctionTypes::StringFn(f) => {
-                if arguments.len() == 1 {
-                    let arg = arguments[0]
-                        .downcast_ref::<String>()
-                        .expect("Expected String");
-                    f(arg.clone());
-                    //println!("StringFn called with: {}", arg);
-                    return Box::new(());
-                } else {
-                    panic!("Expected exactly one argument for StringFn");
-                }
+            for part in parts.iter().filter(|part| !part.trim().is_empty()) {
+                if signature.variadic {
+                    return Err(format!("{}, only the last parameter can be variadic", invalid()));
+                }
+                let (name, type_name) = part.split_once(':').ok_or_else(invalid)?;
+                let mut name = name.trim();
+                if let Some(rest) = name.strip_prefix("...") {
+                    name = rest;
+                    signature.variadic = true;
+                }
+                let type_name = type_name.trim();
+                let optional = type_name.ends_with('?');
+                signature.parameters.push(Parameter {
+                    name: name.to_string(),
+                    value_type: ValueType::parse(type_name.trim_end_matches('?'))?,
+                    optional,
+                });
             }
-            FunctionTypes::DoubleStringFn(f) => {
-                if arguments.len() == 2 {
-                    let arg1 = arguments[0]
-                        .downcast_ref::<String>()
-                        .expect("Expected String");
-                    let arg2 = arguments[1]
-                        .downcast_ref::<String>()
-                        .expect("Expected String");
-                    let result = f(arg1.clone(), arg2.clone());
-                    //println!("DoubleStringFn result: {}", result);
-                    return Box::new(result);
+            Ok(signature)
+        }
+
+        /// Check the arguments of a call against the parameters, null given for trailing
+        /// optional parameters is dropped so the function sees them as left out
+        ///
+        /// params: args: &[BaseTypes] -> The arguments of the call
+        ///
+        /// returns: Result<Vec<BaseTypes>, Box<dyn Error>> -> The arguments to pass on or an
+        /// argument or type error
+        pub fn check_arguments(&self, args: &[BaseTypes]) -> Result<Vec<BaseTypes>, Box<dyn Error>> {
+            let count = self.parameters.len();
+            // a variadic parameter takes zero or more values so it is never required
+            let fixed = if self.variadic { count - 1 } else { count };
+            let required = self.parameters[..fixed]
+                .iter()
+                .filter(|p| !p.optional)
+                .count();
+            if args.len() < required || (!self.variadic && args.len() > count) {
+                let expected = if self.variadic {
+                    format!("at least {}", required)
+                } else if required == count {
+                    count.to_string()
                 } else {
-                    panic!("Expected exactly two arguments for DoubleStringFn");
-                }
+                    format!("{} to {}", required, count)
+                };
+                return Err(format!(
+                    "Argument error: {} expects {} argument(s) but got {}",
+                    self.name,
+                    expected,
+                    args.len()
+                )
+                .into());
             }
-            FunctionTypes::SingleStringFn(f) => {
-                if arguments.len() == 1 {
-                    let arg = arguments[0]
-                        .downcast_ref::<String>()
-                        .expect("Expected String");
-                    let result = f(arg.clone());
-                    //print!("SingleStringFn result: {}", result);
-                    Box::new(result)
-                } else {
-                    panic!("Expected exactly one argument for SingleStringFn");
+
+            for (index, value) in args.iter().enumerate() {
+                let parameter = &self.parameters[index.min(count - 1)];
+                let left_out = parameter.optional && matches!(value, BaseTypes::Null);
+                if !left_out && !parameter.value_type.accepts(value) {
+                    return Err(format!(
+                        "Type error: {} expects argument {} to be {} but got {}",
+                        self.name,
+                        index + 1,
+                        parameter.value_type,
+                        value.GetType()
+                    )
+                    .into());
                 }
             }
 
-            FunctionTypes::DoubleFloatArrayFn(f) => {
-                if arguments.len() == 2 {
-                    let arg1 = arguments[0].downcast_ref::<f64>().expect("Expected f64");
-                    let arg2 = arguments[1].downcast_ref::<f64>().expect("Expected f64");
-                    let result = f(*arg1, *arg2);
-                    //println!("DoubleFloatArrayFn result: {:?}", result);
-                    return Box::new(result);
-                } else {
-                    panic!("Expected exactly two arguments for DoubleFloatArrayFn");
-                }
+            let mut args = args.to_vec();
+            while args.len() > required
+                && matches!(args.last(), Some(BaseTypes::Null))
+                && self.parameters[(args.len() - 1).min(count - 1)].optional
+            {
+                args.pop();
             }
+            Ok(args)
+        }
 
-            FunctionTypes::ArrayAppendFn(f) => {
-                if arguments.len() == 2 {
-                    let arg1 = arguments[0]
-                        .downcast_ref::<Array>()
-                        .expect("Expected Array");
-                    let arg2 = arguments[1]
-                        .downcast_ref::<BaseTypes>()
-                        .expect("Expected BaseTypes");
-                    f(&mut arg1.clone(), arg2.clone());
-                    //println!("ArrayAppendFn called with: {:?}", arg2);
-                    return Box::new(());
-                } else {
-                    panic!("Expected exactly two arguments for ArrayAppendFn");
-                }
+        /// Check that a function gave back the type its signature declares
+        pub fn check_return(&self, value: &BaseTypes) -> Result<(), Box<dyn Error>> {
+            if self.return_type.accepts(value) {
+                return Ok(());
             }
+            Err(format!(
+                "Type error: {} should return {} but returned {}",
+                self.name,
+                self.return_type,
+                value.GetType()
+            )
+            .into())
+        }
+    }
 
-            FunctionTypes::ArrayPopFn(f) => {
-                //println!("ArrayPopFn called arguments: {:?}", arguments);
-                if arguments.len() == 1 {
-                    // Extract the argument and ensure it's of type Array
-                    let arg = arguments[0]
-                        .downcast_ref::<Array>()
-                        .expect("Expected Array");
+    /// A function written in rust that jist code can call by name
+    pub trait NativeFunction: Send + Sync {
+        fn signature(&self) -> &Signature;
 
-                    // Call the function with the array
-                    let result = f(&mut arg.clone()); // Array passed here
+        /// Run the function, the arguments have already been checked against the signature
+        fn call(&self, args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>>;
 
-                    return Box::new(result);
-                } else {
-                    panic!("Expected exactly one argument for ArrayPopFn (the array itself)");
-                }
-            }
+        /// Check the arguments, run the function and check the returned value
+        fn invoke(&self, args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+            let args = self.signature().check_arguments(args)?;
+            let result = self.call(&args)?;
+            self.signature().check_return(&result)?;
+            Ok(result)
+        }
+    }
 
-            FunctionTypes::ArrayPushFn(f) => {
-                //print arguments
-                if arguments.len() == 2 {
-                    let arg1 = arguments[0]
-                        .downcast_ref::<Array>()
-                        .expect("Expected Array");
-                    let arg2 = arguments[1]
-                        .downcast_ref::<BaseTypes>()
-                        .expect("Expected BaseTypes");
-
-                    f(&mut arg1.clone(), arg2.clone());
-                    //println!("ArrayPushFn called with: {:?}", arg2);
-                    return Box::new(());
-                } else {
-                    panic!("Expected exactly two arguments for ArrayPushFn");
-                }
-            }
+    /// A method written in rust that jist code calls on a value with a dot, changes the method
+    /// makes to the receiver are written back to the variable it was called on
+    pub trait NativeMethod: Send + Sync {
+        fn signature(&self) -> &Signature;
+
+        /// Run the method, the arguments have already been checked against the signature
+        fn call(
+            &self,
+            receiver: &mut BaseTypes,
+            args: &[BaseTypes],
+        ) -> Result<BaseTypes, Box<dyn Error>>;
+
+        /// Check the arguments, run the method and check the returned value
+        fn invoke(
+            &self,
+            receiver: &mut BaseTypes,
+            args: &[BaseTypes],
+        ) -> Result<BaseTypes, Box<dyn Error>> {
+            let args = self.signature().check_arguments(args)?;
+            let result = self.call(receiver, &args)?;
+            self.signature().check_return(&result)?;
+            Ok(result)
+        }
+    }
 
-            FunctionTypes::DoubleStringNoOutFn(f) => {
-                if arguments.len() == 2 {
-                    let arg1 = arguments[0]
-                        .downcast_ref::<String>()
-                        .expect("Expected String");
-                    let arg2 = arguments[1]
-                        .downcast_ref::<String>()
-                        .expect("Expected String");
-                    f(arg1.clone(), arg2.clone());
-                    //println!("DoubleStringNoOutFn called with: {:?}, {:?}", arg1, arg2);
-                    return Box::new(());
-                } else {
-                    panic!("Expected exactly two arguments for DoubleStringNoOutFn");
-                }
-            }
+    /// A signature with the closure that runs it, used for both functions and methods
+    pub struct Native<F> {
+        signature: Signature,
+        body: F,
+    }
 
-            FunctionTypes::ArrayRemoveFn(f) => {
-                if arguments.len() == 2 {
-                    let arg1 = arguments[0]
-                        .downcast_ref::<Array>()
-                        .expect("Expected Array");
-                    let arg2 = arguments[1]
-                        .downcast_ref::<BaseTypes>()
-                        .expect("Expected BaseTypes");
-
-                    f(&mut arg1.clone(), (*arg2).clone().into());
-                    //println!("ArrayRemoveFn called with: {:?}", arg2);
-                    return Box::new(());
-                } else {
-                    panic!("Expected exactly two arguments for ArrayRemoveFn");
-                }
-            }
+    impl<F> Native<F> {
+        pub fn new(signature: Signature, body: F) -> Native<F> {
+            Native { signature, body }
+        }
+    }
 
-            FunctionTypes::ArrayInsertFn(f) => {
-                if arguments.len() == 3 {
-                    let arg1 = arguments[0]
-                        .downcast_ref::<Array>()
-                        .expect("Expected Array");
-                    let arg2 = arguments[1]
-                        .downcast_ref::<usize>()
-                        .expect("Expected usize");
-                    let arg3 = arguments[2]
-                        .downcast_ref::<BaseTypes>()
-                        .expect("Expected BaseTypes");
-                    f(&mut arg1.clone(), (*arg2).clone().into(), arg3.clone());
-                    //println!("ArrayInsertFn called with: {:?}", arg2);
-                    return Box::new(());
-                } else {
-                    panic!("Expected exactly three arguments for ArrayInsertFn");
-                }
-            }
+    impl<F> NativeFunction for Native<F>
+    where
+        F: Fn(&[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> + Send + Sync,
+    {
+        fn signature(&self) -> &Signature {
+            &self.signature
+        }
 
-            FunctionTypes::ArrayGetFn(f) => {
-                if arguments.len() == 2 {
-                    let mut arg1 = arguments[0]
-                        .downcast_ref::<Array>()
-                        .expect("Expected Array");
-                    let arg2 = arguments[1]
-                        .downcast_ref::<BaseTypes>()
-                        .expect("Expected BaseTypes");
+        fn call(&self, args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+            (self.body)(args)
+        }
+    }
 
-                    let result = f(&mut arg1, arg2.clone().into());
-                    let result = result.unwrap();
+    impl<F> NativeMethod for Native<F>
+    where
+        F: Fn(&mut BaseTypes, &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> + Send + Sync,
+    {
+        fn signature(&self) -> &Signature {
+            &self.signature
+        }
 
-                    return Box::new(result);
-                } else {
-                    panic!("Expected exactly two arguments for ArrayGetFn");
-                }
-            }
+        fn call(
+            &self,
+            receiver: &mut BaseTypes,
+            args: &[BaseTypes],
+        ) -> Result<BaseTypes, Box<dyn Error>> {
+            (self.body)(receiver, args)
+        }
+    }
 
-            FunctionTypes::ArraySetFn(f) => {
-                if arguments.len() == 3 {
-                    let arg1 = arguments[0]
-                        .downcast_ref::<Array>()
-                        .expect("Expected Array");
-                    let arg2 = arguments[1]
-                        .downcast_ref::<BaseTypes>()
-                        .expect("Expected BaseTypes");
-
-                    let arg3 = arguments[2]
-                        .downcast_ref::<BaseTypes>()
-                        .expect("Expected BaseTypes");
-                    let result = f(&mut arg1.clone(), (*arg2).clone().into(), arg3.clone());
-                    //println!("ArraySetFn result: {:?}", result);
-                    return Box::new(result);
-                } else {
-                    panic!("Expected exactly three arguments for ArraySetFn");
-                }
-            }
+    /// The native functions by name and the native methods by the type they are called on, the
+    /// entries are shared so a call can run after the registry is unlocked
+    #[derive(Default)]
+    pub struct Registry {
+        functions: HashMap<String, Arc<dyn NativeFunction>>,
+        methods: HashMap<(String, String), Arc<dyn NativeMethod>>,
+    }
 
-            FunctionTypes::ArrayPrint(f) => {
-                if arguments.len() == 1 {
-                    let arg = arguments[0]
-                        .downcast_ref::<Array>()
-                        .expect("Expected Array");
-                    f(&arg.clone());
-                    //println!("ArrayPrint called with: {:?}", arg);
-                    return Box::new(());
-                } else {
-                    panic!("Expected exactly one argument for ArrayPrint");
-                }
-            }
+    impl Registry {
+        pub fn new() -> Registry {
+            Registry::default()
+        }
 
-            FunctionTypes::DictionaryPrint(f) => {
-                if arguments.len() == 1 {
-                    let arg = arguments[0]
-                        .downcast_ref::<Dictionary>()
-                        .expect("Expected Dictionary");
-                    f(&arg.clone());
-                    //println!("DictionaryPrint called with: {:?}", arg);
-                    return Box::new(());
-                } else {
-                    panic!("Expected exactly one argument for DictionaryPrint");
-                }
-            }
+        /// Add a function declared with a signature like add(a: number, b: number) -> number,
+        /// an invalid signature is a bug in the caller so it panics
+        pub fn register<F>(&mut self, signature: &str, body: F)
+        where
+            F: Fn(&[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> + Send + Sync + 'static,
+        {
+            let signature = Signature::parse(signature).unwrap_or_else(|e| panic!("{}", e));
+            self.insert(Arc::new(Native::new(signature, body)));
+        }
 
-            FunctionTypes::DictionaryAddFn(f) => {
-                if arguments.len() == 3 {
-                    let arg1 = arguments[0]
-                        .downcast_ref::<Dictionary>()
-                        .expect("Expected Dictionary");
-                    let arg2 = arguments[1]
-                        .downcast_ref::<BaseTypes>()
-                        .expect("Expected BaseTypes");
-                    let arg3 = arguments[2]
-                        .downcast_ref::<BaseTypes>()
-                        .expect("Expected BaseTypes");
-                    f(&mut arg1.clone(), arg2.clone(), arg3.clone());
-                    //println!("DictionaryAddFn called with: {:?}", arg2);
-                    return Box::new(());
-                } else {
-                    panic!("Expected exactly three arguments for DictionaryAddFn");
-                }
-            }
+        /// Add a method for values of a type, the type is the name GetType gives e.g. Array
+        pub fn register_method<F>(&mut self, receiver: &str, signature: &str, body: F)
+        where
+            F: Fn(&mut BaseTypes, &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>>
+                + Send
+                + Sync
+                + 'static,
+        {
+            let signature = Signature::parse(signature).unwrap_or_else(|e| panic!("{}", e));
+            self.insert_method(receiver, Arc::new(Native::new(signature, body)));
+        }
 
-            FunctionTypes::DictionaryRemoveFn(f) => {
-                if arguments.len() == 2 {
-                    let arg1 = arguments[0]
-                        .downcast_ref::<Dictionary>()
-                        .expect("Expected Dictionary");
-                    let arg2 = arguments[1]
-                        .downcast_ref::<BaseTypes>()
-                        .expect("Expected BaseTypes");
-                    f(&mut arg1.clone(), arg2.clone());
-                    //println!("DictionaryRemoveFn called with: {:?}", arg2);
-                    return Box::new(());
-                } else {
-                    panic!("Expected exactly two arguments for DictionaryRemoveFn");
-                }
-            }
+        /// Add a function, it replaces a function with the same name
+        pub fn insert(&mut self, function: Arc<dyn NativeFunction>) {
+            self.functions
+                .insert(function.signature().name.clone(), function);
+        }
 
-            FunctionTypes::DictionaryGetFn(f) => {
-                if arguments.len() == 1 {
-                    let arg = arguments[0]
-                        .downcast_ref::<Dictionary>()
-                        .expect("Expected Dictionary");
-                    let arg2 = arguments[1]
-                        .downcast_ref::<BaseTypes>()
-                        .expect("Expected BaseTypes");
-                    let result: Option<(BaseTypes, BaseTypes)> = f(arg, arg2.clone());
-
-                    // result is now an Option<(BaseTypes, BaseTypes)>
-                    return Box::new(result);
-                } else {
-                    panic!("Expected exactly one argument for DictionaryGetFn");
-                }
-            }
+        /// Add a method, it replaces a method with the same name on the same type
+        pub fn insert_method(&mut self, receiver: &str, method: Arc<dyn NativeMethod>) {
+            let key = (receiver.to_string(), method.signature().name.clone());
+            self.methods.insert(key, method);
+        }
 
-            FunctionTypes::DictionarySetFn(f) => {
-                if arguments.len() == 3 {
-                    let arg1 = arguments[0]
-                        .downcast_ref::<Dictionary>()
-                        .expect("Expected Dictionary");
-                    let arg2 = arguments[1]
-                        .downcast_ref::<BaseTypes>()
-                        .expect("Expected BaseTypes");
-                    let arg3 = arguments[2]
-                        .downcast_ref::<BaseTypes>()
-                        .expect("Expected BaseTypes");
-                    let result = f(&mut arg1.clone(), arg2.clone(), arg3.clone());
-                    //println!("DictionarySetFn result: {:?}", result);
-                    return Box::new(());
-                } else {
-                    panic!("Expected exactly three arguments for DictionarySetFn");
-                }
-            }
+        pub fn get(&self, name: &str) -> Option<Arc<dyn NativeFunction>> {
+            self.functions.get(name).cloned()
+        }
 
-            FunctionTypes::DictionaryKeysFn(f) => {
-                if arguments.len() == 1 {
-                    let arg = arguments[0]
-                        .downcast_ref::<Dictionary>()
-                        .expect("Expected Dictionary");
-                    let result = f(arg); // No need to clone `arg` here
-                    return Box::new(result); // Ensure `result` is of the right type
-                } else {
-                    panic!("Expected exactly one argument for DictionaryKeysFn");
-                }
-            }
+        pub fn get_method(&self, receiver: &str, name: &str) -> Option<Arc<dyn NativeMethod>> {
+            self.methods
+                .get(&(receiver.to_string(), name.to_string()))
+                .cloned()
+        }
 
-            FunctionTypes::DictionaryValuesFn(f) => {
-                if arguments.len() == 1 {
-                    let arg = arguments[0]
-                        .downcast_ref::<Dictionary>()
-                        .expect("Expected Dictionary");
-                    let result = f(arg); // No need to clone `arg` here
-                    return Box::new(result); // Ensure `result` is of the right type
-                } else {
-                    panic!("Expected exactly one argument for DictionaryValuesFn");
-                }
-            }
+        pub fn contains(&self, name: &str) -> bool {
+            self.functions.contains_key(name)
+        }
 
-            FunctionTypes::EchoFn(f) => {
-                if arguments.len() == 1 {
-                    let arg = if let Some(s) = arguments[0].downcast_ref::<String>() {
-                        // Argument is already a String
-                        s.clone()
-                    } else if let Some(i) = arguments[0].downcast_ref::<i32>() {
-                        // Argument is an i32, convert to String
-                        i.to_string()
-                    } else if let Some(flt) = arguments[0].downcast_ref::<f64>() {
-                        // Argument is a f64, convert to String
-                        flt.to_string()
-                    } else if let Some(b) = arguments[0].downcast_ref::<bool>() {
-                        // Argument is a bool, convert to String
-                        b.to_string()
-                    } else if let Some(b) = arguments[0].downcast_ref::<char>() {
-                        // Argument is a char, convert to String
-                        b.to_string()
-                    } else {
-                        panic!(
-                            "Expected String or convertible type, but found {:?}",
-                            arguments[0].type_id()
-                        );
-                    };
-
-                    f(arg); // Call the function with the converted argument.
-                    return Box::new(());
-                } else {
-                    println!("Arguments: {:?}", arguments);
-                    panic!(
-                        "Expected exactly one argument for EchoFn, but got {}",
-                        arguments.len()
-                    );
-                }
-            }
-            _ => {
-                panic!("Function not implemented");
-            }
+        /// Take a function out of the registry, used by sandbox mode
+        pub fn remove(&mut self, name: &str) -> Option<Arc<dyn NativeFunction>> {
+            self.functions.remove(name)
         }
     }
 
@@ -474,7 +432,13 @@ pub mod functions {
         pub name: String,
         pub return_type: BaseTypes,
         pub arguments: Vec<Variable>,
+        // the default expression of each argument, evaluated when the call leaves it out
+        pub defaults: Vec<Option<String>>,
+        // the last argument collects the extra values of a call as an array
+        pub variadic: bool,
         pub body: Vec<ASTNode>,
+        // the body compiled for the virtual machine, None when the function is walked
+        pub code: Option<Arc<Chunk>>,
     }
 
     // Constructor for creating a new function
@@ -488,12 +452,23 @@ pub mod functions {
             Function {
                 name,
                 return_type,
+                defaults: vec![None; arguments.len()],
                 arguments,
+                variadic: false,
                 body,
+                code: None,
             }
         }
     }
 
+    /// The values passed to a call, named arguments are kept apart until they are matched to the
+    /// parameters of the called function
+    #[derive(Clone, Debug, Default)]
+    pub struct CallArguments {
+        pub positional: Vec<BaseTypes>,
+        pub named: Vec<(String, BaseTypes)>,
+    }
+
     // Implementing Display trait for Function
     impl fmt::Display for Function {
         fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -527,3 +502,127 @@ pub mod functions {
         }
     }
 }
+
+#[cfg(test)]
+mod function_tests {
+    use super::functions::*;
+    use crate::base_variable::base_types::BaseTypes;
+
+    fn text(value: &str) -> BaseTypes {
+        BaseTypes::StringWrapper(value.to_string())
+    }
+
+    fn error(result: Result<Vec<BaseTypes>, Box<dyn std::error::Error>>) -> String {
+        result.unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_parse_signature() {
+        let signature =
+            Signature::parse("pad_left(text: string, width: int, fill: string?) -> string").unwrap();
+        assert_eq!(signature.name, "pad_left");
+        assert_eq!(signature.parameters.len(), 3);
+        assert_eq!(signature.parameters[1].value_type, ValueType::Int);
+        assert!(signature.parameters[2].optional);
+        assert!(!signature.variadic);
+        assert_eq!(signature.return_type, ValueType::String);
+
+        let signature = Signature::parse("concat(a: string, ...rest: string) -> string").unwrap();
+        assert!(signature.variadic);
+        assert_eq!(signature.parameters[1].name, "rest");
+
+        let signature = Signature::parse("f(a: dict<string, int>, b: string | regex) -> int?").unwrap();
+        assert_eq!(signature.parameters.len(), 2);
+        assert_eq!(signature.parameters[1].value_type.to_string(), "String or Regex");
+        assert!(signature.return_type.accepts(&BaseTypes::Null));
+
+        assert!(Signature::parse("f(...a: int, b: int) -> int").is_err());
+        assert!(Signature::parse("f(a: widget) -> int").is_err());
+        assert!(Signature::parse("f(a: int)").is_err());
+    }
+
+    #[test]
+    fn test_check_arguments() {
+        let signature =
+            Signature::parse("pad_left(text: string, width: int, fill: string?) -> string").unwrap();
+        assert_eq!(
+            error(signature.check_arguments(&[text("7")])),
+            "Argument error: pad_left expects 2 to 3 argument(s) but got 1"
+        );
+        assert_eq!(
+            error(signature.check_arguments(&[text("7"), BaseTypes::Float(3.0)])),
+            "Type error: pad_left expects argument 2 to be Int but got Float"
+        );
+        // a char is accepted where a string is expected
+        assert!(signature
+            .check_arguments(&[BaseTypes::Char('7'), BaseTypes::Int(3)])
+            .is_ok());
+        // null for a trailing optional parameter is the same as leaving it out
+        let args = signature
+            .check_arguments(&[text("7"), BaseTypes::Int(3), BaseTypes::Null])
+            .unwrap();
+        assert_eq!(args.len(), 2);
+
+        let signature = Signature::parse("max(a: number, b: number) -> number").unwrap();
+        assert_eq!(
+            error(signature.check_arguments(&[BaseTypes::Int(1), text("2")])),
+            "Type error: max expects argument 2 to be Int or Float but got String"
+        );
+    }
+
+    #[test]
+    fn test_check_variadic_arguments() {
+        let signature = Signature::parse("concat(a: string, ...rest: string) -> string").unwrap();
+        assert!(signature.check_arguments(&[text("a")]).is_ok());
+        assert!(signature
+            .check_arguments(&[text("a"), text("b"), text("c")])
+            .is_ok());
+        assert_eq!(
+            error(signature.check_arguments(&[])),
+            "Argument error: concat expects at least 1 argument(s) but got 0"
+        );
+        assert_eq!(
+            error(signature.check_arguments(&[text("a"), text("b"), BaseTypes::Int(3)])),
+            "Type error: concat expects argument 3 to be String but got Int"
+        );
+    }
+
+    #[test]
+    fn test_registry() {
+        let mut registry = Registry::new();
+        registry.register("double(value: int) -> int", |args: &[BaseTypes]| {
+            match args[0] {
+                BaseTypes::Int(i) => Ok(BaseTypes::Int(i * 2)),
+                _ => unreachable!(),
+            }
+        });
+        registry.register("broken() -> int", |_: &[BaseTypes]| Ok(BaseTypes::Null));
+        registry.register_method(
+            "Array",
+            "clear() -> null",
+            |receiver: &mut BaseTypes, _: &[BaseTypes]| {
+                *receiver = BaseTypes::Array(Default::default());
+                Ok(BaseTypes::Null)
+            },
+        );
+
+        assert!(registry.contains("double"));
+        assert!(registry.get("missing").is_none());
+        let double = registry.get("double").unwrap();
+        assert_eq!(double.invoke(&[BaseTypes::Int(21)]).unwrap(), BaseTypes::Int(42));
+        assert_eq!(
+            double.invoke(&[text("21")]).unwrap_err().to_string(),
+            "Type error: double expects argument 1 to be Int but got String"
+        );
+        assert_eq!(
+            registry.get("broken").unwrap().invoke(&[]).unwrap_err().to_string(),
+            "Type error: broken should return Int but returned Null"
+        );
+
+        let mut receiver = BaseTypes::Array(vec![BaseTypes::Int(1)].into());
+        let clear = registry.get_method("Array", "clear").unwrap();
+        clear.invoke(&mut receiver, &[]).unwrap();
+        assert_eq!(receiver, BaseTypes::Array(Default::default()));
+        assert!(registry.get_method("Dictionary", "clear").is_none());
+    }
+}
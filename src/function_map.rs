@@ -1,9 +1,25 @@
 use crate::function::functions::Function;
 
-use crate::function::functions::FunctionTypes;
+use crate::base_variable::base_types::BaseTypes;
+use crate::collection;
+use crate::limits;
+use crate::function::functions::Registry;
+use crate::output;
+use crate::std_lib::arguments::{get_number, get_string};
+use crate::std_lib::fs;
+use crate::std_lib::json;
+use crate::std_lib::math;
+use crate::std_lib::process;
+use crate::std_lib::random;
+use crate::std_lib::time;
+use crate::std_lib::regex;
+use crate::std_lib::stdin;
+use crate::std_lib::string;
+use crate::std_lib::system;
 
 use std::collections::HashMap;
-use std::sync::Mutex;
+use std::error::Error;
+use std::sync::{Arc, Mutex};
 
 lazy_static::lazy_static! {
     pub static ref USER_FUNCTIONS: Mutex<HashMap<String, Function>> = {
@@ -11,37 +27,158 @@ lazy_static::lazy_static! {
         map.into()
     };
 
-    pub static ref FUNCTIONS: Mutex<HashMap<&'static str, FunctionTypes>> = {
-        let mut map = HashMap::new();
-        map.insert("max", FunctionTypes::DoubleFloatFn(FunctionMap::max as fn(f64, f64) -> f64));
-        map.insert("min", FunctionTypes::DoubleFloatFn(FunctionMap::min as fn(f64, f64) -> f64));
-        map.insert("add", FunctionTypes::DoubleFloatFn(FunctionMap::add as fn(f64, f64) -> f64));
-        map.insert("sub", FunctionTypes::DoubleFloatFn(FunctionMap::sub as fn(f64, f64) -> f64));
-        map.insert("mult", FunctionTypes::DoubleFloatFn(FunctionMap::mult as fn(f64, f64) -> f64));
-        map.insert("divide", FunctionTypes::DoubleFloatFn(FunctionMap::divide as fn(f64, f64) -> f64));
-        map.insert("floor", FunctionTypes::SingleFloatFn(FunctionMap::floor as fn(f64) -> f64));
-        map.insert("ceil", FunctionTypes::SingleFloatFn(FunctionMap::ceil as fn(f64) -> f64));
-        map.insert("round", FunctionTypes::SingleFloatFn(FunctionMap::round as fn(f64) -> f64));
-        map.insert("rand", FunctionTypes::NoArgFloatFn(FunctionMap::rand as fn() -> f64));
-        map.insert("print", FunctionTypes::EchoFn(FunctionMap::print as fn(String)));
-        map.insert("println", FunctionTypes::EchoFn(FunctionMap::println as fn(String)));
-        map.insert("abs", FunctionTypes::SingleFloatFn(FunctionMap::abs as fn(f64) -> f64));
-        map.insert("pow", FunctionTypes::DoubleFloatFn(FunctionMap::pow as fn(f64, f64) -> f64));
-        map.insert("sqrt", FunctionTypes::SingleFloatFn(FunctionMap::sqrt as fn(f64) -> f64));
-        map.insert("log", FunctionTypes::DoubleFloatFn(FunctionMap::log as fn(f64, f64) -> f64));
-        map.insert("sin", FunctionTypes::SingleFloatFn(FunctionMap::sin as fn(f64) -> f64));
-        map.insert("cos", FunctionTypes::SingleFloatFn(FunctionMap::cos as fn(f64) -> f64));
-        map.insert("tan", FunctionTypes::SingleFloatFn(FunctionMap::tan as fn(f64) -> f64));
-        map.insert("concat", FunctionTypes::DoubleStringFn(FunctionMap::concat as fn(String, String) -> String));
-        //map.insert("len", FunctionTypes::SingleFn(FunctionMap::len as fn(String) -> usize));
-        map.insert("to_uppercase", FunctionTypes::SingleStringFn(FunctionMap::to_uppercase as fn(String) -> String));
-        map.insert("to_lowercase", FunctionTypes::SingleStringFn(FunctionMap::to_lowercase as fn(String) -> String));
-        map.insert("trim", FunctionTypes::SingleStringFn(FunctionMap::trim as fn(String) -> String));
-        map.insert("input", FunctionTypes::SingleStringFn(FunctionMap::input as fn(String) -> String));
-        map.insert("read", FunctionTypes::SingleStringFn(FunctionMap::read as fn(String) -> String));
-        map.insert("write", FunctionTypes::DoubleStringNoOutFn(FunctionMap::write as fn(String, String)));
-        map.insert("range", FunctionTypes::DoubleFloatArrayFn(FunctionMap::range as fn(f64, f64) -> Vec<f64>));
-        map.into()
+    pub static ref FUNCTIONS: Mutex<Registry> = {
+        let mut registry = Registry::new();
+        registry.register("rand() -> float", FunctionMap::rand);
+        registry.register("print(value: any) -> null", FunctionMap::print);
+        registry.register("println(value: any) -> null", FunctionMap::println);
+        registry.register("concat(a: string, b: string, ...rest: string) -> string", FunctionMap::concat);
+        registry.register("to_uppercase(text: string) -> string", FunctionMap::to_uppercase);
+        registry.register("to_lowercase(text: string) -> string", FunctionMap::to_lowercase);
+        registry.register("trim(text: string) -> string", FunctionMap::trim);
+        registry.register("input(prompt: string) -> string", FunctionMap::input);
+        registry.register("range(start: number, end: number) -> array", FunctionMap::range);
+
+        // math functions
+        registry.register("max(a: number, b: number) -> number", math::max);
+        registry.register("min(a: number, b: number) -> number", math::min);
+        registry.register("add(a: number, b: number) -> number", math::add);
+        registry.register("sub(a: number, b: number) -> number", math::sub);
+        registry.register("mult(a: number, b: number) -> number", math::mult);
+        registry.register("divide(a: number, b: number) -> number", math::divide);
+        registry.register("pow(base: number, exponent: number) -> number", math::pow);
+        registry.register("abs(value: number) -> number", math::abs);
+        registry.register("sign(value: number) -> number", math::sign);
+        registry.register("clamp(value: number, low: number, high: number) -> number", math::clamp);
+        registry.register("gcd(a: int, b: int) -> int", math::gcd);
+        registry.register("lcm(a: int, b: int) -> int", math::lcm);
+        registry.register("mod_pow(base: int, exponent: int, modulus: int) -> int", math::mod_pow);
+        registry.register("floor(value: number) -> number", math::floor);
+        registry.register("ceil(value: number) -> number", math::ceil);
+        registry.register("round(value: number) -> number", math::round);
+        registry.register("trunc(value: number) -> number", math::trunc);
+        registry.register("sqrt(value: number) -> float", math::sqrt);
+        registry.register("sin(angle: number) -> float", math::sin);
+        registry.register("cos(angle: number) -> float", math::cos);
+        registry.register("tan(angle: number) -> float", math::tan);
+        registry.register("asin(value: number) -> float", math::asin);
+        registry.register("acos(value: number) -> float", math::acos);
+        registry.register("atan(value: number) -> float", math::atan);
+        registry.register("atan2(y: number, x: number) -> float", math::atan2);
+        registry.register("hypot(x: number, y: number) -> float", math::hypot);
+        registry.register("exp(value: number) -> float", math::exp);
+        registry.register("ln(value: number) -> float", math::ln);
+        registry.register("log(value: number, base: number) -> float", math::log);
+        registry.register("log2(value: number) -> float", math::log2);
+        registry.register("log10(value: number) -> float", math::log10);
+        registry.register("is_nan(value: number) -> boolean", math::is_nan);
+        registry.register("is_finite(value: number) -> boolean", math::is_finite);
+
+        // string functions
+        registry.register("len(value: string | array) -> int", string::len);
+        registry.register("split(text: string, separator: string) -> array", string::split);
+        registry.register("join(values: array, separator: string) -> string", string::join);
+        registry.register("replace(text: string, pattern: string, replacement: string) -> string", string::replace);
+        registry.register("contains(text: string, pattern: string) -> boolean", string::contains);
+        registry.register("starts_with(text: string, pattern: string) -> boolean", string::starts_with);
+        registry.register("ends_with(text: string, pattern: string) -> boolean", string::ends_with);
+        registry.register("find(text: string, pattern: string) -> int?", string::find);
+        registry.register("substring(text: string, start: int, end: int?) -> string", string::substring);
+        registry.register("repeat(text: string, count: int) -> string", string::repeat);
+        registry.register("pad_left(text: string, width: int, fill: string?) -> string", string::pad_left);
+        registry.register("pad_right(text: string, width: int, fill: string?) -> string", string::pad_right);
+        registry.register("chars(text: string) -> array", string::chars);
+        registry.register("bytes(text: string) -> array", string::bytes);
+        registry.register("reverse(text: string) -> string", string::reverse);
+        registry.register("lines(text: string) -> array", string::lines);
+        registry.register("parse_int(text: string) -> int", string::parse_int);
+        registry.register("parse_float(text: string) -> float", string::parse_float);
+
+        // regular expression functions
+        registry.register("regex(pattern: string) -> regex", regex::regex);
+        registry.register("regex_match(pattern: string | regex, text: string) -> boolean", regex::regex_match);
+        registry.register("regex_find_all(pattern: string | regex, text: string) -> array", regex::regex_find_all);
+        registry.register("regex_replace(pattern: string | regex, text: string, replacement: string) -> string", regex::regex_replace);
+        registry.register("regex_split(pattern: string | regex, text: string) -> array", regex::regex_split);
+
+        // json functions
+        registry.register("json_parse(text: string) -> any", json::json_parse);
+        registry.register("json_stringify(value: any, pretty: boolean?) -> string", json::json_stringify);
+
+        // filesystem functions
+        registry.register("read(path: string) -> string", fs::read);
+        registry.register("write(path: string, contents: string) -> null", fs::write);
+        registry.register("append(path: string, contents: string) -> null", fs::append);
+        registry.register("exists(path: string) -> boolean", fs::exists);
+        registry.register("is_dir(path: string) -> boolean", fs::is_dir);
+        registry.register("remove(path: string) -> null", fs::remove);
+        registry.register("rename(from: string, to: string) -> null", fs::rename);
+        registry.register("list_dir(path: string) -> array", fs::list_dir);
+        registry.register("mkdir(path: string) -> null", fs::mkdir);
+        registry.register("read_lines(path: string) -> array", fs::read_lines);
+        registry.register("file_size(path: string) -> int", fs::file_size);
+        registry.register("open_lines(path: string) -> int", fs::open_lines);
+        registry.register("next_line(handle: int) -> string?", fs::next_line);
+        registry.register("close_lines(handle: int) -> null", fs::close_lines);
+
+        // standard input functions
+        registry.register("read_line() -> string?", stdin::read_line);
+        registry.register("read_all_stdin() -> string", stdin::read_all_stdin);
+        registry.register("stdin_lines() -> int", stdin::stdin_lines);
+
+        // process and environment functions
+        registry.register("args() -> array", system::args);
+        registry.register("env(name: string) -> string?", system::env);
+        registry.register("set_env(name: string, value: string) -> null", system::set_env);
+        registry.register("cwd() -> string", system::cwd);
+        registry.register("exit(code: int?) -> null", system::exit);
+
+        // subprocess functions
+        registry.register("run(command: string, args: array?, options: dict?) -> dict", process::run);
+        registry.register("spawn(command: string, args: array?, options: dict?) -> int", process::spawn);
+        registry.register("wait(handle: int) -> dict", process::wait);
+        registry.register("kill(handle: int) -> null", process::kill);
+
+        // date and time functions
+        registry.register("now(offset: string?) -> datetime", time::now);
+        registry.register("monotonic() -> duration", time::monotonic);
+        registry.register("sleep(ms: int) -> null", time::sleep);
+        registry.register("datetime_parse(text: string, pattern: string?) -> datetime", time::datetime_parse);
+        registry.register("datetime_format(value: datetime, pattern: string?) -> string", time::datetime_format);
+        registry.register("to_offset(value: datetime, offset: string) -> datetime", time::to_offset);
+        registry.register("duration(amount: number, unit: string) -> duration", time::duration);
+        registry.register("duration_ms(value: duration) -> int", time::duration_ms);
+        registry.register("duration_seconds(value: duration) -> float", time::duration_seconds);
+        registry.register("time_add(a: datetime | duration, b: duration) -> datetime | duration", time::time_add);
+        registry.register("time_sub(a: datetime | duration, b: datetime | duration) -> datetime | duration", time::time_sub);
+        registry.register("time_compare(a: datetime | duration, b: datetime | duration) -> int", time::time_compare);
+
+        // random number functions
+        registry.register("seed(seed: int) -> null", random::seed);
+        registry.register("rand_int(low: int, high: int) -> int", random::rand_int);
+        registry.register("rand_float(low: number, high: number) -> float", random::rand_float);
+        registry.register("choice(values: array) -> any", random::choice);
+        registry.register("shuffle(values: array) -> array", random::shuffle);
+        registry.register("sample(values: array, count: int) -> array", random::sample);
+
+        // array methods
+        registry.register_method("Array", "push(value: any) -> null", collection::array_push);
+        registry.register_method("Array", "append(value: any) -> null", collection::array_push);
+        registry.register_method("Array", "pop() -> any", collection::array_pop);
+        registry.register_method("Array", "remove(index: int) -> null", collection::array_remove);
+        registry.register_method("Array", "get(index: int) -> any", collection::array_get);
+        registry.register_method("Array", "set(index: int, value: any) -> any", collection::array_set);
+        registry.register_method("Array", "print() -> null", collection::print_receiver);
+
+        // dictionary methods
+        registry.register_method("Dictionary", "add(key: any, value: any) -> null", collection::dictionary_set);
+        registry.register_method("Dictionary", "set(key: any, value: any) -> null", collection::dictionary_set);
+        registry.register_method("Dictionary", "remove(key: any) -> null", collection::dictionary_remove);
+        registry.register_method("Dictionary", "get(key: any) -> any", collection::dictionary_get);
+        registry.register_method("Dictionary", "keys() -> array", collection::dictionary_keys);
+        registry.register_method("Dictionary", "values() -> array", collection::dictionary_values);
+        registry.register_method("Dictionary", "print() -> null", collection::print_receiver);
+        registry.into()
     };
 
 }
@@ -49,137 +186,76 @@ lazy_static::lazy_static! {
 enum FunctionMap {}
 
 impl FunctionMap {
-    fn read(file_path: String) -> String {
-        let output: String = std::fs::read_to_string(file_path).unwrap();
-        output
-    }
-
-    fn write(file_path: String, content: String) {
-        std::fs::write(file_path, content).unwrap();
-    }
-
-    fn range(start: f64, end: f64) -> Vec<f64> {
+    /// The numbers from start to end including both, ints when both bounds are ints
+    fn range(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+        // the length is checked against the limits before the array is built
+        if let (BaseTypes::Int(start), BaseTypes::Int(end)) = (&args[0], &args[1]) {
+            let length = (*end as i64 - *start as i64 + 1).max(0);
+            limits::check_elements(length as usize)?;
+            return Ok(BaseTypes::Array(Arc::new(
+                (*start..=*end).map(BaseTypes::Int).collect(),
+            )));
+        }
+        let end = get_number("range", args, 1)?;
+        let start = get_number("range", args, 0)?;
+        let length = if end >= start {
+            (end - start).floor() + 1.0
+        } else {
+            0.0
+        };
+        // as usize saturates so a range too long to count is refused too
+        limits::check_elements(length as usize)?;
         let mut output = Vec::new();
         let mut current = start;
-
         while current <= end {
-            output.push(current);
+            output.push(BaseTypes::Float(current));
             current += 1.0;
         }
-
-        println!("{:?}", output);
-        output
-    }
-
-    fn max(a: f64, b: f64) -> f64 {
-        if a > b {
-            a
-        } else {
-            b
-        }
-    }
-
-    fn min(a: f64, b: f64) -> f64 {
-        if a < b {
-            a
-        } else {
-            b
-        }
-    }
-
-    fn rand() -> f64 {
-        rand::random::<f64>()
-    }
-
-    fn floor(a: f64) -> f64 {
-        a.floor()
-    }
-
-    fn ceil(a: f64) -> f64 {
-        a.ceil()
-    }
-
-    fn round(a: f64) -> f64 {
-        a.round()
-    }
-
-    fn add(a: f64, b: f64) -> f64 {
-        a + b
-    }
-
-    fn sub(a: f64, b: f64) -> f64 {
-        a - b
-    }
-
-    fn mult(a: f64, b: f64) -> f64 {
-        a * b
-    }
-
-    fn divide(a: f64, b: f64) -> f64 {
-        a / b
-    }
-
-    fn print(a: String) {
-        print!("{}", a);
-        //println!("After echo");
-    }
-
-    fn println(a: String) {
-        println!("{}", a);
-    }
-
-    fn abs(a: f64) -> f64 {
-        a.abs()
-    }
-
-    fn pow(a: f64, b: f64) -> f64 {
-        a.powf(b)
-    }
-
-    fn sqrt(a: f64) -> f64 {
-        a.sqrt()
-    }
-
-    fn log(a: f64, base: f64) -> f64 {
-        a.log(base)
+        Ok(BaseTypes::Array(Arc::new(output)))
     }
 
-    fn sin(a: f64) -> f64 {
-        a.sin()
+    fn rand(_args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+        Ok(BaseTypes::Float(random::next_float()))
     }
 
-    fn cos(a: f64) -> f64 {
-        a.cos()
+    fn print(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+        output::print(&args[0].to_string());
+        Ok(BaseTypes::Null)
     }
 
-    fn tan(a: f64) -> f64 {
-        a.tan()
+    fn println(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+        output::println(&args[0].to_string());
+        Ok(BaseTypes::Null)
     }
 
-    fn concat(a: String, b: String) -> String {
-        [a, b].concat()
-    }
-
-    fn len(s: String) -> usize {
-        s.len()
+    fn concat(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+        let mut output = String::new();
+        for index in 0..args.len() {
+            output.push_str(&get_string("concat", args, index)?);
+        }
+        Ok(BaseTypes::StringWrapper(output))
     }
 
-    fn to_uppercase(s: String) -> String {
-        s.to_uppercase()
+    fn to_uppercase(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+        let s = get_string("to_uppercase", args, 0)?;
+        Ok(BaseTypes::StringWrapper(s.to_uppercase()))
     }
 
-    fn to_lowercase(s: String) -> String {
-        s.to_lowercase()
+    fn to_lowercase(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+        let s = get_string("to_lowercase", args, 0)?;
+        Ok(BaseTypes::StringWrapper(s.to_lowercase()))
     }
 
-    fn trim(s: String) -> String {
-        s.trim().to_string()
+    fn trim(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+        let s = get_string("trim", args, 0)?;
+        Ok(BaseTypes::StringWrapper(s.trim().to_string()))
     }
 
-    fn input(s: String) -> String {
-        print!("{}", s);
+    fn input(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+        output::print(&get_string("input", args, 0)?);
+        output::flush();
         let mut input = String::new();
-        std::io::stdin().read_line(&mut input).unwrap();
-        return input;
+        std::io::stdin().read_line(&mut input)?;
+        Ok(BaseTypes::StringWrapper(input))
     }
 }
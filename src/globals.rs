@@ -1,3 +1,11 @@
 pub static mut IF_ELSE_SKIP: bool = false;
 pub static mut MAKE_LOOP: bool = false;
 pub static mut TRY_FAIL: bool = false;
+// the repl can read private module names so it can inspect everything
+pub static mut INSPECT_PRIVATE: bool = false;
+// programs are walked instead of compiled to bytecode, set by --tree-walk
+pub static mut TREE_WALK: bool = false;
+// programs are not read from or written to the automatic .jistc cache, set by --no-cache
+pub static mut NO_CACHE: bool = false;
+// statements are written to the emit output after the optimization pass, set by --emit=opt-ast
+pub static mut EMIT_OPT_AST: bool = false;
@@ -0,0 +1,469 @@
+/*
+* This file contains the glue for rust functions and objects registered by a program embedding
+* jist. Arguments are converted from jist values to rust types and back with FromValue and
+* IntoValue, the signature of a registered closure is built from its argument types so calls are
+* checked like any other native function. Host objects live in HOST_OBJECTS by name and their
+* methods are found through the FUNCTIONS registry under the rust type name of the object
+*/
+
+use crate::base_variable::base_types::{BaseTypes, GetType};
+use crate::function::functions::{NativeFunction, NativeMethod, Parameter, Signature, ValueType};
+use lazy_static::lazy_static;
+use std::any::{type_name, Any};
+use std::collections::HashMap;
+use std::error::Error;
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+
+lazy_static! {
+    // registered host objects by the name jist code uses for them
+    pub static ref HOST_OBJECTS: Mutex<HashMap<String, HostObject>> = Mutex::new(HashMap::new());
+}
+
+/// A rust value jist code can call methods on
+pub struct HostObject {
+    pub type_name: &'static str,
+    pub value: Box<dyn Any + Send>,
+}
+
+/// The rust type name a host object's methods are registered under, None when nothing with the
+/// name is registered
+pub fn host_object_type(name: &str) -> Option<&'static str> {
+    HOST_OBJECTS
+        .lock()
+        .unwrap()
+        .get(name)
+        .map(|object| object.type_name)
+}
+
+/// A rust type that can be read from a jist argument
+pub trait FromValue: Sized {
+    /// The type the signature checks the argument against
+    fn value_type() -> ValueType;
+
+    /// Whether the argument can be left out, only Option is
+    fn optional() -> bool {
+        false
+    }
+
+    fn from_value(value: &BaseTypes) -> Result<Self, Box<dyn Error>>;
+}
+
+/// A rust type that can be handed back to jist code
+pub trait IntoValue {
+    /// The type the signature declares as returned
+    fn value_type() -> ValueType;
+
+    fn into_value(self) -> Result<BaseTypes, Box<dyn Error>>;
+}
+
+fn conversion_error(value: &BaseTypes, expected: &str) -> Box<dyn Error> {
+    format!("Type error: expected {} but got {}", expected, value.GetType()).into()
+}
+
+impl FromValue for BaseTypes {
+    fn value_type() -> ValueType {
+        ValueType::Any
+    }
+
+    fn from_value(value: &BaseTypes) -> Result<Self, Box<dyn Error>> {
+        Ok(value.clone())
+    }
+}
+
+impl IntoValue for BaseTypes {
+    fn value_type() -> ValueType {
+        ValueType::Any
+    }
+
+    fn into_value(self) -> Result<BaseTypes, Box<dyn Error>> {
+        Ok(self)
+    }
+}
+
+impl IntoValue for () {
+    fn value_type() -> ValueType {
+        ValueType::Null
+    }
+
+    fn into_value(self) -> Result<BaseTypes, Box<dyn Error>> {
+        Ok(BaseTypes::Null)
+    }
+}
+
+impl FromValue for i32 {
+    fn value_type() -> ValueType {
+        ValueType::Int
+    }
+
+    fn from_value(value: &BaseTypes) -> Result<Self, Box<dyn Error>> {
+        match value {
+            BaseTypes::Int(i) => Ok(*i),
+            other => Err(conversion_error(other, "Int")),
+        }
+    }
+}
+
+impl IntoValue for i32 {
+    fn value_type() -> ValueType {
+        ValueType::Int
+    }
+
+    fn into_value(self) -> Result<BaseTypes, Box<dyn Error>> {
+        Ok(BaseTypes::Int(self))
+    }
+}
+
+impl FromValue for i64 {
+    fn value_type() -> ValueType {
+        ValueType::Int
+    }
+
+    fn from_value(value: &BaseTypes) -> Result<Self, Box<dyn Error>> {
+        i32::from_value(value).map(i64::from)
+    }
+}
+
+impl IntoValue for i64 {
+    fn value_type() -> ValueType {
+        ValueType::Int
+    }
+
+    fn into_value(self) -> Result<BaseTypes, Box<dyn Error>> {
+        // jist ints are 32 bit so larger results are an error rather than wrapping
+        i32::try_from(self)
+            .map(BaseTypes::Int)
+            .map_err(|_| format!("Overflow error: {} does not fit in an int", self).into())
+    }
+}
+
+impl FromValue for f64 {
+    fn value_type() -> ValueType {
+        ValueType::Number
+    }
+
+    fn from_value(value: &BaseTypes) -> Result<Self, Box<dyn Error>> {
+        match value {
+            BaseTypes::Int(i) => Ok(f64::from(*i)),
+            BaseTypes::Float(f) => Ok(*f),
+            other => Err(conversion_error(other, "Int or Float")),
+        }
+    }
+}
+
+impl IntoValue for f64 {
+    fn value_type() -> ValueType {
+        ValueType::Float
+    }
+
+    fn into_value(self) -> Result<BaseTypes, Box<dyn Error>> {
+        Ok(BaseTypes::Float(self))
+    }
+}
+
+impl FromValue for bool {
+    fn value_type() -> ValueType {
+        ValueType::Bool
+    }
+
+    fn from_value(value: &BaseTypes) -> Result<Self, Box<dyn Error>> {
+        match value {
+            BaseTypes::Bool(b) => Ok(*b),
+            other => Err(conversion_error(other, "Bool")),
+        }
+    }
+}
+
+impl IntoValue for bool {
+    fn value_type() -> ValueType {
+        ValueType::Bool
+    }
+
+    fn into_value(self) -> Result<BaseTypes, Box<dyn Error>> {
+        Ok(BaseTypes::Bool(self))
+    }
+}
+
+impl FromValue for char {
+    fn value_type() -> ValueType {
+        ValueType::Char
+    }
+
+    fn from_value(value: &BaseTypes) -> Result<Self, Box<dyn Error>> {
+        match value {
+            BaseTypes::Char(c) => Ok(*c),
+            other => Err(conversion_error(other, "Char")),
+        }
+    }
+}
+
+impl IntoValue for char {
+    fn value_type() -> ValueType {
+        ValueType::Char
+    }
+
+    fn into_value(self) -> Result<BaseTypes, Box<dyn Error>> {
+        Ok(BaseTypes::Char(self))
+    }
+}
+
+impl FromValue for String {
+    fn value_type() -> ValueType {
+        ValueType::String
+    }
+
+    fn from_value(value: &BaseTypes) -> Result<Self, Box<dyn Error>> {
+        match value {
+            BaseTypes::StringWrapper(s) => Ok(s.clone()),
+            BaseTypes::Char(c) => Ok(c.to_string()),
+            other => Err(conversion_error(other, "String")),
+        }
+    }
+}
+
+impl IntoValue for String {
+    fn value_type() -> ValueType {
+        ValueType::String
+    }
+
+    fn into_value(self) -> Result<BaseTypes, Box<dyn Error>> {
+        Ok(BaseTypes::StringWrapper(self))
+    }
+}
+
+impl IntoValue for &str {
+    fn value_type() -> ValueType {
+        ValueType::String
+    }
+
+    fn into_value(self) -> Result<BaseTypes, Box<dyn Error>> {
+        Ok(BaseTypes::StringWrapper(self.to_string()))
+    }
+}
+
+impl<T: FromValue> FromValue for Vec<T> {
+    fn value_type() -> ValueType {
+        ValueType::Array
+    }
+
+    fn from_value(value: &BaseTypes) -> Result<Self, Box<dyn Error>> {
+        match value {
+            BaseTypes::Array(values) => values.iter().map(T::from_value).collect(),
+            other => Err(conversion_error(other, "Array")),
+        }
+    }
+}
+
+impl<T: IntoValue> IntoValue for Vec<T> {
+    fn value_type() -> ValueType {
+        ValueType::Array
+    }
+
+    fn into_value(self) -> Result<BaseTypes, Box<dyn Error>> {
+        let values = self
+            .into_iter()
+            .map(T::into_value)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(BaseTypes::Array(Arc::new(values)))
+    }
+}
+
+impl<T: FromValue> FromValue for Option<T> {
+    fn value_type() -> ValueType {
+        T::value_type()
+    }
+
+    fn optional() -> bool {
+        true
+    }
+
+    fn from_value(value: &BaseTypes) -> Result<Self, Box<dyn Error>> {
+        match value {
+            BaseTypes::Null => Ok(None),
+            value => T::from_value(value).map(Some),
+        }
+    }
+}
+
+impl<T: IntoValue> IntoValue for Option<T> {
+    fn value_type() -> ValueType {
+        ValueType::OneOf(vec![T::value_type(), ValueType::Null])
+    }
+
+    fn into_value(self) -> Result<BaseTypes, Box<dyn Error>> {
+        match self {
+            Some(value) => value.into_value(),
+            None => Ok(BaseTypes::Null),
+        }
+    }
+}
+
+/// A rust closure that can be registered as a jist function, Args is the tuple of its argument
+/// types and only exists to tell the implementations for each arity apart
+pub trait IntoNative<Args>: Send + Sync + 'static {
+    fn parameters() -> Vec<Parameter>;
+
+    fn return_type() -> ValueType;
+
+    fn call_with(&self, args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>>;
+}
+
+/// A rust closure that can be registered as a method of host objects of type T, the first
+/// argument of the closure is the object
+pub trait IntoHostMethod<T, Args>: Send + Sync + 'static {
+    fn parameters() -> Vec<Parameter>;
+
+    fn return_type() -> ValueType;
+
+    fn call_with(&self, object: &mut T, args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>>;
+}
+
+/// The parameters of a registered closure, rust closures have no parameter names so they are
+/// numbered from arg1
+fn parameter(index: usize, value_type: ValueType, optional: bool) -> Parameter {
+    Parameter {
+        name: format!("arg{}", index + 1),
+        value_type,
+        optional,
+    }
+}
+
+macro_rules! impl_host_closures {
+    ($($arg:ident),*) => {
+        impl<F, R, E, $($arg),*> IntoNative<($($arg,)*)> for F
+        where
+            F: Fn($($arg),*) -> Result<R, E> + Send + Sync + 'static,
+            R: IntoValue,
+            E: Into<Box<dyn Error>>,
+            $($arg: FromValue,)*
+        {
+            fn parameters() -> Vec<Parameter> {
+                let types: Vec<(ValueType, bool)> = vec![$(($arg::value_type(), $arg::optional())),*];
+                types
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, (value_type, optional))| parameter(index, value_type, optional))
+                    .collect()
+            }
+
+            fn return_type() -> ValueType {
+                R::value_type()
+            }
+
+            #[allow(non_snake_case, unused_mut, unused_variables)]
+            fn call_with(&self, args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+                let mut values = args.iter();
+                $(let $arg = $arg::from_value(values.next().unwrap_or(&BaseTypes::Null))?;)*
+                (self)($($arg),*).map_err(Into::into)?.into_value()
+            }
+        }
+
+        impl<F, T, R, E, $($arg),*> IntoHostMethod<T, ($($arg,)*)> for F
+        where
+            F: Fn(&mut T, $($arg),*) -> Result<R, E> + Send + Sync + 'static,
+            R: IntoValue,
+            E: Into<Box<dyn Error>>,
+            $($arg: FromValue,)*
+        {
+            fn parameters() -> Vec<Parameter> {
+                let types: Vec<(ValueType, bool)> = vec![$(($arg::value_type(), $arg::optional())),*];
+                types
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, (value_type, optional))| parameter(index, value_type, optional))
+                    .collect()
+            }
+
+            fn return_type() -> ValueType {
+                R::value_type()
+            }
+
+            #[allow(non_snake_case, unused_mut, unused_variables)]
+            fn call_with(
+                &self,
+                object: &mut T,
+                args: &[BaseTypes],
+            ) -> Result<BaseTypes, Box<dyn Error>> {
+                let mut values = args.iter();
+                $(let $arg = $arg::from_value(values.next().unwrap_or(&BaseTypes::Null))?;)*
+                (self)(object, $($arg),*).map_err(Into::into)?.into_value()
+            }
+        }
+    };
+}
+
+impl_host_closures!();
+impl_host_closures!(A1);
+impl_host_closures!(A1, A2);
+impl_host_closures!(A1, A2, A3);
+impl_host_closures!(A1, A2, A3, A4);
+impl_host_closures!(A1, A2, A3, A4, A5);
+impl_host_closures!(A1, A2, A3, A4, A5, A6);
+
+/// A registered rust closure as a native function
+pub struct HostFunction<F, Args> {
+    signature: Signature,
+    body: F,
+    arguments: PhantomData<fn(Args)>,
+}
+
+impl<F: IntoNative<Args>, Args> HostFunction<F, Args> {
+    pub fn new(name: &str, body: F) -> HostFunction<F, Args> {
+        HostFunction {
+            signature: Signature::new(name, F::parameters(), F::return_type(), false),
+            body,
+            arguments: PhantomData,
+        }
+    }
+}
+
+impl<F: IntoNative<Args>, Args> NativeFunction for HostFunction<F, Args> {
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn call(&self, args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+        self.body.call_with(args)
+    }
+}
+
+/// A registered rust closure as a method of host objects, the receiver jist passes is the name of
+/// the object which is looked up in HOST_OBJECTS for the call
+pub struct HostMethod<F, T, Args> {
+    signature: Signature,
+    body: F,
+    arguments: PhantomData<fn(T, Args)>,
+}
+
+impl<F: IntoHostMethod<T, Args>, T, Args> HostMethod<F, T, Args> {
+    pub fn new(name: &str, body: F) -> HostMethod<F, T, Args> {
+        HostMethod {
+            signature: Signature::new(name, F::parameters(), F::return_type(), false),
+            body,
+            arguments: PhantomData,
+        }
+    }
+}
+
+impl<F: IntoHostMethod<T, Args>, T: 'static, Args> NativeMethod for HostMethod<F, T, Args> {
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn call(
+        &self,
+        receiver: &mut BaseTypes,
+        args: &[BaseTypes],
+    ) -> Result<BaseTypes, Box<dyn Error>> {
+        let name = match receiver {
+            BaseTypes::StringWrapper(name) => name.clone(),
+            other => return Err(conversion_error(other, "a host object name")),
+        };
+        let mut objects = HOST_OBJECTS.lock().unwrap();
+        let object = objects
+            .get_mut(&name)
+            .and_then(|object| object.value.downcast_mut::<T>())
+            .ok_or_else(|| format!("Object error: '{}' is not a {}", name, type_name::<T>()))?;
+        self.body.call_with(object, args)
+    }
+}
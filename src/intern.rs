@@ -0,0 +1,82 @@
+/*
+* This file contains the interner for identifiers. A name is stored once and stands for a small
+* symbol, symbols compare and hash as a number so the variable index is keyed by them instead of
+* by the name
+*/
+
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// An interned name, two symbols are equal when their names are
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(u32);
+
+#[derive(Default)]
+struct Interner {
+    symbols: HashMap<Arc<str>, Symbol>,
+    names: Vec<Arc<str>>,
+}
+
+lazy_static! {
+    static ref INTERNER: Mutex<Interner> = Mutex::new(Interner::default());
+}
+
+/// Get the symbol for a name, the name is stored the first time it is seen
+///
+/// params: name: &str -> The name
+///
+/// returns: Symbol -> The symbol standing for the name
+pub fn intern(name: &str) -> Symbol {
+    let mut interner = INTERNER.lock().unwrap();
+    if let Some(symbol) = interner.symbols.get(name) {
+        return *symbol;
+    }
+    let symbol = Symbol(interner.names.len() as u32);
+    let name: Arc<str> = Arc::from(name);
+    interner.names.push(name.clone());
+    interner.symbols.insert(name, symbol);
+    symbol
+}
+
+/// The symbol for a name when the name has been interned, used to look a name up without storing
+/// it
+pub fn lookup(name: &str) -> Option<Symbol> {
+    INTERNER.lock().unwrap().symbols.get(name).copied()
+}
+
+impl Symbol {
+    /// The name the symbol stands for
+    pub fn name(&self) -> Arc<str> {
+        INTERNER.lock().unwrap().names[self.0 as usize].clone()
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// A symbol is shown by its name, the number depends on the order names were first seen
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Symbol({:?})", self.name())
+    }
+}
+
+#[cfg(test)]
+mod intern_tests {
+    use super::*;
+
+    #[test]
+    fn test_intern_returns_the_same_symbol() {
+        let first = intern("intern_test_name");
+        assert_eq!(first, intern("intern_test_name"));
+        assert_ne!(first, intern("intern_test_other"));
+        assert_eq!(&*first.name(), "intern_test_name");
+        assert_eq!(lookup("intern_test_name"), Some(first));
+        assert_eq!(lookup("intern_test_never_seen"), None);
+    }
+}
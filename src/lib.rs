@@ -1,13 +1,26 @@
 pub mod ast;
 pub mod base_variable;
+pub mod bench;
+pub mod bytecode;
+pub mod cache;
 pub mod collection;
 pub mod compiler;
+pub mod engine;
 pub mod function;
 pub mod function_map;
 pub mod globals;
 pub mod highlighter;
+pub mod host;
+pub mod intern;
+pub mod limits;
+pub mod module;
 pub mod node;
+pub mod optimizer;
+pub mod output;
 pub mod token_type;
+pub mod vm;
+
+pub use engine::{Engine, Value};
 pub mod statement_tokenizer {
     pub mod basic_tokenizer;
     pub mod collection_tokenizer;
@@ -24,6 +37,20 @@ pub mod compilers {
     pub mod conditional;
     pub mod function;
     pub mod loops;
-    pub mod operation;
+    pub mod nullable;
     pub mod variable;
 }
+
+pub mod std_lib {
+    pub mod arguments;
+    pub mod fs;
+    pub mod json;
+    pub mod math;
+    pub mod process;
+    pub mod random;
+    pub mod regex;
+    pub mod stdin;
+    pub mod string;
+    pub mod system;
+    pub mod time;
+}
@@ -0,0 +1,514 @@
+/*
+* This file contains the resource limits and the sandbox used to run untrusted scripts. The limits
+* cap the executed steps, the call depth, the running time, the elements held by collections and
+* the length of strings, a step is a statement or a loop iteration. The sandbox refuses calls to
+* the functions that reach the filesystem, the environment, other processes or standard input, it
+* is kept for the thread running the script so an engine can sandbox its own scripts
+*/
+
+use crate::base_variable::base_types::BaseTypes;
+use crate::collection::{ARRAY_STACK, DICTIONARY_STACK};
+use lazy_static::lazy_static;
+use std::cell::Cell;
+use std::error::Error;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// The functions refused in sandbox mode
+pub const SANDBOXED_FUNCTIONS: [&str; 26] = [
+    // filesystem
+    "read",
+    "write",
+    "append",
+    "exists",
+    "is_dir",
+    "remove",
+    "rename",
+    "list_dir",
+    "mkdir",
+    "read_lines",
+    "file_size",
+    "open_lines",
+    "next_line",
+    "close_lines",
+    // environment
+    "env",
+    "set_env",
+    "cwd",
+    // processes
+    "run",
+    "spawn",
+    "wait",
+    "kill",
+    "exit",
+    // standard input
+    "input",
+    "read_line",
+    "read_all_stdin",
+    "stdin_lines",
+];
+
+/// The call depth used when no depth is given, deeper recursion would overflow the native stack
+/// of the interpreter before the limit is reached
+pub const DEFAULT_CALL_DEPTH: usize = 200;
+
+/// The most a script may use, None means no limit
+#[derive(Clone, Debug, PartialEq)]
+pub struct Limits {
+    pub max_steps: Option<u64>,
+    pub max_call_depth: Option<usize>,
+    pub max_duration: Option<Duration>,
+    pub max_collection_elements: Option<usize>,
+    pub max_string_length: Option<usize>,
+}
+
+impl Default for Limits {
+    /// The limits for scripts that are trusted, only the call depth is limited
+    fn default() -> Limits {
+        Limits {
+            max_call_depth: Some(DEFAULT_CALL_DEPTH),
+            ..Limits::none()
+        }
+    }
+}
+
+impl Limits {
+    /// No limits at all, a deep recursion overflows the native stack
+    pub fn none() -> Limits {
+        Limits {
+            max_steps: None,
+            max_call_depth: None,
+            max_duration: None,
+            max_collection_elements: None,
+            max_string_length: None,
+        }
+    }
+
+    /// The limits sandbox mode uses for the limits that were not set
+    pub fn sandbox() -> Limits {
+        Limits {
+            max_steps: Some(1_000_000),
+            max_call_depth: Some(DEFAULT_CALL_DEPTH),
+            max_duration: Some(Duration::from_secs(5)),
+            max_collection_elements: Some(100_000),
+            max_string_length: Some(1_000_000),
+        }
+    }
+
+    /// Use the limits of other where these are not set
+    pub fn or(self, other: Limits) -> Limits {
+        Limits {
+            max_steps: self.max_steps.or(other.max_steps),
+            max_call_depth: self.max_call_depth.or(other.max_call_depth),
+            max_duration: self.max_duration.or(other.max_duration),
+            max_collection_elements: self
+                .max_collection_elements
+                .or(other.max_collection_elements),
+            max_string_length: self.max_string_length.or(other.max_string_length),
+        }
+    }
+
+    /// Check the length of a string and the element count of a collection
+    ///
+    /// params: value: &BaseTypes -> The value to check
+    ///
+    /// returns: Result<(), String> -> A limit error when the value is too large
+    pub fn check_value(&self, value: &BaseTypes) -> Result<(), String> {
+        match value {
+            BaseTypes::StringWrapper(text) => self.check_string(text.chars().count()),
+            BaseTypes::Array(data) => self.check_elements(data.len()),
+            BaseTypes::Dictionary(values) => self.check_elements(values.len()),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn check_string(&self, length: usize) -> Result<(), String> {
+        match self.max_string_length {
+            Some(max) if length > max => Err(format!(
+                "Limit error: string of {} characters is longer than the limit of {}",
+                length, max
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn check_elements(&self, count: usize) -> Result<(), String> {
+        match self.max_collection_elements {
+            Some(max) if count > max => Err(format!(
+                "Limit error: collections hold {} elements, more than the limit of {}",
+                count, max
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// What a script has used so far
+#[derive(Debug)]
+struct Usage {
+    steps: u64,
+    depth: usize,
+    started: Instant,
+}
+
+impl Usage {
+    fn new() -> Usage {
+        Usage {
+            steps: 0,
+            depth: 0,
+            started: Instant::now(),
+        }
+    }
+
+    fn step(&mut self, limits: &Limits) -> Result<(), String> {
+        self.steps += 1;
+        if let Some(max) = limits.max_steps {
+            if self.steps > max {
+                return Err(format!("Limit error: executed more than {} steps", max));
+            }
+        }
+        if let Some(max) = limits.max_duration {
+            if self.started.elapsed() > max {
+                return Err(time_error(max));
+            }
+        }
+        Ok(())
+    }
+
+    /// The time left before the time limit, None when there is no time limit
+    fn remaining(&self, limits: &Limits) -> Option<Duration> {
+        let max = limits.max_duration?;
+        Some(max.saturating_sub(self.started.elapsed()))
+    }
+
+    fn enter_call(&mut self, limits: &Limits) -> Result<(), String> {
+        if let Some(max) = limits.max_call_depth {
+            if self.depth >= max {
+                return Err(format!(
+                    "Limit error: call depth exceeded the limit of {}",
+                    max
+                ));
+            }
+        }
+        self.depth += 1;
+        Ok(())
+    }
+}
+
+fn time_error(max: Duration) -> String {
+    format!(
+        "Limit error: ran longer than the limit of {} ms",
+        max.as_millis()
+    )
+}
+
+/// The limits that were set and the same limits filled by the sandbox limits, both are kept so the
+/// limits in force are borrowed on each step
+struct LimitState {
+    limits: Limits,
+    sandboxed: Limits,
+}
+
+impl LimitState {
+    fn new(limits: Limits) -> LimitState {
+        LimitState {
+            sandboxed: limits.clone().or(Limits::sandbox()),
+            limits,
+        }
+    }
+}
+
+lazy_static! {
+    static ref LIMITS: Mutex<LimitState> = Mutex::new(LimitState::new(Limits::default()));
+    static ref USAGE: Mutex<Usage> = Mutex::new(Usage::new());
+}
+
+thread_local! {
+    static SANDBOX: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Use the limits in force, in sandbox mode the sandbox limits fill the limits that are not set
+fn with_active_limits<R>(f: impl FnOnce(&Limits) -> R) -> R {
+    let state = LIMITS.lock().unwrap();
+    if is_sandboxed() {
+        return f(&state.sandboxed);
+    }
+    f(&state.limits)
+}
+
+/// Replace the limits, the usage starts again from zero
+pub fn set_limits(limits: Limits) {
+    *LIMITS.lock().unwrap() = LimitState::new(limits);
+    reset_usage();
+}
+
+pub fn limits() -> Limits {
+    LIMITS.lock().unwrap().limits.clone()
+}
+
+/// Start counting steps and time again, used before each program is run
+pub fn reset_usage() {
+    *USAGE.lock().unwrap() = Usage::new();
+}
+
+/// Count a statement or loop iteration and check the step and time limits
+///
+/// returns: Result<(), Box<dyn Error>> -> A limit error when a limit is passed
+pub fn step() -> Result<(), Box<dyn Error>> {
+    with_active_limits(|limits| USAGE.lock().unwrap().step(limits))?;
+    Ok(())
+}
+
+/// Wait for a duration without passing the time limit, a wait that reaches the limit ends there
+///
+/// params: duration: Duration -> How long to wait
+///
+/// returns: Result<(), Box<dyn Error>> -> A limit error when the time limit was reached
+pub fn sleep(duration: Duration) -> Result<(), Box<dyn Error>> {
+    let deadline = with_active_limits(|limits| {
+        let remaining = USAGE.lock().unwrap().remaining(limits)?;
+        Some((limits.max_duration?, remaining))
+    });
+    match deadline {
+        Some((max, remaining)) if remaining <= duration => {
+            std::thread::sleep(remaining);
+            Err(time_error(max).into())
+        }
+        _ => {
+            std::thread::sleep(duration);
+            Ok(())
+        }
+    }
+}
+
+/// Marks a running user function, the call depth goes down again when it is dropped
+pub struct CallGuard;
+
+impl Drop for CallGuard {
+    fn drop(&mut self) {
+        let mut usage = USAGE.lock().unwrap();
+        usage.depth = usage.depth.saturating_sub(1);
+    }
+}
+
+/// Count a user function call and check the call depth limit, a call is also a step
+///
+/// returns: Result<CallGuard, Box<dyn Error>> -> The guard to keep until the call ends
+pub fn enter_call() -> Result<CallGuard, Box<dyn Error>> {
+    step()?;
+    with_active_limits(|limits| USAGE.lock().unwrap().enter_call(limits))?;
+    Ok(CallGuard)
+}
+
+/// Check a value against the string length and collection element limits
+///
+/// params: value: &BaseTypes -> The value to check
+///
+/// returns: Result<(), String> -> A limit error when the value is too large
+pub fn check_value(value: &BaseTypes) -> Result<(), String> {
+    with_active_limits(|limits| limits.check_value(value))
+}
+
+/// Check a length in characters against the string limit, used before a string is built
+///
+/// params: length: usize -> The characters the string would hold
+///
+/// returns: Result<(), String> -> A limit error when the string would be too long
+pub fn check_string(length: usize) -> Result<(), String> {
+    with_active_limits(|limits| limits.check_string(length))
+}
+
+/// Check an element count against the collection element limit, used before a collection is built
+///
+/// params: count: usize -> The elements the collection would hold
+///
+/// returns: Result<(), String> -> A limit error when there would be too many elements
+pub fn check_elements(count: usize) -> Result<(), String> {
+    with_active_limits(|limits| limits.check_elements(count))
+}
+
+/// Check the elements held by all arrays and dictionaries together against the limit
+///
+/// returns: Result<(), Box<dyn Error>> -> A limit error when there are too many elements
+pub fn check_collections() -> Result<(), Box<dyn Error>> {
+    if with_active_limits(|limits| limits.max_collection_elements.is_none()) {
+        return Ok(());
+    }
+    let arrays: usize = ARRAY_STACK
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|a| a.data.len())
+        .sum();
+    let dictionaries: usize = DICTIONARY_STACK
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|d| d.values.len())
+        .sum();
+    check_elements(arrays + dictionaries)?;
+    Ok(())
+}
+
+/// Whether an error was raised by a limit, these stop the program and are not caught by try
+pub fn is_limit_error(error: &dyn Error) -> bool {
+    error.to_string().contains("Limit error:")
+}
+
+/// Turn sandbox mode on for the current thread, the sandboxed functions are refused, imports are
+/// refused and the sandbox limits are used for the limits that are not set. The registry is not
+/// changed so the functions are available again once sandbox mode is off
+pub fn enable_sandbox() {
+    SANDBOX.with(|sandbox| sandbox.set(true));
+}
+
+/// Whether the current thread is in sandbox mode
+pub fn is_sandboxed() -> bool {
+    SANDBOX.with(|sandbox| sandbox.get())
+}
+
+/// Keeps the sandbox mode of an engine while it runs a script, the mode before it is restored when
+/// it is dropped
+pub struct SandboxGuard {
+    previous: bool,
+}
+
+impl Drop for SandboxGuard {
+    fn drop(&mut self) {
+        let previous = self.previous;
+        SANDBOX.with(|sandbox| sandbox.set(previous));
+    }
+}
+
+/// Set sandbox mode for the current thread until the guard is dropped
+///
+/// params: sandboxed: bool -> Whether the script about to run is sandboxed
+///
+/// returns: SandboxGuard -> The guard to keep until the script has run
+pub fn sandbox_scope(sandboxed: bool) -> SandboxGuard {
+    let previous = SANDBOX.with(|sandbox| sandbox.replace(sandboxed || sandbox.get()));
+    SandboxGuard { previous }
+}
+
+/// The error for calling a function sandbox mode refuses
+///
+/// params: name: &str -> The name of the called function
+///
+/// returns: Option<String> -> The error or None when the function may be called
+pub fn sandbox_error(name: &str) -> Option<String> {
+    if is_sandboxed() && SANDBOXED_FUNCTIONS.contains(&name) {
+        return Some(format!(
+            "Sandbox error: {} is not available in sandbox mode",
+            name
+        ));
+    }
+    None
+}
+
+#[cfg(test)]
+mod limits_tests {
+    use super::*;
+
+    #[test]
+    fn test_step_limit() {
+        let limits = Limits {
+            max_steps: Some(2),
+            ..Limits::none()
+        };
+        let mut usage = Usage::new();
+        assert!(usage.step(&limits).is_ok());
+        assert!(usage.step(&limits).is_ok());
+        let error = usage.step(&limits).unwrap_err();
+        assert_eq!(error, "Limit error: executed more than 2 steps");
+    }
+
+    #[test]
+    fn test_time_limit() {
+        let limits = Limits {
+            max_duration: Some(Duration::from_millis(1)),
+            ..Limits::none()
+        };
+        let mut usage = Usage::new();
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(usage
+            .step(&limits)
+            .unwrap_err()
+            .starts_with("Limit error: ran longer"));
+    }
+
+    #[test]
+    fn test_call_depth_limit() {
+        let limits = Limits {
+            max_call_depth: Some(1),
+            ..Limits::none()
+        };
+        let mut usage = Usage::new();
+        assert!(usage.enter_call(&limits).is_ok());
+        assert!(usage.enter_call(&limits).is_err());
+    }
+
+    #[test]
+    fn test_default_limits_the_call_depth() {
+        assert_eq!(Limits::default().max_call_depth, Some(DEFAULT_CALL_DEPTH));
+        assert_eq!(Limits::sandbox().max_call_depth, Some(DEFAULT_CALL_DEPTH));
+        assert_eq!(Limits::none().max_call_depth, None);
+    }
+
+    #[test]
+    fn test_remaining_time() {
+        let usage = Usage::new();
+        assert!(usage.remaining(&Limits::none()).is_none());
+        let limits = Limits {
+            max_duration: Some(Duration::from_millis(1)),
+            ..Limits::none()
+        };
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(usage.remaining(&limits), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_check_value() {
+        let limits = Limits {
+            max_string_length: Some(3),
+            max_collection_elements: Some(2),
+            ..Limits::none()
+        };
+        assert!(limits
+            .check_value(&BaseTypes::StringWrapper("abc".into()))
+            .is_ok());
+        assert!(limits
+            .check_value(&BaseTypes::StringWrapper("abcd".into()))
+            .is_err());
+        let data = vec![BaseTypes::Int(1), BaseTypes::Int(2), BaseTypes::Int(3)];
+        assert!(limits.check_value(&BaseTypes::Array(data.into())).is_err());
+        assert!(Limits::none()
+            .check_value(&BaseTypes::StringWrapper("abcd".into()))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_sandbox_scope_is_restored() {
+        // tests run on their own threads so the flag of this one is not seen by the others
+        assert!(sandbox_error("read").is_none());
+        {
+            let _sandbox = sandbox_scope(true);
+            assert!(is_sandboxed());
+            assert!(sandbox_error("read").is_some());
+            let max_steps = with_active_limits(|limits| limits.max_steps);
+            assert_eq!(max_steps, Limits::sandbox().max_steps);
+        }
+        assert!(!is_sandboxed());
+        assert!(sandbox_error("read").is_none());
+    }
+
+    #[test]
+    fn test_sandbox_limits_fill_unset() {
+        let limits = Limits {
+            max_steps: Some(10),
+            ..Limits::none()
+        }
+        .or(Limits::sandbox());
+        assert_eq!(limits.max_steps, Some(10));
+        assert_eq!(limits.max_call_depth, Limits::sandbox().max_call_depth);
+    }
+}
@@ -1,23 +1,48 @@
 mod ast;
 pub mod base_variable;
+mod bench;
+mod bytecode;
+mod cache;
 mod collection;
 pub mod compiler;
+mod engine;
 pub mod function;
 mod function_map;
 pub mod globals;
 pub mod highlighter;
+mod host;
+mod intern;
+mod limits;
+mod module;
 mod node;
+mod optimizer;
+mod output;
 pub mod token_type;
+mod vm;
 
 mod compilers {
     pub mod collection;
     pub mod conditional;
     pub mod function;
     pub mod loops;
-    pub mod operation;
+    pub mod nullable;
     pub mod variable;
 }
 
+mod std_lib {
+    pub mod arguments;
+    pub mod fs;
+    pub mod json;
+    pub mod math;
+    pub mod process;
+    pub mod random;
+    pub mod regex;
+    pub mod stdin;
+    pub mod string;
+    pub mod system;
+    pub mod time;
+}
+
 mod statement_tokenizer {
     pub mod basic_tokenizer;
     pub mod collection_tokenizer;
@@ -35,15 +60,18 @@ use crossterm::{
 };
 use std::error::Error;
 use std::ffi::OsStr;
-use std::fs;
-use std::io::{self, Write};
+use std::io::{self, IsTerminal, Read, Write};
 use std::path::Path;
+use std::time::Duration;
 use std::{env, usize};
 //use crate::collection::collections::{Array, Dictionary};
 use crate::node::nodes::ASTNode;
 use base_variable::variables::VARIABLE_STACK;
 use compiler::compilers::route_to_parser;
-use globals::{MAKE_LOOP, TRY_FAIL};
+use engine::{parse_file, run_lines, split_source};
+use globals::INSPECT_PRIVATE;
+use limits::Limits;
+use module::{import_module, parse_import_statement, strip_pub_marker};
 //use jist::node::nodes::ASTNode;
 use node::nodes::match_token_to_node;
 use statement_tokenizer::tokenizer::tokenizers::tokenize;
@@ -54,16 +82,89 @@ use crate::function::USER_FUNCTION_STACK;
 //use std::sync::Mutex;
 
 ///
-/// This function checks if the file extension is valid. IE: .jist
+/// This function checks if the file extension is valid. IE: .jist or .jistc, a .jistc file must
+/// have been built by this version of jist from the current source
 ///
 fn check_file_extension(file_path: String) -> Result<bool, Box<dyn Error>> {
     let ext = Path::new(&file_path).extension().and_then(OsStr::to_str);
-    let valid_ext = "jist";
-    if ext == Some(valid_ext) {
-        Ok(true)
-    } else {
-        Err("Invalid file extension".into())
+    match ext {
+        Some("jist") => Ok(true),
+        Some("jistc") => {
+            cache::load(&file_path)?;
+            Ok(true)
+        }
+        _ => Err("Invalid file extension".into()),
+    }
+}
+
+///
+///This function compiles a program to a .jistc file, jist build foo.jist -o foo.jistc, the output
+///defaults to the source path with a .jistc extension
+///
+fn build_command(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let usage = "Usage: jist build <file.jist> [-o <file.jistc>]";
+    let source_path = args.first().ok_or(usage)?;
+    let output_path = match args.get(1).map(String::as_str) {
+        Some("-o") => args.get(2).ok_or(usage)?.clone(),
+        Some(_) => return Err(usage.into()),
+        None => Path::new(source_path)
+            .with_extension("jistc")
+            .to_string_lossy()
+            .to_string(),
+    };
+    if Path::new(source_path).extension().and_then(OsStr::to_str) != Some("jist") {
+        return Err(format!("Build error: {} is not a .jist file", source_path).into());
+    }
+    let script = cache::build(source_path, &output_path)?;
+    println!(
+        "Built {} ({} instructions)",
+        output_path,
+        script.chunk.code.len()
+    );
+    Ok(())
+}
+
+///
+///This function benchmarks the functions a program marks with bench, jist bench foo.jist
+///--warmup 3 --runs 20, and prints the mean, median and standard deviation of each
+///
+fn bench_command(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let usage = "Usage: jist bench <file.jist> [--warmup <count>] [--runs <count>]";
+    let file_path = args.first().ok_or(usage)?;
+    let mut options = bench::BenchOptions::default();
+    let mut rest = args[1..].iter();
+    while let Some(flag) = rest.next() {
+        let value = rest.next().ok_or(usage)?;
+        let count: usize = value.parse().map_err(|_| {
+            format!("Flag error: {} expects a number but got '{}'", flag, value)
+        })?;
+        match flag.as_str() {
+            "--warmup" => options.warmup = count,
+            "--runs" => options.runs = count,
+            _ => return Err(usage.into()),
+        }
     }
+    if !check_file_extension(file_path.clone())? {
+        return Err("File path not valid: Does not have .jist or .jistc extension".into());
+    }
+
+    let results = bench::run(file_path, options)?;
+    let width = results.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    println!(
+        "{} ({} warmup, {} runs)",
+        file_path, options.warmup, options.runs
+    );
+    for (name, summary) in results {
+        println!(
+            "  {:<width$}  mean {:>10}  median {:>10}  stddev {:>10}",
+            name,
+            bench::format_time(summary.mean),
+            bench::format_time(summary.median),
+            bench::format_time(summary.stddev),
+            width = width
+        );
+    }
+    Ok(())
 }
 
 ///
@@ -72,7 +173,7 @@ fn check_file_extension(file_path: String) -> Result<bool, Box<dyn Error>> {
 fn print_array_stack() {
     let array_stack = ARRAY_STACK.lock().unwrap(); // Lock the mutex
     for array in array_stack.iter() {
-        println!("{}", array); // Now we can iterate over the Vec
+        output::println(&array.to_string()); // Now we can iterate over the Vec
     }
 }
 
@@ -82,7 +183,7 @@ fn print_array_stack() {
 fn print_dictionary_stack() {
     let dict_stack = DICTIONARY_STACK.lock().unwrap(); // Lock the mutex
     for dict in dict_stack.iter() {
-        println!("{}", dict); // Now we can iterate over the Vec
+        output::println(&dict.to_string()); // Now we can iterate over the Vec
     }
 }
 
@@ -92,7 +193,7 @@ fn print_dictionary_stack() {
 fn print_function_stack() {
     let function_stack = USER_FUNCTION_STACK.lock().unwrap(); // Lock the mutex
     for function in function_stack.iter() {
-        println!("{}", function); // Now we can iterate over the Vec
+        output::println(&function.to_string()); // Now we can iterate over the Vec
     }
 }
 
@@ -180,294 +281,14 @@ fn parse_tokens(tokens: Vec<ASTNode>) -> Result<(), Box<dyn Error>> {
 }
 
 ///
-///This function reads the file and parses it, it was added to support multiple lines of code,
-///multiline coding statements and later multiple files
+///This function reads a whole program from standard input and runs it like a file, imports are
+///resolved from the working directory
 ///
-// Global var if_else_skip
-use crate::globals::IF_ELSE_SKIP;
-
-pub fn parse_lines(file_path: &str) -> Result<Vec<String>, Box<dyn Error>> {
-    let contents = fs::read_to_string(file_path)?;
-    if contents.is_empty() {
-        return Err("Error: Empty file".into());
-    }
-
-    let mut brace_count = 0;
-    let mut bracket_count = 0;
-    let mut current_line = String::new();
-    let mut finished_lines: Vec<String> = Vec::new();
-    let mut multiline_comment = false;
-
-    // Iterate through each line in the file
-    for (line_number, line) in contents.lines().enumerate() {
-        let mut chars = line.chars().peekable(); // Use a peekable iterator for lookahead
-
-        while let Some(ch) = chars.next() {
-            match ch {
-                '/' => {
-                    if chars.peek() == Some(&'/') {
-                        break; // Skip the rest of the line if it's a single-line comment
-                    } else if chars.peek() == Some(&'*') {
-                        multiline_comment = true; // Start of multiline comment
-                        chars.next(); // Consume '*'
-                        continue;
-                    } else {
-                        current_line.push(ch);
-                    }
-                }
-                '*' => {
-                    if multiline_comment && chars.peek() == Some(&'/') {
-                        multiline_comment = false; // End of multiline comment
-                        chars.next(); // Consume '/'
-                        continue;
-                    } else {
-                        current_line.push(ch);
-                    }
-                }
-                '{' => {
-                    brace_count += 1;
-                    current_line.push(ch);
-                }
-                '}' => {
-                    brace_count -= 1;
-                    current_line.push(ch);
-                    if brace_count < 0 {
-                        return Err(format!(
-                            "Unmatched closing curly brace at line {}",
-                            line_number + 1
-                        )
-                        .into());
-                    }
-                    if brace_count == 0 && bracket_count == 0 {
-                        finished_lines.push(current_line.clone());
-                        current_line.clear();
-                    }
-                }
-                '[' => {
-                    bracket_count += 1;
-                    current_line.push(ch);
-                }
-                ']' => {
-                    bracket_count -= 1;
-                    current_line.push(ch);
-                    if bracket_count < 0 {
-                        return Err(format!(
-                            "Unmatched closing square bracket at line {}",
-                            line_number + 1
-                        )
-                        .into());
-                    }
-                }
-                ';' => {
-                    current_line.push(ch);
-                    if brace_count == 0 && bracket_count == 0 {
-                        finished_lines.push(current_line.clone());
-                        current_line.clear();
-                    }
-                }
-                _ => {
-                    if !multiline_comment {
-                        current_line.push(ch); // Only push characters if not in a multiline comment
-                    }
-                }
-            }
-        }
-
-        // Check if there are unmatched braces or brackets at the end of the line
-        if brace_count < 0 {
-            return Err(
-                format!("Unmatched closing curly brace at line {}", line_number + 1).into(),
-            );
-        }
-        if bracket_count < 0 {
-            return Err(format!(
-                "Unmatched closing square bracket at line {}",
-                line_number + 1
-            )
-            .into());
-        }
-    }
-
-    // If there's any remaining content in current_line, add it as a finished line
-    if !current_line.is_empty() {
-        if brace_count > 0 {
-            return Err(format!("Unmatched opening curly brace at end of file").into());
-        }
-        if bracket_count > 0 {
-            return Err(format!("Unmatched opening square bracket at end of file").into());
-        }
-        finished_lines.push(current_line);
-    }
-
-    Ok(finished_lines)
-}
-
-fn parse_file(file_path: &str) -> Result<(), Box<dyn Error>> {
-    let _ast_nodes: Vec<ASTNode> = Vec::new();
-    let finished_lines: Vec<String>;
-
-    match parse_lines(file_path) {
-        Ok(lines) => {
-            finished_lines = lines;
-        }
-        Err(e) => {
-            return Err(e);
-        }
-    }
-
-    let mut tokenized_expression = Vec::new();
-
-    for line in finished_lines {
-        let tokens = tokenize(line.clone());
-
-        let mut hasroot = true;
-        let mut first_node: ASTNode = ASTNode::None;
-        let mut result: bool;
-
-        let mut nodes: Vec<ASTNode> = Vec::new();
-        for parsed_info in tokens.iter() {
-            let node = match_token_to_node(parsed_info.clone());
-            nodes.push(node);
-        }
-
-        for (i, parsed_info) in nodes.clone().iter().enumerate() {
-            if i == 0 {
-                first_node = parsed_info.clone();
-            }
-
-            match parsed_info {
-                ASTNode::Try(t) => {
-                    tokenized_expression.push(parsed_info.clone());
-                    result = route_to_parser(&mut tokenized_expression, None)?;
-                    // check result if Error throw error with line number and exit
-                    if !result {
-                        println!("Error in parsing line: {}", line);
-                        println!("Line: {}", line);
-                        std::process::exit(1);
-                    }
-                    // Clear tokenized_expression after processing
-                    tokenized_expression.clear();
-                }
-                ASTNode::Catch(c) => {
-                    if unsafe { TRY_FAIL } {
-                        tokenized_expression.push(parsed_info.clone());
-                        result = route_to_parser(&mut tokenized_expression, None)?;
-                        // check result if Error throw error with line number and exit
-                        if !result {
-                            println!("Error in parsing line: {}", line);
-                            println!("Line: {}", line);
-                            std::process::exit(1);
-                        }
-                        // Clear tokenized_expression after processing
-                        tokenized_expression.clear();
-
-                        // check result if Error throw error with line number and exit
-                        if !result {
-                            println!("Error in parsing line: {}", line);
-                            println!("Line: {}", line);
-                            std::process::exit(1);
-                        }
-                        // Clear tokenized_expression after processing
-                        tokenized_expression.clear();
-                    } else {
-                        unsafe { TRY_FAIL = false };
-                    }
-                }
-                ASTNode::Finally(f) => {
-                    tokenized_expression.push(parsed_info.clone());
-                    result = route_to_parser(&mut tokenized_expression, None)?;
-                    // check result if Error throw error with line number and exit
-                    if !result {
-                        println!("Error in parsing line: {}", line);
-                        println!("Line: {}", line);
-                        std::process::exit(1);
-                    }
-                    // Clear tokenized_expression after processing
-                    tokenized_expression.clear();
-                }
-
-                ASTNode::Function(f) => {
-                    tokenized_expression.push(parsed_info.clone());
-                    result = route_to_parser(&mut tokenized_expression, None)?;
-                    // check result if Error throw error with line number and exit
-                    if !result {
-                        println!("Error in parsing line: {}", line);
-                        println!("Line: {}", line);
-                        std::process::exit(1);
-                    }
-
-                    // Clear tokenized_expression after processing
-                    tokenized_expression.clear();
-                }
-                ASTNode::SemiColon => {
-                    // Check if the expression is valid before processing
-                    if tokenized_expression.len() == 1 {
-                        println!("Error at line number: {}", line.get(0..i).unwrap());
-                        println!("Line content: {}", line);
-                        println!("Syntax error: expression must be more than a semicolon");
-                        std::process::exit(1);
-                    }
-
-                    // Route to parser only if there are valid tokens
-                    match first_node.clone() {
-                        ASTNode::While(_) => result = route_to_parser(&mut nodes, None)?,
-
-                        ASTNode::If(_) => {
-                            result = route_to_parser(&mut tokenized_expression, None)?
-                        }
-                        ASTNode::Elif(_) => {
-                            if unsafe { IF_ELSE_SKIP } {
-                                break; // Skip processing if IF_ELSE_SKIP is true
-                            } else {
-                                result = route_to_parser(&mut tokenized_expression, None)?;
-
-                                while unsafe { MAKE_LOOP } {
-                                    result = route_to_parser(&mut tokenized_expression, None)?;
-                                }
-                            }
-                        }
-                        ASTNode::Else => {
-                            if unsafe { IF_ELSE_SKIP } {
-                                unsafe { IF_ELSE_SKIP = false }; // Reset IF_ELSE_SKIP
-                                break; // Skip further parsing
-                            } else {
-                                result = route_to_parser(&mut tokenized_expression, None)?;
-                                while unsafe { MAKE_LOOP } {
-                                    result = route_to_parser(&mut tokenized_expression, None)?;
-                                }
-                            }
-                        }
-                        ASTNode::Function(f) => {
-                            print!("route to parser");
-                            result = route_to_parser(&mut tokenized_expression, None)?;
-                        }
-                        _ => {
-                            result = route_to_parser(&mut tokenized_expression, None)?;
-                            while unsafe { MAKE_LOOP } {
-                                result = route_to_parser(&mut tokenized_expression, None)?;
-                            }
-                        }
-                    }
-
-                    // check result if Error throw error with line number and exit
-                    if !result {
-                        println!("Error in parsing line: {}", line);
-                        println!("Line: {}", line);
-                        std::process::exit(1);
-                    }
-
-                    // Clear tokenized_expression after processing
-                    tokenized_expression.clear();
-                }
-                _ => {
-                    hasroot = true; // Mark that we have a valid root
-                    tokenized_expression.push(parsed_info.clone()); // Accumulate tokens
-                }
-            }
-        }
-    }
-
-    Ok(())
+fn parse_stdin() -> Result<(), Box<dyn Error>> {
+    let mut contents = String::new();
+    io::stdin().read_to_string(&mut contents)?;
+    let finished_lines = split_source(&contents)?;
+    run_lines(finished_lines, "-")
 }
 
 fn get_input(
@@ -538,6 +359,9 @@ fn start_repl() -> Result<(), Box<dyn Error>> {
     println!("Welcome to the JistR Read-Eval-Print-Loop!");
     println!("Type 'exit();' to exit the REPL");
 
+    // modules imported in the repl show their private names as well
+    unsafe { INSPECT_PRIVATE = true };
+
     loop {
         enable_raw_mode()?;
         println!();
@@ -553,6 +377,27 @@ fn start_repl() -> Result<(), Box<dyn Error>> {
             break;
         }
 
+        match parse_import_statement(&input) {
+            Ok(Some(statement)) => {
+                if let Err(e) = import_module(statement, Path::new("repl.jist"), parse_file) {
+                    eprintln!("Error in import: {}", e);
+                }
+                continue;
+            }
+            Ok(None) => {}
+            Err(e) => {
+                eprintln!("Error in import: {}", e);
+                continue;
+            }
+        }
+        let input = match strip_pub_marker(&input) {
+            Ok(stripped) => stripped.unwrap_or(input),
+            Err(e) => {
+                eprintln!("Error in parsing: {}", e);
+                continue;
+            }
+        };
+
         // Tokenize input
         let tokens = tokenize_input(&input);
 
@@ -567,7 +412,26 @@ fn start_repl() -> Result<(), Box<dyn Error>> {
 
 fn main() -> Result<(), Box<dyn Error>> {
     // Collect command-line arguments
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+    take_flags(&mut args)?;
+
+    // a program piped in or passed as - is read from standard input
+    if (args.len() == 1 && !io::stdin().is_terminal()) || args.get(1).map(String::as_str) == Some("-")
+    {
+        std_lib::system::set_script_arguments(args.iter().skip(2).cloned().collect());
+        if let Err(e) = parse_stdin() {
+            return Err(format!("Error occurred while parsing standard input: {}", e).into());
+        }
+        print_stacks();
+        return Ok(());
+    }
+
+    if args.get(1).map(String::as_str) == Some("build") {
+        return build_command(&args[2..]);
+    }
+    if args.get(1).map(String::as_str) == Some("bench") {
+        return bench_command(&args[2..]);
+    }
 
     if args.len() == 1 {
         // Start the REPL if no file is passed
@@ -576,8 +440,9 @@ fn main() -> Result<(), Box<dyn Error>> {
         return Ok(());
     }
 
-    // File path provided as an argument
+    // File path provided as an argument, anything after it is passed to the script
     let file_path = &args[1];
+    std_lib::system::set_script_arguments(args[2..].to_vec());
 
     // Check if the file has the correct extension
     match check_file_extension(file_path.clone()) {
@@ -585,7 +450,7 @@ fn main() -> Result<(), Box<dyn Error>> {
             // File extension is valid, continue with parsing
         }
         Ok(false) => {
-            return Err("File path not valid: Does not have .jist extension".into());
+            return Err("File path not valid: Does not have .jist or .jistc extension".into());
         }
         Err(e) => {
             return Err(format!("Failed to check file extension: {}", e).into());
@@ -597,9 +462,102 @@ fn main() -> Result<(), Box<dyn Error>> {
         return Err(format!("Error occurred while parsing the file: {}", e).into());
     }
 
-    // After parsing, print the variable stack and other stacks
-    println!("\n\nStack:");
-    for variable in unsafe { VARIABLE_STACK.iter() } {
+    print_stacks();
+    Ok(())
+}
+
+///
+///This function reads the interpreter flags given before the script path and removes them so the
+///rest of main sees the same arguments as a run without flags
+///
+fn take_flags(args: &mut Vec<String>) -> Result<(), Box<dyn Error>> {
+    while args.len() > 1 && args[1].starts_with("--") {
+        let flag = args.remove(1);
+        let (name, value) = match flag.split_once('=') {
+            Some((name, value)) => (name.to_string(), Some(value.to_string())),
+            None => (flag.clone(), None),
+        };
+
+        match name.as_str() {
+            "--seed" => {
+                let value = match value {
+                    Some(value) => value,
+                    None if args.len() > 1 => args.remove(1),
+                    None => return Err("Flag error: --seed expects a number".into()),
+                };
+                let seed: u64 = value.parse().map_err(|_| {
+                    format!("Flag error: --seed expects a number but got '{}'", value)
+                })?;
+                std_lib::random::set_seed(seed);
+            }
+            // debug messages from the interpreter go to stderr
+            "--trace" => output::set_trace(Some(Box::new(output::StderrOutput))),
+            "--sandbox" => limits::enable_sandbox(),
+            // programs are walked statement by statement instead of run as bytecode
+            "--tree-walk" => unsafe { globals::TREE_WALK = true },
+            "--no-cache" => unsafe { globals::NO_CACHE = true },
+            "--emit" => match value.as_deref() {
+                Some("opt-ast") => unsafe { globals::EMIT_OPT_AST = true },
+                _ => return Err("Flag error: --emit expects opt-ast e.g. --emit=opt-ast".into()),
+            },
+            "--max-steps" => {
+                let max = limit_value(&name, value)?;
+                limits::set_limits(Limits {
+                    max_steps: Some(max),
+                    ..limits::limits()
+                });
+            }
+            "--max-depth" => {
+                let max = limit_value(&name, value)?;
+                limits::set_limits(Limits {
+                    max_call_depth: Some(max as usize),
+                    ..limits::limits()
+                });
+            }
+            "--timeout" => {
+                let max = limit_value(&name, value)?;
+                limits::set_limits(Limits {
+                    max_duration: Some(Duration::from_millis(max)),
+                    ..limits::limits()
+                });
+            }
+            "--max-elements" => {
+                let max = limit_value(&name, value)?;
+                limits::set_limits(Limits {
+                    max_collection_elements: Some(max as usize),
+                    ..limits::limits()
+                });
+            }
+            "--max-string" => {
+                let max = limit_value(&name, value)?;
+                limits::set_limits(Limits {
+                    max_string_length: Some(max as usize),
+                    ..limits::limits()
+                });
+            }
+            _ => return Err(format!("Flag error: unknown flag '{}'", flag).into()),
+        }
+    }
+    Ok(())
+}
+
+///
+///This function reads the number given to a limit flag e.g. --max-steps=1000
+///
+fn limit_value(name: &str, value: Option<String>) -> Result<u64, Box<dyn Error>> {
+    let value = value
+        .ok_or_else(|| format!("Flag error: {} expects a number e.g. {}=1000", name, name))?;
+    value
+        .parse()
+        .map_err(|_| format!("Flag error: {} expects a number but got '{}'", name, value).into())
+}
+
+///
+///This function prints the variable stack and other stacks after a program has run
+///
+fn print_stacks() {
+    output::println("\n\nStack:");
+    for variable in VARIABLE_STACK.lock().unwrap().iter() {
         variable.print();
     }
 
@@ -607,8 +565,6 @@ fn main() -> Result<(), Box<dyn Error>> {
     print_array_stack();
     print_dictionary_stack();
     print_function_stack();
-
-    Ok(())
 }
 
 #[cfg(test)]
@@ -621,6 +577,13 @@ mod main_test {
         assert_eq!(result.is_ok(), true);
     }
 
+    #[test]
+    fn test_check_file_extension_jistc() {
+        // a .jistc file is read to check its version so a missing one is an error
+        let result = super::check_file_extension("missing.jistc".to_string());
+        assert!(result.is_err());
+    }
+
     #[test]
     fn test_check_file_extension_invalid() {
         let file_path = "test.txt";
@@ -715,7 +678,8 @@ mod test_input_output {
         .assert()
         .success()
         .stdout(predicate::str::contains(
-            r#"a: Dict<bool, string> = {"true" => true, "false" => false, "true" => not false}"#,
+            // a key given twice keeps its first position and its last value
+            r#"a: Dict<bool, string> = {"true" => not false, "false" => false}"#,
         ));
     }
 
@@ -820,6 +784,897 @@ mod test_input_output {
             r#"a: Dict<string, float> = {"one" => 1.100000023841858, "two" => 2.0999999046325684, "three" => 3.0999999046325684}"#,
         ));
     }
+    #[test]
+    fn test_nullable_operators() {
+        let file_path = "test_files/nullable.jist";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg(file_path)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Variable Name: maybe\nVariable Type: Int?\nVariable Value: null",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: fallback\nVariable Type: Int\nVariable Value: 42",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: forced\nVariable Type: Int\nVariable Value: 7",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: missing\nVariable Type: Int?\nVariable Value: null",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: size\nVariable Type: Int\nVariable Value: 4",
+            ));
+    }
+
+    #[test]
+    fn test_unknown_function_is_an_error() {
+        // the error is returned to main instead of printed where it happened
+        Command::cargo_bin("jist")
+            .unwrap()
+            .arg("test_files/unknown_function.jist")
+            .assert()
+            .failure()
+            .stdout("before")
+            .stderr(predicate::str::contains(
+                "Function call missing_function is not in any of the registered functions.",
+            ));
+    }
+
+    #[test]
+    fn test_null_safety_error() {
+        let file_path = "test_files/nullable_error.jist";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg(file_path)
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Null safety error: 'nothing'"));
+    }
+
+    #[test]
+    fn test_string_functions() {
+        let file_path = "test_files/string_functions.jist";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg(file_path)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Variable Name: word_len\nVariable Type: Int\nVariable Value: 5",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: middle\nVariable Type: String\nVariable Value: él",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: where_z\nVariable Type: Int?\nVariable Value: null",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: csv_joined\nVariable Type: String\nVariable Value: a | b | c",
+            ))
+            .stdout(predicate::str::contains("csv_parts: Array<string> = [a, b, c]"))
+            .stdout(predicate::str::contains("word_chars: Array<char> = [h, é]"))
+            .stdout(predicate::str::contains("caught parse error"));
+    }
+
+    #[test]
+    fn test_regex_functions() {
+        let file_path = "test_files/regex_functions.jist";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg(file_path)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Variable Name: date_re\nVariable Type: Regex\nVariable Value: (\\d+)-(\\d+)-(\\d+)",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: masked\nVariable Type: String\nVariable Value: ####-##-## ERROR disk full",
+            ))
+            .stdout(predicate::str::contains(
+                "words: Array<string> = [2024-01-05, ERROR, disk, full]",
+            ))
+            .stdout(predicate::str::contains("numbers: Array<string> = [2024, 01, 05]"))
+            .stdout(predicate::str::contains("caught regex error"))
+            .stderr(predicate::str::contains(
+                "Regex error: unclosed group at position 0 in pattern \"(ab\"",
+            ));
+    }
+
+    #[test]
+    fn test_json_functions() {
+        let file_path = "test_files/json_functions.jist";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg(file_path)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                r#"config: Dict<string, string> = {"name" => jist, "level" => 3, "tags" => [a, b]}"#,
+            ))
+            .stdout(predicate::str::contains(
+                r#"Variable Value: {"name":"jist","level":3,"tags":["a","b"]}"#,
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: scores_text\nVariable Type: String\nVariable Value: [\n  1,\n  2,\n  3\n]",
+            ))
+            .stdout(predicate::str::contains("caught json error"))
+            .stderr(predicate::str::contains(
+                "JSON error: expected ':' but found '1' at line 1 column 6",
+            ));
+    }
+
+    #[test]
+    fn test_fs_functions() {
+        let file_path = "test_files/fs_functions.jist";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg(file_path)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Variable Name: notes\nVariable Type: String\nVariable Value: hello world",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: note_size\nVariable Type: Int\nVariable Value: 11",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: still_there\nVariable Type: Bool\nVariable Value: false",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: second_line\nVariable Type: String?\nVariable Value: second",
+            ))
+            .stdout(predicate::str::contains("entries: Array<string> = [renamed.txt]"))
+            .stdout(predicate::str::contains(
+                "all_lines: Array<string> = [first, second, third]",
+            ))
+            .stdout(predicate::str::contains("caught file error"))
+            .stderr(predicate::str::contains(
+                "File error: cannot read 'test_files/missing.txt'",
+            ));
+    }
+
+    #[test]
+    fn test_modules() {
+        let file_path = "test_files/modules.jist";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg(file_path)
+            .env("JIST_PATH", "test_files/modules")
+            .assert()
+            .success()
+            // the module runs once however many times it is imported
+            .stdout(predicate::str::contains("greetings loaded").count(1))
+            .stdout(predicate::str::contains(
+                "Variable Name: message\nVariable Type: String\nVariable Value: Hello, jist",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: loud\nVariable Type: String\nVariable Value: HELLO, MODULES",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: module_version\nVariable Type: Int\nVariable Value: 2",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Value: Hello, search path",
+            ))
+            // only imported names reach the importing file
+            .stdout(predicate::str::contains("Variable Name: greeting").not());
+    }
+
+    #[test]
+    fn test_module_cycle_error() {
+        let file_path = "test_files/module_cycle.jist";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg(file_path).assert().failure().stderr(predicate::str::contains(
+            "Import error: circular import cycle_a.jist -> cycle_b.jist -> cycle_a.jist",
+        ));
+    }
+
+    #[test]
+    fn test_module_private_error() {
+        let file_path = "test_files/module_private.jist";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg(file_path).assert().failure().stderr(predicate::str::contains(
+            "Import error: 'whisper' is private to 'greetings.jist'",
+        ));
+    }
+
+    #[test]
+    fn test_system_functions() {
+        let file_path = "test_files/system_functions.jist";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg(file_path)
+            .args(["foo", "--bar"])
+            .env("JIST_TEST_HOME", "/home/jist")
+            .assert()
+            .code(3)
+            .stdout(predicate::str::contains("2\n"))
+            .stdout(predicate::str::contains("foo --bar\n"))
+            .stdout(predicate::str::contains("/home/jist\n"))
+            .stdout(predicate::str::contains("not set\n"))
+            .stdout(predicate::str::contains("ci\n"))
+            .stdout(predicate::str::contains("after exit").not());
+    }
+
+    #[test]
+    fn test_process_functions() {
+        let file_path = "test_files/process_functions.jist";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg(file_path)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "result: Dict<string, string> = {\"status\" => 2, \"stdout\" => out\n, \"stderr\" => err\n}",
+            ))
+            .stdout(predicate::str::contains(
+                "piped: Dict<string, string> = {\"status\" => 0, \"stdout\" => piped in, \"stderr\" => }",
+            ))
+            .stdout(predicate::str::contains(
+                "greeted: Dict<string, string> = {\"status\" => 0, \"stdout\" => -c echo out; echo err >&2; exit 2\n",
+            ))
+            .stdout(predicate::str::contains("caught process error"))
+            .stderr(predicate::str::contains(
+                "Process error: cannot run 'jist-missing-command'",
+            ));
+    }
+
+    #[test]
+    fn test_time_functions() {
+        let file_path = "test_files/time_functions.jist";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg(file_path)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Variable Name: release\nVariable Type: DateTime\nVariable Value: 2024-02-28T23:00:00+02:00\n",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: day\nVariable Type: String\nVariable Value: Wednesday 28 February 2024\n",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: stamp\nVariable Type: DateTime\nVariable Value: 2024-01-05T08:15:00+00:00\n",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: in_utc\nVariable Type: DateTime\nVariable Value: 2024-02-28T21:00:00+00:00\n",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: deadline\nVariable Type: DateTime\nVariable Value: 2024-02-29T01:00:00+02:00\n",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: gap\nVariable Type: Duration\nVariable Value: 4718700s\n",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: order\nVariable Type: Int\nVariable Value: -1\n",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: gap_ms\nVariable Type: Int\nVariable Value: 7200000\n",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: quick\nVariable Type: Int\nVariable Value: -1\n",
+            ))
+            .stdout(predicate::str::contains("caught time error"))
+            .stderr(predicate::str::contains(
+                "Time error: cannot parse 'yesterday' as RFC 3339",
+            ));
+    }
+
+    #[test]
+    fn test_seeded_runs_repeat() {
+        let file_path = "test_files/random_functions.jist";
+        let run = |flags: &[&str]| {
+            let output = Command::cargo_bin("jist")
+                .unwrap()
+                .args(flags)
+                .arg(file_path)
+                .output()
+                .unwrap();
+            assert!(output.status.success());
+            String::from_utf8(output.stdout).unwrap()
+        };
+
+        let first = run(&["--seed", "42"]);
+        assert_eq!(first, run(&["--seed=42"]));
+        assert_ne!(first, run(&["--seed", "43"]));
+
+        // seed(7) inside the script repeats the next draw
+        let value_of = |name: &str| {
+            let marker = format!("Variable Name: {}\nVariable Type: Int\nVariable Value: ", name);
+            let start = first.find(&marker).unwrap() + marker.len();
+            first[start..].lines().next().unwrap().to_string()
+        };
+        assert_eq!(value_of("first"), value_of("second"));
+    }
+
+    #[test]
+    fn test_bad_seed_flag() {
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.args(["--seed", "ten", "test_files/random_functions.jist"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(
+                "Flag error: --seed expects a number but got 'ten'",
+            ));
+    }
+
+    #[test]
+    fn test_math_functions() {
+        let file_path = "test_files/math_functions.jist";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg(file_path)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Variable Name: biggest\nVariable Type: Int\nVariable Value: 3\n",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: mixed\nVariable Type: Float\nVariable Value: 3.5\n",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: total\nVariable Type: Int\nVariable Value: 42\n",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: quotient\nVariable Type: Int\nVariable Value: 3\n",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: exact\nVariable Type: Float\nVariable Value: 3.5\n",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: power\nVariable Type: Int\nVariable Value: 1024\n",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: distance\nVariable Type: Float\nVariable Value: 5\n",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: divisor\nVariable Type: Int\nVariable Value: 6\n",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: multiple\nVariable Type: Int\nVariable Value: 12\n",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: modular\nVariable Type: Int\nVariable Value: 445\n",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: limited\nVariable Type: Int\nVariable Value: 10\n",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: direction\nVariable Type: Int\nVariable Value: -1\n",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: whole\nVariable Type: Float\nVariable Value: -2\n",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: digits\nVariable Type: Float\nVariable Value: 3\n",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: circle\nVariable Type: Float\nVariable Value: 6.283185307179586\n",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: half_turn\nVariable Type: Float\nVariable Value: 3.141592653589793\n",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: growth\nVariable Type: Float\nVariable Value: 2.718281828459045\n",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: finite\nVariable Type: Bool\nVariable Value: true\n",
+            ))
+            .stdout(predicate::str::contains("caught math error"))
+            .stderr(predicate::str::contains(
+                "Math error: add overflowed the int range",
+            ));
+    }
+
+    #[test]
+    fn test_function_parameters() {
+        let file_path = "test_files/function_parameters.jist";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg(file_path)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Variable Name: default_greeting\nVariable Type: String\nVariable Value: Hello, jist\n",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: given_greeting\nVariable Type: String\nVariable Value: Hi, jist\n",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: named_greeting\nVariable Type: String\nVariable Value: Hey, named\n",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: mixed_greeting\nVariable Type: String\nVariable Value: Yo, mixed\n",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: quiet\nVariable Type: Bool\nVariable Value: false\n",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: loud\nVariable Type: Bool\nVariable Value: true\n",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: no_rest\nVariable Type: Int\nVariable Value: 0\n",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: three_rest\nVariable Type: Int\nVariable Value: 3\n",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: joined\nVariable Type: String\nVariable Value: a b c\n",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: padded\nVariable Type: String\nVariable Value: 007\n",
+            ))
+            .stderr(predicate::str::contains(
+                "Error in Try block: Argument error: greet is missing argument 'name'",
+            ))
+            .stderr(predicate::str::contains(
+                "Error in Try block: Argument error: greet has no parameter named 'nickname'",
+            ))
+            .stderr(predicate::str::contains(
+                "Error in Try block: Type error: names expects argument 2 to be String but got Int",
+            ))
+            .stdout(predicate::str::contains("caught missing argument error"))
+            .stdout(predicate::str::contains("caught unknown name error"))
+            .stdout(predicate::str::contains("caught variadic type error"));
+    }
+
+    #[test]
+    fn test_native_functions() {
+        let file_path = "test_files/native_functions.jist";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg(file_path)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Variable Name: second\nVariable Type: Int\nVariable Value: 2\n",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: missing\nVariable Type: Int?\nVariable Value: null\n",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: last\nVariable Type: Int\nVariable Value: 4\n",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: replaced\nVariable Type: Int\nVariable Value: 10\n",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: b\nVariable Type: Int\nVariable Value: 2\n",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: joined\nVariable Type: String\nVariable Value: abcd\n",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: pair\nVariable Type: String\nVariable Value: xy\n",
+            ))
+            .stdout(predicate::str::contains("nums: Array<int> = [10, 2, 3]"))
+            .stdout(predicate::str::contains(
+                "ages: Dict<string, int> = {\"a\" => 1, \"b\" => 2, \"c\" => 3}",
+            ))
+            .stderr(predicate::str::contains(
+                "Error in Try block: Argument error: concat expects at least 2 argument(s) but got 1",
+            ))
+            .stderr(predicate::str::contains(
+                "Error in Try block: Type error: to_uppercase expects argument 1 to be String but got Int",
+            ));
+    }
+
+    #[test]
+    fn test_trace_flag() {
+        let file_path = "test_files/string_variable_declaration.jist";
+        Command::cargo_bin("jist")
+            .unwrap()
+            .arg(file_path)
+            .assert()
+            .success()
+            .stderr(predicate::str::contains("Variable declaration:").not());
+
+        // a cached program is not tokenized again so the cache is skipped
+        Command::cargo_bin("jist")
+            .unwrap()
+            .args(["--trace", "--no-cache", file_path])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Variable declaration:").not())
+            .stderr(predicate::str::contains("Variable declaration:"));
+    }
+
+    #[test]
+    fn test_limit_flags() {
+        Command::cargo_bin("jist")
+            .unwrap()
+            .args(["--max-steps=4", "test_files/while_loop.jist"])
+            .assert()
+            .failure()
+            .stdout(predicate::str::contains("Looping: 3").not())
+            .stderr(predicate::str::contains(
+                "Limit error: executed more than 4 steps",
+            ));
+
+        // try does not catch a limit
+        Command::cargo_bin("jist")
+            .unwrap()
+            .args(["--max-depth=10", "test_files/limits.jist"])
+            .assert()
+            .failure()
+            .stdout(predicate::str::contains("start"))
+            .stdout(predicate::str::contains("caught").not())
+            .stderr(predicate::str::contains(
+                "Limit error: call depth exceeded the limit of 10",
+            ));
+
+        // the call depth is limited before the native stack overflows when no depth is given
+        for engine in [None, Some("--tree-walk")] {
+            Command::cargo_bin("jist")
+                .unwrap()
+                .args(engine.into_iter().chain(["test_files/limits.jist"]))
+                .assert()
+                .failure()
+                .stderr(predicate::str::contains(
+                    "Limit error: call depth exceeded the limit of 200",
+                ));
+        }
+
+        Command::cargo_bin("jist")
+            .unwrap()
+            .args(["--max-steps", "test_files/while_loop.jist"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Flag error: --max-steps expects a number"));
+    }
+
+    #[test]
+    fn test_range_checks_limit_before_building() {
+        // the range would take gigabytes if it was built before the check
+        Command::cargo_bin("jist")
+            .unwrap()
+            .args(["--max-elements=100", "test_files/range_limit.jist"])
+            .assert()
+            .failure()
+            .stdout(predicate::str::contains("[1, 2, 3]"))
+            .stdout(predicate::str::contains("built").not())
+            .stderr(predicate::str::contains(
+                "Limit error: collections hold 2000000000 elements, more than the limit of 100",
+            ));
+    }
+
+    #[test]
+    fn test_repeat_checks_limit_before_building() {
+        Command::cargo_bin("jist")
+            .unwrap()
+            .args(["--max-string=100", "test_files/repeat_limit.jist"])
+            .assert()
+            .failure()
+            .stdout(predicate::str::contains("abab"))
+            .stdout(predicate::str::contains("built").not())
+            .stderr(predicate::str::contains(
+                "Limit error: string of 6000000000 characters is longer than the limit of 100",
+            ));
+    }
+
+    #[test]
+    fn test_sleep_stops_at_timeout() {
+        let started = std::time::Instant::now();
+        Command::cargo_bin("jist")
+            .unwrap()
+            .args(["--timeout=100", "--sandbox", "test_files/sleep_limit.jist"])
+            .assert()
+            .failure()
+            .stdout(predicate::str::contains("start"))
+            .stdout(predicate::str::contains("woke").not())
+            .stderr(predicate::str::contains(
+                "Limit error: ran longer than the limit of 100 ms",
+            ));
+        assert!(started.elapsed() < std::time::Duration::from_millis(2000));
+    }
+
+    #[test]
+    fn test_sandbox_flag() {
+        Command::cargo_bin("jist")
+            .unwrap()
+            .args(["--sandbox", "test_files/file_read.jist"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(
+                "Sandbox error: read is not available in sandbox mode",
+            ));
+
+        Command::cargo_bin("jist")
+            .unwrap()
+            .args(["--sandbox", "test_files/modules.jist"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Sandbox error: import"));
+
+        Command::cargo_bin("jist")
+            .unwrap()
+            .args(["--sandbox", "test_files/while_loop.jist"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Looping: 3"));
+    }
+
+    #[test]
+    fn test_bytecode_loops() {
+        let file_path = "test_files/bytecode_loop.jist";
+        run_jist_command(file_path).stdout(predicate::str::contains("49995000 123"));
+
+        Command::cargo_bin("jist")
+            .unwrap()
+            .args(["--trace", file_path])
+            .assert()
+            .success()
+            .stderr(predicate::str::contains("Compiled program to bytecode"))
+            .stderr(predicate::str::contains("Declare total: int in 0"))
+            .stderr(predicate::str::contains("StoreSlot 0"));
+
+        for args in [
+            vec!["test_files/while_loop.jist"],
+            vec!["--tree-walk", "test_files/while_loop.jist"],
+        ] {
+            Command::cargo_bin("jist")
+                .unwrap()
+                .args(args)
+                .assert()
+                .success()
+                .stdout(predicate::str::contains(
+                    "Looping: 1Looping: 2Looping: 3Loop ended",
+                ));
+        }
+    }
+
+    /// The output of a program before the stack is printed
+    fn program_output(args: &[&str]) -> String {
+        let output = Command::cargo_bin("jist")
+            .unwrap()
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?} failed", args);
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        stdout.split("\n\nStack:").next().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_engines_agree() {
+        let expected = "4 6 6 5 mid none 3.5 highmidlow 8 caught";
+        assert_eq!(program_output(&["test_files/engines.jist"]), expected);
+        assert_eq!(
+            program_output(&["--tree-walk", "test_files/engines.jist"]),
+            expected
+        );
+
+        for file_path in [
+            "test_files/arithmetic_operations.jist",
+            "test_files/bytecode_loop.jist",
+            "test_files/dictionary_keys.jist",
+            "test_files/for_loop.jist",
+            "test_files/function_parameters.jist",
+            "test_files/if_else_conditions.jist",
+            "test_files/large_array.jist",
+            "test_files/math_functions.jist",
+            "test_files/native_functions.jist",
+            "test_files/nullable.jist",
+            "test_files/string_functions.jist",
+            "test_files/variable_reassignment.jist",
+            "test_files/while_loop.jist",
+        ] {
+            assert_eq!(
+                program_output(&[file_path]),
+                program_output(&["--tree-walk", file_path]),
+                "{} differs between the engines",
+                file_path
+            );
+        }
+    }
+
+    #[test]
+    fn test_loop_body_errors_stop_both_engines() {
+        for file_path in [
+            "test_files/while_overflow.jist",
+            "test_files/for_overflow.jist",
+        ] {
+            for engine in [None, Some("--tree-walk")] {
+                Command::cargo_bin("jist")
+                    .unwrap()
+                    .args(engine.into_iter().chain([file_path]))
+                    .assert()
+                    .failure()
+                    .stdout(predicate::str::is_empty())
+                    .stderr(predicate::str::contains(
+                        "Arithmetic error: integer overflow",
+                    ));
+            }
+        }
+    }
+
+    #[test]
+    fn test_build_jistc() {
+        let dir = std::env::temp_dir().join("jist_build_test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("loop.jist");
+        let compiled = dir.join("loop.jistc");
+        std::fs::copy("test_files/while_loop.jist", &source).unwrap();
+
+        Command::cargo_bin("jist")
+            .unwrap()
+            .args(["build", source.to_str().unwrap(), "-o", compiled.to_str().unwrap()])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Built"));
+        run_jist_command(compiled.to_str().unwrap()).stdout(predicate::str::contains(
+            "Looping: 1Looping: 2Looping: 3Loop ended",
+        ));
+
+        // a .jistc file is stale once its source changes
+        std::fs::write(&source, "print(1);").unwrap();
+        Command::cargo_bin("jist")
+            .unwrap()
+            .arg(compiled.to_str().unwrap())
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("is stale"));
+
+        let corrupt = dir.join("corrupt.jistc");
+        std::fs::write(&corrupt, "print(1);").unwrap();
+        Command::cargo_bin("jist")
+            .unwrap()
+            .arg(corrupt.to_str().unwrap())
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Cache error: not a .jistc file"));
+
+        // the tree walk runs statements and a .jistc file only holds bytecode
+        std::fs::copy("test_files/while_loop.jist", &source).unwrap();
+        Command::cargo_bin("jist")
+            .unwrap()
+            .args([
+                "build",
+                source.to_str().unwrap(),
+                "-o",
+                compiled.to_str().unwrap(),
+            ])
+            .assert()
+            .success();
+        Command::cargo_bin("jist")
+            .unwrap()
+            .args(["--tree-walk", compiled.to_str().unwrap()])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("cannot be run with --tree-walk"));
+    }
+
+    #[test]
+    fn test_automatic_cache() {
+        let dir = std::env::temp_dir().join("jist_cache_test");
+        let file_path = "test_files/while_loop.jist";
+        for _ in 0..2 {
+            Command::cargo_bin("jist")
+                .unwrap()
+                .env("JIST_CACHE_DIR", &dir)
+                .arg(file_path)
+                .assert()
+                .success()
+                .stdout(predicate::str::contains(
+                    "Looping: 1Looping: 2Looping: 3Loop ended",
+                ));
+        }
+        Command::cargo_bin("jist")
+            .unwrap()
+            .env("JIST_CACHE_DIR", &dir)
+            .args(["--trace", file_path])
+            .assert()
+            .success()
+            .stderr(predicate::str::contains(
+                "Loaded test_files/while_loop.jist",
+            ))
+            .stderr(predicate::str::contains("Compiled program to bytecode"));
+        Command::cargo_bin("jist")
+            .unwrap()
+            .env("JIST_CACHE_DIR", &dir)
+            .args(["--trace", "--no-cache", file_path])
+            .assert()
+            .success()
+            .stderr(predicate::str::contains("Loaded").not());
+    }
+
+    #[test]
+    fn test_constant_folding() {
+        let file_path = "test_files/constant_folding.jist";
+        run_jist_command(file_path).stdout(predicate::str::contains("86400 14 ab true 10"));
+
+        Command::cargo_bin("jist")
+            .unwrap()
+            .args(["--emit=opt-ast", file_path])
+            .assert()
+            .success()
+            .stderr(predicate::str::contains("folded 60 * 60 * 24 to 86400"))
+            .stderr(predicate::str::contains("removed if (1 > 2) branch"))
+            .stderr(predicate::str::contains(
+                "removed 1 statement(s) after return",
+            ))
+            .stderr(predicate::str::contains("Compiled program to bytecode").not());
+
+        Command::cargo_bin("jist")
+            .unwrap()
+            .args(["--emit=ast", file_path])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("--emit expects opt-ast"));
+    }
+
+    #[test]
+    fn test_large_array() {
+        let file_path = "test_files/large_array.jist";
+        run_jist_command(file_path).stdout(predicate::str::contains("9999 7 10000 10000"));
+    }
+
+    #[test]
+    fn test_dictionary_keys() {
+        let file_path = "test_files/dictionary_keys.jist";
+        run_jist_command(file_path)
+            .stdout(predicate::str::contains("yes 2 null caught unhashable key"))
+            .stderr(predicate::str::contains("Array cannot be a dictionary key"));
+    }
+
+    #[test]
+    fn test_bench_command() {
+        let file_path = "test_files/bench.jist";
+        Command::cargo_bin("jist")
+            .unwrap()
+            .args(["bench", file_path, "--runs", "3"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("setup ran"))
+            .stdout(predicate::str::contains("(3 warmup, 3 runs)"))
+            .stdout(predicate::str::contains("greeting  mean"))
+            .stdout(predicate::str::contains("stddev"))
+            .stdout(predicate::str::contains("printed while timed").not());
+
+        // outside jist bench the marked functions are declared as usual
+        run_jist_command(file_path).stdout(predicate::str::contains("setup ran"));
+
+        Command::cargo_bin("jist")
+            .unwrap()
+            .args(["bench", "test_files/while_loop.jist"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("has no functions marked bench"));
+    }
+
+    #[test]
+    fn test_stdin_functions() {
+        let file_path = "test_files/stdin_functions.jist";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg(file_path)
+            .write_stdin("first\nsecond\nthird\nfourth\n")
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Variable Name: first\nVariable Type: String?\nVariable Value: first\n",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: second\nVariable Type: String?\nVariable Value: second\n",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: rest\nVariable Type: String\nVariable Value: third\nfourth\n",
+            ))
+            .stdout(predicate::str::contains(
+                "Variable Name: finished\nVariable Type: String\nVariable Value: end of input",
+            ));
+    }
+
+    #[test]
+    fn test_program_from_stdin() {
+        // a piped program runs without a file argument
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.write_stdin("let piped: int = 7;\nprint(\"from a pipe\");\n")
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("from a pipe"))
+            .stdout(predicate::str::contains(
+                "Variable Name: piped\nVariable Type: Int\nVariable Value: 7",
+            ));
+
+        // - reads the program from stdin and passes the rest of the arguments to it
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.args(["-", "extra"])
+            .write_stdin("let script_args: array<string> = args();")
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("script_args: Array<string> = [extra]"));
+    }
+
     // Test variable assignment and modification
     #[test]
     fn test_variable_reassignment() {
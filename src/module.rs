@@ -0,0 +1,539 @@
+/*
+* This file contains the module system, a module is another .jist file loaded with
+* import "file.jist" as name; or from "file.jist" import a, b;. Each module runs once in its own
+* scope and only the top level declarations marked pub can be reached by the files that import it
+*/
+
+use crate::base_variable::base_types::BaseTypes;
+use crate::base_variable::variable::Variable;
+use crate::base_variable::variables::{push_variable, replace_variables, take_variables};
+use crate::collection::collections::{Array, Dictionary};
+use crate::collection::{ARRAY_STACK, DICTIONARY_STACK};
+use crate::compilers::function::{call_user_function, parse_function_call_arguments};
+use crate::function::functions::{CallArguments, Function};
+use crate::function::USER_FUNCTION_STACK;
+use crate::globals::INSPECT_PRIVATE;
+use crate::limits;
+use crate::node::nodes::{match_token_to_node, ASTNode};
+use crate::statement_tokenizer::tokenizer::tokenizers::tokenize;
+use lazy_static::lazy_static;
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Environment variable holding extra directories to search for modules, separated like PATH
+pub const SEARCH_PATH_VARIABLE: &str = "JIST_PATH";
+
+/// The names a file has imported, namespaces map to a module and imported functions map to the
+/// module they are declared in
+#[derive(Clone, Default)]
+pub struct Imports {
+    pub aliases: HashMap<String, PathBuf>,
+    pub functions: HashMap<String, PathBuf>,
+}
+
+/// Everything declared at the top level of a file
+#[derive(Default)]
+pub struct Scope {
+    pub variables: Vec<Variable>,
+    pub arrays: Vec<Array>,
+    pub dictionaries: Vec<Dictionary>,
+    pub functions: Vec<Function>,
+    pub imports: Imports,
+    pub public: HashSet<String>,
+}
+
+lazy_static! {
+    // modules that have finished loading keyed by their canonical path
+    static ref MODULES: Mutex<HashMap<PathBuf, Scope>> = Mutex::new(HashMap::new());
+    // modules that are loading, used to report import cycles
+    static ref LOADING: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+    static ref IMPORTS: Mutex<Imports> = Mutex::new(Imports::default());
+    // names marked pub in the file that is running
+    static ref PUBLIC_NAMES: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
+}
+
+/// An import statement at the top level of a file
+#[derive(Clone, Debug, PartialEq)]
+pub enum ImportStatement {
+    Namespace { path: String, alias: String },
+    Names { path: String, names: Vec<String> },
+}
+
+/// Check if a line is an import statement and parse it
+///
+/// params: line: &str -> The line of code
+///
+/// returns: Result<Option<ImportStatement>, Box<dyn Error>> -> The import or None for other lines
+pub fn parse_import_statement(line: &str) -> Result<Option<ImportStatement>, Box<dyn Error>> {
+    let line = line.trim();
+    let (keyword, rest) = match line.split_once(char::is_whitespace) {
+        Some((keyword, rest)) if keyword == "import" || keyword == "from" => (keyword, rest),
+        _ => return Ok(None),
+    };
+
+    let rest = rest
+        .trim()
+        .strip_suffix(';')
+        .ok_or_else(|| format!("Import error: missing ';' after '{}'", line))?;
+    let (path, rest) = read_quoted_path(rest)
+        .ok_or_else(|| format!("Import error: expected a quoted file path in '{}'", line))?;
+
+    if keyword == "import" {
+        let alias = rest
+            .trim()
+            .strip_prefix("as")
+            .map(str::trim)
+            .filter(|alias| is_identifier(alias))
+            .ok_or_else(|| format!("Import error: expected 'as <name>' in '{}'", line))?;
+        return Ok(Some(ImportStatement::Namespace {
+            path,
+            alias: alias.to_string(),
+        }));
+    }
+
+    let names: Vec<String> = rest
+        .trim()
+        .strip_prefix("import")
+        .map(|names| names.split(',').map(|n| n.trim().to_string()).collect())
+        .unwrap_or_default();
+    if names.is_empty() || !names.iter().all(|name| is_identifier(name)) {
+        return Err(format!("Import error: expected 'import <names>' in '{}'", line).into());
+    }
+    Ok(Some(ImportStatement::Names { path, names }))
+}
+
+/// Check if a line starts with the pub marker, the marker is removed and the declared name is
+/// recorded as public for the file that is running
+///
+/// params: line: &str -> The line of code
+///
+/// returns: Result<Option<String>, Box<dyn Error>> -> The line without the marker or None when
+/// the line is not marked pub
+pub fn strip_pub_marker(line: &str) -> Result<Option<String>, Box<dyn Error>> {
+    let Some((name, declaration)) = pub_declaration(line)? else {
+        return Ok(None);
+    };
+    mark_public(&[name]);
+    Ok(Some(declaration))
+}
+
+/// Split the pub marker from a line without recording the name, used by the bytecode compiler
+/// which keeps the names with the program
+///
+/// params: line: &str -> The line of code
+///
+/// returns: Result<Option<(String, String)>, Box<dyn Error>> -> The declared name and the line
+/// without the marker or None when the line is not marked pub
+pub fn pub_declaration(line: &str) -> Result<Option<(String, String)>, Box<dyn Error>> {
+    let declaration = match line.trim_start().strip_prefix("pub ") {
+        Some(declaration) => declaration.trim_start(),
+        None => return Ok(None),
+    };
+
+    let name = if let Some(rest) = declaration.strip_prefix("func ") {
+        rest.split('(').next().unwrap_or_default().trim()
+    } else if let Some(rest) = declaration.strip_prefix("let ") {
+        rest.split(':').next().unwrap_or_default().trim()
+    } else if declaration.starts_with("struct ") {
+        return Err("Syntax Error: pub struct is not supported, jist has no structs yet".into());
+    } else {
+        return Err(format!(
+            "Syntax Error: pub must be followed by a func or let declaration in '{}'",
+            line.trim()
+        )
+        .into());
+    };
+
+    if !is_identifier(name) {
+        return Err(format!("Syntax Error: invalid pub declaration '{}'", line.trim()).into());
+    }
+    Ok(Some((name.to_string(), declaration.to_string())))
+}
+
+/// Record names as public for the file that is running
+pub fn mark_public(names: &[String]) {
+    PUBLIC_NAMES.lock().unwrap().extend(names.iter().cloned());
+}
+
+/// Build the error for a name that exists in a module but was not marked pub
+fn private_error(module: &str, name: &str) -> Box<dyn Error> {
+    format!(
+        "Import error: '{}' is private to '{}', declare it with pub to use it from another module",
+        name, module
+    )
+    .into()
+}
+
+/// Check that a module name can be reached from outside the module
+fn check_visible(scope: &Scope, module: &str, name: &str) -> Result<(), Box<dyn Error>> {
+    if scope.public.contains(name) || unsafe { INSPECT_PRIVATE } {
+        return Ok(());
+    }
+    Err(private_error(module, name))
+}
+
+/// Read a "quoted" path from the start of a string giving back the path and the rest
+fn read_quoted_path(text: &str) -> Option<(String, &str)> {
+    let text = text.trim_start().strip_prefix('"')?;
+    let end = text.find('"')?;
+    Some((text[..end].to_string(), &text[end + 1..]))
+}
+
+pub fn is_identifier(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Find a module file, paths are relative to the importing file first and then each directory of
+/// the search path
+///
+/// params: module: &str -> The path written in the import statement
+/// params: importer: &Path -> The path of the file doing the import
+///
+/// returns: Result<PathBuf, Box<dyn Error>> -> The canonical path of the module
+pub fn resolve_module_path(module: &str, importer: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    let mut directories: Vec<PathBuf> =
+        vec![importer.parent().unwrap_or(Path::new("")).to_path_buf()];
+    if let Some(search_path) = env::var_os(SEARCH_PATH_VARIABLE) {
+        directories.extend(env::split_paths(&search_path));
+    }
+
+    for directory in directories.iter() {
+        let candidate = directory.join(module);
+        if candidate.is_file() {
+            return Ok(candidate.canonicalize()?);
+        }
+    }
+    Err(format!("Import error: cannot find module '{}'", module).into())
+}
+
+/// Load a module if it has not been loaded yet and bring its names into the importing file
+///
+/// params: statement: ImportStatement -> The import statement
+/// params: importer: &Path -> The path of the file doing the import
+/// params: run: fn(&str) -> Result<(), Box<dyn Error>> -> Runs a file, used to execute the module
+///
+/// returns: Result<(), Box<dyn Error>> -> An import error for missing modules, names or cycles
+pub fn import_module(
+    statement: ImportStatement,
+    importer: &Path,
+    run: fn(&str) -> Result<(), Box<dyn Error>>,
+) -> Result<(), Box<dyn Error>> {
+    if limits::is_sandboxed() {
+        return Err("Sandbox error: import is not available in sandbox mode".into());
+    }
+    match statement {
+        ImportStatement::Namespace { path, alias } => {
+            let module_path = resolve_module_path(&path, importer)?;
+            load_module(&module_path, run)?;
+            IMPORTS.lock().unwrap().aliases.insert(alias, module_path);
+        }
+        ImportStatement::Names { path, names } => {
+            let module_path = resolve_module_path(&path, importer)?;
+            load_module(&module_path, run)?;
+            for name in names {
+                import_name(&module_path, &path, &name)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Run a module once in a fresh scope and keep what it declared
+fn load_module(
+    module_path: &Path,
+    run: fn(&str) -> Result<(), Box<dyn Error>>,
+) -> Result<(), Box<dyn Error>> {
+    if MODULES.lock().unwrap().contains_key(module_path) {
+        return Ok(());
+    }
+
+    {
+        let mut loading = LOADING.lock().unwrap();
+        if let Some(start) = loading.iter().position(|path| path == module_path) {
+            let cycle: Vec<String> = loading[start..]
+                .iter()
+                .chain(std::iter::once(&module_path.to_path_buf()))
+                .map(|path| display_name(path))
+                .collect();
+            return Err(format!("Import error: circular import {}", cycle.join(" -> ")).into());
+        }
+        loading.push(module_path.to_path_buf());
+    }
+
+    let importer_scope = take_scope();
+    let result = run(&module_path.to_string_lossy());
+    let module_scope = take_scope();
+    restore_scope(importer_scope);
+    LOADING.lock().unwrap().pop();
+
+    result?;
+    MODULES
+        .lock()
+        .unwrap()
+        .insert(module_path.to_path_buf(), module_scope);
+    Ok(())
+}
+
+/// Bring one top level declaration of a module into the current scope, variables and collections
+/// are copied while functions keep running inside the module
+fn import_name(module_path: &Path, module: &str, name: &str) -> Result<(), Box<dyn Error>> {
+    let modules = MODULES.lock().unwrap();
+    let scope = modules
+        .get(module_path)
+        .ok_or("Import error: module not loaded")?;
+    let declared = scope.functions.iter().any(|f| f.name == name)
+        || scope.variables.iter().any(|v| v.name == name)
+        || scope.arrays.iter().any(|a| a.name == name)
+        || scope.dictionaries.iter().any(|d| d.name == name);
+    if declared {
+        check_visible(scope, module, name)?;
+    }
+
+    if scope.functions.iter().any(|f| f.name == name) {
+        IMPORTS
+            .lock()
+            .unwrap()
+            .functions
+            .insert(name.to_string(), module_path.to_path_buf());
+    } else if let Some(variable) = scope.variables.iter().find(|v| v.name == name) {
+        push_variable(variable.clone());
+    } else if let Some(array) = scope.arrays.iter().find(|a| a.name == name) {
+        ARRAY_STACK.lock().unwrap().push(array.clone());
+    } else if let Some(dict) = scope.dictionaries.iter().find(|d| d.name == name) {
+        DICTIONARY_STACK.lock().unwrap().push(dict.clone());
+    } else {
+        return Err(format!("Import error: '{}' is not declared in '{}'", name, module).into());
+    }
+    Ok(())
+}
+
+/// Check if a name is the namespace of an imported module
+pub fn is_module_alias(name: &str) -> bool {
+    IMPORTS.lock().unwrap().aliases.contains_key(name)
+}
+
+/// Get a member of a namespace e.g. utils.version or utils.greet("jist"), function arguments are
+/// evaluated in the calling scope before the function runs inside the module
+///
+/// params: alias: &str -> The namespace given in the import
+/// params: member: &str -> The text after the dot
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The value of the member or the function result
+pub fn get_module_member(alias: &str, member: &str) -> Result<BaseTypes, Box<dyn Error>> {
+    let module_path = IMPORTS
+        .lock()
+        .unwrap()
+        .aliases
+        .get(alias)
+        .cloned()
+        .ok_or_else(|| format!("Import error: '{}' is not an imported module", alias))?;
+    let member = member.trim().trim_end_matches(';').trim();
+
+    if member.contains('(') {
+        let nodes: Vec<ASTNode> = tokenize(member.to_string())
+            .into_iter()
+            .map(match_token_to_node)
+            .collect();
+        let name = match nodes.first() {
+            Some(ASTNode::FunctionCall(f)) => f.name.clone(),
+            _ => return Err(format!("Import error: invalid call '{}.{}'", alias, member).into()),
+        };
+        let arguments = match nodes
+            .iter()
+            .position(|n| matches!(n, ASTNode::LeftParenthesis))
+        {
+            Some(start) => parse_function_call_arguments(&nodes[start + 1..])?,
+            None => CallArguments::default(),
+        };
+        {
+            let modules = MODULES.lock().unwrap();
+            let scope = modules
+                .get(&module_path)
+                .ok_or("Import error: module not loaded")?;
+            if scope.functions.iter().any(|f| f.name == name) {
+                check_visible(scope, &display_name(&module_path), &name)?;
+            }
+        }
+        return call_module_function(&module_path, &name, arguments);
+    }
+
+    let modules = MODULES.lock().unwrap();
+    let scope = modules
+        .get(&module_path)
+        .ok_or("Import error: module not loaded")?;
+    let declared = scope.variables.iter().any(|v| v.name == member)
+        || scope.arrays.iter().any(|a| a.name == member)
+        || scope.dictionaries.iter().any(|d| d.name == member);
+    if declared {
+        check_visible(scope, &display_name(&module_path), member)?;
+    }
+    if let Some(variable) = scope.variables.iter().find(|v| v.name == member) {
+        return Ok(variable.value.clone());
+    }
+    if let Some(array) = scope.arrays.iter().find(|a| a.name == member) {
+        return Ok(BaseTypes::Array(array.data.clone()));
+    }
+    if let Some(dict) = scope.dictionaries.iter().find(|d| d.name == member) {
+        return Ok(BaseTypes::Dictionary(dict.values.clone()));
+    }
+    Err(format!(
+        "Import error: module '{}' has no member '{}'",
+        alias, member
+    )
+    .into())
+}
+
+/// Call a function brought in with from ... import
+///
+/// params: name: &str -> The name of the function
+/// params: arguments: CallArguments -> The evaluated positional and named arguments
+///
+/// returns: Option<Result<BaseTypes, Box<dyn Error>>> -> The result or None when not imported
+pub fn call_imported_function(
+    name: &str,
+    arguments: CallArguments,
+) -> Option<Result<BaseTypes, Box<dyn Error>>> {
+    let module_path = IMPORTS.lock().unwrap().functions.get(name).cloned()?;
+    Some(call_module_function(&module_path, name, arguments))
+}
+
+/// Run a module function with the module scope swapped in so it can reach its own declarations,
+/// changes the function makes to module variables are kept for the next call
+fn call_module_function(
+    module_path: &Path,
+    name: &str,
+    arguments: CallArguments,
+) -> Result<BaseTypes, Box<dyn Error>> {
+    let module_scope = MODULES
+        .lock()
+        .unwrap()
+        .remove(module_path)
+        .ok_or("Import error: module not loaded")?;
+    let func = match module_scope.functions.iter().find(|f| f.name == name) {
+        Some(func) => func.clone(),
+        None => {
+            MODULES
+                .lock()
+                .unwrap()
+                .insert(module_path.to_path_buf(), module_scope);
+            return Err(format!(
+                "Import error: '{}' has no function '{}'",
+                display_name(module_path),
+                name
+            )
+            .into());
+        }
+    };
+
+    let caller_scope = take_scope();
+    restore_scope(module_scope);
+    let result = call_user_function(func, arguments);
+    let module_scope = take_scope();
+    restore_scope(caller_scope);
+
+    MODULES
+        .lock()
+        .unwrap()
+        .insert(module_path.to_path_buf(), module_scope);
+    result
+}
+
+/// Move the current scope out of the global stacks leaving them empty
+fn take_scope() -> Scope {
+    Scope {
+        variables: take_variables(),
+        arrays: std::mem::take(&mut *ARRAY_STACK.lock().unwrap()),
+        dictionaries: std::mem::take(&mut *DICTIONARY_STACK.lock().unwrap()),
+        functions: std::mem::take(&mut *USER_FUNCTION_STACK.lock().unwrap()),
+        imports: std::mem::take(&mut *IMPORTS.lock().unwrap()),
+        public: std::mem::take(&mut *PUBLIC_NAMES.lock().unwrap()),
+    }
+}
+
+/// Put a scope back into the global stacks
+fn restore_scope(scope: Scope) {
+    replace_variables(scope.variables);
+    *ARRAY_STACK.lock().unwrap() = scope.arrays;
+    *DICTIONARY_STACK.lock().unwrap() = scope.dictionaries;
+    *USER_FUNCTION_STACK.lock().unwrap() = scope.functions;
+    *IMPORTS.lock().unwrap() = scope.imports;
+    *PUBLIC_NAMES.lock().unwrap() = scope.public;
+}
+
+fn display_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod module_tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_namespace_import() {
+        assert_eq!(
+            parse_import_statement("import \"lib/utils.jist\" as utils;").unwrap(),
+            Some(ImportStatement::Namespace {
+                path: "lib/utils.jist".to_string(),
+                alias: "utils".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_names_import() {
+        assert_eq!(
+            parse_import_statement("from \"math.jist\" import clamp, lerp;").unwrap(),
+            Some(ImportStatement::Names {
+                path: "math.jist".to_string(),
+                names: vec!["clamp".to_string(), "lerp".to_string()],
+            })
+        );
+    }
+
+    #[test]
+    fn test_other_lines_are_not_imports() {
+        assert_eq!(
+            parse_import_statement("let important: int = 1;").unwrap(),
+            None
+        );
+        assert_eq!(parse_import_statement("print(\"from\");").unwrap(), None);
+    }
+
+    #[test]
+    fn test_malformed_imports() {
+        assert!(parse_import_statement("import utils.jist as utils;").is_err());
+        assert!(parse_import_statement("import \"utils.jist\" utils;").is_err());
+        assert!(parse_import_statement("from \"math.jist\" import ;").is_err());
+        assert!(parse_import_statement("import \"utils.jist\" as utils").is_err());
+    }
+
+    #[test]
+    fn test_pub_marker() {
+        assert_eq!(
+            strip_pub_marker("pub func greet(name: string) -> string {}").unwrap(),
+            Some("func greet(name: string) -> string {}".to_string())
+        );
+        assert_eq!(
+            strip_pub_marker("  pub let version: int = 2;").unwrap(),
+            Some("let version: int = 2;".to_string())
+        );
+        assert_eq!(strip_pub_marker("let public: int = 1;").unwrap(), None);
+        assert!(strip_pub_marker("pub struct Point {}").is_err());
+        assert!(strip_pub_marker("pub print(1);").is_err());
+    }
+
+    #[test]
+    fn test_missing_module() {
+        let error = resolve_module_path("missing.jist", Path::new("test_files/main.jist"))
+            .unwrap_err()
+            .to_string();
+        assert_eq!(error, "Import error: cannot find module 'missing.jist'");
+    }
+}
@@ -5,10 +5,10 @@
 
 pub mod nodes {
     use crate::base_variable::base_types::BaseTypes;
+    use crate::intern::Symbol;
     use crate::statement_tokenizer::tokenizer::tokenizers::ParseInfo;
     use crate::token_type::token_types::*;
     use std::fmt::{self, Display};
-    use std::process::exit;
 
     pub fn to_base_type(node: &ASTNode) -> Option<BaseTypes> {
         match node {
@@ -21,13 +21,9 @@ pub mod nodes {
             ASTNode::Char(char_node) => Some(BaseTypes::Char(char_node.value)),
             ASTNode::Bool(bool_node) => Some(BaseTypes::Bool(bool_node.value)),
             ASTNode::Float(float_node) => Some(BaseTypes::Float(float_node.value as f64)),
-            _ => {
-                println!(
-                    "This type {:?} cannot be converted to base type since it is not a value",
-                    node
-                );
-                exit(1)
-            } // cannot convert to base type since it is not a value, error
+            ASTNode::Null => Some(BaseTypes::Null),
+            // cannot convert to base type since it is not a value, the caller reports the error
+            _ => None,
         }
     }
 
@@ -38,7 +34,14 @@ pub mod nodes {
             BaseTypes::Char(value) => ASTNode::Char(CharNode::new(value)),
             BaseTypes::Bool(value) => ASTNode::Bool(BoolNode::new(value)),
             BaseTypes::Float(value) => ASTNode::Float(FloatNode::new(value as f32)),
-            _ => ASTNode::None,
+            BaseTypes::Null => ASTNode::Null,
+            // collections live on their stacks while compiled regexes, datetimes and durations only
+            // come from functions so none of them have a literal node
+            BaseTypes::Array(_)
+            | BaseTypes::Dictionary(_)
+            | BaseTypes::Regex(_)
+            | BaseTypes::DateTime(_)
+            | BaseTypes::Duration(_) => ASTNode::None,
         }
     }
 
@@ -63,6 +66,7 @@ pub mod nodes {
         Char(CharNode),
         Bool(BoolNode),
         Float(FloatNode),
+        Null,
         Assignment(AssignmentNode),
         VarTypeAssignment(VarTypeAssignmentNode),
         Variable(VariableNode),
@@ -113,6 +117,7 @@ pub mod nodes {
                 ASTNode::Char(c) => write!(f, "{}", c),
                 ASTNode::Bool(b) => write!(f, "{}", b),
                 ASTNode::Float(fl) => write!(f, "{}", fl),
+                ASTNode::Null => write!(f, "Null"),
                 ASTNode::Assignment(a) => write!(f, "{}", a),
                 ASTNode::VarTypeAssignment(v) => write!(f, "{}", v),
                 ASTNode::Variable(v) => write!(f, "{}", v),
@@ -766,11 +771,13 @@ pub mod nodes {
     #[derive(Debug, Clone, PartialEq)]
     pub struct VariableCallNode {
         pub name: String,
+        /// The name interned by the tokenizer, variables and slots are looked up by it
+        pub symbol: Symbol,
     }
 
     impl VariableCallNode {
-        pub fn new(name: String) -> Self {
-            VariableCallNode { name }
+        pub fn new(name: String, symbol: Symbol) -> Self {
+            VariableCallNode { name, symbol }
         }
         pub fn display_info(&self) {
             println!("Variable Call: {}", self.name);
@@ -868,7 +875,8 @@ pub mod nodes {
             }
             TokenTypes::String => {
                 let value = &parse_info.value[1..parse_info.value.len() - 1]; // Removes the first and last characters (quotes)
-                ASTNode::String(StringNode::new(value.to_string()))
+                // only \" is unescaped so patterns like "\d+" keep their backslashes
+                ASTNode::String(StringNode::new(value.replace("\\\"", "\"")))
             }
             TokenTypes::Bool => ASTNode::Bool(BoolNode::new(
                 parse_info.value.parse::<bool>().expect("Invalid bool"),
@@ -882,6 +890,7 @@ pub mod nodes {
 
                 ASTNode::Char(CharNode::new(char_value))
             }
+            TokenTypes::Null => ASTNode::Null,
             TokenTypes::Operator => ASTNode::Operator(OperatorNode::new(parse_info.value)),
             TokenTypes::AssignmentOperator => {
                 ASTNode::AssignmentOperator(AssignmentOperatorNode::new(parse_info.value))
@@ -909,8 +918,8 @@ pub mod nodes {
             TokenTypes::VarTypeAssignment => {
                 ASTNode::VariableType(VariableTypeNode::new(parse_info.value))
             }
-            TokenTypes::VariableCall => {
-                ASTNode::VariableCall(VariableCallNode::new(parse_info.value))
+            TokenTypes::VariableCall { symbol } => {
+                ASTNode::VariableCall(VariableCallNode::new(parse_info.value, symbol))
             }
             TokenTypes::ArgumentSeparator => ASTNode::ArgumentSeparator,
             TokenTypes::Assignment => ASTNode::Assignment(AssignmentNode::new(parse_info.value)),
@@ -0,0 +1,511 @@
+/*
+* This file contains the optimization pass run over each statement before it is compiled. Constant
+* arithmetic, string concatenation and boolean expressions are folded into a single value so they
+* are not computed again each time the statement runs. Dead code is removed, branches of an if
+* chain whose condition is always false, the other branches when one is always true, while loops
+* that never run and the statements after a return, statements are parsed to find them. --emit=opt-ast
+* writes the optimized nodes and what was removed to the emit output
+*/
+
+use crate::base_variable::base_types::BaseTypes;
+use crate::bench::strip_bench_marker;
+use crate::bytecode::{block_source, block_statements, compile_expression};
+use crate::globals::EMIT_OPT_AST;
+use crate::module::{parse_import_statement, strip_pub_marker};
+use crate::node::nodes::{match_token_to_node, ASTNode, BoolNode, FloatNode, IntNode, StringNode};
+use crate::output;
+use crate::statement_tokenizer::tokenizer::tokenizers::tokenize;
+use crate::vm::evaluate;
+
+/// Whether the optimized nodes are printed, set by --emit=opt-ast
+pub fn emit_enabled() -> bool {
+    unsafe { EMIT_OPT_AST }
+}
+
+/// Write a statement after optimizing it and what the pass changed in it to the emit output,
+/// the notes for the statements of a program are written without nodes
+///
+/// params: nodes: &[ASTNode] -> The optimized statement
+/// params: notes: &[String] -> The folds and eliminations made
+pub fn emit(nodes: &[ASTNode], notes: &[String]) {
+    if !nodes.is_empty() {
+        let nodes: Vec<String> = nodes.iter().map(|node| node.to_string()).collect();
+        output::emit(&format!("[{}]", nodes.join(", ")));
+    }
+    for note in notes {
+        output::emit(&format!("    ; {}", note));
+    }
+}
+
+/// Fold the constant expressions of a statement and remove dead code from the blocks it holds
+///
+/// params: nodes: Vec<ASTNode> -> The parsed statement
+///
+/// returns: (Vec<ASTNode>, Vec<String>) -> The optimized statement and a note for each change
+pub fn optimize_statement(nodes: Vec<ASTNode>) -> (Vec<ASTNode>, Vec<String>) {
+    let mut notes = Vec::new();
+    let mut optimized = Vec::new();
+    let mut index = 0;
+
+    while index < nodes.len() {
+        match &nodes[index] {
+            // the value of a declaration or assignment runs to the end of the statement
+            ASTNode::AssignmentOperator(_) => {
+                optimized.push(nodes[index].clone());
+                let end = nodes[index..]
+                    .iter()
+                    .position(|node| matches!(node, ASTNode::SemiColon))
+                    .map_or(nodes.len(), |offset| index + offset);
+                optimized.extend(fold(&nodes[index + 1..end], &mut notes));
+                index = end;
+            }
+            // each argument of a call is folded on its own
+            ASTNode::FunctionCall(_)
+                if matches!(nodes.get(index + 1), Some(ASTNode::LeftParenthesis)) =>
+            {
+                let Some(close) = matching_parenthesis(&nodes, index + 1) else {
+                    optimized.extend_from_slice(&nodes[index..]);
+                    break;
+                };
+                optimized.extend_from_slice(&nodes[index..index + 2]);
+                let mut start = index + 2;
+                let mut depth = 0;
+                for position in index + 2..=close {
+                    match &nodes[position] {
+                        ASTNode::LeftParenthesis => depth += 1,
+                        ASTNode::RightParenthesis if depth > 0 => depth -= 1,
+                        ASTNode::ArgumentSeparator | ASTNode::RightParenthesis if depth == 0 => {
+                            optimized.extend(fold(&nodes[start..position], &mut notes));
+                            optimized.push(nodes[position].clone());
+                            start = position + 1;
+                        }
+                        _ => {}
+                    }
+                }
+                index = close + 1;
+            }
+            ASTNode::Function(function) => {
+                let mut function = function.clone();
+                function.block = eliminate_dead_code(function.block, true, &mut notes);
+                optimized.push(ASTNode::Function(function));
+                index += 1;
+            }
+            ASTNode::While(node) => {
+                let mut node = node.clone();
+                node.block = eliminate_dead_code(node.block, true, &mut notes);
+                optimized.push(ASTNode::While(node));
+                index += 1;
+            }
+            ASTNode::For(node) => {
+                let mut node = node.clone();
+                node.block = eliminate_dead_code(node.block, true, &mut notes);
+                optimized.push(ASTNode::For(node));
+                index += 1;
+            }
+            node => {
+                optimized.push(node.clone());
+                index += 1;
+            }
+        }
+    }
+
+    (optimized, notes)
+}
+
+/// Fold an expression into a single value when it only uses literals
+///
+/// params: nodes: &[ASTNode] -> The expression
+/// params: notes: &mut Vec<String> -> Gets a note when the expression is folded
+///
+/// returns: Vec<ASTNode> -> The folded value or the expression unchanged
+fn fold(nodes: &[ASTNode], notes: &mut Vec<String>) -> Vec<ASTNode> {
+    let literal = |node: &ASTNode| {
+        matches!(
+            node,
+            ASTNode::Int(_)
+                | ASTNode::Float(_)
+                | ASTNode::String(_)
+                | ASTNode::Bool(_)
+                | ASTNode::Operator(_)
+                | ASTNode::LeftParenthesis
+                | ASTNode::RightParenthesis
+        )
+    };
+    let has_operator = nodes
+        .iter()
+        .any(|node| matches!(node, ASTNode::Operator(_)));
+    if !has_operator || !nodes.iter().all(literal) {
+        return nodes.to_vec();
+    }
+
+    match constant_value(nodes).and_then(value_node) {
+        Some(folded) => {
+            notes.push(format!(
+                "folded {} to {}",
+                source_text(nodes),
+                source_text(std::slice::from_ref(&folded))
+            ));
+            vec![folded]
+        }
+        None => nodes.to_vec(),
+    }
+}
+
+/// The value of an expression made of literals, None when it reads a name or raises an error so
+/// the error is raised when the statement runs
+fn constant_value(nodes: &[ASTNode]) -> Option<BaseTypes> {
+    let chunk = compile_expression(nodes)?;
+    if !chunk.globals.is_empty() || !chunk.functions.is_empty() || !chunk.statements.is_empty() {
+        return None;
+    }
+    evaluate(&chunk).ok()
+}
+
+/// The literal node of a folded value, None when it has no literal or when a float literal cannot
+/// hold it exactly so the statement computes it at full precision instead
+fn value_node(value: BaseTypes) -> Option<ASTNode> {
+    match value {
+        BaseTypes::Int(value) => Some(ASTNode::Int(IntNode::new(value))),
+        BaseTypes::Float(value) if f64::from(value as f32) == value => {
+            Some(ASTNode::Float(FloatNode::new(value as f32)))
+        }
+        BaseTypes::StringWrapper(value) => Some(ASTNode::String(StringNode::new(value))),
+        BaseTypes::Bool(value) => Some(ASTNode::Bool(BoolNode::new(value))),
+        _ => None,
+    }
+}
+
+/// The nodes of an expression written back as source, used in the notes
+fn source_text(nodes: &[ASTNode]) -> String {
+    let parts: Vec<String> = nodes
+        .iter()
+        .map(|node| match node {
+            ASTNode::Int(n) => n.value.to_string(),
+            ASTNode::Float(f) => format!("{:?}", f.value),
+            ASTNode::String(s) => format!("\"{}\"", s.value),
+            ASTNode::Bool(b) => b.value.to_string(),
+            ASTNode::Operator(o) => o.operator.clone(),
+            ASTNode::LeftParenthesis => "(".to_string(),
+            ASTNode::RightParenthesis => ")".to_string(),
+            other => other.to_string(),
+        })
+        .collect();
+    parts.join(" ")
+}
+
+fn matching_parenthesis(nodes: &[ASTNode], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (position, node) in nodes.iter().enumerate().skip(open) {
+        match node {
+            ASTNode::LeftParenthesis => depth += 1,
+            ASTNode::RightParenthesis => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(position);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// How far an if chain has been decided while its branches are read
+#[derive(Clone, Copy, PartialEq)]
+enum Chain {
+    /// Not in an if chain
+    None,
+    /// Every branch so far was always false and removed, the next branch starts the chain
+    AllFalse,
+    /// A branch depends on a value known when the program runs
+    Open,
+    /// A branch is always taken so the rest of the chain is dead
+    Taken,
+}
+
+/// Remove dead statements, if chain branches that never run, while loops whose condition is always
+/// false and with after_return the statements after a return. A branch that always runs is
+/// replaced by the statements in its block
+///
+/// params: statements: Vec<String> -> The statements of a program or block
+/// params: after_return: bool -> Whether the statements are the block of a function or loop
+/// params: notes: &mut Vec<String> -> Gets a note for each elimination
+///
+/// returns: Vec<String> -> The statements that are left
+pub fn eliminate_dead_code(
+    statements: Vec<String>,
+    after_return: bool,
+    notes: &mut Vec<String>,
+) -> Vec<String> {
+    let mut kept: Vec<String> = Vec::new();
+    let mut chain = Chain::None;
+    let count = statements.len();
+
+    for (position, statement) in statements.into_iter().enumerate() {
+        let nodes = parse(&statement);
+        let branch = match nodes.first() {
+            Some(ASTNode::If(node)) => {
+                chain = Chain::AllFalse;
+                Some(("if", node.condition.as_str()))
+            }
+            Some(ASTNode::Elif(node)) => Some(("elif", node.condition.as_str())),
+            Some(ASTNode::Else) => Some(("else", "")),
+            _ => None,
+        };
+
+        let Some((keyword, condition_text)) = branch else {
+            chain = Chain::None;
+            if let Some(ASTNode::While(node)) = nodes.first() {
+                if condition(&node.condition) == Some(false) {
+                    notes.push("removed while (false) loop".to_string());
+                    continue;
+                }
+            }
+            let returns = after_return && matches!(nodes.first(), Some(ASTNode::Return(_)));
+            kept.push(statement);
+            if returns && position + 1 < count {
+                notes.push(format!(
+                    "removed {} statement(s) after return",
+                    count - position - 1
+                ));
+                break;
+            }
+            continue;
+        };
+
+        // a branch after one that is always taken never runs
+        if chain == Chain::Taken || chain == Chain::None {
+            if chain == Chain::Taken {
+                notes.push(format!(
+                    "removed {} branch after a branch that is always taken",
+                    keyword
+                ));
+            } else {
+                kept.push(statement);
+            }
+            continue;
+        }
+        // a statement holding more than one block is left for the compiler
+        let value = if !single_block(&nodes) {
+            None
+        } else if keyword == "else" {
+            Some(true)
+        } else {
+            condition(condition_text)
+        };
+        match (value, chain) {
+            (Some(false), _) => {
+                notes.push(format!(
+                    "removed {} ({}) branch",
+                    keyword,
+                    condition_text.trim()
+                ));
+            }
+            // the first branch that runs becomes the statements of its block
+            (Some(true), Chain::AllFalse) => {
+                notes.push(format!("inlined {} branch that always runs", keyword));
+                if let Ok(inner) = block_statements(&statement) {
+                    let inner = inner.into_iter().filter(|s| !s.trim().is_empty()).collect();
+                    kept.extend(eliminate_dead_code(inner, after_return, notes));
+                }
+                chain = Chain::Taken;
+            }
+            (Some(true), _) => {
+                chain = Chain::Taken;
+                match block_source(&statement) {
+                    Ok(block) if keyword == "elif" => {
+                        notes.push("replaced elif (true) with else".to_string());
+                        kept.push(format!("else {{{}}}", block));
+                    }
+                    _ => kept.push(statement),
+                }
+            }
+            // the first branch left after removed ones starts the chain
+            (None, Chain::AllFalse) if keyword == "elif" => {
+                chain = Chain::Open;
+                let text = statement.trim_start();
+                kept.push(format!("if{}", &text["elif".len()..]));
+            }
+            (None, _) => {
+                chain = Chain::Open;
+                kept.push(statement);
+            }
+        }
+    }
+
+    kept
+}
+
+/// The nodes of a statement, an import has none and pub and bench markers are left out
+fn parse(statement: &str) -> Vec<ASTNode> {
+    if !matches!(parse_import_statement(statement), Ok(None)) {
+        return Vec::new();
+    }
+    let statement = strip_pub_marker(statement)
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| statement.to_string());
+    let statement = strip_bench_marker(&statement)
+        .ok()
+        .flatten()
+        .unwrap_or(statement);
+    tokenize(statement)
+        .into_iter()
+        .map(match_token_to_node)
+        .collect()
+}
+
+/// Whether a branch ends at the curly brace closing its block, the curly brace of an else is read
+/// with it
+fn single_block(nodes: &[ASTNode]) -> bool {
+    let mut depth = 0;
+    for (position, node) in nodes.iter().enumerate() {
+        match node {
+            ASTNode::LeftCurly | ASTNode::Else => depth += 1,
+            ASTNode::RightCurly => {
+                depth -= 1;
+                if depth == 0 {
+                    return position + 1 == nodes.len();
+                }
+            }
+            _ => {}
+        }
+    }
+    false
+}
+
+/// The value of a condition when it only uses literals, a condition naming a variable or function
+/// is decided when the program runs
+fn condition(text: &str) -> Option<bool> {
+    // a condition that is only true or false is read as a name by the tokenizer
+    let nodes: Vec<ASTNode> = parse(text)
+        .into_iter()
+        .map(|node| match node {
+            ASTNode::VariableCall(v) if v.name == "true" || v.name == "false" => {
+                ASTNode::Bool(BoolNode::new(v.name == "true"))
+            }
+            node => node,
+        })
+        .collect();
+    match constant_value(&nodes)? {
+        BaseTypes::Bool(value) => Some(value),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod optimizer_tests {
+    use super::*;
+    use crate::compilers::variable::evaluate_expression;
+
+    fn lines(statements: &[&str]) -> Vec<String> {
+        statements.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_fold_arithmetic() {
+        let (nodes, notes) = optimize_statement(parse("let x: int = 60 * 60 * 24;"));
+        assert!(matches!(nodes[3], ASTNode::Int(IntNode { value: 86400 })));
+        assert_eq!(nodes.len(), 5);
+        assert_eq!(notes, vec!["folded 60 * 60 * 24 to 86400"]);
+
+        let (nodes, _) = optimize_statement(parse("let y: int = (2 + 3) * 4 - 1;"));
+        assert!(matches!(nodes[3], ASTNode::Int(IntNode { value: 19 })));
+    }
+
+    #[test]
+    fn test_fold_matches_evaluation() {
+        // folding runs the expression on the same evaluator as the statement would
+        let expression = parse("10 - 2 - 3");
+        let (nodes, _) = optimize_statement(parse("let d: int = 10 - 2 - 3;"));
+        assert!(matches!(nodes[3], ASTNode::Int(IntNode { value: 5 })));
+        assert_eq!(
+            evaluate_expression(&expression).unwrap(),
+            Some(BaseTypes::Int(5))
+        );
+
+        let (nodes, _) = optimize_statement(parse("let f: float = 1.5 + 2.25;"));
+        assert!(matches!(&nodes[3], ASTNode::Float(f) if f.value == 3.75));
+
+        // float literals are f32 so a sum they cannot hold is left for the statement to compute
+        let (nodes, notes) = optimize_statement(parse("let g: float = 1.1 + 2.2;"));
+        assert_eq!(nodes.len(), 7);
+        assert!(notes.is_empty());
+    }
+
+    #[test]
+    fn test_fold_strings_and_booleans() {
+        let (nodes, _) = optimize_statement(parse("let s: string = \"a\" + \"b\";"));
+        assert!(matches!(&nodes[3], ASTNode::String(s) if s.value == "ab"));
+
+        let (nodes, _) = optimize_statement(parse("let b: boolean = true && !false;"));
+        assert!(matches!(nodes[3], ASTNode::Bool(BoolNode { value: true })));
+    }
+
+    #[test]
+    fn test_fold_call_arguments() {
+        let (nodes, notes) = optimize_statement(parse("print(1 + 2, 3);"));
+        assert!(matches!(nodes[2], ASTNode::Int(IntNode { value: 3 })));
+        assert!(matches!(nodes[3], ASTNode::ArgumentSeparator));
+        assert_eq!(notes.len(), 1);
+    }
+
+    #[test]
+    fn test_errors_are_not_folded() {
+        let statement = parse("let x: int = 1 / 0;");
+        let (nodes, notes) = optimize_statement(statement.clone());
+        assert_eq!(nodes, statement);
+        assert!(notes.is_empty());
+    }
+
+    #[test]
+    fn test_remove_dead_branches() {
+        let mut notes = Vec::new();
+        let kept = eliminate_dead_code(
+            lines(&[
+                "if (1 > 2) { print(1); }",
+                " elif (x > 0) { print(2); }",
+                " elif (false) { print(3); }",
+                " else { print(4); }",
+                "print(5);",
+            ]),
+            false,
+            &mut notes,
+        );
+        assert_eq!(
+            kept,
+            lines(&[
+                "if (x > 0) { print(2); }",
+                " else { print(4); }",
+                "print(5);"
+            ])
+        );
+        assert_eq!(notes.len(), 2);
+    }
+
+    #[test]
+    fn test_inline_branch_that_always_runs() {
+        let mut notes = Vec::new();
+        let kept = eliminate_dead_code(
+            lines(&[
+                "if (false) { print(1); }",
+                " else { print(2); print(3); }",
+                "while (false) { print(4); }",
+            ]),
+            false,
+            &mut notes,
+        );
+        assert_eq!(kept, lines(&[" print(2);", " print(3);"]));
+        assert_eq!(notes.len(), 3);
+    }
+
+    #[test]
+    fn test_remove_code_after_return() {
+        let mut notes = Vec::new();
+        let block = lines(&["print(1);", "return a;", "print(2);", "print(3);"]);
+        let kept = eliminate_dead_code(block.clone(), true, &mut notes);
+        assert_eq!(kept, lines(&["print(1);", "return a;"]));
+        assert_eq!(notes, vec!["removed 2 statement(s) after return"]);
+        assert_eq!(eliminate_dead_code(block.clone(), false, &mut notes), block);
+    }
+}
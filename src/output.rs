@@ -0,0 +1,199 @@
+/*
+* This file contains the output channels of the interpreter. Everything a program prints goes to
+* the output, which is stdout unless an embedder or test swaps it for a buffer or a callback.
+* Warnings and errors caught by a try block go to the error output, stderr unless it is swapped
+* the same way. Debug messages from the compiler go to the trace channel which is off unless
+* --trace is given or a trace output is set. The statements written by --emit=opt-ast have their
+* own emit output, stderr by default, so they are not mixed with the trace
+*/
+
+use lazy_static::lazy_static;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+/// Somewhere printed text can go
+pub trait Output: Send {
+    fn write(&mut self, text: &str);
+
+    fn flush(&mut self) {}
+}
+
+/// Writes to standard output, the default output
+pub struct StdoutOutput;
+
+impl Output for StdoutOutput {
+    fn write(&mut self, text: &str) {
+        print!("{}", text);
+    }
+
+    fn flush(&mut self) {
+        let _ = io::stdout().flush();
+    }
+}
+
+/// Writes to standard error, the default error and emit outputs and the trace channel of --trace
+pub struct StderrOutput;
+
+impl Output for StderrOutput {
+    fn write(&mut self, text: &str) {
+        eprint!("{}", text);
+    }
+}
+
+/// Keeps the text in memory, clones share the same buffer so one can be given to the interpreter
+/// and the other read afterwards
+#[derive(Clone, Default)]
+pub struct BufferOutput {
+    buffer: Arc<Mutex<String>>,
+}
+
+impl BufferOutput {
+    pub fn new() -> BufferOutput {
+        BufferOutput::default()
+    }
+
+    /// Everything written so far
+    pub fn contents(&self) -> String {
+        self.buffer.lock().unwrap().clone()
+    }
+
+    /// Everything written so far, leaving the buffer empty
+    pub fn take(&self) -> String {
+        std::mem::take(&mut *self.buffer.lock().unwrap())
+    }
+}
+
+impl Output for BufferOutput {
+    fn write(&mut self, text: &str) {
+        self.buffer.lock().unwrap().push_str(text);
+    }
+}
+
+/// Hands the text to a closure, the closure must not print through the interpreter itself
+pub struct CallbackOutput<F: FnMut(&str) + Send> {
+    callback: F,
+}
+
+impl<F: FnMut(&str) + Send> CallbackOutput<F> {
+    pub fn new(callback: F) -> CallbackOutput<F> {
+        CallbackOutput { callback }
+    }
+}
+
+impl<F: FnMut(&str) + Send> Output for CallbackOutput<F> {
+    fn write(&mut self, text: &str) {
+        (self.callback)(text);
+    }
+}
+
+lazy_static! {
+    static ref OUTPUT: Mutex<Box<dyn Output>> = Mutex::new(Box::new(StdoutOutput));
+    static ref ERRORS: Mutex<Box<dyn Output>> = Mutex::new(Box::new(StderrOutput));
+    static ref EMIT: Mutex<Box<dyn Output>> = Mutex::new(Box::new(StderrOutput));
+    static ref TRACE: Mutex<Option<Box<dyn Output>>> = Mutex::new(None);
+}
+
+/// Replace the output
+///
+/// params: output: Box<dyn Output> -> The new output
+///
+/// returns: Box<dyn Output> -> The output that was replaced
+pub fn set_output(output: Box<dyn Output>) -> Box<dyn Output> {
+    std::mem::replace(&mut *OUTPUT.lock().unwrap(), output)
+}
+
+/// Replace the error output
+///
+/// params: errors: Box<dyn Output> -> The new error output
+///
+/// returns: Box<dyn Output> -> The error output that was replaced
+pub fn set_errors(errors: Box<dyn Output>) -> Box<dyn Output> {
+    std::mem::replace(&mut *ERRORS.lock().unwrap(), errors)
+}
+
+/// Replace the emit output
+///
+/// params: emit: Box<dyn Output> -> The new emit output
+///
+/// returns: Box<dyn Output> -> The emit output that was replaced
+pub fn set_emit(emit: Box<dyn Output>) -> Box<dyn Output> {
+    std::mem::replace(&mut *EMIT.lock().unwrap(), emit)
+}
+
+/// Turn the trace channel on with an output or off with None
+pub fn set_trace(trace: Option<Box<dyn Output>>) {
+    *TRACE.lock().unwrap() = trace;
+}
+
+/// Write text to the output
+pub fn print(text: &str) {
+    OUTPUT.lock().unwrap().write(text);
+}
+
+/// Write text and a newline to the output
+pub fn println(text: &str) {
+    let mut output = OUTPUT.lock().unwrap();
+    output.write(text);
+    output.write("\n");
+}
+
+/// Write a warning or an error that did not stop the program and a newline to the error output
+pub fn eprintln(text: &str) {
+    let mut errors = ERRORS.lock().unwrap();
+    errors.write(text);
+    errors.write("\n");
+}
+
+/// Write a line of the optimized statements and a newline to the emit output
+pub fn emit(text: &str) {
+    let mut emit = EMIT.lock().unwrap();
+    emit.write(text);
+    emit.write("\n");
+}
+
+/// Flush the output, used before waiting on input so a prompt is shown
+pub fn flush() {
+    OUTPUT.lock().unwrap().flush();
+}
+
+/// Write a debug message to the trace channel, the message is only built when tracing is on
+///
+/// params: message: impl FnOnce() -> String -> Builds the message
+pub fn trace(message: impl FnOnce() -> String) {
+    if TRACE.lock().unwrap().is_none() {
+        return;
+    }
+    let message = message();
+    if let Some(trace) = TRACE.lock().unwrap().as_mut() {
+        trace.write(&message);
+        trace.write("\n");
+    }
+}
+
+#[cfg(test)]
+mod output_tests {
+    use super::*;
+
+    #[test]
+    fn test_buffer_output() {
+        let mut buffer = BufferOutput::new();
+        let reader = buffer.clone();
+        buffer.write("a");
+        buffer.write("b\n");
+        assert_eq!(reader.contents(), "ab\n");
+        assert_eq!(reader.take(), "ab\n");
+        assert_eq!(reader.contents(), "");
+    }
+
+    #[test]
+    fn test_callback_output() {
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let seen = lines.clone();
+        let mut output = CallbackOutput::new(move |text: &str| {
+            seen.lock().unwrap().push(text.to_string());
+        });
+        output.write("one");
+        output.write("two");
+        assert_eq!(*lines.lock().unwrap(), vec!["one", "two"]);
+    }
+}
@@ -2,42 +2,36 @@ pub mod basic_tokenizers {
     use crate::statement_tokenizer::tokenizer::tokenizers::ParseInfo;
     use crate::token_type::token_types::TokenTypes;
 
-    pub fn read_object_call(expression: &String) -> ParseInfo {
-        let mut object_name: String = String::new();
-        let mut j = 0;
-
-        // First, collect the object name (alphabetic characters)
-        while j < expression.len() {
-            let char = expression.chars().nth(j).unwrap();
-            if !char.is_alphabetic() {
-                break; // Stop if it's not an alphabetic character
-            } else {
-                object_name.push(char); // Collect alphabetic characters
+    /// Read the method of a dot call, it ends after the parentheses of the call so the rest of
+    /// the expression e.g. `?? 0` in `name?.len() ?? 0` is tokenized on its own
+    ///
+    /// params: expression: &str -> The expression being tokenized
+    /// params: start: usize -> The char index after the dot
+    ///
+    /// returns: (String, usize) -> The method and the char index after it
+    pub fn read_dot_method(expression: &str, start: usize) -> (String, usize) {
+        let mut method = String::new();
+        let mut depth = 0;
+        let mut in_string = false;
+        let mut k = start;
+        for char in expression.chars().skip(start) {
+            if char == '"' {
+                in_string = !in_string;
+            } else if !in_string {
+                match char {
+                    ';' => break,
+                    '(' => depth += 1,
+                    ')' => depth -= 1,
+                    _ => {}
+                }
             }
-            j += 1;
-        }
-
-        // Check if the next character exists after the object name
-        if j < expression.len() {
-            let next_char = expression.chars().nth(j).unwrap();
-
-            // If the next character is not a dot, return None
-            if next_char != '.' {
-                return ParseInfo::new(TokenTypes::None, 0, "none".to_string());
-            } else {
-                // If it's a dot, return an object call
-                return ParseInfo::new(
-                    TokenTypes::ObjectCall {
-                        name: object_name.clone(),
-                    },
-                    expression.len().try_into().unwrap(),
-                    object_name,
-                );
+            method.push(char);
+            k += 1;
+            if char == ')' && depth == 0 && !in_string {
+                break;
             }
         }
-
-        // If there's no valid character after the object name, return VariableCall
-        ParseInfo::new(TokenTypes::VariableCall, j.try_into().unwrap(), object_name)
+        (method, k)
     }
 
     pub fn read_boolean(expression: String, index: usize) -> ParseInfo {
@@ -72,6 +66,19 @@ pub mod basic_tokenizers {
         ParseInfo::new(TokenTypes::None, 0, "none".to_string())
     }
 
+    pub fn read_null(expression: String, index: usize) -> ParseInfo {
+        let rest: Vec<char> = expression.chars().skip(index).collect();
+        let keyword: String = rest.iter().take(4).collect();
+
+        // make sure null is not just the start of a longer name e.g. null_count
+        let next_char = rest.get(4).copied().unwrap_or('\0');
+        if keyword == "null" && !(next_char.is_alphanumeric() || next_char == '_') {
+            return ParseInfo::new(TokenTypes::Null, 4, keyword);
+        }
+
+        ParseInfo::new(TokenTypes::None, 0, "none".to_string())
+    }
+
     pub fn read_operators(
         _expression: String,
         char: char,
@@ -80,7 +87,7 @@ pub mod basic_tokenizers {
     ) -> ParseInfo {
         let two_chars = format!("{}{}", char, next_char);
         match two_chars.to_string().as_str() {
-            "==" | "!=" | ">=" | "<=" | "&&" | "||" | "++" | "--" | ".." => {
+            "==" | "!=" | ">=" | "<=" | "&&" | "||" | "++" | "--" | ".." | "??" => {
                 let chars_read = 2;
                 return ParseInfo::new(
                     TokenTypes::Operator,
@@ -111,38 +118,42 @@ pub mod basic_tokenizers {
         }
     }
 
-    pub fn read_strings_chars(expression: String, char: char, index: usize) -> ParseInfo {
+    pub fn read_strings_chars(chars: &[char], char: char, index: usize) -> ParseInfo {
+        // index counts chars so the literal is read by chars to allow non ascii text
+        let literal = |end: usize| -> String { chars[index..end].iter().collect() };
+
         match char {
             '"' | '\'' => {
                 let mut j = index + 1;
-                while j < expression.len() {
+                while j < chars.len() {
+                    // a backslash escapes the next char so \" does not end the string
+                    if chars[j] == '\\' {
+                        j += 2;
+                        continue;
+                    }
                     match char {
                         '"' => {
-                            if expression.chars().nth(j).unwrap() == char {
+                            if chars[j] == char {
                                 return ParseInfo::new(
                                     TokenTypes::String,
                                     (j - index + 1).try_into().unwrap(),
-                                    expression[index..j + 1].to_string(),
+                                    literal(j + 1),
                                 );
                             }
                         }
                         '\'' => {
-                            if expression.chars().nth(j).unwrap() == char {
+                            if chars[j] == char {
                                 return ParseInfo::new(
                                     TokenTypes::Char,
                                     (j - index + 1).try_into().unwrap(),
-                                    expression[index..j + 1].to_string(),
+                                    literal(j + 1),
                                 );
                             }
                         }
                         _ => {}
                     }
-                    if expression.chars().nth(j).unwrap() == char {
-                        return ParseInfo::new(
-                            TokenTypes::Char,
-                            1,
-                            expression[index..j + 1].to_string(),
-                        );
+                    if chars[j] == char {
+                        return ParseInfo::new(TokenTypes::Char, 1, literal(j + 1));
                     }
                     j += 1;
                 }
@@ -151,7 +162,7 @@ pub mod basic_tokenizers {
                     return ParseInfo::new(
                         TokenTypes::String,
                         chars_read.try_into().unwrap(),
-                        expression[index..j + 1].to_string(),
+                        literal(j.min(chars.len())),
                     );
                 }
             }
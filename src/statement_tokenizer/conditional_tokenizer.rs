@@ -26,7 +26,8 @@ pub mod conditional_tokenizers {
     }
 
     fn parse_keyword(expression: &str, index: usize, keyword: &str) -> Option<usize> {
-        let slice = &expression[index..];
+        // index counts chars so the slice is taken by chars to stay on a char boundary
+        let slice: String = expression.chars().skip(index).collect();
         if slice.starts_with(keyword) {
             Some(index + keyword.len())
         } else {
@@ -41,7 +42,9 @@ pub mod conditional_tokenizers {
 
         let keyword = String::new();
 
-        while j < chars.len() {
+        // the keyword starts the statement, an if later on is part of the block of another
+        // statement e.g. a while loop
+        if j < chars.len() {
             // Handle 'if'
             if let Some(new_index) = parse_keyword(expression, j, "if") {
                 j = new_index;
@@ -52,7 +55,7 @@ pub mod conditional_tokenizers {
                     let (statement, new_j) = extract_statement(&chars, j + 1);
                     return ParseInfo::new(
                         TokenTypes::If { statement },
-                        new_j.try_into().unwrap(),
+                        (new_j - index).try_into().unwrap(),
                         "if".to_string(),
                     );
                 }
@@ -67,7 +70,7 @@ pub mod conditional_tokenizers {
                     let (statement, new_j) = extract_statement(&chars, j + 1);
                     return ParseInfo::new(
                         TokenTypes::Elif { statement },
-                        new_j.try_into().unwrap(),
+                        (new_j - index).try_into().unwrap(),
                         "elif".to_string(),
                     );
                 }
@@ -83,13 +86,11 @@ pub mod conditional_tokenizers {
                     j += 1; // skip over '{'
                     return ParseInfo::new(
                         TokenTypes::Else, // You may want to create a new TokenTypes variant if you need additional info.
-                        j.try_into().unwrap(),
+                        (j - index).try_into().unwrap(),
                         "else".to_string(),
                     );
                 }
             }
-
-            j += 1;
         }
 
         ParseInfo::new(TokenTypes::None, 0, "none".to_string())
@@ -107,15 +108,17 @@ pub mod conditional_tokenizers {
             }
 
             if let Some(new_index) = parse_keyword(expression, j, "try") {
-                let body: Vec<String>;
-                let index_of_curly_brace = expression[j..].find('{').unwrap();
-                let mut new_index = new_index + index_of_curly_brace;
+                // the block starts after the first curly brace following the keyword
+                let curly_brace = chars[new_index..]
+                    .iter()
+                    .position(|&c| c == '{')
+                    .map_or(chars.len(), |position| new_index + position);
 
-                (body, new_index) = extract_block(&chars, new_index + 1);
+                let (body, new_index) = extract_block(&chars, curly_brace + 1);
 
                 return ParseInfo::new(
                     TokenTypes::Try { block: body },
-                    new_index.try_into().unwrap(),
+                    (new_index - index).try_into().unwrap(),
                     "try".to_string(),
                 );
             } else if let Some(new_index) = parse_keyword(expression, j, "catch") {
@@ -142,7 +145,8 @@ pub mod conditional_tokenizers {
                 );
             }
 
-            j += 1;
+            // the keyword starts the statement, a try later on is part of another statement
+            break;
         }
 
         ParseInfo::new(TokenTypes::None, 0, "none".to_string())
@@ -1,10 +1,12 @@
 pub mod function_tokenizers {
+    use crate::statement_tokenizer::basic_tokenizer::basic_tokenizers::read_dot_method;
     use crate::statement_tokenizer::loop_tokenizer::loop_tokenizers::extract_block;
     use crate::statement_tokenizer::tokenizer::tokenizers::ParseInfo;
     use crate::token_type::token_types::TokenTypes;
 
     fn parse_keyword(expression: &str, index: usize, keyword: &str) -> (bool, Option<usize>) {
-        let slice = &expression[index..];
+        // index counts chars so the slice is taken by chars to stay on a char boundary
+        let slice: String = expression.chars().skip(index).collect();
         if slice.starts_with(keyword) {
             (true, Some(index + keyword.len()))
         } else {
@@ -19,29 +21,33 @@ pub mod function_tokenizers {
         let mut function_name = String::new();
         let chars: Vec<char> = expression.chars().collect();
 
-        let reserved_chars: Vec<char> = ['+', '-', '*', '/', '!'].into();
+        // a name followed by an operator is not the name of a function e.g. i < len(items)
+        let reserved_chars: Vec<char> = ['+', '-', '*', '/', '!', '<', '>', '&', '|'].into();
 
         let function_found = parse_keyword(expression, 0, "func");
         if function_found.0 {
             return ParseInfo::new(TokenTypes::None, 0, "none".to_string());
         }
 
-        // Collect the function name
+        // a returned call is read whole by read_return_statement
+        if parse_keyword(expression, index, "return ").0 {
+            return ParseInfo::new(TokenTypes::None, 0, "none".to_string());
+        }
+
+        // Collect the function name, characters inside a string literal are not operators
+        let mut in_string = false;
         while j < chars.len() {
             let char = chars[j];
             //let next_char = chars.get(j + 1).cloned().unwrap_or('\0');
+            if char == '"' {
+                in_string = !in_string;
+            }
+            if in_string {
+                j += 1;
+                continue;
+            }
             if char == '.' {
-                let mut function_call = String::new();
-                let mut k = j + 1;
-                // loop and collect the function call until we hit ';'
-                while k < expression.len() {
-                    let char = expression.chars().nth(k).unwrap();
-                    if char == ';' {
-                        break;
-                    }
-                    function_call.push(char);
-                    k += 1;
-                }
+                let (function_call, k) = read_dot_method(expression, j + 1);
                 return ParseInfo::new(
                     TokenTypes::Dot {
                         object: (function_name.clone()), //please excuse the naming, it's a bit misleading
@@ -116,37 +122,35 @@ pub mod function_tokenizers {
     }
 
     pub fn read_return_statement(expression: &str, index: usize) -> ParseInfo {
-        let mut chars = expression.chars().peekable();
-        let mut j = index;
         let mut return_statement = String::new();
-        let return_value;
-        // Check for the `return` keyword
-        let return_found = parse_keyword(expression, 0, "return");
-        if return_found.0 {
-            j = return_found.1.unwrap();
-        } else {
+        // Check for the `return` keyword, a return can follow other statements of a block
+        let mut j = match parse_keyword(expression, index, "return") {
+            (true, Some(j)) => j,
+            _ => return ParseInfo::new(TokenTypes::None, 0, "none".to_string()),
+        };
+        // a name that starts with return e.g. returned is not a return statement
+        if expression
+            .chars()
+            .nth(j)
+            .is_some_and(|char| char.is_alphanumeric() || char == '_')
+        {
             return ParseInfo::new(TokenTypes::None, 0, "none".to_string());
         }
-        // Skip "return " keyword
-        for _ in 0..6 {
-            chars.next();
-        }
-        // Extract return value
-        while let Some(&char) = chars.peek() {
+        // Extract return value up to and including its semicolon
+        for char in expression.chars().skip(j) {
+            j += 1;
+            return_statement.push(char);
             if char == ';' {
-                return_statement.push(';');
                 break;
             }
-            return_statement.push(char);
-            chars.next();
         }
-        return_value = return_statement.trim().to_string();
+        let return_value = return_statement.trim().to_string();
         // Return the parsed result
         ParseInfo::new(
             TokenTypes::ReturnStatement {
                 value: return_value.clone(),
             },
-            expression.chars().count().try_into().unwrap(),
+            (j - index).try_into().unwrap(),
             return_value,
         )
     }
@@ -200,18 +204,18 @@ pub mod function_tokenizers {
             .collect::<Vec<_>>();
 
         for arg in args {
-            // split by : then 1 by =
-            let arg_parts = arg.split(':').collect::<Vec<_>>();
+            // split by the first : then the type by the first = so the default keeps its text
+            let arg_parts = arg.splitn(2, ':').collect::<Vec<_>>();
             let arg_name = arg_parts[0].to_string();
             let mut arg_value = String::new();
             let arg_type;
 
-            if arg_parts.contains(&"=") {
-                let arg_parts_2 = arg_parts[1].split('=').collect::<Vec<_>>();
-                arg_type = arg_parts_2[0].to_string();
-                arg_value = arg_parts_2[1].to_string();
-            } else {
-                arg_type = arg_parts[1].to_string();
+            match arg_parts.get(1).and_then(|part| part.split_once('=')) {
+                Some((type_part, default_part)) => {
+                    arg_type = type_part.to_string();
+                    arg_value = default_part.to_string();
+                }
+                None => arg_type = arg_parts.get(1).unwrap_or(&"").to_string(),
             }
 
             function_arguments.push((
@@ -26,56 +26,31 @@ pub mod loop_tokenizers {
         (condition, index)
     }
 
-    fn extract_for_condition(chars: &[char], mut index: usize) -> (String, (i32, i32), usize) {
-        let mut for_variable = String::new();
-        let mut start_range = String::new();
-        let mut end_range = String::new();
-        let mut parenthesis_count = 1;
-        let mut in_range = false;
-
-        // Extract content within parentheses
-        while index < chars.len() && parenthesis_count > 0 {
-            let c = chars[index];
-            match c {
-                '(' => parenthesis_count += 1,
-                ')' => {
-                    parenthesis_count -= 1;
-                    if parenthesis_count == 0 {
-                        index += 1;
-                        break;
-                    }
-                }
-                ' ' => {} // Ignore whitespace
-                ',' => {
-                    if !in_range {
-                        for_variable = for_variable.trim().to_string();
-                        in_range = true; // Now start parsing the range
-                    }
-                }
-                '.' => {
-                    // Detect start of range's second number (if `..` found)
-                    if !start_range.is_empty() && chars.get(index + 1) == Some(&'.') {
-                        index += 1; // Skip the second dot
-                    }
-                }
-                _ => {
-                    if !in_range {
-                        for_variable.push(c);
-                    } else if in_range && start_range.is_empty() {
-                        start_range.push(c);
-                    } else {
-                        end_range.push(c);
-                    }
-                }
-            }
-            index += 1;
-        }
+    /// Read the head of a for loop, the loop variable and range are written as `i in 0..3` or
+    /// `i, 0..3`
+    ///
+    /// returns: (String, (i32, i32), usize) -> The variable, the range and the index after the
+    /// closing parenthesis
+    fn extract_for_condition(chars: &[char], index: usize) -> (String, (i32, i32), usize) {
+        let (condition, index) = extract_condition(chars, index);
+
+        let (for_variable, range) = match condition.split_once(',') {
+            Some((variable, range)) => (variable, range),
+            None => match condition.trim_start().split_once(char::is_whitespace) {
+                Some((variable, rest)) => (
+                    variable,
+                    rest.trim_start().strip_prefix("in").unwrap_or(rest),
+                ),
+                None => (condition.as_str(), ""),
+            },
+        };
+        let (start_range, end_range) = range.split_once("..").unwrap_or((range, ""));
 
         // Convert ranges to integers
         let start = start_range.trim().parse::<i32>().unwrap_or(0);
         let end = end_range.trim().parse::<i32>().unwrap_or(0);
 
-        (for_variable, (start, end), index)
+        (for_variable.trim().to_string(), (start, end), index)
     }
 
     pub fn extract_block(chars: &[char], mut index: usize) -> (Vec<String>, usize) {
@@ -83,43 +58,49 @@ pub mod loop_tokenizers {
         let mut line: String = String::new();
 
         let mut curly_brace_count = 1; // Start at 1 because we've entered an outer `{`.
+        let mut in_string = false;
 
         while index < chars.len() && curly_brace_count > 0 {
             let c = chars[index];
 
+            // braces and semicolons inside a string literal are kept as text
+            if in_string || c == '"' {
+                line.push(c);
+                if in_string && c == '\\' && index + 1 < chars.len() {
+                    index += 1;
+                    line.push(chars[index]);
+                } else if c == '"' {
+                    in_string = !in_string;
+                }
+                index += 1;
+                continue;
+            }
+
             match c {
                 '{' => {
                     curly_brace_count += 1;
+                    line.push(c);
                 }
                 '}' => {
                     curly_brace_count -= 1;
                     if curly_brace_count == 0 {
                         index += 1; // Move past the closing brace
-                        return (block, index);
-                    } else {
-                        line.push(c);
+                        break;
                     }
-                }
-                ';' => {
                     line.push(c);
-                    // if next is } then add } to end of block
-                    // search for next character that is not is_whitespace
-                    while index < chars.len() && chars[index].is_whitespace() {
-                        index += 1;
-                    }
-
-                    if chars[index] == '}' {
-                        curly_brace_count -= 1;
-                        if curly_brace_count == 0 {
-                            return (block, index + 1);
-                        }
-                        line.push('}');
-                        index += 1;
+                    // a nested block is one statement of the block unless a branch follows it
+                    if curly_brace_count == 1 && !continues_block(chars, index + 1) {
+                        block.push(line.trim().to_string());
+                        line.clear();
                     }
+                }
+                // a semicolon ends a statement of this block, in a nested block it is kept
+                ';' if curly_brace_count == 1 => {
+                    line.push(c);
                     if !line.trim().is_empty() {
                         block.push(line.trim().to_string());
-                        line.clear();
                     }
+                    line.clear();
                 }
                 '\n' | '\r' | '\t' => {
                     // Ignore specific whitespace characters
@@ -142,21 +123,23 @@ pub mod loop_tokenizers {
             block.push(String::new());
         }
 
-        if let Some(last) = block.last() {
-            if last.trim() == "}" {
-                // Remove the standalone '}' and append it to the previous line
-                block.pop();
-                let mut new_last = block.pop().unwrap();
-                new_last.push('}');
-                block.push(new_last);
-            }
-        }
-
         (block, index)
     }
 
+    /// Whether the block closed before index is followed by the next branch of its statement
+    /// e.g. the else of an if
+    fn continues_block(chars: &[char], index: usize) -> bool {
+        let rest: String = chars[index.min(chars.len())..].iter().collect();
+        let rest = rest.trim_start();
+        ["else", "elif", "catch", "finally"].iter().any(|keyword| {
+            rest.strip_prefix(keyword)
+                .is_some_and(|after| !after.starts_with(|c: char| c.is_alphanumeric() || c == '_'))
+        })
+    }
+
     fn parse_keyword(expression: &str, index: usize, keyword: &str) -> Option<usize> {
-        let slice = &expression[index..];
+        // index counts chars so the slice is taken by chars to stay on a char boundary
+        let slice: String = expression.chars().skip(index).collect();
         if slice.starts_with(keyword) {
             Some(index + keyword.len())
         } else {
@@ -1,5 +1,6 @@
 #[cfg(test)]
 mod tokenizer_tests {
+    use crate::intern::intern;
     use crate::statement_tokenizer::tokenizer::tokenizers::ParseInfo;
     use crate::statement_tokenizer::tokenizer::tokenizers::{self, tokenize};
     use crate::token_type::token_types::TokenTypes;
@@ -376,12 +377,12 @@ mod tokenizer_tests {
         let expected = vec![
             ParseInfo {
                 token: TokenTypes::Variable,
-                chars_read: 1,
+                chars_read: 5,
                 value: "a".to_string(),
             },
             ParseInfo {
                 token: TokenTypes::VarTypeAssignment,
-                chars_read: 10,
+                chars_read: 6,
                 value: "int".to_string(),
             },
             ParseInfo {
@@ -411,12 +412,12 @@ mod tokenizer_tests {
         let expected = vec![
             ParseInfo {
                 token: TokenTypes::Variable,
-                chars_read: 1,
+                chars_read: 5,
                 value: "a".to_string(),
             },
             ParseInfo {
                 token: TokenTypes::VarTypeAssignment,
-                chars_read: 12,
+                chars_read: 8,
                 value: "float".to_string(),
             },
             ParseInfo {
@@ -445,12 +446,12 @@ mod tokenizer_tests {
         let expected = vec![
             ParseInfo {
                 token: TokenTypes::Variable,
-                chars_read: 1,
+                chars_read: 5,
                 value: "a".to_string(),
             },
             ParseInfo {
                 token: TokenTypes::VarTypeAssignment,
-                chars_read: 13,
+                chars_read: 9,
                 value: "string".to_string(),
             },
             ParseInfo {
@@ -479,12 +480,12 @@ mod tokenizer_tests {
         let expected = vec![
             ParseInfo {
                 token: TokenTypes::Variable,
-                chars_read: 1,
+                chars_read: 5,
                 value: "a".to_string(),
             },
             ParseInfo {
                 token: TokenTypes::VarTypeAssignment,
-                chars_read: 11,
+                chars_read: 7,
                 value: "char".to_string(),
             },
             ParseInfo {
@@ -513,12 +514,12 @@ mod tokenizer_tests {
         let expected = vec![
             ParseInfo {
                 token: TokenTypes::Variable,
-                chars_read: 1,
+                chars_read: 5,
                 value: "a".to_string(),
             },
             ParseInfo {
                 token: TokenTypes::VarTypeAssignment,
-                chars_read: 11,
+                chars_read: 7,
                 value: "bool".to_string(),
             },
             ParseInfo {
@@ -547,12 +548,12 @@ mod tokenizer_tests {
         let expected = vec![
             ParseInfo {
                 token: TokenTypes::Variable,
-                chars_read: 1,
+                chars_read: 5,
                 value: "a".to_string(),
             },
             ParseInfo {
                 token: TokenTypes::VarTypeAssignment,
-                chars_read: 11,
+                chars_read: 7,
                 value: "bool".to_string(),
             },
             ParseInfo {
@@ -580,7 +581,9 @@ mod tokenizer_tests {
         let input = "a = 1;".to_string();
         let expected = vec![
             ParseInfo {
-                token: TokenTypes::VariableCall,
+                token: TokenTypes::VariableCall {
+                    symbol: intern("a"),
+                },
                 chars_read: 1,
                 value: "a".to_string(),
             },
@@ -609,7 +612,9 @@ mod tokenizer_tests {
         let input = "a = 1.102;".to_string();
         let expected = vec![
             ParseInfo {
-                token: TokenTypes::VariableCall,
+                token: TokenTypes::VariableCall {
+                    symbol: intern("a"),
+                },
                 chars_read: 1,
                 value: "a".to_string(),
             },
@@ -638,7 +643,9 @@ mod tokenizer_tests {
         let input = "a = \"Hello, World!\";".to_string();
         let expected = vec![
             ParseInfo {
-                token: TokenTypes::VariableCall,
+                token: TokenTypes::VariableCall {
+                    symbol: intern("a"),
+                },
                 chars_read: 1,
                 value: "a".to_string(),
             },
@@ -667,7 +674,9 @@ mod tokenizer_tests {
         let input = "a = 'a';".to_string();
         let expected = vec![
             ParseInfo {
-                token: TokenTypes::VariableCall,
+                token: TokenTypes::VariableCall {
+                    symbol: intern("a"),
+                },
                 chars_read: 1,
                 value: "a".to_string(),
             },
@@ -696,7 +705,9 @@ mod tokenizer_tests {
         let input = "a = True;".to_string();
         let expected = vec![
             ParseInfo {
-                token: TokenTypes::VariableCall,
+                token: TokenTypes::VariableCall {
+                    symbol: intern("a"),
+                },
                 chars_read: 1,
                 value: "a".to_string(),
             },
@@ -924,4 +935,52 @@ mod tokenizer_tests {
         let result = tokenize(input);
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn test_function_declaration_defaults_and_variadic() {
+        let input = r#"func log(text: string, level: string = "info", ...tags: array<string>) {
+            print(text);
+        }"#
+        .to_string();
+        let result = tokenize(input);
+        assert_eq!(
+            result[0].token,
+            TokenTypes::Function {
+                name: "log".to_string(),
+                arguments: vec![
+                    ("text".to_string(), "string".to_string(), "".to_string()),
+                    ("level".to_string(), "string".to_string(), "\"info\"".to_string()),
+                    ("...tags".to_string(), "array<string>".to_string(), "".to_string()),
+                ],
+                return_type: "".to_string(),
+                block: vec!["print(text);".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn test_read_strings_chars_from_slice() {
+        use crate::statement_tokenizer::basic_tokenizer::basic_tokenizers::read_strings_chars;
+        let chars: Vec<char> = r#"x = "héllo \"jist\"";"#.chars().collect();
+        let result = read_strings_chars(&chars, '"', 4);
+        assert_eq!(result.token, TokenTypes::String);
+        assert_eq!(result.value, r#""héllo \"jist\"""#);
+        assert_eq!(result.chars_read, 16);
+    }
+
+    #[test]
+    fn test_while_block_keeps_nested_blocks() {
+        let input = "while (n < 9) { if (n > 2) { return n; } else { n--; } n++; }".to_string();
+        let result = tokenize(input);
+        assert_eq!(
+            result[0].token,
+            TokenTypes::While {
+                statement: "n < 9".to_string(),
+                block: vec![
+                    "if (n > 2) { return n; } else { n--; }".to_string(),
+                    "n++;".to_string(),
+                ],
+            }
+        );
+    }
 }
@@ -5,7 +5,7 @@
 
 pub mod tokenizers {
     use crate::statement_tokenizer::basic_tokenizer::basic_tokenizers::{
-        read_boolean, read_numbers, read_object_call, read_operators, read_strings_chars,
+        read_boolean, read_null, read_numbers, read_operators, read_strings_chars,
     };
     use crate::statement_tokenizer::collection_tokenizer::collection_tokenizers::read_collection_assignment;
     use crate::statement_tokenizer::function_tokenizer::function_tokenizers::{
@@ -140,7 +140,7 @@ pub mod tokenizers {
             }
 
             // Process regular tokens
-            let info: ParseInfo = read_token(&expression, index);
+            let info: ParseInfo = read_token(&expression, &chars, index);
             if info.token != none.token {
                 index += info.chars_read as usize;
                 token_list.push(info);
@@ -153,31 +153,38 @@ pub mod tokenizers {
         token_list
     }
 
-    pub fn read_token(expression: &String, index: usize) -> ParseInfo {
+    /// Read the token starting at index
+    ///
+    /// params: expression: &String -> The expression being tokenized
+    /// params: chars: &[char] -> The chars of the expression, collected once by tokenize
+    /// params: index: usize -> The char index the token starts at
+    ///
+    /// returns: ParseInfo -> The token or None when no token starts at index
+    pub fn read_token(expression: &String, chars: &[char], index: usize) -> ParseInfo {
         let none: ParseInfo = ParseInfo::new(TokenTypes::None, 0, "none".to_string());
         let mut j = index;
         let mut decimals = 0;
 
         // check for [ and ]
-        if expression.chars().nth(j).unwrap() == '[' {
+        if chars[j] == '[' {
             let info: ParseInfo = ParseInfo::new(TokenTypes::LeftBracket, 1, "[".to_string());
             return info;
-        } else if expression.chars().nth(j).unwrap() == ']' {
+        } else if chars[j] == ']' {
             let info: ParseInfo = ParseInfo::new(TokenTypes::RightBracket, 1, "]".to_string());
             return info;
         }
 
         // check for fat arrow
-        if expression.chars().nth(j).unwrap() == '=' {
-            if expression.chars().nth(j + 1).unwrap() == '>' {
+        if chars[j] == '=' {
+            if chars.get(j + 1) == Some(&'>') {
                 let info: ParseInfo = ParseInfo::new(TokenTypes::FatArrow, 2, "=>".to_string());
                 return info;
             }
         }
 
         // Loop through the expression
-        while j < expression.len() {
-            let char: char = expression.chars().nth(j).unwrap();
+        while j < chars.len() {
+            let char: char = chars[j];
 
             // Break if the character is not a digit or decimal point
             if !char.is_digit(10) && char != '.' {
@@ -196,14 +203,14 @@ pub mod tokenizers {
             j += 1;
         }
 
-        let char = expression.chars().nth(index).unwrap();
+        let char = chars[index];
 
-        let info = read_object_call(expression);
+        let info = read_boolean(expression.to_string(), index);
         if info.token != none.token {
             return info;
         }
 
-        let info = read_boolean(expression.to_string(), index);
+        let info = read_null(expression.to_string(), index);
         if info.token != none.token {
             return info;
         }
@@ -213,13 +220,19 @@ pub mod tokenizers {
             return info;
         }
 
-        let next_char = expression.chars().nth(index + 1).unwrap_or('\0');
+        let next_char = chars.get(index + 1).copied().unwrap_or('\0');
         let info = read_operators(expression.to_string(), char, next_char, index);
         if info.token != none.token {
             return info;
         }
-        let info = read_strings_chars(expression.to_string(), char, index);
+        let info = read_strings_chars(chars, char, index);
+
+        if info.token != none.token {
+            return info;
+        }
 
+        // a function declaration holds a whole block so it is read before the statements in it
+        let info = read_function_declaration(expression, index);
         if info.token != none.token {
             return info;
         }
@@ -254,11 +267,6 @@ pub mod tokenizers {
             return info;
         }
 
-        let info = read_function_declaration(expression, index);
-        if info.token != none.token {
-            return info;
-        }
-
         let info = read_return_statement(expression, index);
         if info.token != none.token {
             return info;
@@ -1,5 +1,6 @@
 pub mod variable_tokenizers {
-    use crate::base_variable::variables::VARIABLE_STACK;
+    use crate::intern::intern;
+    use crate::output;
     use crate::statement_tokenizer::tokenizer::tokenizers::ParseInfo;
     use crate::token_type::token_types::TokenTypes;
 
@@ -43,6 +44,8 @@ pub mod variable_tokenizers {
                 // Check for ':' and potentially a collection type
                 while j < expression.len() {
                     if expression.chars().nth(j) == Some(':') {
+                        // the token ends at the ':' where the type is read from
+                        let colon = j;
                         j += 1; // Move past ':'
                                 // Skip whitespace after ':'
                         while j < expression.len()
@@ -79,13 +82,10 @@ pub mod variable_tokenizers {
                                 VARIABLE_DECLARATION = true; // Update global state
                             }
 
-                            println!(
-                                /*"Variable Name: '{}', Type Declaration: '{}'",
-                                variable_name, type_declaration */
-                            );
+                            output::trace(|| format!("Variable declaration: {}", variable_name));
                             return ParseInfo::new(
                                 TokenTypes::Variable,
-                                variable_name.len().try_into().unwrap(),
+                                (colon - index).try_into().unwrap(),
                                 variable_name,
                             );
                         }
@@ -110,6 +110,16 @@ pub mod variable_tokenizers {
         let mut j = index;
         let original_index = index;
 
+        // the type follows the name of a let statement, a `:` later on belongs to another
+        // statement e.g. a let in the body of a loop
+        while j < chars.len() && chars[j].is_whitespace() {
+            j += 1;
+        }
+        if chars.get(j) != Some(&':') {
+            unsafe { VARIABLE_DECLARATION = false };
+            return ParseInfo::new(TokenTypes::None, 0, "none".to_string());
+        }
+
         // Look for the `:` pattern
         while j < chars.len() {
             let char = chars[j];
@@ -134,6 +144,12 @@ pub mod variable_tokenizers {
                     }
                 }
 
+                // A trailing `?` marks an optional type e.g. int?
+                if j < chars.len() && chars[j] == '?' {
+                    var_type.push('?');
+                    j += 1;
+                }
+
                 // Skip whitespace after the type and check for `=`
                 while j < chars.len() && chars[j].is_whitespace() {
                     j += 1;
@@ -166,84 +182,46 @@ pub mod variable_tokenizers {
         ParseInfo::new(TokenTypes::None, 0, "none".to_string())
     }
 
+    /// Words of the language that are never the name of a variable
+    const KEYWORDS: [&str; 21] = [
+        "let", "func", "fn", "if", "elif", "else", "while", "for", "in", "try", "catch",
+        "finally", "return", "import", "pub", "bench", "true", "false", "null", "break",
+        "continue",
+    ];
+
+    /// Read the name of a variable, collection, host object or constant. Any name that is not a
+    /// keyword is read whether or not it has been declared yet, so a block is tokenized the same
+    /// before and after the statements in front of it have run and an unknown name is reported
+    /// when the statement runs
+    ///
+    /// params: expression: &String -> The expression being tokenized
+    /// params: index: usize -> The char index the name starts at
+    ///
+    /// returns: ParseInfo -> The variable call or None when no name starts at index
     pub fn read_variable_call(expression: &String, index: usize) -> ParseInfo {
-        let mut j = index;
-        let mut variable_name = String::new();
-
-        // Iterate over characters in the string starting at 'index'
-        let chars = expression.chars().skip(index).enumerate();
-
-        for (_i, char) in chars {
-            // Check if the current or next character is '=' (assignment)
-            let next_char = expression.chars().nth(j + 1).unwrap_or('\0');
-            // Check if its a dot for dot notation then grab the function call as well as a string
-            if char == '.' || next_char == '.' {
-                let mut function_call = String::new();
-                let mut k = j + 1;
-                // loop and collect the function call until we hit ';'
-                while k < expression.len() {
-                    let char = expression.chars().nth(k).unwrap();
-                    if char == ';' {
-                        break;
-                    }
-                    function_call.push(char);
-                    k += 1;
-                }
-                return ParseInfo::new(
-                    TokenTypes::Dot {
-                        object: (variable_name.clone()),
-                        method: (function_call.clone()),
-                    },
-                    (k - index).try_into().unwrap(),
-                    "Dot Notation".to_string(),
-                );
-            } else if char == '(' || next_char == '(' {
-                return ParseInfo::new(
-                    TokenTypes::FunctionCall,
-                    (j - index).try_into().unwrap(),
-                    variable_name,
-                );
-            } else if char == '=' || next_char == '=' {
-                return ParseInfo::new(
-                    TokenTypes::VariableCall,
-                    (j - index).try_into().unwrap(),
-                    variable_name,
-                );
-            }
-
-            // Collect valid variable name characters (alphanumeric and '_')
-
-            if char.is_alphanumeric() || char == '_' {
-                variable_name.push(char);
-            } else if char.is_whitespace() {
-                continue;
-            } else {
-                break;
-            }
-
-            j += 1;
-        }
-
-        // After collecting the variable name, check if it exists in the stack
-        if !variable_name.is_empty() {
-            for variable in unsafe { &VARIABLE_STACK } {
-                if variable.name == variable_name {
-                    return ParseInfo::new(
-                        TokenTypes::VariableCall,
-                        (j - index).try_into().unwrap(),
-                        variable_name,
-                    );
-                } else {
-                    print!("Variable '{}' not found in the stack", variable_name);
-                }
-            }
+        let variable_name: String = expression
+            .chars()
+            .skip(index)
+            .take_while(|char| char.is_alphanumeric() || *char == '_')
+            .collect();
+        let starts_name = variable_name
+            .chars()
+            .next()
+            .is_some_and(|char| char.is_alphabetic() || char == '_');
+        if !starts_name || KEYWORDS.contains(&variable_name.as_str()) {
+            return ParseInfo::new(
+                TokenTypes::None,
+                0,
+                "No valid variable call found".to_string(),
+            );
         }
 
-        // Return None if no valid variable call found
         ParseInfo::new(
-            TokenTypes::None,
-            0,
-            "No valid variable call found".to_string(),
+            TokenTypes::VariableCall {
+                symbol: intern(&variable_name),
+            },
+            variable_name.chars().count().try_into().unwrap(),
+            variable_name,
         )
     }
 }
@@ -0,0 +1,188 @@
+/*
+* This file contains the helpers the std library functions use to check and read their arguments so
+* every native function reports arity and type errors the same way
+*/
+
+use crate::base_variable::base_types::{BaseTypes, GetType};
+use crate::collection::entries::DictionaryEntries;
+use std::error::Error;
+use std::sync::Arc;
+
+/// Check that a function was called with an exact number of arguments
+///
+/// params: name: &str -> The name of the function being called
+/// params: args: &[BaseTypes] -> The arguments passed to the function
+/// params: count: usize -> The number of arguments the function expects
+///
+/// returns: Result<(), Box<dyn Error>> -> An argument error if the count does not match
+pub fn expect_arity(name: &str, args: &[BaseTypes], count: usize) -> Result<(), Box<dyn Error>> {
+    expect_arity_range(name, args, count, count)
+}
+
+/// Check that a function was called with a number of arguments inside a range, used by functions
+/// with optional arguments
+///
+/// params: name: &str -> The name of the function being called
+/// params: args: &[BaseTypes] -> The arguments passed to the function
+/// params: min: usize -> The least number of arguments allowed
+/// params: max: usize -> The most number of arguments allowed
+///
+/// returns: Result<(), Box<dyn Error>> -> An argument error if the count is out of range
+pub fn expect_arity_range(
+    name: &str,
+    args: &[BaseTypes],
+    min: usize,
+    max: usize,
+) -> Result<(), Box<dyn Error>> {
+    if args.len() >= min && args.len() <= max {
+        return Ok(());
+    }
+
+    let expected = if min == max {
+        format!("{}", min)
+    } else {
+        format!("{} to {}", min, max)
+    };
+    Err(format!(
+        "Argument error: {} expects {} argument(s) but got {}",
+        name,
+        expected,
+        args.len()
+    )
+    .into())
+}
+
+/// Build the type error for an argument that has the wrong type
+fn type_error(name: &str, index: usize, expected: &str, found: &BaseTypes) -> Box<dyn Error> {
+    format!(
+        "Type error: {} expects argument {} to be {} but got {}",
+        name,
+        index + 1,
+        expected,
+        found.GetType()
+    )
+    .into()
+}
+
+/// Read a string argument, chars are accepted as single character strings
+///
+/// params: name: &str -> The name of the function being called
+/// params: args: &[BaseTypes] -> The arguments passed to the function
+/// params: index: usize -> The position of the argument
+///
+/// returns: Result<String, Box<dyn Error>> -> The string or a type error
+pub fn get_string(name: &str, args: &[BaseTypes], index: usize) -> Result<String, Box<dyn Error>> {
+    match args.get(index) {
+        Some(BaseTypes::StringWrapper(s)) => Ok(s.clone()),
+        Some(BaseTypes::Char(c)) => Ok(c.to_string()),
+        Some(other) => Err(type_error(name, index, "String", other)),
+        None => Err(type_error(name, index, "String", &BaseTypes::Null)),
+    }
+}
+
+/// Read an integer argument
+///
+/// params: name: &str -> The name of the function being called
+/// params: args: &[BaseTypes] -> The arguments passed to the function
+/// params: index: usize -> The position of the argument
+///
+/// returns: Result<i32, Box<dyn Error>> -> The integer or a type error
+pub fn get_int(name: &str, args: &[BaseTypes], index: usize) -> Result<i32, Box<dyn Error>> {
+    match args.get(index) {
+        Some(BaseTypes::Int(i)) => Ok(*i),
+        Some(other) => Err(type_error(name, index, "Int", other)),
+        None => Err(type_error(name, index, "Int", &BaseTypes::Null)),
+    }
+}
+
+/// Read a number argument, ints are widened to floats
+///
+/// params: name: &str -> The name of the function being called
+/// params: args: &[BaseTypes] -> The arguments passed to the function
+/// params: index: usize -> The position of the argument
+///
+/// returns: Result<f64, Box<dyn Error>> -> The number or a type error
+pub fn get_number(name: &str, args: &[BaseTypes], index: usize) -> Result<f64, Box<dyn Error>> {
+    match args.get(index) {
+        Some(BaseTypes::Int(i)) => Ok(*i as f64),
+        Some(BaseTypes::Float(f)) => Ok(*f),
+        Some(other) => Err(type_error(name, index, "Int or Float", other)),
+        None => Err(type_error(name, index, "Int or Float", &BaseTypes::Null)),
+    }
+}
+
+/// Read an array argument
+///
+/// params: name: &str -> The name of the function being called
+/// params: args: &[BaseTypes] -> The arguments passed to the function
+/// params: index: usize -> The position of the argument
+///
+/// returns: Result<Arc<Vec<BaseTypes>>, Box<dyn Error>> -> The shared array values or a type error
+pub fn get_array(
+    name: &str,
+    args: &[BaseTypes],
+    index: usize,
+) -> Result<Arc<Vec<BaseTypes>>, Box<dyn Error>> {
+    match args.get(index) {
+        Some(BaseTypes::Array(values)) => Ok(values.clone()),
+        Some(other) => Err(type_error(name, index, "Array", other)),
+        None => Err(type_error(name, index, "Array", &BaseTypes::Null)),
+    }
+}
+
+/// Read a dictionary argument
+///
+/// params: name: &str -> The name of the function being called
+/// params: args: &[BaseTypes] -> The arguments passed to the function
+/// params: index: usize -> The position of the argument
+///
+/// returns: Result<Arc<DictionaryEntries>, Box<dyn Error>> -> The shared entries or a type error
+pub fn get_dictionary(
+    name: &str,
+    args: &[BaseTypes],
+    index: usize,
+) -> Result<Arc<DictionaryEntries>, Box<dyn Error>> {
+    match args.get(index) {
+        Some(BaseTypes::Dictionary(entries)) => Ok(entries.clone()),
+        Some(other) => Err(type_error(name, index, "Dictionary", other)),
+        None => Err(type_error(name, index, "Dictionary", &BaseTypes::Null)),
+    }
+}
+
+/// Read a datetime argument
+///
+/// params: name: &str -> The name of the function being called
+/// params: args: &[BaseTypes] -> The arguments passed to the function
+/// params: index: usize -> The position of the argument
+///
+/// returns: Result<DateTime<FixedOffset>, Box<dyn Error>> -> The datetime or a type error
+pub fn get_datetime(
+    name: &str,
+    args: &[BaseTypes],
+    index: usize,
+) -> Result<chrono::DateTime<chrono::FixedOffset>, Box<dyn Error>> {
+    match args.get(index) {
+        Some(BaseTypes::DateTime(datetime)) => Ok(*datetime),
+        Some(other) => Err(type_error(name, index, "DateTime", other)),
+        None => Err(type_error(name, index, "DateTime", &BaseTypes::Null)),
+    }
+}
+
+/// Read a duration argument
+///
+/// params: name: &str -> The name of the function being called
+/// params: args: &[BaseTypes] -> The arguments passed to the function
+/// params: index: usize -> The position of the argument
+///
+/// returns: Result<Duration, Box<dyn Error>> -> The duration or a type error
+pub fn get_duration(
+    name: &str,
+    args: &[BaseTypes],
+    index: usize,
+) -> Result<chrono::Duration, Box<dyn Error>> {
+    match args.get(index) {
+        Some(BaseTypes::Duration(duration)) => Ok(*duration),
+        Some(other) => Err(type_error(name, index, "Duration", other)),
+        None => Err(type_error(name, index, "Duration", &BaseTypes::Null)),
+    }
+}
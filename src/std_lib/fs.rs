@@ -0,0 +1,350 @@
+/*
+* This file contains the filesystem functions of the std library, every operation returns a file
+* error instead of panicking so scripts can recover from a missing file with try/catch. Large
+* files can be streamed one line at a time through open_lines and next_line
+*/
+
+use crate::base_variable::base_types::BaseTypes;
+use crate::std_lib::arguments::{expect_arity, get_int, get_string};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// Reads the next line into the string and gives back the number of bytes read, 0 at the end
+pub type LineReader = Box<dyn FnMut(&mut String) -> std::io::Result<usize> + Send>;
+
+lazy_static::lazy_static! {
+    // open line readers keyed by the handle given back to the script
+    static ref LINE_READERS: Mutex<HashMap<i32, LineReader>> = Mutex::new(HashMap::new());
+    static ref NEXT_HANDLE: Mutex<i32> = Mutex::new(1);
+}
+
+/// Build the file error for an io error on a path
+fn file_error(action: &str, path: &str, error: std::io::Error) -> Box<dyn Error> {
+    format!("File error: cannot {} '{}': {}", action, path, error).into()
+}
+
+/// Read a whole file into a string
+///
+/// params: args: &[BaseTypes] -> The path of the file
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The contents of the file
+pub fn read(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("read", args, 1)?;
+    let path = get_string("read", args, 0)?;
+    let contents = fs::read_to_string(&path).map_err(|e| file_error("read", &path, e))?;
+    Ok(BaseTypes::StringWrapper(contents))
+}
+
+/// Write a string to a file, replacing anything already in it
+///
+/// params: args: &[BaseTypes] -> The path of the file and the contents
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> Null once written
+pub fn write(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("write", args, 2)?;
+    let path = get_string("write", args, 0)?;
+    let contents = get_string("write", args, 1)?;
+    fs::write(&path, contents).map_err(|e| file_error("write", &path, e))?;
+    Ok(BaseTypes::Null)
+}
+
+/// Add a string to the end of a file, the file is created when it does not exist
+///
+/// params: args: &[BaseTypes] -> The path of the file and the contents
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> Null once written
+pub fn append(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("append", args, 2)?;
+    let path = get_string("append", args, 0)?;
+    let contents = get_string("append", args, 1)?;
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(contents.as_bytes()))
+        .map_err(|e| file_error("append to", &path, e))?;
+    Ok(BaseTypes::Null)
+}
+
+/// Check if a file or directory exists
+///
+/// params: args: &[BaseTypes] -> The path to check
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> True when the path exists
+pub fn exists(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("exists", args, 1)?;
+    let path = get_string("exists", args, 0)?;
+    Ok(BaseTypes::Bool(Path::new(&path).exists()))
+}
+
+/// Check if a path is a directory
+///
+/// params: args: &[BaseTypes] -> The path to check
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> True when the path is a directory
+pub fn is_dir(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("is_dir", args, 1)?;
+    let path = get_string("is_dir", args, 0)?;
+    Ok(BaseTypes::Bool(Path::new(&path).is_dir()))
+}
+
+/// Remove a file or an empty directory
+///
+/// params: args: &[BaseTypes] -> The path to remove
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> Null once removed
+pub fn remove(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("remove", args, 1)?;
+    let path = get_string("remove", args, 0)?;
+    let result = if Path::new(&path).is_dir() {
+        fs::remove_dir(&path)
+    } else {
+        fs::remove_file(&path)
+    };
+    result.map_err(|e| file_error("remove", &path, e))?;
+    Ok(BaseTypes::Null)
+}
+
+/// Move or rename a file or directory
+///
+/// params: args: &[BaseTypes] -> The current path and the new path
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> Null once renamed
+pub fn rename(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("rename", args, 2)?;
+    let from = get_string("rename", args, 0)?;
+    let to = get_string("rename", args, 1)?;
+    fs::rename(&from, &to).map_err(|e| file_error("rename", &from, e))?;
+    Ok(BaseTypes::Null)
+}
+
+/// List the names of the entries in a directory, sorted so the order is the same on every platform
+///
+/// params: args: &[BaseTypes] -> The path of the directory
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> An array of the entry names
+pub fn list_dir(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("list_dir", args, 1)?;
+    let path = get_string("list_dir", args, 0)?;
+    let mut names = fs::read_dir(&path)
+        .and_then(|entries| {
+            entries
+                .map(|entry| entry.map(|e| e.file_name().to_string_lossy().to_string()))
+                .collect::<Result<Vec<String>, _>>()
+        })
+        .map_err(|e| file_error("list", &path, e))?;
+    names.sort();
+    Ok(BaseTypes::Array(Arc::new(
+        names.into_iter().map(BaseTypes::StringWrapper).collect(),
+    )))
+}
+
+/// Create a directory along with any missing parent directories
+///
+/// params: args: &[BaseTypes] -> The path of the directory
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> Null once created
+pub fn mkdir(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("mkdir", args, 1)?;
+    let path = get_string("mkdir", args, 0)?;
+    fs::create_dir_all(&path).map_err(|e| file_error("create directory", &path, e))?;
+    Ok(BaseTypes::Null)
+}
+
+/// Read a file into an array with one element per line
+///
+/// params: args: &[BaseTypes] -> The path of the file
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> An array of the lines
+pub fn read_lines(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("read_lines", args, 1)?;
+    let path = get_string("read_lines", args, 0)?;
+    let file = File::open(&path).map_err(|e| file_error("read", &path, e))?;
+    let lines = BufReader::new(file)
+        .lines()
+        .map(|line| line.map(BaseTypes::StringWrapper))
+        .collect::<Result<Vec<BaseTypes>, _>>()
+        .map_err(|e| file_error("read", &path, e))?;
+    Ok(BaseTypes::Array(Arc::new(lines)))
+}
+
+/// Get the size of a file in bytes
+///
+/// params: args: &[BaseTypes] -> The path of the file
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The size of the file
+pub fn file_size(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("file_size", args, 1)?;
+    let path = get_string("file_size", args, 0)?;
+    let size = fs::metadata(&path)
+        .map_err(|e| file_error("read size of", &path, e))?
+        .len();
+    match i32::try_from(size) {
+        Ok(size) => Ok(BaseTypes::Int(size)),
+        Err(_) => Err(format!("File error: '{}' is too large to fit in an int", path).into()),
+    }
+}
+
+/// Open a file for reading one line at a time, the file is never loaded whole
+///
+/// params: args: &[BaseTypes] -> The path of the file
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> A handle to pass to next_line and close_lines
+pub fn open_lines(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("open_lines", args, 1)?;
+    let path = get_string("open_lines", args, 0)?;
+    let file = File::open(&path).map_err(|e| file_error("read", &path, e))?;
+    let mut reader = BufReader::new(file);
+    Ok(BaseTypes::Int(register_line_reader(Box::new(
+        move |line: &mut String| reader.read_line(line),
+    ))))
+}
+
+/// Keep a reader that next_line can read from, used for files and standard input
+///
+/// params: reader: LineReader -> Reads one line at a time
+///
+/// returns: i32 -> The handle for the reader
+pub fn register_line_reader(reader: LineReader) -> i32 {
+    let mut next_handle = NEXT_HANDLE.lock().unwrap();
+    let handle = *next_handle;
+    *next_handle += 1;
+    LINE_READERS.lock().unwrap().insert(handle, reader);
+    handle
+}
+
+/// Read the next line from a handle made by open_lines or stdin_lines, the handle is closed at the end of the file
+///
+/// params: args: &[BaseTypes] -> The handle
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The line without its newline or null at the end
+pub fn next_line(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("next_line", args, 1)?;
+    let handle = get_int("next_line", args, 0)?;
+
+    let mut readers = LINE_READERS.lock().unwrap();
+    let reader = match readers.get_mut(&handle) {
+        Some(reader) => reader,
+        None => return Ok(BaseTypes::Null),
+    };
+
+    let mut line = String::new();
+    let read = reader(&mut line)
+        .map_err(|e| format!("File error: cannot read line from handle {}: {}", handle, e))?;
+    if read == 0 {
+        readers.remove(&handle);
+        return Ok(BaseTypes::Null);
+    }
+
+    if line.ends_with('\n') {
+        line.pop();
+        if line.ends_with('\r') {
+            line.pop();
+        }
+    }
+    Ok(BaseTypes::StringWrapper(line))
+}
+
+/// Close a handle made by open_lines before reaching the end of the file
+///
+/// params: args: &[BaseTypes] -> The handle
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> Null once closed
+pub fn close_lines(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("close_lines", args, 1)?;
+    let handle = get_int("close_lines", args, 0)?;
+    LINE_READERS.lock().unwrap().remove(&handle);
+    Ok(BaseTypes::Null)
+}
+
+#[cfg(test)]
+mod fs_tests {
+    use super::*;
+
+    fn string(s: &str) -> BaseTypes {
+        BaseTypes::StringWrapper(s.to_string())
+    }
+
+    fn temp_path(name: &str) -> String {
+        std::env::temp_dir()
+            .join(format!("jist_fs_{}_{}", std::process::id(), name))
+            .to_string_lossy()
+            .to_string()
+    }
+
+    #[test]
+    fn test_missing_file_is_an_error() {
+        let path = temp_path("missing.txt");
+        let error = read(&[string(&path)]).unwrap_err().to_string();
+        assert!(error.starts_with(&format!("File error: cannot read '{}'", path)));
+        assert!(file_size(&[string(&path)]).is_err());
+        assert!(open_lines(&[string(&path)]).is_err());
+    }
+
+    #[test]
+    fn test_write_append_and_read() {
+        let path = temp_path("write.txt");
+        write(&[string(&path), string("one\n")]).unwrap();
+        append(&[string(&path), string("two\r\nthree")]).unwrap();
+
+        assert_eq!(read(&[string(&path)]).unwrap(), string("one\ntwo\r\nthree"));
+        assert_eq!(
+            read_lines(&[string(&path)]).unwrap(),
+            BaseTypes::Array(vec![string("one"), string("two"), string("three")].into())
+        );
+        assert_eq!(file_size(&[string(&path)]).unwrap(), BaseTypes::Int(14));
+        remove(&[string(&path)]).unwrap();
+        assert!(matches!(
+            exists(&[string(&path)]).unwrap(),
+            BaseTypes::Bool(false)
+        ));
+    }
+
+    #[test]
+    fn test_line_iterator() {
+        let path = temp_path("lines.txt");
+        write(&[string(&path), string("a\nb\n")]).unwrap();
+
+        let handle = open_lines(&[string(&path)]).unwrap();
+        assert_eq!(next_line(&[handle.clone()]).unwrap(), string("a"));
+        assert_eq!(next_line(&[handle.clone()]).unwrap(), string("b"));
+        assert!(matches!(
+            next_line(&[handle.clone()]).unwrap(),
+            BaseTypes::Null
+        ));
+        // the handle is closed at the end so reading again stays null
+        assert!(matches!(next_line(&[handle]).unwrap(), BaseTypes::Null));
+        remove(&[string(&path)]).unwrap();
+    }
+
+    #[test]
+    fn test_directories() {
+        let dir = temp_path("dir");
+        let nested = format!("{}/inner", dir);
+        mkdir(&[string(&nested)]).unwrap();
+        write(&[string(&format!("{}/b.txt", dir)), string("")]).unwrap();
+        rename(&[
+            string(&format!("{}/b.txt", dir)),
+            string(&format!("{}/a.txt", dir)),
+        ])
+        .unwrap();
+
+        assert!(matches!(
+            is_dir(&[string(&nested)]).unwrap(),
+            BaseTypes::Bool(true)
+        ));
+        assert_eq!(
+            list_dir(&[string(&dir)]).unwrap(),
+            BaseTypes::Array(vec![string("a.txt"), string("inner")].into())
+        );
+
+        remove(&[string(&nested)]).unwrap();
+        remove(&[string(&format!("{}/a.txt", dir))]).unwrap();
+        remove(&[string(&dir)]).unwrap();
+        assert!(remove(&[string(&dir)]).is_err());
+    }
+}
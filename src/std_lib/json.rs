@@ -0,0 +1,458 @@
+/*
+* This file contains json_parse and json_stringify, json objects map onto dictionaries with string
+* keys in their original order, json arrays onto arrays and numbers onto Int when they are whole
+* and fit in an i32 otherwise Float
+*/
+
+use crate::base_variable::base_types::{BaseTypes, GetType};
+use crate::collection::entries::DictionaryEntries;
+use crate::std_lib::arguments::{expect_arity, expect_arity_range, get_string};
+use std::error::Error;
+use std::sync::Arc;
+
+/// A recursive descent json parser that keeps track of the line and column for errors
+struct JsonParser {
+    chars: Vec<char>,
+    index: usize,
+    line: usize,
+    column: usize,
+}
+
+impl JsonParser {
+    fn new(text: &str) -> JsonParser {
+        JsonParser {
+            chars: text.chars().collect(),
+            index: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+
+    fn error(&self, message: &str) -> Box<dyn Error> {
+        format!(
+            "JSON error: {} at line {} column {}",
+            message, self.line, self.column
+        )
+        .into()
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.index).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.index += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            if !c.is_whitespace() {
+                break;
+            }
+            self.next();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), Box<dyn Error>> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.next();
+                Ok(())
+            }
+            Some(c) => Err(self.error(&format!("expected '{}' but found '{}'", expected, c))),
+            None => Err(self.error(&format!("expected '{}' but found end of input", expected))),
+        }
+    }
+
+    /// Parse a whole document, anything but whitespace after the value is an error
+    fn parse_document(&mut self) -> Result<BaseTypes, Box<dyn Error>> {
+        let value = self.parse_value()?;
+        self.skip_whitespace();
+        if let Some(c) = self.peek() {
+            return Err(self.error(&format!("unexpected '{}' after the value", c)));
+        }
+        Ok(value)
+    }
+
+    fn parse_value(&mut self) -> Result<BaseTypes, Box<dyn Error>> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => self.parse_object(),
+            Some('[') => self.parse_array(),
+            Some('"') => Ok(BaseTypes::StringWrapper(self.parse_string()?)),
+            Some('t') => self.parse_literal("true", BaseTypes::Bool(true)),
+            Some('f') => self.parse_literal("false", BaseTypes::Bool(false)),
+            Some('n') => self.parse_literal("null", BaseTypes::Null),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.parse_number(),
+            Some(c) => Err(self.error(&format!("unexpected '{}'", c))),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn parse_literal(&mut self, word: &str, value: BaseTypes) -> Result<BaseTypes, Box<dyn Error>> {
+        for expected in word.chars() {
+            if self.peek() != Some(expected) {
+                return Err(self.error(&format!("invalid literal, expected '{}'", word)));
+            }
+            self.next();
+        }
+        Ok(value)
+    }
+
+    fn parse_number(&mut self) -> Result<BaseTypes, Box<dyn Error>> {
+        let (line, column) = (self.line, self.column);
+        let mut number = String::new();
+        while let Some(c) = self.peek() {
+            if c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E') {
+                number.push(c);
+                self.next();
+            } else {
+                break;
+            }
+        }
+
+        let is_float = number.contains(['.', 'e', 'E']);
+        if !is_float {
+            if let Ok(value) = number.parse::<i32>() {
+                return Ok(BaseTypes::Int(value));
+            }
+        }
+        match number.parse::<f64>() {
+            Ok(value) => Ok(BaseTypes::Float(value)),
+            Err(_) => Err(format!(
+                "JSON error: invalid number '{}' at line {} column {}",
+                number, line, column
+            )
+            .into()),
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, Box<dyn Error>> {
+        self.expect('"')?;
+        let mut output = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(output),
+                Some('\\') => match self.next() {
+                    Some('"') => output.push('"'),
+                    Some('\\') => output.push('\\'),
+                    Some('/') => output.push('/'),
+                    Some('b') => output.push('\u{8}'),
+                    Some('f') => output.push('\u{c}'),
+                    Some('n') => output.push('\n'),
+                    Some('r') => output.push('\r'),
+                    Some('t') => output.push('\t'),
+                    Some('u') => output.push(self.parse_unicode_escape()?),
+                    Some(c) => return Err(self.error(&format!("invalid escape '\\{}'", c))),
+                    None => return Err(self.error("unterminated string")),
+                },
+                Some('\n') => return Err(self.error("unescaped newline in string")),
+                Some(c) => output.push(c),
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    /// Read the 4 hex digits after \u, surrogate pairs are combined into one char
+    fn parse_unicode_escape(&mut self) -> Result<char, Box<dyn Error>> {
+        let high = self.parse_hex()?;
+        if (0xD800..0xDC00).contains(&high) {
+            if self.next() != Some('\\') || self.next() != Some('u') {
+                return Err(self.error("unpaired surrogate in unicode escape"));
+            }
+            let low = self.parse_hex()?;
+            let code = 0x10000 + ((high - 0xD800) << 10) + (low.wrapping_sub(0xDC00) & 0x3FF);
+            return char::from_u32(code).ok_or_else(|| self.error("invalid unicode escape"));
+        }
+        char::from_u32(high).ok_or_else(|| self.error("invalid unicode escape"))
+    }
+
+    fn parse_hex(&mut self) -> Result<u32, Box<dyn Error>> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let digit = self
+                .peek()
+                .and_then(|c| c.to_digit(16))
+                .ok_or_else(|| self.error("invalid unicode escape"))?;
+            self.next();
+            code = code * 16 + digit;
+        }
+        Ok(code)
+    }
+
+    fn parse_array(&mut self) -> Result<BaseTypes, Box<dyn Error>> {
+        self.expect('[')?;
+        let mut values = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.next();
+            return Ok(BaseTypes::Array(Arc::new(values)));
+        }
+
+        loop {
+            values.push(self.parse_value()?);
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some(']') => return Ok(BaseTypes::Array(Arc::new(values))),
+                Some(c) => {
+                    return Err(self.error(&format!("expected ',' or ']' but found '{}'", c)))
+                }
+                None => return Err(self.error("expected ',' or ']' but found end of input")),
+            }
+        }
+    }
+
+    fn parse_object(&mut self) -> Result<BaseTypes, Box<dyn Error>> {
+        self.expect('{')?;
+        let mut values = DictionaryEntries::new();
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.next();
+            return Ok(BaseTypes::Dictionary(Arc::new(values)));
+        }
+
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some('"') {
+                return Err(self.error("expected a string key"));
+            }
+            let key = self.parse_string()?;
+            self.skip_whitespace();
+            self.expect(':')?;
+            let value = self.parse_value()?;
+            // a key given twice keeps the last value like most JSON parsers
+            values.insert(BaseTypes::StringWrapper(key), value)?;
+
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some('}') => return Ok(BaseTypes::Dictionary(Arc::new(values))),
+                Some(c) => {
+                    return Err(self.error(&format!("expected ',' or '}}' but found '{}'", c)))
+                }
+                None => return Err(self.error("expected ',' or '}' but found end of input")),
+            }
+        }
+    }
+}
+
+/// Write a string as a json string with quotes and escapes
+fn write_string(output: &mut String, s: &str) {
+    output.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if (c as u32) < 0x20 => output.push_str(&format!("\\u{:04x}", c as u32)),
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+}
+
+/// Write a newline and indentation when pretty printing
+fn write_indent(output: &mut String, pretty: bool, depth: usize) {
+    if pretty {
+        output.push('\n');
+        output.push_str(&"  ".repeat(depth));
+    }
+}
+
+/// Serialize a jist value into json, dictionary keys that are not strings use their text form
+///
+/// params: output: &mut String -> The json being written
+/// params: value: &BaseTypes -> The value to serialize
+/// params: pretty: bool -> Whether to indent the output
+/// params: depth: usize -> The current nesting depth for indentation
+///
+/// returns: Result<(), Box<dyn Error>> -> An error for values json cannot represent
+fn write_value(
+    output: &mut String,
+    value: &BaseTypes,
+    pretty: bool,
+    depth: usize,
+) -> Result<(), Box<dyn Error>> {
+    match value {
+        BaseTypes::Int(i) => output.push_str(&i.to_string()),
+        BaseTypes::Float(f) => {
+            if !f.is_finite() {
+                return Err(format!("JSON error: cannot serialize the float {}", f).into());
+            }
+            output.push_str(&f.to_string());
+        }
+        BaseTypes::StringWrapper(s) => write_string(output, s),
+        BaseTypes::Char(c) => write_string(output, &c.to_string()),
+        BaseTypes::Bool(b) => output.push_str(&b.to_string()),
+        BaseTypes::Null => output.push_str("null"),
+        BaseTypes::DateTime(t) => write_string(output, &t.to_rfc3339()),
+        BaseTypes::Array(values) => {
+            output.push('[');
+            for (i, item) in values.iter().enumerate() {
+                if i > 0 {
+                    output.push(',');
+                }
+                write_indent(output, pretty, depth + 1);
+                write_value(output, item, pretty, depth + 1)?;
+            }
+            if !values.is_empty() {
+                write_indent(output, pretty, depth);
+            }
+            output.push(']');
+        }
+        BaseTypes::Dictionary(values) => {
+            output.push('{');
+            for (i, (key, item)) in values.iter().enumerate() {
+                if i > 0 {
+                    output.push(',');
+                }
+                write_indent(output, pretty, depth + 1);
+                write_string(output, &key.to_string());
+                output.push(':');
+                if pretty {
+                    output.push(' ');
+                }
+                write_value(output, item, pretty, depth + 1)?;
+            }
+            if !values.is_empty() {
+                write_indent(output, pretty, depth);
+            }
+            output.push('}');
+        }
+        other => {
+            return Err(format!(
+                "JSON error: cannot serialize a value of type {}",
+                other.GetType()
+            )
+            .into())
+        }
+    }
+    Ok(())
+}
+
+/// Parse a json document into jist values
+///
+/// params: args: &[BaseTypes] -> The json text
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The value or an error with the line and column
+pub fn json_parse(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("json_parse", args, 1)?;
+    let text = get_string("json_parse", args, 0)?;
+    JsonParser::new(&text).parse_document()
+}
+
+/// Serialize any jist value to json, pretty printing with two space indents when asked
+///
+/// params: args: &[BaseTypes] -> The value and optionally a boolean for pretty printing
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The json string
+pub fn json_stringify(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity_range("json_stringify", args, 1, 2)?;
+    let pretty = match args.get(1) {
+        None => false,
+        Some(BaseTypes::Bool(b)) => *b,
+        Some(other) => {
+            return Err(format!(
+                "Type error: json_stringify expects argument 2 to be Bool but got {}",
+                other.GetType()
+            )
+            .into())
+        }
+    };
+
+    let mut output = String::new();
+    write_value(&mut output, &args[0], pretty, 0)?;
+    Ok(BaseTypes::StringWrapper(output))
+}
+
+#[cfg(test)]
+mod json_tests {
+    use super::*;
+
+    fn string(s: &str) -> BaseTypes {
+        BaseTypes::StringWrapper(s.to_string())
+    }
+
+    fn dictionary(pairs: Vec<(BaseTypes, BaseTypes)>) -> BaseTypes {
+        BaseTypes::Dictionary(DictionaryEntries::from_pairs(pairs).unwrap().into())
+    }
+
+    #[test]
+    fn test_parse_nested_keeps_order() {
+        let value = json_parse(&[string(r#"{"z": [1, 2.5, "é\n"], "a": {"ok": "yes"}}"#)]).unwrap();
+        assert_eq!(
+            value,
+            dictionary(vec![
+                (
+                    string("z"),
+                    BaseTypes::Array(
+                        vec![BaseTypes::Int(1), BaseTypes::Float(2.5), string("é\n")].into()
+                    )
+                ),
+                (string("a"), dictionary(vec![(string("ok"), string("yes"))])),
+            ])
+        );
+        // dictionaries are equal whatever the order so the keys are checked on their own
+        match value {
+            BaseTypes::Dictionary(entries) => {
+                assert_eq!(
+                    entries.keys().collect::<Vec<_>>(),
+                    vec![&string("z"), &string("a")]
+                )
+            }
+            other => panic!("expected a dictionary but got {}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_error_line_and_column() {
+        let error = json_parse(&[string("{\n  \"a\": 1,\n  \"b\" 2\n}")])
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            error,
+            "JSON error: expected ':' but found '2' at line 3 column 7"
+        );
+
+        let error = json_parse(&[string("[1, 2")]).unwrap_err().to_string();
+        assert_eq!(
+            error,
+            "JSON error: expected ',' or ']' but found end of input at line 1 column 6"
+        );
+    }
+
+    #[test]
+    fn test_stringify_round_trip() {
+        let text = r#"{"name":"jist","tags":["a","b"],"version":0.5,"big":3000000000}"#;
+        let value = json_parse(&[string(text)]).unwrap();
+        assert_eq!(json_stringify(&[value]).unwrap(), string(text));
+    }
+
+    #[test]
+    fn test_stringify_pretty() {
+        let value = dictionary(vec![(
+            string("a"),
+            BaseTypes::Array(vec![BaseTypes::Int(1), BaseTypes::Bool(true)].into()),
+        )]);
+        assert_eq!(
+            json_stringify(&[value, BaseTypes::Bool(true)]).unwrap(),
+            string("{\n  \"a\": [\n    1,\n    true\n  ]\n}")
+        );
+    }
+
+    #[test]
+    fn test_stringify_rejects_nan() {
+        assert!(json_stringify(&[BaseTypes::Float(f64::NAN)]).is_err());
+    }
+}
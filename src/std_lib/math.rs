@@ -0,0 +1,559 @@
+/*
+* This file contains the math functions of the std library, functions that only combine ints keep
+* their result an int so max(2, 3) is 3 and not 3.0, any float argument makes the result a float
+*/
+
+use crate::base_variable::base_types::BaseTypes;
+use crate::std_lib::arguments::{expect_arity, get_int, get_number};
+use std::error::Error;
+
+/// Read every argument as an int, None when any of them is not an int
+fn all_ints(args: &[BaseTypes]) -> Option<Vec<i32>> {
+    args.iter()
+        .map(|arg| match arg {
+            BaseTypes::Int(i) => Some(*i),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Read every argument as a float, ints are widened
+fn all_numbers(name: &str, args: &[BaseTypes]) -> Result<Vec<f64>, Box<dyn Error>> {
+    (0..args.len())
+        .map(|index| get_number(name, args, index))
+        .collect()
+}
+
+/// Build the error for int arithmetic that does not fit an int
+fn overflow(name: &str) -> Box<dyn Error> {
+    format!("Math error: {} overflowed the int range", name).into()
+}
+
+/// Call a function of two numbers, checked int arithmetic when both are ints
+fn binary(
+    name: &str,
+    args: &[BaseTypes],
+    int_fn: fn(i32, i32) -> Option<i32>,
+    float_fn: fn(f64, f64) -> f64,
+) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity(name, args, 2)?;
+    if let Some(ints) = all_ints(args) {
+        return int_fn(ints[0], ints[1])
+            .map(BaseTypes::Int)
+            .ok_or_else(|| overflow(name));
+    }
+    let numbers = all_numbers(name, args)?;
+    Ok(BaseTypes::Float(float_fn(numbers[0], numbers[1])))
+}
+
+/// Call a function of one number that keeps ints as they are
+fn unary_keep_int(
+    name: &str,
+    args: &[BaseTypes],
+    float_fn: fn(f64) -> f64,
+) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity(name, args, 1)?;
+    match args[0] {
+        BaseTypes::Int(i) => Ok(BaseTypes::Int(i)),
+        _ => Ok(BaseTypes::Float(float_fn(get_number(name, args, 0)?))),
+    }
+}
+
+/// Call a function of one number that always gives a float
+fn unary_float(
+    name: &str,
+    args: &[BaseTypes],
+    float_fn: fn(f64) -> f64,
+) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity(name, args, 1)?;
+    Ok(BaseTypes::Float(float_fn(get_number(name, args, 0)?)))
+}
+
+/// Get the larger of two numbers
+///
+/// params: args: &[BaseTypes] -> Two numbers
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> An int when both are ints otherwise a float
+pub fn max(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    binary("max", args, |a, b| Some(a.max(b)), f64::max)
+}
+
+/// Get the smaller of two numbers
+///
+/// params: args: &[BaseTypes] -> Two numbers
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> An int when both are ints otherwise a float
+pub fn min(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    binary("min", args, |a, b| Some(a.min(b)), f64::min)
+}
+
+/// Add two numbers
+///
+/// params: args: &[BaseTypes] -> Two numbers
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The sum, an error when an int sum overflows
+pub fn add(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    binary("add", args, i32::checked_add, |a, b| a + b)
+}
+
+/// Subtract the second number from the first
+///
+/// params: args: &[BaseTypes] -> Two numbers
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The difference, an error when it overflows
+pub fn sub(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    binary("sub", args, i32::checked_sub, |a, b| a - b)
+}
+
+/// Multiply two numbers
+///
+/// params: args: &[BaseTypes] -> Two numbers
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The product, an error when it overflows
+pub fn mult(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    binary("mult", args, i32::checked_mul, |a, b| a * b)
+}
+
+/// Divide the first number by the second, two ints divide to an int rounded towards zero
+///
+/// params: args: &[BaseTypes] -> Two numbers
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The quotient, an error for an int division by 0
+pub fn divide(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    if let Some([_, 0]) = all_ints(args).as_deref() {
+        return Err("Math error: divide by zero".into());
+    }
+    binary("divide", args, i32::checked_div, |a, b| a / b)
+}
+
+/// Raise a number to a power, an int to a non negative int power stays an int
+///
+/// params: args: &[BaseTypes] -> The base and the exponent
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The power
+pub fn pow(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("pow", args, 2)?;
+    if let Some([base, exponent]) = all_ints(args).as_deref() {
+        if *exponent >= 0 {
+            return base
+                .checked_pow(*exponent as u32)
+                .map(BaseTypes::Int)
+                .ok_or_else(|| overflow("pow"));
+        }
+    }
+    let numbers = all_numbers("pow", args)?;
+    Ok(BaseTypes::Float(numbers[0].powf(numbers[1])))
+}
+
+/// Get the absolute value of a number
+///
+/// params: args: &[BaseTypes] -> The number
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The absolute value in the same type
+pub fn abs(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("abs", args, 1)?;
+    match args[0] {
+        BaseTypes::Int(i) => i
+            .checked_abs()
+            .map(BaseTypes::Int)
+            .ok_or_else(|| overflow("abs")),
+        _ => Ok(BaseTypes::Float(get_number("abs", args, 0)?.abs())),
+    }
+}
+
+/// Get the sign of a number as -1, 0 or 1
+///
+/// params: args: &[BaseTypes] -> The number
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The sign in the same type, NaN stays NaN
+pub fn sign(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("sign", args, 1)?;
+    match args[0] {
+        BaseTypes::Int(i) => Ok(BaseTypes::Int(i.signum())),
+        _ => {
+            let value = get_number("sign", args, 0)?;
+            let sign = if value == 0.0 || value.is_nan() {
+                value
+            } else {
+                value.signum()
+            };
+            Ok(BaseTypes::Float(sign))
+        }
+    }
+}
+
+/// Limit a number to a range
+///
+/// params: args: &[BaseTypes] -> The number, the lowest and the highest value
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The clamped number
+pub fn clamp(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("clamp", args, 3)?;
+    let numbers = all_numbers("clamp", args)?;
+    if numbers[1].is_nan() || numbers[2].is_nan() || numbers[1] > numbers[2] {
+        return Err(format!(
+            "Math error: clamp expects lo <= hi but got {} and {}",
+            args[1], args[2]
+        )
+        .into());
+    }
+    if let Some(ints) = all_ints(args) {
+        return Ok(BaseTypes::Int(ints[0].clamp(ints[1], ints[2])));
+    }
+    Ok(BaseTypes::Float(numbers[0].clamp(numbers[1], numbers[2])))
+}
+
+fn gcd_of(a: i64, b: i64) -> i64 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// Get the greatest common divisor of two ints
+///
+/// params: args: &[BaseTypes] -> Two ints
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The divisor, never negative
+pub fn gcd(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("gcd", args, 2)?;
+    let a = get_int("gcd", args, 0)?;
+    let b = get_int("gcd", args, 1)?;
+    i32::try_from(gcd_of(a as i64, b as i64))
+        .map(BaseTypes::Int)
+        .map_err(|_| overflow("gcd"))
+}
+
+/// Get the least common multiple of two ints
+///
+/// params: args: &[BaseTypes] -> Two ints
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The multiple, never negative
+pub fn lcm(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("lcm", args, 2)?;
+    let a = get_int("lcm", args, 0)? as i64;
+    let b = get_int("lcm", args, 1)? as i64;
+    if a == 0 || b == 0 {
+        return Ok(BaseTypes::Int(0));
+    }
+    i32::try_from((a / gcd_of(a, b) * b).abs())
+        .map(BaseTypes::Int)
+        .map_err(|_| overflow("lcm"))
+}
+
+/// Raise an int to a power modulo another int without overflowing on the way
+///
+/// params: args: &[BaseTypes] -> The base, a non negative exponent and a positive modulus
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The result between 0 and the modulus
+pub fn mod_pow(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("mod_pow", args, 3)?;
+    let base = get_int("mod_pow", args, 0)? as i64;
+    let mut exponent = get_int("mod_pow", args, 1)?;
+    let modulus = get_int("mod_pow", args, 2)? as i64;
+    if exponent < 0 || modulus <= 0 {
+        return Err(format!(
+            "Math error: mod_pow expects a non negative exponent and a positive modulus but got {} and {}",
+            exponent, modulus
+        )
+        .into());
+    }
+
+    let mut base = base.rem_euclid(modulus);
+    let mut result = 1 % modulus;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = result * base % modulus;
+        }
+        base = base * base % modulus;
+        exponent >>= 1;
+    }
+    Ok(BaseTypes::Int(result as i32))
+}
+
+/// Round a number down
+///
+/// params: args: &[BaseTypes] -> The number
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The number rounded down in the same type
+pub fn floor(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    unary_keep_int("floor", args, f64::floor)
+}
+
+/// Round a number up
+///
+/// params: args: &[BaseTypes] -> The number
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The number rounded up in the same type
+pub fn ceil(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    unary_keep_int("ceil", args, f64::ceil)
+}
+
+/// Round a number to the nearest whole number, halves round away from zero
+///
+/// params: args: &[BaseTypes] -> The number
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The rounded number in the same type
+pub fn round(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    unary_keep_int("round", args, f64::round)
+}
+
+/// Drop the fraction of a number
+///
+/// params: args: &[BaseTypes] -> The number
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The whole part in the same type
+pub fn trunc(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    unary_keep_int("trunc", args, f64::trunc)
+}
+
+/// Get the square root of a number
+///
+/// params: args: &[BaseTypes] -> The number
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The root as a float
+pub fn sqrt(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    unary_float("sqrt", args, f64::sqrt)
+}
+
+/// Get the sine of an angle in radians
+///
+/// params: args: &[BaseTypes] -> The angle in radians
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The sine as a float
+pub fn sin(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    unary_float("sin", args, f64::sin)
+}
+
+/// Get the cosine of an angle in radians
+///
+/// params: args: &[BaseTypes] -> The angle in radians
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The cosine as a float
+pub fn cos(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    unary_float("cos", args, f64::cos)
+}
+
+/// Get the tangent of an angle in radians
+///
+/// params: args: &[BaseTypes] -> The angle in radians
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The tangent as a float
+pub fn tan(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    unary_float("tan", args, f64::tan)
+}
+
+/// Get the angle in radians whose sine is the number
+///
+/// params: args: &[BaseTypes] -> The number
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The angle as a float
+pub fn asin(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    unary_float("asin", args, f64::asin)
+}
+
+/// Get the angle in radians whose cosine is the number
+///
+/// params: args: &[BaseTypes] -> The number
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The angle as a float
+pub fn acos(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    unary_float("acos", args, f64::acos)
+}
+
+/// Get the angle in radians whose tangent is the number
+///
+/// params: args: &[BaseTypes] -> The number
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The angle as a float
+pub fn atan(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    unary_float("atan", args, f64::atan)
+}
+
+/// Get the angle in radians of the point (x, y)
+///
+/// params: args: &[BaseTypes] -> y then x
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The angle between -PI and PI
+pub fn atan2(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("atan2", args, 2)?;
+    let numbers = all_numbers("atan2", args)?;
+    Ok(BaseTypes::Float(numbers[0].atan2(numbers[1])))
+}
+
+/// Get the length of the hypotenuse of a right angled triangle
+///
+/// params: args: &[BaseTypes] -> The lengths of the other two sides
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The length
+pub fn hypot(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("hypot", args, 2)?;
+    let numbers = all_numbers("hypot", args)?;
+    Ok(BaseTypes::Float(numbers[0].hypot(numbers[1])))
+}
+
+/// Raise E to a power
+///
+/// params: args: &[BaseTypes] -> The number
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The power as a float
+pub fn exp(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    unary_float("exp", args, f64::exp)
+}
+
+/// Get the natural logarithm of a number
+///
+/// params: args: &[BaseTypes] -> The number
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The logarithm as a float
+pub fn ln(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    unary_float("ln", args, f64::ln)
+}
+
+/// Get the base 2 logarithm of a number
+///
+/// params: args: &[BaseTypes] -> The number
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The logarithm as a float
+pub fn log2(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    unary_float("log2", args, f64::log2)
+}
+
+/// Get the base 10 logarithm of a number
+///
+/// params: args: &[BaseTypes] -> The number
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The logarithm as a float
+pub fn log10(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    unary_float("log10", args, f64::log10)
+}
+
+/// Get the logarithm of a number in any base
+///
+/// params: args: &[BaseTypes] -> The number and the base
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The logarithm
+pub fn log(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("log", args, 2)?;
+    let numbers = all_numbers("log", args)?;
+    Ok(BaseTypes::Float(numbers[0].log(numbers[1])))
+}
+
+/// Check if a number is NaN, ints never are
+///
+/// params: args: &[BaseTypes] -> The number
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> True for NaN
+pub fn is_nan(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("is_nan", args, 1)?;
+    Ok(BaseTypes::Bool(get_number("is_nan", args, 0)?.is_nan()))
+}
+
+/// Check if a number is neither infinite nor NaN, ints always are
+///
+/// params: args: &[BaseTypes] -> The number
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> True for a finite number
+pub fn is_finite(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("is_finite", args, 1)?;
+    Ok(BaseTypes::Bool(
+        get_number("is_finite", args, 0)?.is_finite(),
+    ))
+}
+
+#[cfg(test)]
+mod math_tests {
+    use super::*;
+
+    fn int(i: i32) -> BaseTypes {
+        BaseTypes::Int(i)
+    }
+
+    fn float(f: f64) -> BaseTypes {
+        BaseTypes::Float(f)
+    }
+
+    #[test]
+    fn test_ints_stay_ints() {
+        assert_eq!(max(&[int(2), int(3)]).unwrap(), int(3));
+        assert_eq!(min(&[int(2), int(-3)]).unwrap(), int(-3));
+        assert_eq!(add(&[int(2), int(3)]).unwrap(), int(5));
+        assert_eq!(sub(&[int(2), int(3)]).unwrap(), int(-1));
+        assert_eq!(mult(&[int(4), int(3)]).unwrap(), int(12));
+        assert_eq!(divide(&[int(7), int(2)]).unwrap(), int(3));
+        assert_eq!(pow(&[int(2), int(10)]).unwrap(), int(1024));
+        assert_eq!(abs(&[int(-4)]).unwrap(), int(4));
+        assert_eq!(round(&[int(4)]).unwrap(), int(4));
+        assert_eq!(sign(&[int(-9)]).unwrap(), int(-1));
+        assert_eq!(clamp(&[int(15), int(0), int(10)]).unwrap(), int(10));
+    }
+
+    #[test]
+    fn test_floats_stay_floats() {
+        assert_eq!(max(&[int(2), float(3.5)]).unwrap(), float(3.5));
+        assert_eq!(add(&[float(0.5), int(1)]).unwrap(), float(1.5));
+        assert_eq!(divide(&[float(7.0), int(2)]).unwrap(), float(3.5));
+        assert_eq!(pow(&[int(2), int(-1)]).unwrap(), float(0.5));
+        assert_eq!(round(&[float(2.5)]).unwrap(), float(3.0));
+        assert_eq!(trunc(&[float(-2.7)]).unwrap(), float(-2.0));
+        assert_eq!(sign(&[float(-0.1)]).unwrap(), float(-1.0));
+        assert_eq!(clamp(&[float(-1.5), int(0), int(10)]).unwrap(), float(0.0));
+        assert_eq!(sqrt(&[int(9)]).unwrap(), float(3.0));
+    }
+
+    #[test]
+    fn test_int_errors() {
+        assert!(add(&[int(i32::MAX), int(1)]).is_err());
+        assert!(mult(&[int(i32::MAX), int(2)]).is_err());
+        assert!(pow(&[int(10), int(10)]).is_err());
+        assert!(abs(&[int(i32::MIN)]).is_err());
+        assert_eq!(
+            divide(&[int(1), int(0)]).unwrap_err().to_string(),
+            "Math error: divide by zero"
+        );
+        assert!(clamp(&[int(1), int(5), int(0)]).is_err());
+        assert!(max(&[int(1), BaseTypes::StringWrapper("2".to_string())]).is_err());
+    }
+
+    #[test]
+    fn test_number_theory() {
+        assert_eq!(gcd(&[int(12), int(-18)]).unwrap(), int(6));
+        assert_eq!(gcd(&[int(0), int(0)]).unwrap(), int(0));
+        assert_eq!(lcm(&[int(4), int(6)]).unwrap(), int(12));
+        assert_eq!(lcm(&[int(0), int(6)]).unwrap(), int(0));
+        assert!(lcm(&[int(i32::MAX), int(i32::MAX - 1)]).is_err());
+        assert_eq!(mod_pow(&[int(4), int(13), int(497)]).unwrap(), int(445));
+        assert_eq!(mod_pow(&[int(-2), int(3), int(5)]).unwrap(), int(2));
+        assert_eq!(mod_pow(&[int(5), int(0), int(1)]).unwrap(), int(0));
+        assert!(mod_pow(&[int(2), int(-1), int(5)]).is_err());
+    }
+
+    #[test]
+    fn test_float_functions() {
+        assert_eq!(hypot(&[int(3), int(4)]).unwrap(), float(5.0));
+        assert_eq!(
+            atan2(&[int(0), int(-1)]).unwrap(),
+            float(std::f64::consts::PI)
+        );
+        assert_eq!(asin(&[int(1)]).unwrap(), float(std::f64::consts::FRAC_PI_2));
+        assert_eq!(acos(&[int(1)]).unwrap(), float(0.0));
+        assert_eq!(atan(&[int(0)]).unwrap(), float(0.0));
+        assert_eq!(exp(&[int(0)]).unwrap(), float(1.0));
+        assert_eq!(ln(&[int(1)]).unwrap(), float(0.0));
+        assert_eq!(log2(&[int(8)]).unwrap(), float(3.0));
+        assert_eq!(log10(&[int(1000)]).unwrap(), float(3.0));
+        assert!(matches!(
+            is_nan(&[float(f64::NAN)]).unwrap(),
+            BaseTypes::Bool(true)
+        ));
+        assert!(matches!(
+            is_finite(&[float(f64::INFINITY)]).unwrap(),
+            BaseTypes::Bool(false)
+        ));
+        assert!(matches!(
+            is_finite(&[int(1)]).unwrap(),
+            BaseTypes::Bool(true)
+        ));
+    }
+}
@@ -0,0 +1,266 @@
+/*
+* This file contains the subprocess functions of the std library, run waits for a command and
+* spawn starts one in the background. A command that cannot be started gives a process error that
+* try/catch can handle while a command that fails just reports its status
+*/
+
+use crate::base_variable::base_types::BaseTypes;
+use crate::collection::entries::DictionaryEntries;
+use crate::std_lib::arguments::{
+    expect_arity, expect_arity_range, get_array, get_dictionary, get_int, get_string,
+};
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::Write;
+use std::process::{Child, Command, Output, Stdio};
+use std::sync::{Arc, Mutex};
+
+lazy_static::lazy_static! {
+    // processes started with spawn keyed by the handle given back to the script
+    static ref CHILDREN: Mutex<HashMap<i32, Child>> = Mutex::new(HashMap::new());
+    static ref NEXT_HANDLE: Mutex<i32> = Mutex::new(1);
+}
+
+/// Build a command from the name, the optional array of arguments and the optional options
+/// dictionary, the options are cwd, env (a dictionary of variables) and stdin
+fn build_command(
+    name: &str,
+    args: &[BaseTypes],
+) -> Result<(Command, Option<String>), Box<dyn Error>> {
+    expect_arity_range(name, args, 1, 3)?;
+    let program = get_string(name, args, 0)?;
+    let mut command = Command::new(&program);
+
+    if args.len() > 1 && !matches!(args[1], BaseTypes::Null) {
+        for argument in get_array(name, args, 1)?.iter() {
+            command.arg(value_to_string(argument));
+        }
+    }
+
+    let mut input = None;
+    if args.len() > 2 && !matches!(args[2], BaseTypes::Null) {
+        for (key, value) in get_dictionary(name, args, 2)?.iter() {
+            match (value_to_string(key).as_str(), value) {
+                ("cwd", value) => {
+                    command.current_dir(value_to_string(value));
+                }
+                ("env", BaseTypes::Dictionary(variables)) => {
+                    for (variable, value) in variables.iter() {
+                        command.env(value_to_string(variable), value_to_string(value));
+                    }
+                }
+                ("stdin", value) => input = Some(value_to_string(value)),
+                (option, _) => {
+                    return Err(format!(
+                        "Process error: unknown option '{}', expected cwd, env or stdin",
+                        option
+                    )
+                    .into())
+                }
+            }
+        }
+    }
+
+    command
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    Ok((command, input))
+}
+
+/// Strings are passed as they are, other values use their display form
+fn value_to_string(value: &BaseTypes) -> String {
+    match value {
+        BaseTypes::StringWrapper(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Start a command and write the stdin option to it
+fn start(name: &str, args: &[BaseTypes]) -> Result<(Child, String), Box<dyn Error>> {
+    let (mut command, input) = build_command(name, args)?;
+    let program = get_string(name, args, 0)?;
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("Process error: cannot run '{}': {}", program, e))?;
+
+    if let Some(input) = input {
+        // the pipe is dropped after writing so the child sees the end of its input
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(input.as_bytes())
+                .map_err(|e| format!("Process error: cannot write to '{}': {}", program, e))?;
+        }
+    }
+    Ok((child, program))
+}
+
+/// Turn the output of a finished process into a dictionary of status, stdout and stderr, a
+/// process stopped by a signal has status -1
+fn output_to_dictionary(output: Output) -> BaseTypes {
+    let entries = DictionaryEntries::from_pairs(vec![
+        (
+            BaseTypes::StringWrapper("status".to_string()),
+            BaseTypes::Int(output.status.code().unwrap_or(-1)),
+        ),
+        (
+            BaseTypes::StringWrapper("stdout".to_string()),
+            BaseTypes::StringWrapper(String::from_utf8_lossy(&output.stdout).to_string()),
+        ),
+        (
+            BaseTypes::StringWrapper("stderr".to_string()),
+            BaseTypes::StringWrapper(String::from_utf8_lossy(&output.stderr).to_string()),
+        ),
+    ]);
+    BaseTypes::Dictionary(Arc::new(entries.expect("string keys can be hashed")))
+}
+
+/// Run a command and wait for it to finish
+///
+/// params: args: &[BaseTypes] -> The command, an optional array of arguments and an optional
+/// dictionary of options (cwd, env and stdin)
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> A dictionary with status, stdout and stderr
+pub fn run(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    let (child, program) = start("run", args)?;
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("Process error: cannot wait for '{}': {}", program, e))?;
+    Ok(output_to_dictionary(output))
+}
+
+/// Start a command in the background
+///
+/// params: args: &[BaseTypes] -> The same arguments as run
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> A handle to pass to wait and kill
+pub fn spawn(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    let (child, _) = start("spawn", args)?;
+
+    let mut next_handle = NEXT_HANDLE.lock().unwrap();
+    let handle = *next_handle;
+    *next_handle += 1;
+    CHILDREN.lock().unwrap().insert(handle, child);
+    Ok(BaseTypes::Int(handle))
+}
+
+/// Take a spawned process out of the table
+fn take_child(name: &str, args: &[BaseTypes]) -> Result<Child, Box<dyn Error>> {
+    expect_arity(name, args, 1)?;
+    let handle = get_int(name, args, 0)?;
+    CHILDREN
+        .lock()
+        .unwrap()
+        .remove(&handle)
+        .ok_or_else(|| format!("Process error: no running process with handle {}", handle).into())
+}
+
+/// Wait for a spawned process to finish
+///
+/// params: args: &[BaseTypes] -> The handle from spawn
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> A dictionary with status, stdout and stderr
+pub fn wait(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    let child = take_child("wait", args)?;
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("Process error: cannot wait for process: {}", e))?;
+    Ok(output_to_dictionary(output))
+}
+
+/// Stop a spawned process
+///
+/// params: args: &[BaseTypes] -> The handle from spawn
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> Null once the process has stopped
+pub fn kill(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    let mut child = take_child("kill", args)?;
+    child
+        .kill()
+        .and_then(|_| child.wait())
+        .map_err(|e| format!("Process error: cannot kill process: {}", e))?;
+    Ok(BaseTypes::Null)
+}
+
+#[cfg(test)]
+mod process_tests {
+    use super::*;
+
+    fn string(s: &str) -> BaseTypes {
+        BaseTypes::StringWrapper(s.to_string())
+    }
+
+    fn dictionary(pairs: Vec<(BaseTypes, BaseTypes)>) -> BaseTypes {
+        BaseTypes::Dictionary(DictionaryEntries::from_pairs(pairs).unwrap().into())
+    }
+
+    fn entry(result: &BaseTypes, key: &str) -> BaseTypes {
+        match result {
+            BaseTypes::Dictionary(entries) => entries.get(&string(key)).unwrap().unwrap().clone(),
+            _ => panic!("expected a dictionary"),
+        }
+    }
+
+    #[test]
+    fn test_run_captures_output() {
+        let result = run(&[
+            string("sh"),
+            BaseTypes::Array(vec![string("-c"), string("echo out; echo err >&2; exit 4")].into()),
+        ])
+        .unwrap();
+        assert_eq!(entry(&result, "status"), BaseTypes::Int(4));
+        assert_eq!(entry(&result, "stdout"), string("out\n"));
+        assert_eq!(entry(&result, "stderr"), string("err\n"));
+    }
+
+    #[test]
+    fn test_run_options() {
+        let options = dictionary(vec![
+            (string("cwd"), string("/")),
+            (
+                string("env"),
+                dictionary(vec![(string("JIST_PROCESS_TEST"), string("set"))]),
+            ),
+            (string("stdin"), string("piped")),
+        ]);
+        let script = string("pwd; echo $JIST_PROCESS_TEST; cat");
+        let result = run(&[
+            string("sh"),
+            BaseTypes::Array(vec![string("-c"), script].into()),
+            options,
+        ])
+        .unwrap();
+        assert_eq!(entry(&result, "stdout"), string("/\nset\npiped"));
+    }
+
+    #[test]
+    fn test_missing_command_is_an_error() {
+        let error = run(&[string("jist-command-that-does-not-exist")])
+            .unwrap_err()
+            .to_string();
+        assert!(error.starts_with("Process error: cannot run 'jist-command-that-does-not-exist'"));
+    }
+
+    #[test]
+    fn test_spawn_wait_and_kill() {
+        let handle = spawn(&[
+            string("sh"),
+            BaseTypes::Array(vec![string("-c"), string("echo done")].into()),
+        ])
+        .unwrap();
+        assert_eq!(
+            entry(&wait(&[handle.clone()]).unwrap(), "stdout"),
+            string("done\n")
+        );
+        assert!(wait(&[handle]).is_err());
+
+        let handle =
+            spawn(&[string("sleep"), BaseTypes::Array(vec![string("10")].into())]).unwrap();
+        kill(&[handle.clone()]).unwrap();
+        assert!(kill(&[handle]).is_err());
+    }
+}
@@ -0,0 +1,211 @@
+/*
+* This file contains the random number functions of the std library, every draw comes from one
+* seedable generator so a run started with --seed or seed(n) gives the same numbers every time
+*/
+
+use crate::base_variable::base_types::BaseTypes;
+use crate::std_lib::arguments::{expect_arity, get_array, get_int, get_number};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+
+lazy_static::lazy_static! {
+    // seeded from the operating system until the script or the --seed flag picks a seed
+    static ref GENERATOR: Mutex<StdRng> = Mutex::new(StdRng::from_entropy());
+}
+
+/// Restart the generator from a seed so the numbers after it repeat between runs
+///
+/// params: seed: u64 -> The seed
+pub fn set_seed(seed: u64) {
+    *GENERATOR.lock().unwrap() = StdRng::seed_from_u64(seed);
+}
+
+/// Draw a float between 0 and 1, used by rand()
+///
+/// returns: f64 -> A float in [0, 1)
+pub fn next_float() -> f64 {
+    GENERATOR.lock().unwrap().gen()
+}
+
+/// Seed the generator
+///
+/// params: args: &[BaseTypes] -> The seed
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> Null once seeded
+pub fn seed(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("seed", args, 1)?;
+    set_seed(get_int("seed", args, 0)? as i64 as u64);
+    Ok(BaseTypes::Null)
+}
+
+/// Draw an int between two bounds
+///
+/// params: args: &[BaseTypes] -> The lowest and highest values, both can be drawn
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The int
+pub fn rand_int(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("rand_int", args, 2)?;
+    let low = get_int("rand_int", args, 0)?;
+    let high = get_int("rand_int", args, 1)?;
+    if low > high {
+        return Err(format!(
+            "Random error: rand_int expects lo <= hi but got {} and {}",
+            low, high
+        )
+        .into());
+    }
+    Ok(BaseTypes::Int(
+        GENERATOR.lock().unwrap().gen_range(low..=high),
+    ))
+}
+
+/// Draw a float between two bounds
+///
+/// params: args: &[BaseTypes] -> The lowest value, which can be drawn, and the highest which
+/// cannot
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The float
+pub fn rand_float(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("rand_float", args, 2)?;
+    let low = get_number("rand_float", args, 0)?;
+    let high = get_number("rand_float", args, 1)?;
+    if !low.is_finite() || !high.is_finite() || low > high {
+        return Err(format!(
+            "Random error: rand_float expects finite lo <= hi but got {} and {}",
+            low, high
+        )
+        .into());
+    }
+    if low == high {
+        return Ok(BaseTypes::Float(low));
+    }
+    Ok(BaseTypes::Float(
+        GENERATOR.lock().unwrap().gen_range(low..high),
+    ))
+}
+
+/// Pick one value from an array
+///
+/// params: args: &[BaseTypes] -> The array
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The value, an error when the array is empty
+pub fn choice(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("choice", args, 1)?;
+    let values = get_array("choice", args, 0)?;
+    values
+        .choose(&mut *GENERATOR.lock().unwrap())
+        .cloned()
+        .ok_or_else(|| "Random error: choice cannot pick from an empty array".into())
+}
+
+/// Put the values of an array in a random order
+///
+/// params: args: &[BaseTypes] -> The array
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> A shuffled copy of the array
+pub fn shuffle(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("shuffle", args, 1)?;
+    let mut values = get_array("shuffle", args, 0)?;
+    Arc::make_mut(&mut values).shuffle(&mut *GENERATOR.lock().unwrap());
+    Ok(BaseTypes::Array(values))
+}
+
+/// Pick a number of values from an array without picking any position twice
+///
+/// params: args: &[BaseTypes] -> The array and how many values to pick
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> An array of the picked values
+pub fn sample(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("sample", args, 2)?;
+    let values = get_array("sample", args, 0)?;
+    let count = get_int("sample", args, 1)?;
+    if count < 0 || count as usize > values.len() {
+        return Err(format!(
+            "Random error: sample cannot pick {} values from an array of {}",
+            count,
+            values.len()
+        )
+        .into());
+    }
+    let picked = values
+        .choose_multiple(&mut *GENERATOR.lock().unwrap(), count as usize)
+        .cloned()
+        .collect();
+    Ok(BaseTypes::Array(Arc::new(picked)))
+}
+
+#[cfg(test)]
+mod random_tests {
+    use super::*;
+
+    lazy_static::lazy_static! {
+        // the generator is shared so tests that seed it must not run at the same time
+        static ref SEED_LOCK: Mutex<()> = Mutex::new(());
+    }
+
+    fn numbers(count: i32) -> BaseTypes {
+        BaseTypes::Array((1..=count).map(BaseTypes::Int).collect::<Vec<_>>().into())
+    }
+
+    fn draw_all() -> Vec<BaseTypes> {
+        vec![
+            rand_int(&[BaseTypes::Int(1), BaseTypes::Int(100)]).unwrap(),
+            rand_float(&[BaseTypes::Int(0), BaseTypes::Float(1.0)]).unwrap(),
+            choice(&[numbers(10)]).unwrap(),
+            shuffle(&[numbers(10)]).unwrap(),
+            sample(&[numbers(10), BaseTypes::Int(3)]).unwrap(),
+            BaseTypes::Float(next_float()),
+        ]
+    }
+
+    #[test]
+    fn test_seed_repeats_draws() {
+        let _guard = SEED_LOCK.lock().unwrap();
+        seed(&[BaseTypes::Int(42)]).unwrap();
+        let first = draw_all();
+        seed(&[BaseTypes::Int(42)]).unwrap();
+        assert_eq!(draw_all(), first);
+        seed(&[BaseTypes::Int(-42)]).unwrap();
+        assert_ne!(draw_all(), first);
+    }
+
+    #[test]
+    fn test_ranges() {
+        let _guard = SEED_LOCK.lock().unwrap();
+        for _ in 0..100 {
+            let value = rand_int(&[BaseTypes::Int(-2), BaseTypes::Int(2)]).unwrap();
+            assert!(matches!(value, BaseTypes::Int(-2..=2)));
+            match rand_float(&[BaseTypes::Float(1.5), BaseTypes::Int(2)]).unwrap() {
+                BaseTypes::Float(f) => assert!((1.5..2.0).contains(&f)),
+                other => panic!("expected a float but got {:?}", other),
+            }
+        }
+        assert_eq!(
+            rand_int(&[BaseTypes::Int(3), BaseTypes::Int(3)]).unwrap(),
+            BaseTypes::Int(3)
+        );
+        assert!(rand_int(&[BaseTypes::Int(3), BaseTypes::Int(1)]).is_err());
+        assert!(rand_float(&[BaseTypes::Float(f64::NAN), BaseTypes::Int(1)]).is_err());
+    }
+
+    #[test]
+    fn test_arrays() {
+        let _guard = SEED_LOCK.lock().unwrap();
+        match shuffle(&[numbers(20)]).unwrap() {
+            BaseTypes::Array(mut values) => {
+                Arc::make_mut(&mut values).sort_by(|a, b| a.partial_cmp(b).unwrap());
+                assert_eq!(BaseTypes::Array(values), numbers(20));
+            }
+            other => panic!("expected an array but got {:?}", other),
+        }
+        match sample(&[numbers(5), BaseTypes::Int(5)]).unwrap() {
+            BaseTypes::Array(values) => assert_eq!(values.len(), 5),
+            other => panic!("expected an array but got {:?}", other),
+        }
+        assert!(sample(&[numbers(5), BaseTypes::Int(6)]).is_err());
+        assert!(choice(&[BaseTypes::Array(Default::default())]).is_err());
+    }
+}
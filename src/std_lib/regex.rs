@@ -0,0 +1,210 @@
+/*
+* This file contains the regular expression functions of the std library, each function takes
+* either a pattern string or a regex value made by regex() so a pattern can be compiled once and
+* reused across calls
+*/
+
+use crate::base_variable::base_types::{BaseTypes, GetType};
+use crate::std_lib::arguments::{expect_arity, get_string};
+use ::regex::Regex;
+use std::error::Error;
+use std::sync::Arc;
+
+/// Compile a pattern, invalid patterns give an error with the char position of the problem
+///
+/// params: pattern: &str -> The pattern to compile
+///
+/// returns: Result<Regex, Box<dyn Error>> -> The compiled regex or a regex error
+pub fn compile_pattern(pattern: &str) -> Result<Regex, Box<dyn Error>> {
+    // the syntax parser reports where in the pattern the error is which Regex::new does not
+    let syntax_error = match regex_syntax::Parser::new().parse(pattern) {
+        Ok(_) => None,
+        Err(regex_syntax::Error::Parse(e)) => Some((e.kind().to_string(), e.span().start.offset)),
+        Err(regex_syntax::Error::Translate(e)) => {
+            Some((e.kind().to_string(), e.span().start.offset))
+        }
+        Err(e) => Some((e.to_string(), 0)),
+    };
+
+    if let Some((message, offset)) = syntax_error {
+        let position = pattern[..offset].chars().count();
+        return Err(format!(
+            "Regex error: {} at position {} in pattern \"{}\"",
+            message, position, pattern
+        )
+        .into());
+    }
+
+    Regex::new(pattern).map_err(|e| format!("Regex error: {}", e).into())
+}
+
+/// Read a regex argument, pattern strings are compiled on the spot
+fn get_regex(name: &str, args: &[BaseTypes], index: usize) -> Result<Regex, Box<dyn Error>> {
+    match args.get(index) {
+        Some(BaseTypes::Regex(r)) => Ok(r.clone()),
+        Some(BaseTypes::StringWrapper(pattern)) => compile_pattern(pattern),
+        Some(other) => Err(format!(
+            "Type error: {} expects argument {} to be Regex or String but got {}",
+            name,
+            index + 1,
+            other.GetType()
+        )
+        .into()),
+        None => Err(format!(
+            "Type error: {} expects argument {} to be Regex or String but got Null",
+            name,
+            index + 1
+        )
+        .into()),
+    }
+}
+
+/// Compile a pattern into a regex value that can be stored and reused
+///
+/// params: args: &[BaseTypes] -> The pattern
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The regex value
+pub fn regex(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("regex", args, 1)?;
+    let pattern = get_string("regex", args, 0)?;
+    Ok(BaseTypes::Regex(compile_pattern(&pattern)?))
+}
+
+/// Check if a pattern matches anywhere in a string
+///
+/// params: args: &[BaseTypes] -> The pattern or regex and the string
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> True when there is a match
+pub fn regex_match(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("regex_match", args, 2)?;
+    let re = get_regex("regex_match", args, 0)?;
+    let s = get_string("regex_match", args, 1)?;
+    Ok(BaseTypes::Bool(re.is_match(&s)))
+}
+
+/// Find every match of a pattern, when the pattern has capture groups each element is an array
+/// of the groups (null for groups that did not take part) otherwise it is the matched text
+///
+/// params: args: &[BaseTypes] -> The pattern or regex and the string
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> An array of the matches
+pub fn regex_find_all(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("regex_find_all", args, 2)?;
+    let re = get_regex("regex_find_all", args, 0)?;
+    let s = get_string("regex_find_all", args, 1)?;
+
+    let matches = re
+        .captures_iter(&s)
+        .map(|captures| {
+            if captures.len() == 1 {
+                return BaseTypes::StringWrapper(captures[0].to_string());
+            }
+            BaseTypes::Array(Arc::new(
+                captures
+                    .iter()
+                    .skip(1)
+                    .map(|group| match group {
+                        Some(m) => BaseTypes::StringWrapper(m.as_str().to_string()),
+                        None => BaseTypes::Null,
+                    })
+                    .collect(),
+            ))
+        })
+        .collect();
+    Ok(BaseTypes::Array(Arc::new(matches)))
+}
+
+/// Replace every match of a pattern, the replacement can refer to groups with $1 or ${name}
+///
+/// params: args: &[BaseTypes] -> The pattern or regex, the string and the replacement
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The new string
+pub fn regex_replace(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("regex_replace", args, 3)?;
+    let re = get_regex("regex_replace", args, 0)?;
+    let s = get_string("regex_replace", args, 1)?;
+    let replacement = get_string("regex_replace", args, 2)?;
+    Ok(BaseTypes::StringWrapper(
+        re.replace_all(&s, replacement.as_str()).to_string(),
+    ))
+}
+
+/// Split a string on every match of a pattern
+///
+/// params: args: &[BaseTypes] -> The pattern or regex and the string
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> An array of the parts
+pub fn regex_split(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("regex_split", args, 2)?;
+    let re = get_regex("regex_split", args, 0)?;
+    let s = get_string("regex_split", args, 1)?;
+    Ok(BaseTypes::Array(Arc::new(
+        re.split(&s)
+            .map(|part| BaseTypes::StringWrapper(part.to_string()))
+            .collect(),
+    )))
+}
+
+#[cfg(test)]
+mod regex_tests {
+    use super::*;
+
+    fn string(s: &str) -> BaseTypes {
+        BaseTypes::StringWrapper(s.to_string())
+    }
+
+    #[test]
+    fn test_invalid_pattern_reports_position() {
+        let error = regex(&[string("ab(cd")]).unwrap_err().to_string();
+        assert!(error.starts_with("Regex error: unclosed group at position 2"));
+
+        let error = regex_match(&[string("é[z"), string("x")])
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("at position 1"));
+    }
+
+    #[test]
+    fn test_compiled_regex_is_reused() {
+        let re = regex(&[string(r"\d+")]).unwrap();
+        assert!(matches!(
+            regex_match(&[re.clone(), string("abc 12")]).unwrap(),
+            BaseTypes::Bool(true)
+        ));
+        assert_eq!(
+            regex_split(&[re, string("a1b22c")]).unwrap(),
+            BaseTypes::Array(vec![string("a"), string("b"), string("c")].into())
+        );
+    }
+
+    #[test]
+    fn test_find_all_with_and_without_groups() {
+        assert_eq!(
+            regex_find_all(&[string(r"\d+"), string("1 and 22")]).unwrap(),
+            BaseTypes::Array(vec![string("1"), string("22")].into())
+        );
+        assert_eq!(
+            regex_find_all(&[string(r"(\w+)=(\d+)"), string("a=1 b=2")]).unwrap(),
+            BaseTypes::Array(
+                vec![
+                    BaseTypes::Array(vec![string("a"), string("1")].into()),
+                    BaseTypes::Array(vec![string("b"), string("2")].into()),
+                ]
+                .into()
+            )
+        );
+    }
+
+    #[test]
+    fn test_replace_with_groups() {
+        assert_eq!(
+            regex_replace(&[
+                string(r"(\w+)@(\w+)"),
+                string("me@host"),
+                string("$2 at $1")
+            ])
+            .unwrap(),
+            string("host at me")
+        );
+    }
+}
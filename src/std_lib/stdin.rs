@@ -0,0 +1,64 @@
+/*
+* This file contains the standard input functions of the std library, they read from stdin
+* without a terminal so scripts work the same when input is piped in
+*/
+
+use crate::base_variable::base_types::BaseTypes;
+use crate::std_lib::arguments::expect_arity;
+use crate::std_lib::fs::register_line_reader;
+use std::error::Error;
+use std::io::{self, Read};
+
+/// Build the error for a failed read from standard input
+fn stdin_error(error: io::Error) -> Box<dyn Error> {
+    format!("Input error: cannot read standard input: {}", error).into()
+}
+
+/// Read one line from standard input without its newline
+///
+/// params: args: &[BaseTypes] -> No arguments
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The line or null at the end of the input
+pub fn read_line(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("read_line", args, 0)?;
+    let mut line = String::new();
+    let read = io::stdin().read_line(&mut line).map_err(stdin_error)?;
+    if read == 0 {
+        return Ok(BaseTypes::Null);
+    }
+
+    if line.ends_with('\n') {
+        line.pop();
+        if line.ends_with('\r') {
+            line.pop();
+        }
+    }
+    Ok(BaseTypes::StringWrapper(line))
+}
+
+/// Read everything left on standard input
+///
+/// params: args: &[BaseTypes] -> No arguments
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The input, empty when there is nothing left
+pub fn read_all_stdin(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("read_all_stdin", args, 0)?;
+    let mut input = String::new();
+    io::stdin()
+        .lock()
+        .read_to_string(&mut input)
+        .map_err(stdin_error)?;
+    Ok(BaseTypes::StringWrapper(input))
+}
+
+/// Stream standard input one line at a time, the handle is read with next_line like a file
+///
+/// params: args: &[BaseTypes] -> No arguments
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> A handle to pass to next_line and close_lines
+pub fn stdin_lines(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("stdin_lines", args, 0)?;
+    // lines are read through the shared stdin buffer so read_line and next_line can be mixed
+    let handle = register_line_reader(Box::new(|line: &mut String| io::stdin().read_line(line)));
+    Ok(BaseTypes::Int(handle))
+}
@@ -0,0 +1,369 @@
+/*
+* This file contains the string functions of the std library, lengths and indexes count unicode
+* scalar values (chars) rather than bytes so "héllo" has a length of 5
+*/
+
+use crate::base_variable::base_types::BaseTypes;
+use crate::limits;
+use crate::std_lib::arguments::{get_array, get_int, get_string};
+use std::error::Error;
+use std::sync::Arc;
+
+/// Resolve a possibly negative index against a length, negative indexes count from the end
+fn resolve_index(index: i32, len: usize) -> i32 {
+    if index < 0 {
+        len as i32 + index
+    } else {
+        index
+    }
+}
+
+/// Read the fill character for padding, defaults to a space
+fn get_fill(name: &str, args: &[BaseTypes]) -> Result<char, Box<dyn Error>> {
+    if args.len() < 3 {
+        return Ok(' ');
+    }
+    let fill = get_string(name, args, 2)?;
+    let mut chars = fill.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        _ => Err(format!(
+            "Argument error: {} expects the fill to be a single character but got \"{}\"",
+            name, fill
+        )
+        .into()),
+    }
+}
+
+/// Turn a list of strings into a jist array value
+fn string_array(values: Vec<&str>) -> BaseTypes {
+    BaseTypes::Array(Arc::new(
+        values
+            .into_iter()
+            .map(|value| BaseTypes::StringWrapper(value.to_string()))
+            .collect(),
+    ))
+}
+
+/// Get the length of a string in chars or the number of elements of an array
+///
+/// params: args: &[BaseTypes] -> The string or array
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The length as an Int
+pub fn len(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    match &args[0] {
+        BaseTypes::Array(values) => Ok(BaseTypes::Int(values.len() as i32)),
+        _ => Ok(BaseTypes::Int(
+            get_string("len", args, 0)?.chars().count() as i32
+        )),
+    }
+}
+
+/// Split a string on a separator, an empty separator splits the string into its chars
+///
+/// params: args: &[BaseTypes] -> The string and the separator
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> An array of the parts
+pub fn split(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    let s = get_string("split", args, 0)?;
+    let separator = get_string("split", args, 1)?;
+
+    if separator.is_empty() {
+        return Ok(BaseTypes::Array(Arc::new(
+            s.chars()
+                .map(|c| BaseTypes::StringWrapper(c.to_string()))
+                .collect(),
+        )));
+    }
+    Ok(string_array(s.split(separator.as_str()).collect()))
+}
+
+/// Join the elements of an array into a string with a separator between them
+///
+/// params: args: &[BaseTypes] -> The array and the separator
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The joined string
+pub fn join(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    let values = get_array("join", args, 0)?;
+    let separator = get_string("join", args, 1)?;
+
+    let parts: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+    Ok(BaseTypes::StringWrapper(parts.join(&separator)))
+}
+
+/// Replace every occurrence of a pattern in a string
+///
+/// params: args: &[BaseTypes] -> The string, the pattern and the replacement
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The new string
+pub fn replace(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    let s = get_string("replace", args, 0)?;
+    let from = get_string("replace", args, 1)?;
+    let to = get_string("replace", args, 2)?;
+    Ok(BaseTypes::StringWrapper(s.replace(&from, &to)))
+}
+
+pub fn contains(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    let s = get_string("contains", args, 0)?;
+    let pattern = get_string("contains", args, 1)?;
+    Ok(BaseTypes::Bool(s.contains(&pattern)))
+}
+
+pub fn starts_with(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    let s = get_string("starts_with", args, 0)?;
+    let prefix = get_string("starts_with", args, 1)?;
+    Ok(BaseTypes::Bool(s.starts_with(&prefix)))
+}
+
+pub fn ends_with(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    let s = get_string("ends_with", args, 0)?;
+    let suffix = get_string("ends_with", args, 1)?;
+    Ok(BaseTypes::Bool(s.ends_with(&suffix)))
+}
+
+/// Find the char index of the first occurrence of a pattern
+///
+/// params: args: &[BaseTypes] -> The string and the pattern
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The index as an Int or null when not found
+pub fn find(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    let s = get_string("find", args, 0)?;
+    let pattern = get_string("find", args, 1)?;
+
+    match s.find(&pattern) {
+        Some(byte_index) => Ok(BaseTypes::Int(s[..byte_index].chars().count() as i32)),
+        None => Ok(BaseTypes::Null),
+    }
+}
+
+/// Slice a string by char indexes, the end is exclusive and optional, negative indexes count
+/// from the end of the string
+///
+/// params: args: &[BaseTypes] -> The string, the start index and optionally the end index
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The slice or an index error when out of bounds
+pub fn substring(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    let s = get_string("substring", args, 0)?;
+    let len = s.chars().count();
+
+    let start = resolve_index(get_int("substring", args, 1)?, len);
+    let end = if args.len() == 3 {
+        resolve_index(get_int("substring", args, 2)?, len)
+    } else {
+        len as i32
+    };
+
+    if start < 0 || end > len as i32 || start > end {
+        return Err(format!(
+            "Index error: substring range {}..{} is out of bounds for a string of length {}",
+            start, end, len
+        )
+        .into());
+    }
+
+    Ok(BaseTypes::StringWrapper(
+        s.chars()
+            .skip(start as usize)
+            .take((end - start) as usize)
+            .collect(),
+    ))
+}
+
+pub fn repeat(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    let s = get_string("repeat", args, 0)?;
+    let count = get_int("repeat", args, 1)?;
+    if count < 0 {
+        return Err(format!(
+            "Argument error: repeat expects a count of 0 or more but got {}",
+            count
+        )
+        .into());
+    }
+    // the length is checked first so a large count fails before the string is built
+    limits::check_string(s.chars().count().saturating_mul(count as usize))?;
+    Ok(BaseTypes::StringWrapper(s.repeat(count as usize)))
+}
+
+/// Pad the start of a string with a fill char until it is the given width in chars
+///
+/// params: args: &[BaseTypes] -> The string, the width and optionally the fill char
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The padded string
+pub fn pad_left(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    let s = get_string("pad_left", args, 0)?;
+    let width = get_int("pad_left", args, 1)?.max(0) as usize;
+    let fill = get_fill("pad_left", args)?;
+
+    let padding: String =
+        std::iter::repeat_n(fill, width.saturating_sub(s.chars().count())).collect();
+    Ok(BaseTypes::StringWrapper(padding + &s))
+}
+
+/// Pad the end of a string with a fill char until it is the given width in chars
+///
+/// params: args: &[BaseTypes] -> The string, the width and optionally the fill char
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The padded string
+pub fn pad_right(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    let s = get_string("pad_right", args, 0)?;
+    let width = get_int("pad_right", args, 1)?.max(0) as usize;
+    let fill = get_fill("pad_right", args)?;
+
+    let padding: String =
+        std::iter::repeat_n(fill, width.saturating_sub(s.chars().count())).collect();
+    Ok(BaseTypes::StringWrapper(s + &padding))
+}
+
+pub fn chars(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    let s = get_string("chars", args, 0)?;
+    Ok(BaseTypes::Array(Arc::new(
+        s.chars().map(BaseTypes::Char).collect(),
+    )))
+}
+
+/// Get the utf-8 bytes of a string
+///
+/// params: args: &[BaseTypes] -> The string
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> An array of the bytes as Ints
+pub fn bytes(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    let s = get_string("bytes", args, 0)?;
+    Ok(BaseTypes::Array(Arc::new(
+        s.bytes().map(|b| BaseTypes::Int(b as i32)).collect(),
+    )))
+}
+
+pub fn reverse(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    let s = get_string("reverse", args, 0)?;
+    Ok(BaseTypes::StringWrapper(s.chars().rev().collect()))
+}
+
+pub fn lines(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    let s = get_string("lines", args, 0)?;
+    Ok(string_array(s.lines().collect()))
+}
+
+/// Parse a string as an integer, surrounding whitespace is ignored
+///
+/// params: args: &[BaseTypes] -> The string
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The Int or a parse error
+pub fn parse_int(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    let s = get_string("parse_int", args, 0)?;
+    match s.trim().parse::<i32>() {
+        Ok(value) => Ok(BaseTypes::Int(value)),
+        Err(e) => Err(format!("Parse error: cannot parse \"{}\" as an int, {}", s, e).into()),
+    }
+}
+
+/// Parse a string as a float, surrounding whitespace is ignored
+///
+/// params: args: &[BaseTypes] -> The string
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The Float or a parse error
+pub fn parse_float(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    let s = get_string("parse_float", args, 0)?;
+    match s.trim().parse::<f64>() {
+        Ok(value) => Ok(BaseTypes::Float(value)),
+        Err(e) => Err(format!("Parse error: cannot parse \"{}\" as a float, {}", s, e).into()),
+    }
+}
+
+#[cfg(test)]
+mod string_tests {
+    use super::*;
+    use crate::function_map::FUNCTIONS;
+
+    fn string(s: &str) -> BaseTypes {
+        BaseTypes::StringWrapper(s.to_string())
+    }
+
+    #[test]
+    fn test_len_counts_chars() {
+        assert_eq!(len(&[string("héllo")]).unwrap(), BaseTypes::Int(5));
+        assert_eq!(
+            len(&[BaseTypes::Array(
+                vec![BaseTypes::Int(1), BaseTypes::Int(2)].into()
+            )])
+            .unwrap(),
+            BaseTypes::Int(2)
+        );
+    }
+
+    #[test]
+    fn test_split_and_join() {
+        let parts = split(&[string("a,b,c"), string(",")]).unwrap();
+        assert_eq!(
+            parts,
+            BaseTypes::Array(vec![string("a"), string("b"), string("c")].into())
+        );
+        assert_eq!(join(&[parts, string("-")]).unwrap(), string("a-b-c"));
+    }
+
+    #[test]
+    fn test_substring_unicode_and_negative() {
+        assert_eq!(
+            substring(&[string("żółw!"), BaseTypes::Int(1), BaseTypes::Int(3)]).unwrap(),
+            string("ół")
+        );
+        assert_eq!(
+            substring(&[string("hello"), BaseTypes::Int(-3)]).unwrap(),
+            string("llo")
+        );
+        assert!(substring(&[string("hi"), BaseTypes::Int(0), BaseTypes::Int(5)]).is_err());
+    }
+
+    #[test]
+    fn test_find_returns_char_index_or_null() {
+        assert_eq!(
+            find(&[string("añb"), string("b")]).unwrap(),
+            BaseTypes::Int(2)
+        );
+        assert!(matches!(
+            find(&[string("abc"), string("z")]).unwrap(),
+            BaseTypes::Null
+        ));
+    }
+
+    #[test]
+    fn test_padding() {
+        assert_eq!(
+            pad_left(&[string("7"), BaseTypes::Int(3), string("0")]).unwrap(),
+            string("007")
+        );
+        assert_eq!(
+            pad_right(&[string("ab"), BaseTypes::Int(4)]).unwrap(),
+            string("ab  ")
+        );
+        assert!(pad_left(&[string("7"), BaseTypes::Int(3), string("00")]).is_err());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(parse_int(&[string(" 42 ")]).unwrap(), BaseTypes::Int(42));
+        assert_eq!(
+            parse_float(&[string("2.5")]).unwrap(),
+            BaseTypes::Float(2.5)
+        );
+        let error = parse_int(&[string("4x")]).unwrap_err().to_string();
+        assert!(error.starts_with("Parse error: cannot parse \"4x\" as an int"));
+    }
+
+    #[test]
+    fn test_argument_errors() {
+        // the count is checked by the signature the function is registered with
+        let reverse = FUNCTIONS.lock().unwrap().get("reverse").unwrap();
+        let error = reverse.invoke(&[]).unwrap_err().to_string();
+        assert_eq!(
+            error,
+            "Argument error: reverse expects 1 argument(s) but got 0"
+        );
+        let error = repeat(&[BaseTypes::Int(1), BaseTypes::Int(2)])
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            error,
+            "Type error: repeat expects argument 1 to be String but got Int"
+        );
+    }
+}
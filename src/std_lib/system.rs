@@ -0,0 +1,139 @@
+/*
+* This file contains the functions of the std library that talk to the process running the script,
+* the command line arguments given after the script path, environment variables, the working
+* directory and the exit code
+*/
+
+use crate::base_variable::base_types::BaseTypes;
+use crate::std_lib::arguments::{expect_arity, get_int, get_string};
+use std::env;
+use std::error::Error;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+lazy_static::lazy_static! {
+    // the arguments given after the script path e.g. jist script.jist foo --bar
+    static ref SCRIPT_ARGUMENTS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+}
+
+/// Store the arguments passed to the script so args() can return them
+///
+/// params: arguments: Vec<String> -> The arguments after the script path
+pub fn set_script_arguments(arguments: Vec<String>) {
+    *SCRIPT_ARGUMENTS.lock().unwrap() = arguments;
+}
+
+/// Get the command line arguments passed to the script, the script path is not included
+///
+/// params: args: &[BaseTypes] -> No arguments
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> An array of the arguments
+pub fn args(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("args", args, 0)?;
+    Ok(BaseTypes::Array(Arc::new(
+        SCRIPT_ARGUMENTS
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|argument| BaseTypes::StringWrapper(argument.clone()))
+            .collect(),
+    )))
+}
+
+/// Read an environment variable
+///
+/// params: args: &[BaseTypes] -> The name of the variable
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The value or null when it is not set
+pub fn env(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("env", args, 1)?;
+    let name = get_string("env", args, 0)?;
+    match env::var(&name) {
+        Ok(value) => Ok(BaseTypes::StringWrapper(value)),
+        Err(env::VarError::NotPresent) => Ok(BaseTypes::Null),
+        Err(env::VarError::NotUnicode(_)) => {
+            Err(format!("Environment error: '{}' is not valid unicode", name).into())
+        }
+    }
+}
+
+/// Set an environment variable for the script and any process it starts
+///
+/// params: args: &[BaseTypes] -> The name and the value
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> Null once set
+pub fn set_env(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("set_env", args, 2)?;
+    let name = get_string("set_env", args, 0)?;
+    let value = get_string("set_env", args, 1)?;
+    if name.is_empty() || name.contains('=') || name.contains('\0') || value.contains('\0') {
+        return Err(format!("Environment error: invalid variable name '{}'", name).into());
+    }
+    env::set_var(name, value);
+    Ok(BaseTypes::Null)
+}
+
+/// Get the current working directory
+///
+/// params: args: &[BaseTypes] -> No arguments
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The path of the working directory
+pub fn cwd(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("cwd", args, 0)?;
+    let directory = env::current_dir()
+        .map_err(|e| format!("Environment error: cannot read working directory: {}", e))?;
+    Ok(BaseTypes::StringWrapper(
+        directory.to_string_lossy().to_string(),
+    ))
+}
+
+/// Stop the script straight away with an exit code
+///
+/// params: args: &[BaseTypes] -> The exit code, 0 when left out
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> Never returns unless the arguments are wrong
+pub fn exit(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    let code = match args.len() {
+        0 => 0,
+        _ => {
+            expect_arity("exit", args, 1)?;
+            get_int("exit", args, 0)?
+        }
+    };
+    std::io::stdout().flush()?;
+    std::process::exit(code);
+}
+
+#[cfg(test)]
+mod system_tests {
+    use super::*;
+
+    fn string(s: &str) -> BaseTypes {
+        BaseTypes::StringWrapper(s.to_string())
+    }
+
+    #[test]
+    fn test_args() {
+        set_script_arguments(vec!["foo".to_string(), "--bar".to_string()]);
+        assert_eq!(
+            args(&[]).unwrap(),
+            BaseTypes::Array(vec![string("foo"), string("--bar")].into())
+        );
+        assert!(args(&[string("extra")]).is_err());
+    }
+
+    #[test]
+    fn test_env_round_trip() {
+        let name = "JIST_SYSTEM_TEST_VARIABLE";
+        assert!(matches!(env(&[string(name)]).unwrap(), BaseTypes::Null));
+        set_env(&[string(name), string("on")]).unwrap();
+        assert_eq!(env(&[string(name)]).unwrap(), string("on"));
+        assert!(set_env(&[string("A=B"), string("x")]).is_err());
+    }
+
+    #[test]
+    fn test_cwd() {
+        let expected = env::current_dir().unwrap().to_string_lossy().to_string();
+        assert_eq!(cwd(&[]).unwrap(), string(&expected));
+    }
+}
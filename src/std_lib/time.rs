@@ -0,0 +1,469 @@
+/*
+* This file contains the date and time functions of the std library, datetimes carry their UTC offset
+* and durations are signed so both can be added, subtracted and compared
+*/
+
+use crate::base_variable::base_types::{BaseTypes, GetType};
+use crate::limits;
+use crate::std_lib::arguments::{
+    expect_arity, expect_arity_range, get_datetime, get_duration, get_int, get_number, get_string,
+};
+use chrono::format::{Item, ParseErrorKind, StrftimeItems};
+use chrono::{DateTime, Duration, FixedOffset, Local, NaiveDate, NaiveDateTime, Offset, Utc};
+use std::cmp::Ordering;
+use std::error::Error;
+use std::time::Instant;
+
+lazy_static::lazy_static! {
+    // the fixed point monotonic() measures from, only differences between readings mean anything
+    static ref MONOTONIC_START: Instant = Instant::now();
+}
+
+/// Show a duration in seconds without trailing zeros e.g. 1.5s or -20s
+///
+/// params: duration: &Duration -> The duration to show
+///
+/// returns: String -> The duration in seconds
+pub fn format_duration(duration: &Duration) -> String {
+    let sign = if *duration < Duration::zero() {
+        "-"
+    } else {
+        ""
+    };
+    let duration = duration.abs();
+    let seconds = duration.num_seconds();
+    let nanos = (duration - Duration::seconds(seconds))
+        .num_nanoseconds()
+        .unwrap_or(0);
+
+    if nanos == 0 {
+        return format!("{}{}s", sign, seconds);
+    }
+    let fraction = format!("{:09}", nanos);
+    format!("{}{}.{}s", sign, seconds, fraction.trim_end_matches('0'))
+}
+
+/// Read a UTC offset written as Z, UTC, local, +HH:MM, +HHMM or +HH
+fn parse_offset(offset: &str) -> Result<FixedOffset, Box<dyn Error>> {
+    let invalid = || -> Box<dyn Error> {
+        format!(
+            "Time error: invalid UTC offset '{}', expected Z, local or +HH:MM",
+            offset
+        )
+        .into()
+    };
+
+    match offset {
+        "Z" | "z" | "UTC" | "utc" => return Ok(Utc.fix()),
+        "local" => return Ok(*Local::now().offset()),
+        _ => {}
+    }
+
+    let sign = match offset.chars().next() {
+        Some('+') => 1,
+        Some('-') => -1,
+        _ => return Err(invalid()),
+    };
+    let digits: String = offset[1..].chars().filter(|c| *c != ':').collect();
+    if !(digits.len() == 2 || digits.len() == 4) || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    let hours: i32 = digits[..2].parse()?;
+    let minutes: i32 = if digits.len() == 4 {
+        digits[2..].parse()?
+    } else {
+        0
+    };
+    if minutes >= 60 {
+        return Err(invalid());
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60)).ok_or_else(invalid)
+}
+
+/// Get the current date and time
+///
+/// params: args: &[BaseTypes] -> An optional UTC offset, UTC when left out
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The current datetime
+pub fn now(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity_range("now", args, 0, 1)?;
+    let offset = match args.len() {
+        0 => Utc.fix(),
+        _ => parse_offset(&get_string("now", args, 0)?)?,
+    };
+    Ok(BaseTypes::DateTime(Utc::now().with_timezone(&offset)))
+}
+
+/// Read a clock that never goes backwards, used to time parts of a script
+///
+/// params: args: &[BaseTypes] -> No arguments
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The duration since a fixed point
+pub fn monotonic(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("monotonic", args, 0)?;
+    let elapsed = Duration::from_std(MONOTONIC_START.elapsed())?;
+    Ok(BaseTypes::Duration(elapsed))
+}
+
+/// Pause the script, the pause ends at the time limit with the limit error
+///
+/// params: args: &[BaseTypes] -> The number of milliseconds to wait
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> Null once the time has passed
+pub fn sleep(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("sleep", args, 1)?;
+    let milliseconds = get_int("sleep", args, 0)?;
+    if milliseconds < 0 {
+        return Err(format!("Time error: cannot sleep for {}ms", milliseconds).into());
+    }
+    limits::sleep(std::time::Duration::from_millis(milliseconds as u64))?;
+    Ok(BaseTypes::Null)
+}
+
+/// Check a strftime style pattern before it is used, chrono fails late on unknown specifiers
+fn check_pattern(pattern: &str) -> Result<(), Box<dyn Error>> {
+    if StrftimeItems::new(pattern).any(|item| item == Item::Error) {
+        return Err(format!("Time error: invalid pattern '{}'", pattern).into());
+    }
+    Ok(())
+}
+
+/// Parse text with a pattern, text without an offset is read as UTC and text without a time is
+/// read as midnight
+fn parse_with_pattern(
+    text: &str,
+    pattern: &str,
+) -> Result<DateTime<FixedOffset>, chrono::ParseError> {
+    match DateTime::parse_from_str(text, pattern) {
+        Err(e) if e.kind() == ParseErrorKind::NotEnough => {}
+        result => return result,
+    }
+    match NaiveDateTime::parse_from_str(text, pattern) {
+        Err(e) if e.kind() == ParseErrorKind::NotEnough => {}
+        result => return result.map(|naive| naive.and_utc().fixed_offset()),
+    }
+    NaiveDate::parse_from_str(text, pattern).map(|date| {
+        date.and_hms_opt(0, 0, 0)
+            .unwrap_or_default()
+            .and_utc()
+            .fixed_offset()
+    })
+}
+
+/// Parse a datetime
+///
+/// params: args: &[BaseTypes] -> The text and an optional strftime style pattern, RFC 3339 when
+/// left out
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The datetime or a time error
+pub fn datetime_parse(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity_range("datetime_parse", args, 1, 2)?;
+    let text = get_string("datetime_parse", args, 0)?;
+    let parsed = match args.len() {
+        1 => DateTime::parse_from_rfc3339(&text)
+            .map_err(|e| format!("Time error: cannot parse '{}' as RFC 3339: {}", text, e))?,
+        _ => {
+            let pattern = get_string("datetime_parse", args, 1)?;
+            check_pattern(&pattern)?;
+            parse_with_pattern(&text, &pattern).map_err(|e| {
+                format!(
+                    "Time error: cannot parse '{}' with pattern '{}': {}",
+                    text, pattern, e
+                )
+            })?
+        }
+    };
+    Ok(BaseTypes::DateTime(parsed))
+}
+
+/// Format a datetime
+///
+/// params: args: &[BaseTypes] -> The datetime and an optional strftime style pattern, RFC 3339 when
+/// left out
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The formatted text
+pub fn datetime_format(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity_range("datetime_format", args, 1, 2)?;
+    let datetime = get_datetime("datetime_format", args, 0)?;
+    let text = match args.len() {
+        1 => datetime.to_rfc3339(),
+        _ => {
+            let pattern = get_string("datetime_format", args, 1)?;
+            check_pattern(&pattern)?;
+            datetime.format(&pattern).to_string()
+        }
+    };
+    Ok(BaseTypes::StringWrapper(text))
+}
+
+/// Show the same moment at another UTC offset
+///
+/// params: args: &[BaseTypes] -> The datetime and the offset e.g. "+02:00", "Z" or "local"
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The datetime at the new offset
+pub fn to_offset(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("to_offset", args, 2)?;
+    let datetime = get_datetime("to_offset", args, 0)?;
+    let offset = parse_offset(&get_string("to_offset", args, 1)?)?;
+    Ok(BaseTypes::DateTime(datetime.with_timezone(&offset)))
+}
+
+/// Build a duration from an amount and a unit
+///
+/// params: args: &[BaseTypes] -> The amount as an int or float and the unit, one of ns, us, ms,
+/// s, m, h or d
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The duration
+pub fn duration(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("duration", args, 2)?;
+    let amount = get_number("duration", args, 0)?;
+    let unit = get_string("duration", args, 1)?;
+    let nanos_per_unit = match unit.as_str() {
+        "ns" => 1.0,
+        "us" => 1e3,
+        "ms" => 1e6,
+        "s" => 1e9,
+        "m" => 60e9,
+        "h" => 3600e9,
+        "d" => 86400e9,
+        _ => {
+            return Err(format!(
+                "Time error: unknown unit '{}', expected ns, us, ms, s, m, h or d",
+                unit
+            )
+            .into())
+        }
+    };
+
+    let nanos = amount * nanos_per_unit;
+    if !nanos.is_finite() || nanos.abs() >= i64::MAX as f64 {
+        return Err(format!("Time error: duration of {}{} is out of range", amount, unit).into());
+    }
+    Ok(BaseTypes::Duration(Duration::nanoseconds(
+        nanos.round() as i64
+    )))
+}
+
+/// Get the length of a duration in whole milliseconds
+///
+/// params: args: &[BaseTypes] -> The duration
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The milliseconds, an error if they do not fit an int
+pub fn duration_ms(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("duration_ms", args, 1)?;
+    let duration = get_duration("duration_ms", args, 0)?;
+    let milliseconds = i32::try_from(duration.num_milliseconds()).map_err(|_| {
+        format!(
+            "Time error: {} is too long to fit an int of milliseconds",
+            format_duration(&duration)
+        )
+    })?;
+    Ok(BaseTypes::Int(milliseconds))
+}
+
+/// Get the length of a duration in seconds
+///
+/// params: args: &[BaseTypes] -> The duration
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The seconds including the fraction
+pub fn duration_seconds(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("duration_seconds", args, 1)?;
+    let duration = get_duration("duration_seconds", args, 0)?;
+    let seconds = match duration.num_nanoseconds() {
+        Some(nanos) => nanos as f64 / 1e9,
+        None => duration.num_milliseconds() as f64 / 1e3,
+    };
+    Ok(BaseTypes::Float(seconds))
+}
+
+/// Build the error for time arithmetic on values that do not go together
+fn pair_error(name: &str, expected: &str, args: &[BaseTypes]) -> Box<dyn Error> {
+    format!(
+        "Type error: {} expects {} but got {} and {}",
+        name,
+        expected,
+        args[0].GetType(),
+        args[1].GetType()
+    )
+    .into()
+}
+
+fn out_of_range(name: &str) -> Box<dyn Error> {
+    format!("Time error: {} result is out of range", name).into()
+}
+
+/// Add a duration to a datetime or to another duration
+///
+/// params: args: &[BaseTypes] -> A datetime and a duration or two durations
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The later datetime or the summed duration
+pub fn time_add(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("time_add", args, 2)?;
+    match (&args[0], &args[1]) {
+        (BaseTypes::DateTime(datetime), BaseTypes::Duration(duration))
+        | (BaseTypes::Duration(duration), BaseTypes::DateTime(datetime)) => datetime
+            .checked_add_signed(*duration)
+            .map(BaseTypes::DateTime)
+            .ok_or_else(|| out_of_range("time_add")),
+        (BaseTypes::Duration(left), BaseTypes::Duration(right)) => left
+            .checked_add(right)
+            .map(BaseTypes::Duration)
+            .ok_or_else(|| out_of_range("time_add")),
+        _ => Err(pair_error(
+            "time_add",
+            "a datetime and a duration or two durations",
+            args,
+        )),
+    }
+}
+
+/// Subtract a duration from a datetime, or find the time between two datetimes or two durations
+///
+/// params: args: &[BaseTypes] -> A datetime and a duration, two datetimes or two durations
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> The earlier datetime or the difference
+pub fn time_sub(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("time_sub", args, 2)?;
+    match (&args[0], &args[1]) {
+        (BaseTypes::DateTime(datetime), BaseTypes::Duration(duration)) => datetime
+            .checked_sub_signed(*duration)
+            .map(BaseTypes::DateTime)
+            .ok_or_else(|| out_of_range("time_sub")),
+        (BaseTypes::DateTime(left), BaseTypes::DateTime(right)) => {
+            Ok(BaseTypes::Duration(left.signed_duration_since(*right)))
+        }
+        (BaseTypes::Duration(left), BaseTypes::Duration(right)) => left
+            .checked_sub(right)
+            .map(BaseTypes::Duration)
+            .ok_or_else(|| out_of_range("time_sub")),
+        _ => Err(pair_error(
+            "time_sub",
+            "a datetime and a duration, two datetimes or two durations",
+            args,
+        )),
+    }
+}
+
+/// Compare two datetimes or two durations, datetimes at different offsets compare by the moment
+/// they describe
+///
+/// params: args: &[BaseTypes] -> Two datetimes or two durations
+///
+/// returns: Result<BaseTypes, Box<dyn Error>> -> -1, 0 or 1 when the first is earlier, the same or
+/// later
+pub fn time_compare(args: &[BaseTypes]) -> Result<BaseTypes, Box<dyn Error>> {
+    expect_arity("time_compare", args, 2)?;
+    let ordering = match (&args[0], &args[1]) {
+        (BaseTypes::DateTime(left), BaseTypes::DateTime(right)) => left.cmp(right),
+        (BaseTypes::Duration(left), BaseTypes::Duration(right)) => left.cmp(right),
+        _ => {
+            return Err(pair_error(
+                "time_compare",
+                "two datetimes or two durations",
+                args,
+            ))
+        }
+    };
+    Ok(BaseTypes::Int(match ordering {
+        Ordering::Less => -1,
+        Ordering::Equal => 0,
+        Ordering::Greater => 1,
+    }))
+}
+
+#[cfg(test)]
+mod time_tests {
+    use super::*;
+
+    fn string(s: &str) -> BaseTypes {
+        BaseTypes::StringWrapper(s.to_string())
+    }
+
+    fn parse(text: &str) -> BaseTypes {
+        datetime_parse(&[string(text)]).unwrap()
+    }
+
+    fn seconds(amount: i32) -> BaseTypes {
+        duration(&[BaseTypes::Int(amount), string("s")]).unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_format() {
+        let datetime = parse("2024-03-01T12:30:00+02:00");
+        assert_eq!(datetime.to_string(), "2024-03-01T12:30:00+02:00");
+        assert_eq!(
+            datetime_format(&[datetime.clone(), string("%Y/%m/%d %H:%M")]).unwrap(),
+            string("2024/03/01 12:30")
+        );
+
+        let naive =
+            datetime_parse(&[string("01.03.2024 08:00"), string("%d.%m.%Y %H:%M")]).unwrap();
+        assert_eq!(naive.to_string(), "2024-03-01T08:00:00+00:00");
+        let date = datetime_parse(&[string("2024-03-01"), string("%Y-%m-%d")]).unwrap();
+        assert_eq!(date.to_string(), "2024-03-01T00:00:00+00:00");
+
+        assert!(datetime_parse(&[string("yesterday")]).is_err());
+        assert!(datetime_format(&[datetime, string("%Q")]).is_err());
+    }
+
+    #[test]
+    fn test_offsets() {
+        let datetime = parse("2024-03-01T12:30:00Z");
+        let shifted = to_offset(&[datetime.clone(), string("+05:30")]).unwrap();
+        assert_eq!(shifted.to_string(), "2024-03-01T18:00:00+05:30");
+        assert_eq!(shifted, datetime);
+        assert!(to_offset(&[datetime, string("+25:00")]).is_err());
+        assert!(now(&[string("nowhere")]).is_err());
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let start = parse("2024-02-28T23:00:00Z");
+        let later = time_add(&[start.clone(), seconds(7200)]).unwrap();
+        assert_eq!(later.to_string(), "2024-02-29T01:00:00+00:00");
+        assert_eq!(
+            time_sub(&[later.clone(), start.clone()]).unwrap(),
+            seconds(7200)
+        );
+        assert_eq!(time_sub(&[later, seconds(7200)]).unwrap(), start);
+        assert_eq!(time_add(&[seconds(1), seconds(2)]).unwrap(), seconds(3));
+        assert!(time_add(&[start.clone(), start]).is_err());
+    }
+
+    #[test]
+    fn test_compare() {
+        let early = parse("2024-01-01T00:00:00Z");
+        let late = parse("2024-01-01T03:00:00+02:00");
+        assert_eq!(
+            time_compare(&[early.clone(), late.clone()]).unwrap(),
+            BaseTypes::Int(-1)
+        );
+        assert_eq!(time_compare(&[late, early]).unwrap(), BaseTypes::Int(1));
+        assert_eq!(
+            time_compare(&[seconds(5), seconds(5)]).unwrap(),
+            BaseTypes::Int(0)
+        );
+        assert!(seconds(1) < seconds(2));
+    }
+
+    #[test]
+    fn test_durations() {
+        let half = duration(&[BaseTypes::Float(1.5), string("s")]).unwrap();
+        assert_eq!(half.to_string(), "1.5s");
+        assert_eq!(duration_ms(&[half.clone()]).unwrap(), BaseTypes::Int(1500));
+        assert_eq!(duration_seconds(&[half]).unwrap(), BaseTypes::Float(1.5));
+        assert_eq!(seconds(-20).to_string(), "-20s");
+        assert!(duration(&[BaseTypes::Int(1), string("weeks")]).is_err());
+        assert!(duration_ms(&[duration(&[BaseTypes::Int(100), string("d")]).unwrap()]).is_err());
+    }
+
+    #[test]
+    fn test_monotonic_and_sleep() {
+        let before = monotonic(&[]).unwrap();
+        sleep(&[BaseTypes::Int(5)]).unwrap();
+        let after = monotonic(&[]).unwrap();
+        let elapsed = time_sub(&[after, before]).unwrap();
+        assert!(elapsed >= duration(&[BaseTypes::Int(5), string("ms")]).unwrap());
+        assert!(sleep(&[BaseTypes::Int(-1)]).is_err());
+    }
+}
@@ -4,6 +4,7 @@
 */
 
 pub mod token_types {
+    use crate::intern::Symbol;
 
     #[derive(Debug, Clone)]
     pub enum TokenTypes {
@@ -62,9 +63,11 @@ pub mod token_types {
          */
         ArgumentSeparator,
         /*
-         * a = 2
+         * a = 2, the name is interned when it is read
          */
-        VariableCall,
+        VariableCall {
+            symbol: Symbol,
+        },
         /*
          * values within () in a function call
          */
@@ -209,6 +212,11 @@ pub mod token_types {
          * */
         Not,
 
+        /*
+         * null, the absence of a value for optional types e.g. int?
+         */
+        Null,
+
         /*
          * Used for a return statement
          */
@@ -245,7 +253,10 @@ pub mod token_types {
                 (TokenTypes::LeftParenthesis, TokenTypes::LeftParenthesis) => true,
                 (TokenTypes::RightParenthesis, TokenTypes::RightParenthesis) => true,
                 (TokenTypes::FunctionCall, TokenTypes::FunctionCall) => true,
-                (TokenTypes::VariableCall, TokenTypes::VariableCall) => true,
+                (
+                    TokenTypes::VariableCall { symbol: symbol1 },
+                    TokenTypes::VariableCall { symbol: symbol2 },
+                ) => symbol1 == symbol2,
                 (TokenTypes::ArgumentSeparator, TokenTypes::ArgumentSeparator) => true,
                 (TokenTypes::Assignment, TokenTypes::Assignment) => true,
                 (TokenTypes::VarTypeAssignment, TokenTypes::VarTypeAssignment) => true,
@@ -332,6 +343,7 @@ pub mod token_types {
                     TokenTypes::Finally { block: ref block_b },
                 ) => block_a == block_b,
                 (TokenTypes::Not, TokenTypes::Not) => true,
+                (TokenTypes::Null, TokenTypes::Null) => true,
                 (
                     TokenTypes::While {
                         statement: ref a, ..
@@ -381,6 +393,7 @@ pub mod token_types {
                     )
                 }
                 TokenTypes::Not => "Not".to_string(),
+                TokenTypes::Null => "Null".to_string(),
                 TokenTypes::Else => "Else".to_string(),
                 TokenTypes::Elif { statement } => format!("Elif: {}", statement),
                 TokenTypes::If { statement } => format!("If: {}", statement),
@@ -417,7 +430,7 @@ pub mod token_types {
                 TokenTypes::RightParenthesis => "RightParenthesis".to_string(),
                 TokenTypes::FunctionCall => "FunctionCall".to_string(),
                 TokenTypes::Variable => "Variable".to_string(),
-                TokenTypes::VariableCall => "VariableCall".to_string(),
+                TokenTypes::VariableCall { .. } => "VariableCall".to_string(),
                 TokenTypes::ArgumentSeparator => "ArgumentSeparator".to_string(),
                 TokenTypes::Assignment => "Assignment".to_string(),
                 TokenTypes::VarTypeAssignment => "VarTypeAssignment".to_string(),
@@ -0,0 +1,574 @@
+/*
+* This file contains the virtual machine that runs compiled programs and function bodies. Values
+* are kept on a stack and the variables a chunk declares are reached through its slots, each slot
+* holds the index on the VARIABLE_STACK the variable was declared at so loads and stores do not
+* search by name. Names the chunk does not declare are looked up on the VARIABLE_STACK or read as
+* arrays, dictionaries or constants
+*/
+
+use crate::base_variable::base_types::{BaseTypes, GetType};
+use crate::base_variable::base_variables::constant_value;
+use crate::base_variable::variable::Variable;
+use crate::base_variable::variables::{
+    find_symbol, push_variable, truncate_variables, variable_count, with_variable_at,
+};
+use crate::bytecode::{BinaryOp, Chunk, Op};
+use crate::compiler::compilers::route_to_parser;
+use crate::compilers::conditional::conditional_compilers::compile_conditional_statement;
+use crate::compilers::function::{
+    get_function_result, parse_function_call, parse_function_declaration,
+};
+use crate::compilers::variable::{
+    compile_dot_statement, declared_variable, get_array, get_dict, type_from_name,
+};
+use crate::engine::parse_file;
+use crate::globals::TREE_WALK;
+use crate::limits;
+use crate::module::import_module;
+use crate::node::nodes::ASTNode;
+use crate::output;
+use std::error::Error;
+use std::path::Path;
+
+/// Whether programs are run by the virtual machine, --tree-walk turns it off to debug the compiler
+pub fn enabled() -> bool {
+    !unsafe { TREE_WALK }
+}
+
+/// A try block that is running, an error jumps to the catch block at target
+struct Handler {
+    target: usize,
+    stack: usize,
+    scopes: usize,
+    variables: usize,
+}
+
+/// Run a chunk until its last instruction or a return
+///
+/// params: chunk: &Chunk -> The compiled code
+///
+/// returns: Result<Option<BaseTypes>, Box<dyn Error>> -> The value of a return statement or of an
+/// expression, None when the chunk leaves no value
+pub fn run(chunk: &Chunk) -> Result<Option<BaseTypes>, Box<dyn Error>> {
+    Vm::new(chunk, variable_count()).run()
+}
+
+/// Run the compiled body of a function once its parameters are on the VARIABLE_STACK
+///
+/// params: chunk: &Chunk -> The compiled body
+/// params: base: usize -> The index of the first parameter on the VARIABLE_STACK
+///
+/// returns: Result<Option<BaseTypes>, Box<dyn Error>> -> The value of a return statement
+pub fn call(chunk: &Chunk, base: usize) -> Result<Option<BaseTypes>, Box<dyn Error>> {
+    Vm::new(chunk, base).run()
+}
+
+/// Run a compiled expression and return its value
+pub fn evaluate(chunk: &Chunk) -> Result<BaseTypes, Box<dyn Error>> {
+    Ok(run(chunk)?.unwrap_or(BaseTypes::Null))
+}
+
+struct Vm<'a> {
+    chunk: &'a Chunk,
+    stack: Vec<BaseTypes>,
+    /// The index on the VARIABLE_STACK of the variable in each slot, set when it is declared
+    slots: Vec<Option<usize>>,
+    /// The number of variables when each open block started
+    scopes: Vec<usize>,
+    handlers: Vec<Handler>,
+}
+
+impl<'a> Vm<'a> {
+    fn new(chunk: &'a Chunk, base: usize) -> Vm<'a> {
+        Vm {
+            chunk,
+            stack: Vec::new(),
+            slots: (0..chunk.locals)
+                .map(|slot| (slot < chunk.parameters).then_some(base + slot))
+                .collect(),
+            scopes: Vec::new(),
+            handlers: Vec::new(),
+        }
+    }
+
+    fn pop(&mut self) -> Result<BaseTypes, Box<dyn Error>> {
+        self.stack
+            .pop()
+            .ok_or_else(|| "Bytecode error: the stack is empty".into())
+    }
+
+    /// Use the variable in a slot, the VARIABLE_STACK is locked during the call
+    fn with_slot<R>(
+        &self,
+        slot: usize,
+        call: impl FnOnce(&mut Variable) -> Result<R, Box<dyn Error>>,
+    ) -> Result<R, Box<dyn Error>> {
+        self.slots[slot]
+            .and_then(|index| with_variable_at(index, call))
+            .unwrap_or_else(|| {
+                Err(format!("Bytecode error: slot {} holds no variable", slot).into())
+            })
+    }
+
+    /// Use a variable the chunk does not declare, it is found by its name
+    fn with_global<R>(
+        &self,
+        global: usize,
+        call: impl FnOnce(&mut Variable) -> Result<R, Box<dyn Error>>,
+    ) -> Result<R, Box<dyn Error>> {
+        let symbol = self.chunk.globals[global];
+        find_symbol(symbol)
+            .and_then(|index| with_variable_at(index, call))
+            .unwrap_or_else(|| {
+                Err(format!("Syntax Error: Variable '{}' not found in stack.", symbol).into())
+            })
+    }
+
+    fn load_global(&self, global: usize) -> Result<BaseTypes, Box<dyn Error>> {
+        let symbol = self.chunk.globals[global];
+        if find_symbol(symbol).is_some() {
+            return self.with_global(global, |variable| Ok(variable.value.clone()));
+        }
+        // the same lookup as a variable passed to a function
+        let name = symbol.name().to_string();
+        if let Ok(array) = get_array(name.clone()) {
+            return Ok(BaseTypes::Array(array.data.clone()));
+        }
+        if let Ok(dictionary) = get_dict(name.clone()) {
+            return Ok(BaseTypes::Dictionary(dictionary.values.clone()));
+        }
+        constant_value(&name)
+            .ok_or_else(|| format!("Syntax Error: Variable '{}' not found in stack.", name).into())
+    }
+
+    /// Run the instructions, an error inside a try block continues at its catch block
+    fn run(&mut self) -> Result<Option<BaseTypes>, Box<dyn Error>> {
+        let mut ip = 0;
+        while ip < self.chunk.code.len() {
+            match self.step(&mut ip) {
+                Ok(Some(value)) => return Ok(Some(value)),
+                Ok(None) => {}
+                // running out of a limit is not caught
+                Err(e) if limits::is_limit_error(e.as_ref()) => return Err(e),
+                Err(e) => {
+                    let handler = match self.handlers.pop() {
+                        Some(handler) => handler,
+                        None => return Err(e),
+                    };
+                    output::eprintln(&format!("Error in Try block: {}", e));
+                    self.stack.truncate(handler.stack);
+                    self.scopes.truncate(handler.scopes);
+                    truncate_variables(handler.variables);
+                    ip = handler.target;
+                }
+            }
+        }
+        Ok(self.stack.pop())
+    }
+
+    /// Run the instruction at ip, a return statement gives its value
+    fn step(&mut self, ip: &mut usize) -> Result<Option<BaseTypes>, Box<dyn Error>> {
+        let chunk = self.chunk;
+        let op = &chunk.code[*ip];
+        *ip += 1;
+        match op {
+            Op::Constant(index) => self.stack.push(chunk.constants[*index].clone()),
+            Op::LoadSlot(slot) => {
+                let value = self.with_slot(*slot, |variable| Ok(variable.value.clone()))?;
+                self.stack.push(value);
+            }
+            Op::StoreSlot(slot) => {
+                let value = self.pop()?;
+                self.with_slot(*slot, |variable| assign(variable, value))?;
+            }
+            Op::IncrementSlot(slot) => {
+                self.with_slot(*slot, |variable| step_variable(variable, 1))?
+            }
+            Op::DecrementSlot(slot) => {
+                self.with_slot(*slot, |variable| step_variable(variable, -1))?
+            }
+            Op::LoadGlobal(global) => {
+                let value = self.load_global(*global)?;
+                self.stack.push(value);
+            }
+            Op::StoreGlobal(global) => {
+                let value = self.pop()?;
+                self.with_global(*global, |variable| assign(variable, value))?;
+            }
+            Op::IncrementGlobal(global) => {
+                self.with_global(*global, |variable| step_variable(variable, 1))?
+            }
+            Op::DecrementGlobal(global) => {
+                self.with_global(*global, |variable| step_variable(variable, -1))?
+            }
+            Op::Binary(op) => {
+                let right = self.pop()?;
+                let left = self.pop()?;
+                self.stack.push(binary(*op, left, right)?);
+            }
+            Op::Not => {
+                let value = self.pop()?;
+                self.stack.push(BaseTypes::Bool(!truthy(&value)?));
+            }
+            Op::Negate => {
+                let value = match self.pop()? {
+                    BaseTypes::Int(n) => BaseTypes::Int(n.checked_neg().ok_or(OVERFLOW)?),
+                    BaseTypes::Float(f) => BaseTypes::Float(-f),
+                    other => {
+                        return Err(format!("Type error: cannot negate {}", other.GetType()).into())
+                    }
+                };
+                self.stack.push(value);
+            }
+            Op::ToBool => {
+                let value = self.pop()?;
+                self.stack.push(BaseTypes::Bool(truthy(&value)?));
+            }
+            Op::Unwrap => {
+                if let Some(BaseTypes::Null) = self.stack.last() {
+                    return Err("Unwrap error: tried to unwrap a null value with !".into());
+                }
+            }
+            Op::Call { function, argc } => {
+                let mut args = self.stack.split_off(self.stack.len() - argc);
+                let result = get_function_result(
+                    chunk.functions[*function].clone(),
+                    &mut args,
+                    Vec::new(),
+                    "None".to_string(),
+                    None,
+                    None,
+                    None,
+                )?;
+                self.stack.push(result);
+            }
+            Op::Pop => {
+                self.pop()?;
+            }
+            Op::Jump(target) => *ip = *target,
+            Op::JumpIfFalse(target) => {
+                let value = self.pop()?;
+                if !truthy(&value)? {
+                    *ip = *target;
+                }
+            }
+            Op::JumpIfFalseOrPop(target) => match self.stack.last() {
+                Some(BaseTypes::Bool(false)) => *ip = *target,
+                _ => {
+                    self.pop()?;
+                }
+            },
+            Op::JumpIfTrueOrPop(target) => match self.stack.last() {
+                Some(BaseTypes::Bool(true)) => *ip = *target,
+                _ => {
+                    self.pop()?;
+                }
+            },
+            Op::JumpIfNotNull(target) => match self.stack.last() {
+                Some(BaseTypes::Null) => {
+                    self.pop()?;
+                }
+                _ => *ip = *target,
+            },
+            Op::Step => limits::step()?,
+            Op::Condition(index) => {
+                let mut nodes = chunk.statements[*index].clone();
+                let result = compile_conditional_statement(&mut nodes)?;
+                self.stack.push(BaseTypes::Bool(result));
+            }
+            Op::Interpret(index) => {
+                let mut nodes = chunk.statements[*index].clone();
+                route_to_parser(&mut nodes, Some(0))?;
+            }
+            Op::Evaluate(index) => {
+                let mut nodes = chunk.statements[*index].clone();
+                let value = match nodes.first() {
+                    Some(ASTNode::Dot(_)) => compile_dot_statement(&mut nodes)?,
+                    _ => parse_function_call(&nodes, "None".to_string(), None, None, None)?,
+                };
+                self.stack.push(value);
+            }
+            Op::Declare(index) => {
+                let declaration = &chunk.declarations[*index];
+                let value = self.pop()?;
+                let variable = declared_variable(
+                    declaration.name.clone(),
+                    value,
+                    type_from_name(&declaration.type_name)?,
+                    declaration.type_name.ends_with('?'),
+                )?;
+                self.slots[declaration.slot] = Some(push_variable(variable));
+            }
+            Op::Bind(slot) => {
+                let index = variable_count()
+                    .checked_sub(1)
+                    .ok_or("Bytecode error: no variable to bind")?;
+                self.slots[*slot] = Some(index);
+            }
+            Op::EnterScope => self.scopes.push(variable_count()),
+            Op::ExitScope => {
+                let len = self
+                    .scopes
+                    .pop()
+                    .ok_or("Bytecode error: no scope to exit")?;
+                truncate_variables(len);
+            }
+            Op::Function { statement, body } => {
+                parse_function_declaration(
+                    &chunk.statements[*statement],
+                    Some(chunk.bodies[*body].clone()),
+                )?;
+            }
+            Op::Return => return Ok(Some(self.pop()?)),
+            Op::Try(target) => self.handlers.push(Handler {
+                target: *target,
+                stack: self.stack.len(),
+                scopes: self.scopes.len(),
+                variables: variable_count(),
+            }),
+            Op::EndTry => {
+                self.handlers.pop();
+            }
+            Op::Import(index) => {
+                import_module(
+                    chunk.imports[*index].clone(),
+                    Path::new(&chunk.path),
+                    parse_file,
+                )?;
+            }
+        }
+        Ok(None)
+    }
+}
+
+const OVERFLOW: &str = "Arithmetic error: integer overflow";
+
+fn assign(variable: &mut Variable, value: BaseTypes) -> Result<(), Box<dyn Error>> {
+    limits::check_value(&value)?;
+    variable.check_null_safety(&value)?;
+    variable.value = value;
+    Ok(())
+}
+
+fn step_variable(variable: &mut Variable, by: i32) -> Result<(), Box<dyn Error>> {
+    match &mut variable.value {
+        BaseTypes::Int(n) => *n = n.checked_add(by).ok_or(OVERFLOW)?,
+        BaseTypes::Float(f) => *f += by as f64,
+        other => {
+            let operation = if by > 0 { "increment" } else { "decrement" };
+            return Err(format!("Type error: cannot {} {}", operation, other.GetType()).into());
+        }
+    }
+    Ok(())
+}
+
+/// Whether a value counts as true in a condition, ints are true when they are not 0
+pub fn truthy(value: &BaseTypes) -> Result<bool, Box<dyn Error>> {
+    match value {
+        BaseTypes::Bool(b) => Ok(*b),
+        BaseTypes::Int(n) => Ok(*n != 0),
+        BaseTypes::Null => Ok(false),
+        other => Err(format!(
+            "Type error: a condition must be a boolean but got {}",
+            other.GetType()
+        )
+        .into()),
+    }
+}
+
+fn type_error(op: BinaryOp, left: &BaseTypes, right: &BaseTypes) -> Box<dyn Error> {
+    format!(
+        "Type error: cannot apply {} to {} and {}",
+        op.symbol(),
+        left.GetType(),
+        right.GetType()
+    )
+    .into()
+}
+
+/// Apply a binary operator, ints stay ints unless a float is involved and + joins strings
+pub fn binary(
+    op: BinaryOp,
+    left: BaseTypes,
+    right: BaseTypes,
+) -> Result<BaseTypes, Box<dyn Error>> {
+    use BaseTypes::{Bool, Char, Float, Int, Null, StringWrapper};
+
+    let result = match (op, &left, &right) {
+        (BinaryOp::Add, Int(a), Int(b)) => Int(a.checked_add(*b).ok_or(OVERFLOW)?),
+        (BinaryOp::Sub, Int(a), Int(b)) => Int(a.checked_sub(*b).ok_or(OVERFLOW)?),
+        (BinaryOp::Mul, Int(a), Int(b)) => Int(a.checked_mul(*b).ok_or(OVERFLOW)?),
+        (BinaryOp::Div, Int(_), Int(0)) => return Err("Division by zero.".into()),
+        (BinaryOp::Div, Int(a), Int(b)) => Int(a.checked_div(*b).ok_or(OVERFLOW)?),
+        (BinaryOp::Add, StringWrapper(a), StringWrapper(b)) => StringWrapper(format!("{}{}", a, b)),
+        (BinaryOp::Add, StringWrapper(a), Char(b)) => StringWrapper(format!("{}{}", a, b)),
+        (BinaryOp::Add, Char(a), StringWrapper(b)) => StringWrapper(format!("{}{}", a, b)),
+        (BinaryOp::Eq, _, _) => Bool(equal(&left, &right)),
+        (BinaryOp::Ne, _, _) => Bool(!equal(&left, &right)),
+        (BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge, _, _) => {
+            let ordering = match (&left, &right) {
+                (StringWrapper(a), StringWrapper(b)) => a.partial_cmp(b),
+                (Char(a), Char(b)) => a.partial_cmp(b),
+                _ => match (number(&left), number(&right)) {
+                    (Some(a), Some(b)) => a.partial_cmp(&b),
+                    _ => None,
+                },
+            }
+            .ok_or_else(|| type_error(op, &left, &right))?;
+            Bool(match op {
+                BinaryOp::Lt => ordering.is_lt(),
+                BinaryOp::Le => ordering.is_le(),
+                BinaryOp::Gt => ordering.is_gt(),
+                _ => ordering.is_ge(),
+            })
+        }
+        (_, Null, _) | (_, _, Null) => return Err(type_error(op, &left, &right)),
+        _ => {
+            let (a, b) = match (number(&left), number(&right)) {
+                (Some(a), Some(b)) => (a, b),
+                _ => return Err(type_error(op, &left, &right)),
+            };
+            match op {
+                BinaryOp::Add => Float(a + b),
+                BinaryOp::Sub => Float(a - b),
+                BinaryOp::Mul => Float(a * b),
+                BinaryOp::Div if b == 0.0 => return Err("Division by zero.".into()),
+                _ => Float(a / b),
+            }
+        }
+    };
+    Ok(result)
+}
+
+fn number(value: &BaseTypes) -> Option<f64> {
+    match value {
+        BaseTypes::Int(n) => Some(*n as f64),
+        BaseTypes::Float(f) => Some(*f),
+        _ => None,
+    }
+}
+
+fn equal(left: &BaseTypes, right: &BaseTypes) -> bool {
+    match (left, right) {
+        (BaseTypes::Int(a), BaseTypes::Int(b)) => a == b,
+        (BaseTypes::StringWrapper(a), BaseTypes::StringWrapper(b)) => a == b,
+        (BaseTypes::Char(a), BaseTypes::Char(b)) => a == b,
+        (BaseTypes::Bool(a), BaseTypes::Bool(b)) => a == b,
+        (BaseTypes::Null, BaseTypes::Null) => true,
+        _ => match (number(left), number(right)) {
+            (Some(a), Some(b)) => a == b,
+            _ => false,
+        },
+    }
+}
+
+#[cfg(test)]
+mod vm_tests {
+    use super::*;
+    use crate::bytecode;
+    use crate::engine::Engine;
+    use crate::node::nodes::{match_token_to_node, ASTNode};
+    use crate::statement_tokenizer::tokenizer::tokenizers::tokenize;
+
+    fn eval(expression: &str) -> Result<BaseTypes, Box<dyn Error>> {
+        let nodes: Vec<ASTNode> = tokenize(expression.to_string())
+            .into_iter()
+            .map(match_token_to_node)
+            .collect();
+        evaluate(&bytecode::compile_expression(&nodes).unwrap())
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert_eq!(eval("1 + 2 * 3").unwrap(), BaseTypes::Int(7));
+        assert_eq!(eval("(1 + 2) * 3").unwrap(), BaseTypes::Int(9));
+        assert_eq!(eval("10 - 4 - 3").unwrap(), BaseTypes::Int(3));
+        assert_eq!(eval("7 / 2").unwrap(), BaseTypes::Int(3));
+        assert_eq!(eval("1 + 0.5").unwrap(), BaseTypes::Float(1.5));
+        assert_eq!(
+            eval("\"a\" + \"b\"").unwrap(),
+            BaseTypes::StringWrapper("ab".to_string())
+        );
+        assert_eq!(eval("1 / 0").unwrap_err().to_string(), "Division by zero.");
+    }
+
+    #[test]
+    fn test_comparison_and_logic() {
+        assert!(matches!(eval("2 < 3").unwrap(), BaseTypes::Bool(true)));
+        assert!(matches!(eval("2 >= 3").unwrap(), BaseTypes::Bool(false)));
+        assert!(matches!(eval("1 == 1.0").unwrap(), BaseTypes::Bool(true)));
+        assert!(matches!(
+            eval("1 < 2 && 2 < 1").unwrap(),
+            BaseTypes::Bool(false)
+        ));
+        assert!(matches!(
+            eval("1 < 2 || 2 < 1").unwrap(),
+            BaseTypes::Bool(true)
+        ));
+        assert!(eval("\"a\" < 1").is_err());
+    }
+
+    #[test]
+    fn test_while_loop() {
+        let mut engine = Engine::new();
+        engine
+            .eval(
+                "let vm_total: int = 0;\nlet vm_i: int = 0;\n\
+                 while (vm_i < 1000) {\n    vm_total = vm_total + vm_i * 2;\n    vm_i++;\n}",
+            )
+            .unwrap();
+        assert_eq!(engine.get_global("vm_total"), Some(BaseTypes::Int(999000)));
+        assert_eq!(engine.get_global("vm_i"), Some(BaseTypes::Int(1000)));
+    }
+
+    #[test]
+    fn test_for_loop() {
+        let mut engine = Engine::new();
+        engine
+            .eval("let vm_sum: int = 0;\nfor (vm_k in 1..4) {\n    vm_sum += vm_k;\n}")
+            .unwrap();
+        assert_eq!(engine.get_global("vm_sum"), Some(BaseTypes::Int(10)));
+        // the loop variable belongs to the loop
+        assert_eq!(engine.get_global("vm_k"), None);
+    }
+
+    #[test]
+    fn test_declaration_in_loop() {
+        let mut engine = Engine::new();
+        engine
+            .eval(
+                "let vm_last: int = 0;\nlet vm_n: int = 0;\n\
+                 while (vm_n < 3) {\n    let vm_twice: int = vm_n * 2;\n    \
+                 vm_last = vm_twice;\n    vm_n++;\n}",
+            )
+            .unwrap();
+        assert_eq!(engine.get_global("vm_last"), Some(BaseTypes::Int(4)));
+        assert_eq!(engine.get_global("vm_twice"), None);
+    }
+
+    #[test]
+    fn test_slots_follow_scopes() {
+        let mut engine = Engine::new();
+        engine
+            .eval(
+                "let vm_outer: int = 1;\nfunc vm_add(vm_outer: int) -> int {\n    \
+                 return vm_outer + vm_base;\n}\nlet vm_base: int = 10;\n\
+                 if (vm_outer == 1) {\n    let vm_outer: int = 5;\n    \
+                 vm_base = vm_base + vm_outer;\n}\nlet vm_result: int = vm_add(2);",
+            )
+            .unwrap();
+        // the block and the parameter shadow vm_outer, the function reads vm_base by name
+        assert_eq!(engine.get_global("vm_outer"), Some(BaseTypes::Int(1)));
+        assert_eq!(engine.get_global("vm_base"), Some(BaseTypes::Int(15)));
+        assert_eq!(engine.get_global("vm_result"), Some(BaseTypes::Int(17)));
+    }
+
+    #[test]
+    fn test_increment_error() {
+        let mut engine = Engine::new();
+        let error = engine
+            .eval("let vm_text: string = \"a\";\nvm_text++;")
+            .unwrap_err();
+        assert_eq!(error.to_string(), "Type error: cannot increment String");
+    }
+}